@@ -0,0 +1,22 @@
+/// 퍼즈 입력을 임의의 위치에서 나눈 스트림 조각들로 변환 (증분 디코더에 차례로 push)
+/// 첫 바이트 % 16개의 바이트가 조각 크기(각 1~256, 다 쓰면 처음부터 반복), 나머지가 스트림
+/// 조각 크기 바이트가 없으면 스트림 전체가 한 조각
+pub fn split_chunks(data: &[u8]) -> Vec<&[u8]> {
+    let Some((&count, rest)) = data.split_first() else {
+        return Vec::new();
+    };
+    let (sizes, mut stream) = rest.split_at((count as usize % 16).min(rest.len()));
+
+    let mut sizes = sizes.iter().cycle();
+    let mut chunks = Vec::new();
+    while !stream.is_empty() {
+        let size = sizes
+            .next()
+            .map_or(stream.len(), |&size| size as usize + 1)
+            .min(stream.len());
+        let (chunk, tail) = stream.split_at(size);
+        chunks.push(chunk);
+        stream = tail;
+    }
+    chunks
+}
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_wireshark::tds::{TdsPacketType, TdsParser};
+
+// 단일 패킷 디코딩: 헤더 파싱, 본문 추출, UTF-16LE 디코딩
+fuzz_target!(|data: &[u8]| {
+    let _ = TdsParser::parse_header(data);
+    let _ = TdsParser::extract_payload(data);
+    let _ = TdsParser::decode_tds_packet(data);
+    let _ = TdsParser::decode_tds_packet_unchecked(data, TdsPacketType::SqlBatch);
+    let _ = TdsParser::decode_tds_packet_unchecked(data, TdsPacketType::RpcRequest);
+});
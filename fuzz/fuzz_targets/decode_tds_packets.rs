@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_wireshark::tds::TdsParser;
+
+// 재조립된 스트림의 프레이밍 루프 (클라이언트 요청, 로그인, 서버 응답)
+fuzz_target!(|data: &[u8]| {
+    let _ = TdsParser::decode_tds_packets_with_encoding(data, true);
+    let _ = TdsParser::decode_truncated_packet(data, true);
+    let _ = TdsParser::frame_tds_packets(data);
+    let _ = TdsParser::skip_login_packets(data);
+    let _ = TdsParser::find_login7(data);
+    let _ = TdsParser::parse_server_tokens(data);
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+mod chunks;
+
+use libfuzzer_sys::fuzz_target;
+use rust_wireshark::tds::stream::ResponseStreamDecoder;
+
+// 서버 응답 스트림 증분 디코딩: 임의의 위치에서 나눈 조각을 차례로 넘겨 토큰을 읽음 (토큰 / 패킷 경계 걸침)
+fuzz_target!(|data: &[u8]| {
+    let mut decoder = ResponseStreamDecoder::new();
+    for chunk in chunks::split_chunks(data) {
+        let _ = decoder.push(chunk);
+    }
+});
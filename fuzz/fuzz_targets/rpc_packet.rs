@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_wireshark::tds::TdsParser;
+
+// RPC 파싱 (parse_rpc_body, 배치 플래그로 이어진 여러 RPC 포함): 타입과 길이를 맞춘 헤더 뒤에 임의의 본문
+fuzz_target!(|body: &[u8]| {
+    let length = (body.len() + 8).min(u16::MAX as usize) as u16;
+    let mut packet = vec![0x03, 0x01];
+    packet.extend_from_slice(&length.to_be_bytes());
+    packet.extend_from_slice(&[0x00, 0x00, 0x01, 0x00]);
+    packet.extend_from_slice(body);
+
+    let _ = TdsParser::decode_tds_packet(&packet);
+    let _ = TdsParser::rpc_option_flags(&packet);
+});
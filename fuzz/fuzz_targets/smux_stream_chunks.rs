@@ -0,0 +1,17 @@
+#![no_main]
+
+mod chunks;
+
+use libfuzzer_sys::fuzz_target;
+use rust_wireshark::tds::smux::{SmuxDemuxer, SmuxStreamDecoder};
+
+// MARS 스트림 증분 처리: 임의의 위치에서 나눈 조각을 세션별 요청 디코더와 응답용 SMUX 헤더 제거에 차례로 넘김
+fuzz_target!(|data: &[u8]| {
+    let mut decoder = SmuxStreamDecoder::new(true);
+    let mut demuxer = SmuxDemuxer::new();
+    for chunk in chunks::split_chunks(data) {
+        let _ = decoder.push(chunk);
+        let _ = demuxer.push(chunk);
+    }
+    let _ = decoder.finish();
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+mod chunks;
+
+use libfuzzer_sys::fuzz_target;
+use rust_wireshark::tds::stream::TdsStreamDecoder;
+
+// 요청 스트림 증분 디코딩: 임의의 위치에서 나눈 조각을 차례로 넘긴 뒤 끝의 잘린 메시지까지 디코딩
+fuzz_target!(|data: &[u8]| {
+    let mut decoder = TdsStreamDecoder::new(true);
+    let mut raw = TdsStreamDecoder::raw();
+    for chunk in chunks::split_chunks(data) {
+        let _ = decoder.push(chunk);
+        let _ = raw.push(chunk);
+    }
+    let _ = decoder.finish();
+    let _ = raw.finish();
+});
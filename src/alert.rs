@@ -0,0 +1,202 @@
+use crate::normalize::{tokenize_sql, TokenKind};
+use crate::output::{dangerous_statements, DangerousStatement};
+use crate::query::EventPredicate;
+use crate::SqlEvent;
+use regex::Regex;
+
+/// 경고 규칙
+/// operation(선택) + 검사 조건(SQL 정규식 또는 위험한 문장 종류)이 모두 맞으면 경고
+#[derive(Debug, Clone)]
+pub struct AlertRule {
+    pub name: String,
+    /// 이 operation이 포함된 SQL만 검사 (None이면 전체)
+    pub operation: Option<String>,
+    check: RuleCheck,
+}
+
+/// 경고 규칙의 SQL 검사 조건
+#[derive(Debug, Clone)]
+enum RuleCheck {
+    /// SQL 정규식, unless가 있으면 pattern에 걸린 부분에 unless가 없을 때만 경고
+    /// 정규식은 주석과 문자열 리터럴을 뺀 SQL에 적용 (code_text 참고)
+    Pattern {
+        pattern: Regex,
+        unless: Option<Regex>,
+    },
+    /// 위험한 문장 감지 결과 (SQL 목록의 위험 라벨과 같은 기준, dangerous_statements 참고)
+    Dangerous(DangerousStatement),
+}
+
+impl AlertRule {
+    pub fn new(
+        name: &str,
+        operation: Option<&str>,
+        pattern: &str,
+        unless: Option<&str>,
+    ) -> Result<Self, regex::Error> {
+        Ok(Self {
+            name: name.to_string(),
+            operation: operation.map(|op| op.to_uppercase()),
+            check: RuleCheck::Pattern {
+                pattern: Regex::new(pattern)?,
+                unless: unless.map(Regex::new).transpose()?,
+            },
+        })
+    }
+
+    /// 배치에 해당 종류의 위험한 문장이 있으면 경고하는 규칙
+    pub fn dangerous(name: &str, statement: DangerousStatement) -> Self {
+        Self {
+            name: name.to_string(),
+            operation: None,
+            check: RuleCheck::Dangerous(statement),
+        }
+    }
+
+    /// 이벤트가 규칙에 해당하는지 확인
+    pub fn matches(&self, event: &SqlEvent) -> bool {
+        if let Some(ref operation) = self.operation {
+            if !EventPredicate::Operation(operation.clone()).matches(event) {
+                return false;
+            }
+        }
+
+        match self.check {
+            // pattern에 걸린 부분 중 하나라도 unless가 없으면 경고
+            // (문장 경계는 세미콜론 기준이라 세미콜론 없는 배치에서는 근사치)
+            RuleCheck::Pattern {
+                ref pattern,
+                ref unless,
+            } => pattern
+                .find_iter(&code_text(&event.sql_text))
+                .any(|m| match unless {
+                    Some(unless) => !unless.is_match(m.as_str()),
+                    None => true,
+                }),
+            RuleCheck::Dangerous(statement) => {
+                dangerous_statements(&event.sql_text).contains(&statement)
+            }
+        }
+    }
+}
+
+/// 주석을 공백으로, 문자열 리터럴을 빈 문자열('')로 바꾼 SQL
+/// 주석이나 문자열 안의 WHERE / DROP 같은 키워드가 규칙에 걸리지 않도록 함
+fn code_text(sql_text: &str) -> String {
+    tokenize_sql(sql_text)
+        .into_iter()
+        .map(|token| match token.kind {
+            TokenKind::Comment => " ",
+            TokenKind::String | TokenKind::NString => "''",
+            _ => token.text,
+        })
+        .collect()
+}
+
+/// 기본 경고 규칙 (SQL 목록의 위험 라벨과 같은 기준)
+/// - WHERE 없는 DELETE / UPDATE
+/// - DROP / TRUNCATE (임시 테이블만 대상이면 제외)
+pub fn default_alert_rules() -> Vec<AlertRule> {
+    vec![
+        AlertRule::dangerous("WHERE 없는 DELETE", DangerousStatement::DeleteWithoutWhere),
+        AlertRule::dangerous("WHERE 없는 UPDATE", DangerousStatement::UpdateWithoutWhere),
+        AlertRule::dangerous("DROP", DangerousStatement::Drop),
+        AlertRule::dangerous("TRUNCATE", DangerousStatement::Truncate),
+    ]
+}
+
+/// 이벤트에 해당하는 규칙 이름 목록
+pub fn matching_rules<'a>(rules: &'a [AlertRule], event: &SqlEvent) -> Vec<&'a str> {
+    rules
+        .iter()
+        .filter(|rule| rule.matches(event))
+        .map(|rule| rule.name.as_str())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alerts(sql_text: &str) -> Vec<String> {
+        let rules = default_alert_rules();
+        matching_rules(&rules, &SqlEvent::for_test(sql_text, 0))
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn delete_without_where_triggers_alert() {
+        assert_eq!(alerts("DELETE FROM TB_x"), vec!["WHERE 없는 DELETE"]);
+        assert!(alerts("DELETE FROM TB_x WHERE id=1").is_empty());
+        // 두 번째 문장에만 WHERE가 없어도 경고
+        assert_eq!(
+            alerts("delete from TB_x where id = 1; delete from TB_y"),
+            vec!["WHERE 없는 DELETE"]
+        );
+    }
+
+    #[test]
+    fn update_without_where_triggers_alert() {
+        assert_eq!(alerts("UPDATE TB_x SET a = 1"), vec!["WHERE 없는 UPDATE"]);
+        assert!(alerts("UPDATE TB_x SET a = 1 WHERE id = 2").is_empty());
+    }
+
+    #[test]
+    fn where_in_comments_or_literals_does_not_qualify() {
+        assert_eq!(
+            alerts("DELETE FROM TB_x -- WHERE id = 1"),
+            vec!["WHERE 없는 DELETE"]
+        );
+        assert_eq!(
+            alerts("DELETE FROM TB_x /* WHERE id = 1 */"),
+            vec!["WHERE 없는 DELETE"]
+        );
+        assert_eq!(
+            alerts("UPDATE TB_x SET note = 'where'"),
+            vec!["WHERE 없는 UPDATE"]
+        );
+        assert!(alerts("SELECT 'drop table x'").is_empty());
+    }
+
+    #[test]
+    fn alerts_agree_with_danger_label() {
+        for sql in [
+            "DROP TABLE #tmp",
+            "TRUNCATE TABLE TB_x",
+            "DELETE FROM TB_x -- WHERE id = 1",
+            "WITH c AS (SELECT 1 AS a) DELETE FROM TB_x",
+            "SELECT 'drop table x'",
+        ] {
+            assert_eq!(
+                alerts(sql).is_empty(),
+                crate::dangerous_statement_label(sql).is_none(),
+                "{}",
+                sql
+            );
+        }
+        assert!(alerts("DROP TABLE #tmp").is_empty());
+    }
+
+    #[test]
+    fn pattern_rules_skip_comments_and_literals() {
+        let rule = AlertRule::new("password", None, r"(?i)\bpassword\b", None).unwrap();
+        assert!(rule.matches(&SqlEvent::for_test("SELECT password FROM TB_user", 0)));
+        assert!(!rule.matches(&SqlEvent::for_test("SELECT 'password' -- password", 0)));
+    }
+
+    #[test]
+    fn drop_and_truncate_always_trigger_alerts() {
+        assert_eq!(alerts("DROP TABLE TB_x"), vec!["DROP"]);
+        assert_eq!(alerts("truncate table TB_x"), vec!["TRUNCATE"]);
+        assert!(alerts("SELECT * FROM TB_x").is_empty());
+    }
+
+    #[test]
+    fn operation_filter_limits_rule() {
+        let rule = AlertRule::new("x", Some("select"), r"(?i)\bTB_x\b", None).unwrap();
+        assert!(rule.matches(&SqlEvent::for_test("SELECT * FROM TB_x", 0)));
+        assert!(!rule.matches(&SqlEvent::for_test("DELETE FROM TB_x WHERE id = 1", 0)));
+    }
+}
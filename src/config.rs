@@ -0,0 +1,299 @@
+use crate::extractor::{ExtractorBuilder, DEFAULT_SQL_SERVER_PORTS};
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// GUI 테마
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// 중복 제거 범위
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum DedupScope {
+    /// 모든 연결에서 같은 SQL이면 하나로 표시
+    #[default]
+    Global,
+    /// 연결(Flow)별로 중복 제거 (같은 SQL도 보낸 연결이 다르면 따로 표시)
+    PerFlow,
+}
+
+impl DedupScope {
+    /// 중복 제거에 쓰는 키 (SQL 텍스트는 앞뒤 공백을 뺀 것)
+    pub fn key(&self, flow_id: &str, sql_text: &str) -> String {
+        match self {
+            DedupScope::Global => sql_text.trim().to_string(),
+            DedupScope::PerFlow => format!("{}\0{}", flow_id, sql_text.trim()),
+        }
+    }
+}
+
+/// 화면과 로그에 시각을 표시할 시간대 (저장하는 시각은 항상 UTC)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum DisplayTimezone {
+    /// 이 PC의 시간대
+    #[default]
+    Local,
+    Utc,
+    /// UTC 기준 고정 오프셋 (분 단위, 예: KST는 540)
+    Offset(i32),
+}
+
+impl DisplayTimezone {
+    /// 시간대를 적용해 포맷하고 끝에 UTC 오프셋을 붙인 시각 (예: "2025-12-30 21:34:56.789 +09:00")
+    /// 잘못된 오프셋이면 UTC로 표시
+    pub fn format(&self, timestamp: &DateTime<Utc>, pattern: &str) -> String {
+        let pattern = format!("{} %:z", pattern);
+        match self {
+            DisplayTimezone::Local => timestamp.with_timezone(&Local).format(&pattern).to_string(),
+            DisplayTimezone::Utc => timestamp.format(&pattern).to_string(),
+            DisplayTimezone::Offset(minutes) => match FixedOffset::east_opt(minutes * 60) {
+                Some(offset) => timestamp
+                    .with_timezone(&offset)
+                    .format(&pattern)
+                    .to_string(),
+                None => timestamp.format(&pattern).to_string(),
+            },
+        }
+    }
+}
+
+/// 기본 캡처 설정
+/// 파일에 없는 항목은 기본값 사용 (일부만 적어도 됨)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// SQL Server 포트 목록
+    pub server_ports: Vec<u16>,
+    /// SQL Server 포트 범위 목록 ([시작, 끝], 양 끝 포함)
+    pub server_port_ranges: Vec<(u16, u16)>,
+    /// 서버 포트와 겹쳐도 클라이언트 쪽으로 볼 포트 범위 목록 ([시작, 끝], 양 끝 포함)
+    pub client_port_ranges: Vec<(u16, u16)>,
+    /// 마지막으로 선택한 네트워크 인터페이스
+    pub interface: Option<String>,
+    /// 이 클라이언트 IP의 플로우만 처리 (비어있으면 전체)
+    pub client_allowlist: Vec<IpAddr>,
+    /// 이 SQL Server 인스턴스("IP:포트")의 플로우만 처리 (지정하면 서버 포트 목록 대신 사용)
+    pub target_server: Option<SocketAddr>,
+    /// promiscuous 모드로 캡처 (관리형 NIC 등에서 허용하지 않으면 끔)
+    pub promisc: bool,
+    /// 패킷당 캡처할 최대 바이트 수 (줄이면 부하가 줄지만 긴 SQL은 잘림)
+    pub snaplen: i32,
+    /// 로그 파일 기본 폴더 (하위에 basic/, raw/ 생성)
+    pub log_dir: String,
+    /// 같은 SQL 텍스트 중복 제거 여부
+    pub dedup: bool,
+    /// 중복 제거 범위 (전체 / 연결별)
+    pub dedup_scope: DedupScope,
+    /// 한 배치의 여러 문장(; 또는 GO로 구분)을 문장별 이벤트로 분리
+    pub split_batches: bool,
+    /// 진단 모드 (디코딩하지 못한 TDS 패킷을 UNDECODED 이벤트로 표시)
+    pub diagnostics: bool,
+    /// SQL Batch 텍스트 인코딩 자동 감지 (false면 항상 UTF-16LE)
+    pub detect_encoding: bool,
+    /// 양방향 디코딩 (프록시를 거치는 구간 캡처, 양쪽 구간의 같은 쿼리는 하나로)
+    pub bidirectional: bool,
+    /// 서버 응답의 결과 집합 컬럼 이름과 타입 수집 (상세 패널에 표시, 스키마 파악용)
+    pub result_columns: bool,
+    /// 이벤트가 이 개수에 도달하면 캡처 자동 중지 (중복 제거가 켜져 있으면 고유 SQL 수, None이면 계속 캡처)
+    pub stop_after: Option<usize>,
+    /// 캡처를 시작하고 이 시간(분)이 지나면 캡처 자동 중지 (None이면 계속 캡처)
+    pub capture_minutes: Option<u32>,
+    /// 캡처를 중지할 때 세션(요약 + 고유 SQL 이벤트)을 JSONL로 저장할 파일 (폴더면 그 안에 시각별 파일, None이면 저장 안 함)
+    pub auto_export: Option<PathBuf>,
+    /// 초당 최대 이벤트 수 (넘는 이벤트는 버림, None이면 제한 없음)
+    pub max_events_per_sec: Option<u32>,
+    /// 초당 이벤트 제한을 플로우별로 적용 (false면 전체 기준)
+    pub rate_limit_per_flow: bool,
+    /// N개 이벤트 중 하나만 캡처 (부하가 큰 서버용 샘플링, None이나 1이면 모두 캡처)
+    pub sample_every: Option<u32>,
+    /// 리터럴 값을 ?로 바꿔 표시/기록 (원본 패킷은 보관하지 않음)
+    pub anonymize: bool,
+    /// 실시간 로그에 표시할 최근 이벤트 수
+    pub tail_size: usize,
+    /// 응답 시간이 이보다 길면(ms) 목록에서 강조 표시
+    pub slow_query_ms: f64,
+    /// 상세 패널에 한 번에 표시할 SQL 최대 길이 (바이트, 넘는 부분은 '더 보기'로 이 크기씩 추가)
+    pub max_sql_display: usize,
+    pub theme: Theme,
+    /// 시각 표시 시간대 (화면, 로그 파일)
+    pub timezone: DisplayTimezone,
+    /// Flow의 IP를 역방향 DNS로 조회한 호스트 이름으로 표시 (false면 IP 그대로)
+    pub resolve_hosts: bool,
+    /// 목록의 시간 열에 마지막 실행 시각 표시 (false면 처음 실행 시각, 중복 제거 시 의미 있음)
+    pub show_last_seen: bool,
+    /// 테이블별 보기에서 테이블을 찾지 못한 SQL(SET 문, SELECT 1 등)의 "기타" 그룹 숨기기 (전체 목록에서도 제외)
+    pub hide_other_tables: bool,
+    /// 경고 발생 시 POST할 웹훅 URL (webhook 기능으로 빌드한 경우에만 사용)
+    pub webhook_url: Option<String>,
+    /// 제어 API를 받을 localhost TCP 포트 (control-api 기능으로 빌드한 경우에만 사용, None이면 끔)
+    pub control_port: Option<u16>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            server_ports: DEFAULT_SQL_SERVER_PORTS.to_vec(),
+            server_port_ranges: Vec::new(),
+            client_port_ranges: Vec::new(),
+            interface: None,
+            client_allowlist: Vec::new(),
+            target_server: None,
+            promisc: true,
+            snaplen: 65535,
+            log_dir: "log".to_string(),
+            dedup: true,
+            dedup_scope: DedupScope::default(),
+            split_batches: false,
+            diagnostics: false,
+            detect_encoding: true,
+            bidirectional: false,
+            result_columns: false,
+            stop_after: None,
+            capture_minutes: None,
+            auto_export: None,
+            max_events_per_sec: None,
+            rate_limit_per_flow: false,
+            sample_every: None,
+            anonymize: false,
+            tail_size: 100,
+            slow_query_ms: 1000.0,
+            max_sql_display: 64 * 1024,
+            theme: Theme::default(),
+            timezone: DisplayTimezone::default(),
+            resolve_hosts: false,
+            show_last_seen: false,
+            hide_other_tables: false,
+            webhook_url: None,
+            control_port: None,
+        }
+    }
+}
+
+impl Config {
+    /// 설정을 반영한 Extractor 빌더
+    pub fn extractor_builder(&self) -> ExtractorBuilder {
+        let builder = ExtractorBuilder::new()
+            .ports(&self.server_ports)
+            .port_ranges(&self.server_port_ranges)
+            .client_port_ranges(&self.client_port_ranges)
+            .client_allowlist(&self.client_allowlist)
+            .target_server(
+                self.target_server
+                    .map(|address| (address.ip(), address.port())),
+            )
+            .promisc(self.promisc)
+            .snaplen(self.snaplen)
+            .diagnostics(self.diagnostics)
+            .detect_encoding(self.detect_encoding)
+            .bidirectional(self.bidirectional)
+            .result_columns(self.result_columns)
+            .capture_duration(
+                self.capture_minutes
+                    .map(|minutes| Duration::from_secs(u64::from(minutes) * 60)),
+            );
+
+        let builder = match self.sample_every {
+            Some(every) => builder.sample_every(every),
+            None => builder,
+        };
+        match self.max_events_per_sec {
+            Some(max_per_sec) => builder.rate_limit(max_per_sec, self.rate_limit_per_flow),
+            None => builder,
+        }
+    }
+
+    /// 설정 파일 경로 (예: %APPDATA%/tds-sniffer/config.json)
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("tds-sniffer").join("config.json"))
+    }
+
+    /// 설정 파일 로드
+    /// 파일이 없거나 형식이 잘못된 경우 기본값 사용
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::from_json(&text),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// JSON 문자열에서 설정 읽기 (실패하면 기본값)
+    pub fn from_json(text: &str) -> Self {
+        serde_json::from_str(text).unwrap_or_else(|e| {
+            tracing::warn!("설정 파일 형식 오류, 기본값 사용: {}", e);
+            Self::default()
+        })
+    }
+
+    /// 설정 파일 저장
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = Self::path().ok_or_else(|| "Failed to find config directory".to_string())?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        std::fs::write(&path, text).map_err(|e| format!("Failed to write config file: {}", e))?;
+
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_config_uses_defaults_for_missing_fields() {
+        let config = Config::from_json(r#"{ "server_ports": [14330], "theme": "Light" }"#);
+
+        assert_eq!(config.server_ports, vec![14330]);
+        assert_eq!(config.theme, Theme::Light);
+        assert_eq!(
+            config,
+            Config {
+                server_ports: vec![14330],
+                theme: Theme::Light,
+                ..Config::default()
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_config_falls_back_to_defaults() {
+        assert_eq!(Config::from_json("{ not json"), Config::default());
+        assert_eq!(
+            Config::from_json(r#"{ "server_ports": "1433" }"#),
+            Config::default()
+        );
+    }
+
+    #[test]
+    fn config_round_trips_through_json() {
+        let config = Config {
+            server_ports: vec![1433, 14330],
+            interface: Some("eth0".to_string()),
+            log_dir: "D:/logs".to_string(),
+            dedup: false,
+            dedup_scope: DedupScope::PerFlow,
+            theme: Theme::Light,
+            timezone: DisplayTimezone::Offset(540),
+            ..Config::default()
+        };
+
+        let text = serde_json::to_string_pretty(&config).unwrap();
+        assert_eq!(Config::from_json(&text), config);
+    }
+}
@@ -0,0 +1,162 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+/// ============================================
+/// 제어 API (control-api 기능)
+/// ============================================
+/// 테스트 스크립트에서 실행 중인 GUI의 캡처를 제어하기 위한 localhost TCP 서버
+/// 한 줄에 JSON 명령 하나를 받고 한 줄 JSON으로 응답 ({"ok": true, ...} 또는 {"ok": false, "error": ...})
+///
+/// 명령:
+/// - `{"command": "start"}`: 캡처 시작 (`"interface"`를 지정하면 그 인터페이스 선택 후 시작)
+/// - `{"command": "stop"}`: 캡처 중지
+/// - `{"command": "clear"}`: 수집한 이벤트 지우기
+/// - `{"command": "get_events"}`: 수집한 이벤트 목록 (`"since"`를 지정하면 그 인덱스부터)
+///
+/// 명령은 채널로 GUI 스레드에 넘겨 GuiState::handle_control_command로 처리
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    Start {
+        #[serde(default)]
+        interface: Option<String>,
+    },
+    Stop,
+    Clear,
+    GetEvents {
+        #[serde(default)]
+        since: usize,
+    },
+}
+
+impl ControlCommand {
+    /// 응답의 command 필드에 넣는 이름
+    pub fn name(&self) -> &'static str {
+        match self {
+            ControlCommand::Start { .. } => "start",
+            ControlCommand::Stop => "stop",
+            ControlCommand::Clear => "clear",
+            ControlCommand::GetEvents { .. } => "get_events",
+        }
+    }
+}
+
+/// GUI 스레드로 넘기는 명령 (처리 결과는 reply로 응답)
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    pub reply: mpsc::Sender<Value>,
+}
+
+/// 127.0.0.1:port에서 제어 명령을 받는 서버 스레드 시작
+/// 명령이 오면 화면을 다시 그려 GUI 스레드가 바로 처리하도록 함 (창이 숨겨져 있어도 동작)
+/// 반환값: GUI 스레드에서 명령을 받을 채널
+pub fn start_server(port: u16, ctx: &egui::Context) -> io::Result<mpsc::Receiver<ControlRequest>> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    let (sender, receiver) = mpsc::channel();
+    let ctx = ctx.clone();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    tracing::warn!("제어 API 연결 실패: {}", e);
+                    continue;
+                }
+            };
+            let sender = sender.clone();
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                if let Err(e) = serve_client(stream, &sender, &ctx) {
+                    tracing::debug!("제어 API 연결 종료: {}", e);
+                }
+            });
+        }
+    });
+
+    Ok(receiver)
+}
+
+/// 연결 하나의 명령을 순서대로 처리
+fn serve_client(
+    stream: TcpStream,
+    sender: &mpsc::Sender<ControlRequest>,
+    ctx: &egui::Context,
+) -> io::Result<()> {
+    let mut output = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<ControlCommand>(line) {
+            Ok(command) => {
+                let (reply, receiver) = mpsc::channel();
+                if sender.send(ControlRequest { command, reply }).is_err() {
+                    // GUI가 종료됨
+                    return Ok(());
+                }
+                ctx.request_repaint();
+                match receiver.recv() {
+                    Ok(response) => response,
+                    Err(_) => return Ok(()),
+                }
+            }
+            Err(e) => json!({ "ok": false, "error": format!("Invalid command: {}", e) }),
+        };
+
+        writeln!(output, "{}", response)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<ControlCommand, serde_json::Error> {
+        serde_json::from_str(line)
+    }
+
+    #[test]
+    fn parses_commands_with_optional_fields() {
+        assert_eq!(
+            parse(r#"{"command": "start"}"#).unwrap(),
+            ControlCommand::Start { interface: None }
+        );
+        assert_eq!(
+            parse(r#"{"command": "start", "interface": "eth0"}"#).unwrap(),
+            ControlCommand::Start {
+                interface: Some("eth0".to_string())
+            }
+        );
+        assert_eq!(
+            parse(r#"{"command": "stop"}"#).unwrap(),
+            ControlCommand::Stop
+        );
+        assert_eq!(
+            parse(r#"{"command": "clear"}"#).unwrap(),
+            ControlCommand::Clear
+        );
+        assert_eq!(
+            parse(r#"{"command": "get_events"}"#).unwrap(),
+            ControlCommand::GetEvents { since: 0 }
+        );
+        assert_eq!(
+            parse(r#"{"command": "get_events", "since": 3}"#).unwrap(),
+            ControlCommand::GetEvents { since: 3 }
+        );
+        assert_eq!(ControlCommand::GetEvents { since: 3 }.name(), "get_events");
+
+        assert!(parse(r#"{"command": "restart"}"#).is_err());
+        assert!(parse(r#"{"interface": "eth0"}"#).is_err());
+        assert!(parse("start").is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use crate::normalize::tokenize_sql;
+use similar::{ChangeTag, TextDiff};
+use std::time::Duration;
+
+/// 비교 계산 최대 시간 (아주 긴 SQL은 이 시간 안에 찾은 만큼만 정밀하게 비교)
+const DIFF_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// 비교 결과 조각 종류
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// 두 SQL에 모두 있음
+    Equal,
+    /// 첫 번째 SQL에만 있음 (삭제)
+    Delete,
+    /// 두 번째 SQL에만 있음 (추가)
+    Insert,
+}
+
+/// 비교 결과 조각 (같은 종류가 이어지면 하나로 합침)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffSpan {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// ============================================
+/// SQL 비교
+/// ============================================
+/// 두 SQL 텍스트를 SQL 토큰 단위로 비교 (tokenize_sql 기준이라 WHERE id=5 와 id=7은 5, 7만 다름)
+/// 조각을 순서대로 이어 붙이면 Delete를 뺀 것은 new, Insert를 뺀 것은 old와 같음
+pub fn diff_sql(old: &str, new: &str) -> Vec<DiffSpan> {
+    let old_tokens: Vec<&str> = tokenize_sql(old).iter().map(|token| token.text).collect();
+    let new_tokens: Vec<&str> = tokenize_sql(new).iter().map(|token| token.text).collect();
+    let diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_slices(&old_tokens, &new_tokens);
+
+    let mut spans: Vec<DiffSpan> = Vec::new();
+    for change in diff.iter_all_changes() {
+        let kind = match change.tag() {
+            ChangeTag::Equal => DiffKind::Equal,
+            ChangeTag::Delete => DiffKind::Delete,
+            ChangeTag::Insert => DiffKind::Insert,
+        };
+        match spans.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(change.value()),
+            _ => spans.push(DiffSpan {
+                kind,
+                text: change.value().to_string(),
+            }),
+        }
+    }
+    spans
+}
+
+/// 바뀐 부분 목록 (삭제된 텍스트, 추가된 텍스트), 같은 부분 사이마다 하나
+/// 예: "WHERE id = 5" → "WHERE id = 7" 이면 [("5", "7")]
+pub fn changed_hunks(spans: &[DiffSpan]) -> Vec<(String, String)> {
+    let mut hunks = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for span in spans {
+        match span.kind {
+            DiffKind::Equal => hunks.extend(current.take()),
+            DiffKind::Delete => current.get_or_insert_with(Default::default).0 += &span.text,
+            DiffKind::Insert => current.get_or_insert_with(Default::default).1 += &span.text,
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn joined(spans: &[DiffSpan], skip: DiffKind) -> String {
+        spans
+            .iter()
+            .filter(|span| span.kind != skip)
+            .map(|span| span.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn literal_change_is_a_single_hunk() {
+        let old = "SELECT * FROM TB_A WHERE id = 5";
+        let new = "SELECT * FROM TB_A WHERE id = 7";
+        let spans = diff_sql(old, new);
+
+        assert_eq!(
+            changed_hunks(&spans),
+            vec![("5".to_string(), "7".to_string())]
+        );
+        assert_eq!(joined(&spans, DiffKind::Insert), old);
+        assert_eq!(joined(&spans, DiffKind::Delete), new);
+    }
+
+    #[test]
+    fn added_and_removed_clauses_are_separate_hunks() {
+        let old = "SELECT a FROM TB_A WHERE x = 1 ORDER BY a";
+        let new = "SELECT a, b FROM TB_A WHERE x = 1";
+        let spans = diff_sql(old, new);
+
+        assert_eq!(
+            changed_hunks(&spans),
+            vec![
+                (String::new(), ", b".to_string()),
+                (" ORDER BY a".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(joined(&spans, DiffKind::Insert), old);
+        assert_eq!(joined(&spans, DiffKind::Delete), new);
+    }
+
+    #[test]
+    fn identical_sql_has_no_hunks() {
+        let sql = "SELECT * FROM TB_A";
+        assert_eq!(
+            diff_sql(sql, sql),
+            vec![DiffSpan {
+                kind: DiffKind::Equal,
+                text: sql.to_string(),
+            }]
+        );
+        assert!(changed_hunks(&diff_sql(sql, sql)).is_empty());
+    }
+}
@@ -0,0 +1,364 @@
+use crate::query::EventPredicate;
+use crate::{extract_table_name, extract_tables_from_sql, SqlEvent};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// ============================================
+/// 내보내기 필터
+/// ============================================
+/// 내보낼 이벤트 조건 (설정하지 않은 조건은 검사하지 않음, 모든 조건을 만족해야 포함)
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    /// 작업 유형 (SELECT, DELETE 등, 한 쿼리의 여러 operation 중 하나라도 같으면 포함)
+    pub operation: Option<String>,
+    /// 테이블명 (TB_ 다음 부분 기준 비교, 예: "TB_진료내역"과 "dbo.TB_진료내역"은 같음)
+    pub table: Option<String>,
+    /// SQL 텍스트 검색어 (대소문자 무시)
+    pub search: String,
+    /// 이 시각 이후의 이벤트만
+    pub from: Option<DateTime<Utc>>,
+    /// 이 시각 이전의 이벤트만
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl ExportFilter {
+    /// 필터 조건을 이벤트 조건식으로 (설정한 조건의 And)
+    pub fn to_predicate(&self) -> EventPredicate {
+        let mut predicates = Vec::new();
+        if let Some(ref operation) = self.operation {
+            predicates.push(EventPredicate::Operation(operation.clone()));
+        }
+        if let Some(ref table) = self.table {
+            predicates.push(EventPredicate::Table(table.clone()));
+        }
+        let search = self.search.trim();
+        if !search.is_empty() {
+            predicates.push(EventPredicate::SqlContains(search.to_string()));
+        }
+        if self.from.is_some() || self.to.is_some() {
+            predicates.push(EventPredicate::TimeBetween(self.from, self.to));
+        }
+        EventPredicate::And(predicates)
+    }
+
+    /// 이벤트가 모든 조건을 만족하는지
+    pub fn matches(&self, event: &SqlEvent) -> bool {
+        self.to_predicate().matches(event)
+    }
+}
+
+/// 필터를 통과한 이벤트 (필터가 없으면 전체)
+pub fn filter_events<'a>(
+    events: &'a [SqlEvent],
+    filter: Option<&ExportFilter>,
+) -> Vec<&'a SqlEvent> {
+    let predicate = filter.map(ExportFilter::to_predicate);
+    events
+        .iter()
+        .filter(|event| predicate.as_ref().is_none_or(|p| p.matches(event)))
+        .collect()
+}
+
+/// ============================================
+/// JSON / CSV 내보내기
+/// ============================================
+/// 이벤트를 JSON 배열로 저장 (headless dump와 같은 형식)
+/// 반환값: 기록한 이벤트 수
+pub fn export_json(
+    path: &Path,
+    events: &[SqlEvent],
+    filter: Option<&ExportFilter>,
+) -> Result<usize, String> {
+    write_file(path, |writer| write_json(writer, events, filter))
+}
+
+/// 이벤트를 CSV로 저장 (한 이벤트가 한 행)
+/// 반환값: 기록한 이벤트 수
+pub fn export_csv(
+    path: &Path,
+    events: &[SqlEvent],
+    filter: Option<&ExportFilter>,
+) -> Result<usize, String> {
+    write_file(path, |writer| write_csv(writer, events, filter))
+}
+
+/// JSON 형식으로 기록 (파일 외 출력 대상에도 사용 가능)
+pub fn write_json<W: Write>(
+    writer: &mut W,
+    events: &[SqlEvent],
+    filter: Option<&ExportFilter>,
+) -> io::Result<usize> {
+    let events = filter_events(events, filter);
+    serde_json::to_writer_pretty(&mut *writer, &events)?;
+    Ok(events.len())
+}
+
+/// CSV 형식으로 기록 (파일 외 출력 대상에도 사용 가능)
+/// 열: timestamp, flow_id, operation, tables, database, app_name, latency_ms, sql_text
+pub fn write_csv<W: Write>(
+    writer: &mut W,
+    events: &[SqlEvent],
+    filter: Option<&ExportFilter>,
+) -> io::Result<usize> {
+    writeln!(
+        writer,
+        "timestamp,flow_id,operation,tables,database,app_name,latency_ms,sql_text"
+    )?;
+
+    let events = filter_events(events, filter);
+    for event in &events {
+        let tables = if event.tables.is_empty() {
+            extract_tables_from_sql(&event.sql_text)
+        } else {
+            event.tables.clone()
+        };
+        let fields = [
+            event.timestamp.to_rfc3339(),
+            event.flow_id.clone(),
+            event.operation.clone(),
+            tables.join(", "),
+            event.database.clone().unwrap_or_default(),
+            event.app_name.clone().unwrap_or_default(),
+            event
+                .latency_ms
+                .map(|latency| format!("{:.3}", latency))
+                .unwrap_or_default(),
+            event.full_text().into_owned(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        writeln!(writer, "{}", row.join(","))?;
+    }
+
+    Ok(events.len())
+}
+
+/// ============================================
+/// 캡처 세션 자동 내보내기
+/// ============================================
+/// 자동 내보내기 파일 첫 줄의 세션 요약
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionSummary {
+    pub interface: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub stopped_at: DateTime<Utc>,
+    pub events: usize,
+}
+
+/// 자동 내보내기 파일 경로
+/// 설정한 경로가 이미 있는 폴더면 그 안에 sql_capture_YYYYMMDD_HHMMSS.jsonl (중지 시각, UTC)
+pub fn auto_export_path(target: &Path, stopped_at: &DateTime<Utc>) -> PathBuf {
+    if target.is_dir() {
+        target.join(format!(
+            "sql_capture_{}.jsonl",
+            stopped_at.format("%Y%m%d_%H%M%S")
+        ))
+    } else {
+        target.to_path_buf()
+    }
+}
+
+/// 세션을 JSONL로 저장 (첫 줄은 {"summary": ...}, 다음 줄부터 이벤트 하나씩)
+/// 반환값: 기록한 이벤트 수
+pub fn export_session(
+    path: &Path,
+    summary: &SessionSummary,
+    events: &[SqlEvent],
+) -> Result<usize, String> {
+    write_file(path, |writer| write_session(writer, summary, events))
+}
+
+/// JSONL 세션 형식으로 기록 (파일 외 출력 대상에도 사용 가능)
+pub fn write_session<W: Write>(
+    writer: &mut W,
+    summary: &SessionSummary,
+    events: &[SqlEvent],
+) -> io::Result<usize> {
+    serde_json::to_writer(&mut *writer, &serde_json::json!({ "summary": summary }))?;
+    writeln!(writer)?;
+    for event in events {
+        serde_json::to_writer(&mut *writer, event)?;
+        writeln!(writer)?;
+    }
+    Ok(events.len())
+}
+
+/// ============================================
+/// 테이블 관계 그래프
+/// ============================================
+/// 같은 SQL에 함께 등장한 두 테이블 (테이블명은 TB_ 다음 부분, source < target)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableEdge {
+    pub source: String,
+    pub target: String,
+    /// 두 테이블이 함께 등장한 고유 SQL 수
+    pub weight: usize,
+}
+
+/// 이벤트들의 테이블 동시 등장 그래프 (가중치 큰 순, 같으면 테이블명 순)
+/// 같은 SQL 텍스트는 한 번만 셈 (중복 제거를 끈 경우에도 고유 SQL 기준)
+pub fn table_graph(events: &[SqlEvent]) -> Vec<TableEdge> {
+    let mut seen_sql = HashSet::new();
+    let mut weights: HashMap<(String, String), usize> = HashMap::new();
+
+    for event in events {
+        if !seen_sql.insert(event.sql_text.as_str()) {
+            continue;
+        }
+
+        let tables = if event.tables.is_empty() {
+            extract_tables_from_sql(&event.sql_text)
+        } else {
+            event.tables.clone()
+        };
+        let tables: BTreeSet<String> = tables
+            .iter()
+            .map(|table| extract_table_name(table))
+            .filter(|table| !table.is_empty())
+            .collect();
+        let tables: Vec<&String> = tables.iter().collect();
+
+        for (i, source) in tables.iter().enumerate() {
+            for target in &tables[i + 1..] {
+                *weights
+                    .entry(((*source).clone(), (*target).clone()))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    let mut edges: Vec<TableEdge> = weights
+        .into_iter()
+        .map(|((source, target), weight)| TableEdge {
+            source,
+            target,
+            weight,
+        })
+        .collect();
+    edges.sort_by(|a, b| {
+        b.weight
+            .cmp(&a.weight)
+            .then_with(|| (&a.source, &a.target).cmp(&(&b.source, &b.target)))
+    });
+    edges
+}
+
+/// 테이블 관계 그래프를 파일로 저장
+/// 확장자가 .dot / .gv면 GraphViz DOT, 그 외에는 JSON 간선 목록 ([{source, target, weight}])
+/// 반환값: 기록한 간선 수
+pub fn export_table_graph(path: &Path, events: &[SqlEvent]) -> Result<usize, String> {
+    let edges = table_graph(events);
+    let is_dot = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("dot") || ext.eq_ignore_ascii_case("gv"));
+
+    write_file(path, |writer| {
+        if is_dot {
+            write_table_graph_dot(writer, &edges)?;
+        } else {
+            serde_json::to_writer_pretty(&mut *writer, &edges)?;
+        }
+        Ok(edges.len())
+    })
+}
+
+/// GraphViz DOT 형식으로 기록 (무방향 그래프, 간선 굵기와 라벨이 가중치)
+pub fn write_table_graph_dot<W: Write>(writer: &mut W, edges: &[TableEdge]) -> io::Result<()> {
+    let quote = |name: &str| format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+    writeln!(writer, "graph tables {{")?;
+    for edge in edges {
+        writeln!(
+            writer,
+            "    {} -- {} [weight={}, label=\"{}\", penwidth={:.1}];",
+            quote(&edge.source),
+            quote(&edge.target),
+            edge.weight,
+            edge.weight,
+            1.0 + (edge.weight as f64).ln()
+        )?;
+    }
+    writeln!(writer, "}}")
+}
+
+/// 파일을 만들어 기록 (오류는 메시지 문자열로)
+fn write_file(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<usize>,
+) -> Result<usize, String> {
+    let file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    write(&mut writer)
+        .and_then(|count| writer.flush().map(|_| count))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// CSV 필드 (쉼표, 따옴표, 줄바꿈이 있으면 따옴표로 감싸고 따옴표는 두 번)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events() -> Vec<SqlEvent> {
+        vec![
+            SqlEvent::for_test("SELECT * FROM TB_진료내역 WHERE id=1", 1),
+            SqlEvent::for_test("DELETE FROM TB_진료내역 WHERE id=1", 2),
+            SqlEvent::for_test("DELETE FROM TB_환자 WHERE id=2", 3),
+        ]
+    }
+
+    #[test]
+    fn csv_export_with_operation_filter_writes_only_matching_rows() {
+        let filter = ExportFilter {
+            operation: Some("DELETE".to_string()),
+            ..ExportFilter::default()
+        };
+        let mut output = Vec::new();
+        let count = write_csv(&mut output, &events(), Some(&filter)).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let rows: Vec<&str> = output.lines().skip(1).collect();
+        assert_eq!(count, 2);
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| row.contains("DELETE FROM")));
+    }
+
+    #[test]
+    fn json_export_combines_filter_conditions() {
+        let filter = ExportFilter {
+            operation: Some("DELETE".to_string()),
+            table: Some("dbo.TB_진료내역".to_string()),
+            ..ExportFilter::default()
+        };
+        let mut output = Vec::new();
+        let count = write_json(&mut output, &events(), Some(&filter)).unwrap();
+
+        let written: Vec<serde_json::Value> = serde_json::from_slice(&output).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(written.len(), 1);
+        assert_eq!(written[0]["sql_text"], "DELETE FROM TB_진료내역 WHERE id=1");
+    }
+
+    #[test]
+    fn export_without_filter_writes_every_event() {
+        let mut output = Vec::new();
+        assert_eq!(write_json(&mut output, &events(), None).unwrap(), 3);
+
+        let filter = ExportFilter {
+            from: DateTime::from_timestamp(2, 0),
+            ..ExportFilter::default()
+        };
+        assert_eq!(filter_events(&events(), Some(&filter)).len(), 2);
+    }
+}
@@ -1,20 +1,735 @@
-use crate::tcp::{FlowId, TcpReassembler};
-use crate::tds::TdsParser;
-use crate::SqlEvent;
+use crate::stats::CaptureStats;
+use crate::tcp::{Direction, FlowId, FlowStat, TcpReassembler};
+use crate::tds::login::Login7;
+use crate::tds::response::{EnvChange, ResponseToken};
+use crate::tds::smux::{self, SmuxStreamDecoder};
+use crate::tds::stream::{DecodedMessage, ResponseStreamDecoder, TdsStreamDecoder};
+use crate::tds::{RpcCall, TdsParser, TextEncoding};
+use crate::{dangerous_statement_label, hex_dump, split_batch, SqlEvent};
+use std::collections::{HashMap, VecDeque};
 use std::net::IpAddr;
-use std::sync::mpsc;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+/// SQL Server 기본 포트 (1434는 SQL Browser)
+pub const DEFAULT_SQL_SERVER_PORTS: [u16; 3] = [1433, 1434, 1436];
+
+/// LOGIN7을 찾을 클라이언트 스트림 앞부분 크기 (로그인은 연결 직후에 옴)
+const LOGIN_SCAN_LIMIT: usize = 16 * 1024;
+
+/// 이 시간(초, 패킷 타임스탬프 기준) 동안 패킷이 없는 플로우는 끝난 것으로 보고 상태를 정리
+/// (FIN / RST를 놓친 연결이 재조립기와 디코더에 계속 남지 않도록)
+const FLOW_IDLE_TIMEOUT: f64 = 300.0;
+
+/// 유휴 플로우를 찾는 간격 (초, 패킷 타임스탬프 기준)
+const FLOW_SWEEP_INTERVAL: f64 = 10.0;
+
+/// 양방향 디코딩 모드에서 다른 연결의 같은 SQL을 프록시가 중계한 것으로 볼 시간 (초)
+const RELAY_DEDUP_WINDOW: f64 = 1.0;
+
+/// TCP 플래그 (연결 시작 / 종료 감지)
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+
+/// Npcap 루프백 어댑터 장치 이름 끝부분 (\Device\NPF_Loopback)
+const NPCAP_LOOPBACK_SUFFIX: &str = "NPF_Loopback";
+
+/// 인터페이스 목록에서 루프백 어댑터 설명 앞에 붙이는 이름
+const LOOPBACK_LABEL: &str = "[루프백: localhost SQL Server]";
+
+/// 캡처 중 장치 오류가 나면 장치를 다시 여는 최대 시도 횟수 (모두 실패하면 캡처 종료)
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+/// 다시 열기 전 대기 시간 (시도마다 두 배, 최대 MAX_RECONNECT_DELAY)
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(8);
+
+/// pcap 파일 처리 중 이 패킷 수마다 진행 상황 전송
+const PROGRESS_INTERVAL: u64 = 1000;
+
+/// pcap 파일 전역 헤더 / 레코드 헤더 크기 (읽은 바이트 추정용)
+const PCAP_GLOBAL_HEADER_LEN: u64 = 24;
+const PCAP_RECORD_HEADER_LEN: u64 = 16;
+
+/// 진단 모드에서 디코딩하지 못한 TDS 패킷 이벤트의 operation
+pub const UNDECODED_OPERATION: &str = "UNDECODED";
+
+/// 캡처 링크 계층 타입 (IP 헤더 앞에 붙는 헤더 형식)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkType {
+    /// Ethernet II (DLT_EN10MB)
+    Ethernet,
+    /// BSD/Npcap loopback (DLT_NULL, DLT_LOOP): 4바이트 address family
+    Null,
+    /// Raw IP (DLT_RAW): 링크 헤더 없음
+    Raw,
+    /// Linux cooked capture (DLT_LINUX_SLL): 16바이트
+    LinuxSll,
+}
+
+impl LinkType {
+    /// pcap 링크 타입을 변환 (지원하지 않는 타입이면 None)
+    pub fn from_pcap(linktype: pcap::Linktype) -> Option<Self> {
+        match linktype.0 {
+            0 | 108 => Some(LinkType::Null),
+            1 => Some(LinkType::Ethernet),
+            12 | 14 | 101 => Some(LinkType::Raw),
+            113 => Some(LinkType::LinuxSll),
+            _ => None,
+        }
+    }
+
+    /// 링크 헤더를 건너뛴 IP 헤더 시작 위치
+    fn ip_offset(&self, data: &[u8]) -> Option<usize> {
+        let offset = match self {
+            LinkType::Ethernet => {
+                // 802.1Q VLAN 태그가 있으면 4바이트 추가
+                if data.len() >= 14 && data[12] == 0x81 && data[13] == 0x00 {
+                    18
+                } else {
+                    14
+                }
+            }
+            LinkType::Null => 4,
+            LinkType::Raw => 0,
+            LinkType::LinuxSll => 16,
+        };
+
+        if data.len() < offset {
+            None
+        } else {
+            Some(offset)
+        }
+    }
+}
+
+/// 요청 스트림 디코더 (MARS 연결은 SMUX 세션별로 나눠 디코딩)
+enum RequestDecoder {
+    Tds(TdsStreamDecoder),
+    Mars(SmuxStreamDecoder),
+}
+
+/// 플로우 한 방향의 요청 스트림 디코더
+/// 재조립기가 새로 이어 붙인 바이트만 넘기고, 디코딩을 마친 앞부분을 재조립 버퍼에서 버릴 수 있도록 스트림 위치를 셈
+struct FlowDecoder {
+    decoder: RequestDecoder,
+    // 디코더에 처음 넘긴 바이트의 스트림 위치 (로그인 단계 패킷 이후)
+    start: usize,
+}
+
+impl FlowDecoder {
+    fn new(decoder: TdsStreamDecoder, start: usize) -> Self {
+        Self {
+            decoder: RequestDecoder::Tds(decoder),
+            start,
+        }
+    }
+
+    /// MARS 연결의 요청 스트림 디코더 (SMUX 헤더로 시작하는 스트림)
+    fn mars(decoder: SmuxStreamDecoder, start: usize) -> Self {
+        Self {
+            decoder: RequestDecoder::Mars(decoder),
+            start,
+        }
+    }
+
+    fn is_mars(&self) -> bool {
+        matches!(self.decoder, RequestDecoder::Mars(_))
+    }
+
+    /// 스트림에 새로 이어진 바이트를 넘기고 이번에 완성된 메시지 반환
+    fn feed(&mut self, data: &[u8]) -> Vec<DecodedMessage> {
+        match &mut self.decoder {
+            RequestDecoder::Tds(decoder) => decoder.push(data),
+            RequestDecoder::Mars(decoder) => decoder.push(data),
+        }
+    }
+
+    /// 디코딩을 마친 스트림 위치 (TcpReassembler::consume에 넘기면 이 앞의 바이트를 버림)
+    fn consumed(&self) -> usize {
+        self.start
+            + match &self.decoder {
+                RequestDecoder::Tds(decoder) => decoder.consumed(),
+                RequestDecoder::Mars(decoder) => decoder.consumed(),
+            }
+    }
+
+    /// 나머지 바이트를 기다리던 메시지를 있는 바이트만으로 디코딩 (TdsStreamDecoder::finish 참고)
+    /// MARS 연결은 세션마다 하나씩 나올 수 있음
+    fn finish(&mut self) -> Vec<DecodedMessage> {
+        match &mut self.decoder {
+            RequestDecoder::Tds(decoder) => decoder.finish().into_iter().collect(),
+            RequestDecoder::Mars(decoder) => decoder.finish(),
+        }
+    }
+}
+
+/// 1/N 샘플링 (N번째 이벤트마다 하나만 보냄, 첫 이벤트는 항상 보냄)
+/// 무작위가 아니라 순서 기준이라 같은 입력이면 항상 같은 이벤트가 남음
+struct Sampler {
+    every: u32,
+    seen: u64,
+}
+
+impl Sampler {
+    fn new(every: u32) -> Self {
+        Self { every, seen: 0 }
+    }
+
+    /// 이벤트를 보낼 차례면 true
+    fn keep(&mut self) -> bool {
+        let keep = self.seen.is_multiple_of(u64::from(self.every));
+        self.seen += 1;
+        keep
+    }
+}
+
+/// 초당 이벤트 수 제한
+/// 패킷 타임스탬프 기준 1초 구간마다 최대 max_per_sec개만 통과 (전체 또는 플로우별)
+struct RateLimiter {
+    max_per_sec: u32,
+    per_flow: bool,
+    window: f64,
+    counts: HashMap<String, u32>,
+    dropped_in_window: u64,
+}
+
+impl RateLimiter {
+    fn new(max_per_sec: u32, per_flow: bool) -> Self {
+        Self {
+            max_per_sec,
+            per_flow,
+            window: f64::MIN,
+            counts: HashMap::new(),
+            dropped_in_window: 0,
+        }
+    }
+
+    /// 이벤트를 보낼 수 있으면 true (제한을 넘으면 false)
+    fn allow(&mut self, event: &SqlEvent, timestamp: f64) -> bool {
+        let window = timestamp.floor();
+        if window != self.window {
+            self.flush_summary();
+            self.window = window;
+            self.counts.clear();
+        }
+
+        let key = if self.per_flow {
+            event.flow_id.clone()
+        } else {
+            String::new()
+        };
+        let count = self.counts.entry(key).or_insert(0);
+        *count += 1;
+
+        if *count > self.max_per_sec {
+            self.dropped_in_window += 1;
+            false
+        } else {
+            true
+        }
+    }
+
+    /// 현재 구간에 버린 이벤트가 있으면 요약 기록 후 초기화
+    /// 구간이 바뀔 때와 캡처가 끝날 때 호출 (마지막 구간의 요약이 빠지지 않도록)
+    /// 반환값: 기록한 버린 이벤트 수
+    fn flush_summary(&mut self) -> u64 {
+        let dropped = std::mem::take(&mut self.dropped_in_window);
+        if dropped > 0 {
+            tracing::warn!(
+                "초당 이벤트 제한({}개) 초과: {}개 이벤트 버림",
+                self.max_per_sec,
+                dropped
+            );
+        }
+        dropped
+    }
+}
+
+/// 캡처 장치 오류 (Extractor::can_capture)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractorError {
+    /// 캡처 권한 없음 (Linux: root 또는 CAP_NET_RAW 필요, Windows: Npcap 관리자 전용 설치)
+    PermissionDenied(String),
+    /// 인터페이스를 찾을 수 없음
+    NoSuchDevice(String),
+    /// 그 외 pcap 오류
+    Pcap(String),
+}
+
+impl From<pcap::Error> for ExtractorError {
+    fn from(error: pcap::Error) -> Self {
+        if let pcap::Error::IoError(std::io::ErrorKind::PermissionDenied) = error {
+            return ExtractorError::PermissionDenied(error.to_string());
+        }
+
+        // libpcap / Npcap 오류 메시지로 구분
+        let message = error.to_string();
+        let lower = message.to_lowercase();
+        if [
+            "permission",
+            "not permitted",
+            "access is denied",
+            "access denied",
+        ]
+        .iter()
+        .any(|pattern| lower.contains(pattern))
+        {
+            ExtractorError::PermissionDenied(message)
+        } else if ["no such device", "cannot find the device"]
+            .iter()
+            .any(|pattern| lower.contains(pattern))
+        {
+            ExtractorError::NoSuchDevice(message)
+        } else {
+            ExtractorError::Pcap(message)
+        }
+    }
+}
+
+impl std::fmt::Display for ExtractorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExtractorError::PermissionDenied(message) => write!(
+                f,
+                "캡처 권한이 없습니다 (관리자/root로 실행하거나 CAP_NET_RAW 권한 필요): {}",
+                message
+            ),
+            ExtractorError::NoSuchDevice(message) => {
+                write!(f, "인터페이스를 찾을 수 없습니다: {}", message)
+            }
+            ExtractorError::Pcap(message) => write!(f, "캡처 장치 오류: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ExtractorError {}
+
+impl ExtractorError {
+    /// 장치를 다시 열면 회복될 수 있는 오류인지
+    /// 무선 어댑터의 일시적인 읽기 실패 등 그 외 pcap 오류만 해당 (권한 없음 / 장치 없음은 다시 열어도 같음)
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, ExtractorError::Pcap(_))
+    }
+}
+
+/// 캡처 장치를 열 때 적용하는 설정 (실시간 캡처, 재연결, 인터페이스 테스트가 같은 값을 사용)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CaptureOptions {
+    promisc: bool,
+    snaplen: i32,
+    /// 읽기 타임아웃 (ms), 짧을수록 중지 신호를 자주 확인
+    timeout_ms: i32,
+}
+
+impl CaptureOptions {
+    fn apply(self, capture: pcap::Capture<pcap::Inactive>) -> pcap::Capture<pcap::Inactive> {
+        capture
+            .promisc(self.promisc)
+            .snaplen(self.snaplen)
+            .timeout(self.timeout_ms)
+    }
+}
+
+/// 캡처 스레드 상태 알림 (ExtractorBuilder::status로 받음, GUI 상태 표시줄용)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureStatus {
+    /// 캡처 장치 오류로 장치를 다시 여는 중
+    Reconnecting {
+        attempt: u32,
+        max_attempts: u32,
+        error: String,
+    },
+    /// 장치를 다시 열어 캡처를 이어감
+    Reconnected,
+    /// 캡처를 시작하지 못했거나 장치를 다시 열지 못해 캡처가 끝남
+    Failed(String),
+    /// 캡처 시간(capture_duration)이 지나 캡처가 끝남 (대기 중이던 이벤트까지 전송한 뒤)
+    DurationElapsed,
+}
+
+/// 인터페이스 테스트 결과 (Extractor::test_interface)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InterfaceTestResult {
+    /// 받은 전체 패킷 수
+    pub total_packets: u64,
+    /// SQL Server 포트의 TCP 패킷 수
+    pub sql_packets: u64,
+}
+
+impl InterfaceTestResult {
+    /// 결과와 확인할 점 요약
+    pub fn summary(&self) -> String {
+        if self.total_packets == 0 {
+            "패킷을 받지 못했습니다 — 인터페이스 선택과 케이블 / 미러링 연결을 확인하세요"
+                .to_string()
+        } else if self.sql_packets == 0 {
+            format!(
+                "패킷 {}개 수신, SQL 포트 0개 — 미러링(SPAN) 설정과 SQL Server 포트를 확인하세요",
+                self.total_packets
+            )
+        } else {
+            format!(
+                "패킷 {}개 수신, SQL 포트 {}개 — 정상",
+                self.total_packets, self.sql_packets
+            )
+        }
+    }
+}
+
+/// 이벤트 콜백 (Extractor::on_event, 채널과 별도로 라이브러리 사용자가 이벤트마다 직접 처리)
+pub type EventCallback = Box<dyn FnMut(&SqlEvent) + Send>;
+
+/// pcap 파일 처리 진행 상황 (ExtractorBuilder::progress로 받음, GUI 진행 표시줄용)
+/// 읽은 바이트는 pcap 레코드 헤더를 포함해 계산한 값이라 파일 크기와 정확히 같지 않을 수 있음
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileProgress {
+    /// 지금까지 읽은 바이트 수 (추정)
+    pub bytes: u64,
+    /// 파일 크기
+    pub total_bytes: u64,
+    /// 지금까지 처리한 패킷 수
+    pub packets: u64,
+}
+
+impl FileProgress {
+    /// 진행률 (0.0 ~ 1.0)
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        (self.bytes as f64 / self.total_bytes as f64).min(1.0) as f32
+    }
+}
+
+/// 캡처 스레드에 보내는 요청
+/// 재조립기는 캡처 스레드의 Extractor가 소유하므로 GUI는 채널로 조회
+pub enum CaptureRequest {
+    /// 플로우의 재조립된 클라이언트 → 서버 스트림 중 아직 디코딩하지 않은 부분 (없으면 None)
+    ClientStream {
+        flow_id: FlowId,
+        reply: mpsc::Sender<Option<Vec<u8>>>,
+    },
+    /// 모든 플로우의 재조립 상태
+    FlowStats { reply: mpsc::Sender<Vec<FlowStat>> },
+}
+
+/// Extractor 설정 빌더
+/// 설정하지 않은 항목은 기존 기본값 사용
+pub struct ExtractorBuilder {
+    server_ports: Vec<u16>,
+    server_port_ranges: Vec<(u16, u16)>,
+    client_port_ranges: Vec<(u16, u16)>,
+    promisc: bool,
+    snaplen: i32,
+    capture_responses: bool,
+    client_allowlist: Vec<IpAddr>,
+    target_server: Option<(IpAddr, u16)>,
+    link_type: Option<LinkType>,
+    use_tds_parsing: bool,
+    diagnostics: bool,
+    detect_encoding: bool,
+    rate_limit: Option<(u32, bool)>,
+    sample_every: Option<u32>,
+    bidirectional: bool,
+    result_columns: bool,
+    stats: Option<Arc<CaptureStats>>,
+    requests: Option<mpsc::Receiver<CaptureRequest>>,
+    status: Option<mpsc::Sender<CaptureStatus>>,
+    capture_duration: Option<Duration>,
+    progress: Option<mpsc::Sender<FileProgress>>,
+}
+
+impl ExtractorBuilder {
+    pub fn new() -> Self {
+        Self {
+            server_ports: DEFAULT_SQL_SERVER_PORTS.to_vec(),
+            server_port_ranges: Vec::new(),
+            client_port_ranges: Vec::new(),
+            promisc: true,
+            snaplen: 65535, // 전체 패킷 캡처
+            capture_responses: true,
+            client_allowlist: Vec::new(),
+            target_server: None,
+            link_type: None,
+            use_tds_parsing: true,
+            diagnostics: false,
+            detect_encoding: true,
+            rate_limit: None,
+            sample_every: None,
+            bidirectional: false,
+            result_columns: false,
+            stats: None,
+            requests: None,
+            status: None,
+            capture_duration: None,
+            progress: None,
+        }
+    }
+
+    /// SQL Server 포트 목록 (이 포트 쪽이 서버)
+    pub fn ports(mut self, ports: &[u16]) -> Self {
+        self.server_ports = ports.to_vec();
+        self
+    }
+
+    /// SQL Server 포트 범위 목록 ((시작, 끝), 양 끝 포함, ports와 함께 서버 포트로 봄)
+    /// 서버 인스턴스마다 다른 포트를 쓰는 경우 (예: (5000, 5010))
+    pub fn port_ranges(mut self, ranges: &[(u16, u16)]) -> Self {
+        self.server_port_ranges = ranges.to_vec();
+        self
+    }
+
+    /// 클라이언트 포트 범위 목록 ((시작, 끝), 양 끝 포함)
+    /// 이 범위의 포트는 서버 포트 목록에 있어도 클라이언트 쪽으로 봄
+    /// (애플리케이션 서버의 임시 포트 범위가 서버 포트 범위와 겹치는 경우)
+    pub fn client_port_ranges(mut self, ranges: &[(u16, u16)]) -> Self {
+        self.client_port_ranges = ranges.to_vec();
+        self
+    }
+
+    /// promiscuous 모드 사용 여부
+    pub fn promisc(mut self, promisc: bool) -> Self {
+        self.promisc = promisc;
+        self
+    }
+
+    /// 패킷당 캡처할 최대 바이트 수
+    pub fn snaplen(mut self, snaplen: i32) -> Self {
+        self.snaplen = snaplen;
+        self
+    }
+
+    /// 서버 응답 스트림 파싱 여부 (결과 행 수 등)
+    pub fn capture_responses(mut self, capture_responses: bool) -> Self {
+        self.capture_responses = capture_responses;
+        self
+    }
+
+    /// 이 클라이언트 IP에서 시작된 플로우만 처리 (비어있으면 전체)
+    pub fn client_allowlist(mut self, clients: &[IpAddr]) -> Self {
+        self.client_allowlist = clients.to_vec();
+        self
+    }
+
+    /// 이 SQL Server 인스턴스(IP, 포트)의 플로우만 처리 (None이면 서버 포트 목록 기준)
+    /// 지정하면 서버 포트 목록 / 범위 대신 이 IP와 포트 쪽을 서버로 봄
+    pub fn target_server(mut self, target_server: Option<(IpAddr, u16)>) -> Self {
+        self.target_server = target_server;
+        self
+    }
+
+    /// 링크 계층 타입 고정 (설정하지 않으면 캡처 장치에서 자동 감지)
+    pub fn link_type(mut self, link_type: LinkType) -> Self {
+        self.link_type = Some(link_type);
+        self
+    }
+
+    /// TDS 파싱 여부
+    /// false면 raw 모드: SQL 추출 없이 모든 TDS 패킷을 hex 이벤트로 전달 (프로토콜 디버깅용)
+    pub fn tds_parsing(mut self, use_tds_parsing: bool) -> Self {
+        self.use_tds_parsing = use_tds_parsing;
+        self
+    }
+
+    /// 진단 모드 여부
+    /// true면 TDS로 보이지만 디코딩하지 못한 SQL Batch / RPC 패킷을
+    /// operation이 UNDECODED이고 sql_text가 빈 이벤트로 전달 (raw_data로 확인)
+    pub fn diagnostics(mut self, diagnostics: bool) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// SQL Batch 텍스트 인코딩 자동 감지 여부 (기본값 true)
+    /// UTF-16LE로 디코딩한 결과가 깨지면 UTF-8 / windows-1252로도 디코딩해 더 자연스러운 쪽 사용
+    /// false면 항상 UTF-16LE
+    pub fn detect_encoding(mut self, detect_encoding: bool) -> Self {
+        self.detect_encoding = detect_encoding;
+        self
+    }
+
+    /// 초당 이벤트 수 제한 (start_live_capture에서 전송하는 이벤트에 적용)
+    /// 1초에 max_per_sec개를 넘는 이벤트는 버리고 통계의 rate_limited_events로 집계
+    /// per_flow가 true면 플로우별로, false면 전체 이벤트 기준으로 셈
+    pub fn rate_limit(mut self, max_per_sec: u32, per_flow: bool) -> Self {
+        self.rate_limit = Some((max_per_sec, per_flow));
+        self
+    }
+
+    /// 1/N 샘플링 (start_live_capture에서 전송하는 이벤트에 적용)
+    /// N개 이벤트 중 하나만 보내고 나머지는 통계의 sampled_out_events로 집계 (N이 1 이하면 샘플링하지 않음)
+    /// 초당 이벤트 제한과 함께 쓰면 샘플링을 먼저 적용
+    pub fn sample_every(mut self, every: u32) -> Self {
+        self.sample_every = Some(every).filter(|&every| every > 1);
+        self
+    }
+
+    /// 양방향 디코딩 (기본값 false, 프록시 / 게이트웨이를 거치는 구간을 캡처할 때 사용)
+    /// 서버 포트 쪽에서 오는 스트림도 SQL Batch / RPC 요청으로 시작하면 요청으로 디코딩
+    /// 같은 SQL이 RELAY_DEDUP_WINDOW 안에 다른 연결에서 다시 나오면 프록시가 중계한 것으로 보고 버림
+    /// (앱 → 프록시, 프록시 → DB 두 구간의 같은 쿼리가 하나의 이벤트가 됨)
+    pub fn bidirectional(mut self, bidirectional: bool) -> Self {
+        self.bidirectional = bidirectional;
+        self
+    }
+
+    /// 결과 집합 컬럼 수집 (기본값 false, capture_responses가 true일 때만)
+    /// 서버 응답의 COLMETADATA에서 컬럼 이름과 타입을 읽어 응답을 기다리던 요청의 이벤트에 기록 (스키마 파악용)
+    pub fn result_columns(mut self, result_columns: bool) -> Self {
+        self.result_columns = result_columns;
+        self
+    }
+
+    /// 캡처 통계 공유 (설정하지 않으면 새로 생성, Extractor::stats로 조회)
+    pub fn stats(mut self, stats: Arc<CaptureStats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// 캡처 중 처리할 요청 수신기 (start_live_capture 루프에서 처리)
+    pub fn requests(mut self, requests: mpsc::Receiver<CaptureRequest>) -> Self {
+        self.requests = Some(requests);
+        self
+    }
+
+    /// 캡처 상태 알림 송신기 (장치 다시 열기 시도 등을 start_live_capture에서 전송)
+    pub fn status(mut self, status: mpsc::Sender<CaptureStatus>) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// 캡처 시간 (지나면 start_live_capture가 중지 신호 없이 끝남, None이면 중지할 때까지)
+    pub fn capture_duration(mut self, duration: Option<Duration>) -> Self {
+        self.capture_duration = duration;
+        self
+    }
+
+    /// pcap 파일 처리 진행 상황 송신기 (process_file에서 PROGRESS_INTERVAL 패킷마다 전송)
+    pub fn progress(mut self, progress: mpsc::Sender<FileProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn build(self) -> Extractor {
+        Extractor {
+            reassembler: TcpReassembler::new(),
+            server_ports: self.server_ports,
+            server_port_ranges: self.server_port_ranges,
+            client_port_ranges: self.client_port_ranges,
+            promisc: self.promisc,
+            snaplen: self.snaplen,
+            capture_responses: self.capture_responses,
+            client_allowlist: self.client_allowlist,
+            target_server: self.target_server,
+            link_type: self.link_type,
+            use_tds_parsing: self.use_tds_parsing,
+            diagnostics: self.diagnostics,
+            detect_encoding: self.detect_encoding,
+            rate_limiter: self
+                .rate_limit
+                .map(|(max_per_sec, per_flow)| RateLimiter::new(max_per_sec, per_flow)),
+            sampler: self.sample_every.map(Sampler::new),
+            bidirectional: self.bidirectional,
+            result_columns: self.result_columns,
+            stats: self.stats.unwrap_or_default(),
+            requests: self.requests,
+            status: self.status,
+            capture_duration: self.capture_duration,
+            progress: self.progress,
+            callbacks: Vec::new(),
+            last_packet_times: HashMap::new(),
+            stream_decoders: HashMap::new(),
+            reverse_decoders: HashMap::new(),
+            relayed_requests: VecDeque::new(),
+            response_decoders: HashMap::new(),
+            current_databases: HashMap::new(),
+            logins: HashMap::new(),
+            flow_generations: HashMap::new(),
+            pending_events: HashMap::new(),
+            last_flow_sweep: f64::MIN,
+        }
+    }
+}
+
+impl Default for ExtractorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// TDS 패킷 추출기
 /// TCP 스트림에서 TDS 프로토콜 패킷을 식별, 파싱, 재조립, 디코딩
 pub struct Extractor {
     reassembler: TcpReassembler,
+    server_ports: Vec<u16>,
+    server_port_ranges: Vec<(u16, u16)>,
+    client_port_ranges: Vec<(u16, u16)>,
+    promisc: bool,
+    snaplen: i32,
+    capture_responses: bool,
+    client_allowlist: Vec<IpAddr>,
+    target_server: Option<(IpAddr, u16)>,
+    link_type: Option<LinkType>,
+    use_tds_parsing: bool,
+    diagnostics: bool,
+    detect_encoding: bool,
+    rate_limiter: Option<RateLimiter>,
+    sampler: Option<Sampler>,
+    bidirectional: bool,
+    result_columns: bool,
+    stats: Arc<CaptureStats>,
+    requests: Option<mpsc::Receiver<CaptureRequest>>,
+    status: Option<mpsc::Sender<CaptureStatus>>,
+    capture_duration: Option<Duration>,
+    progress: Option<mpsc::Sender<FileProgress>>,
+    // 이벤트를 보낼 때마다 호출할 콜백 (on_event로 등록한 순서대로)
+    callbacks: Vec<EventCallback>,
+    // 플로우별 마지막 패킷 타임스탬프 (잘린 요청 이벤트의 시각, 유휴 플로우 정리)
+    last_packet_times: HashMap<FlowId, f64>,
+    // 플로우별 클라이언트 요청 스트림 디코더 / 서버 응답 디코더
+    stream_decoders: HashMap<FlowId, FlowDecoder>,
+    response_decoders: HashMap<FlowId, ResponseStreamDecoder>,
+    // 양방향 디코딩 모드에서 서버 포트 쪽 요청 스트림 디코더
+    reverse_decoders: HashMap<FlowId, FlowDecoder>,
+    // 양방향 디코딩 모드의 최근 요청 (패킷 타임스탬프, 플로우, SQL), 구간 간 중복 제거용
+    relayed_requests: VecDeque<(f64, FlowId, String)>,
+    // 플로우별 현재 데이터베이스 (서버 ENVCHANGE로 갱신)
+    current_databases: HashMap<FlowId, String>,
+    // 플로우별 로그인 정보 (LOGIN7의 애플리케이션 / 호스트 / 라이브러리 이름, 찾지 못하면 None으로 확정)
+    logins: HashMap<FlowId, Option<Login7>>,
+    // 플로우별로 마지막으로 처리한 연결 세대 (TcpReassembler::generation)
+    flow_generations: HashMap<FlowId, u32>,
+    // 서버 응답(DONE)을 기다리는 이벤트 (요청 메시지를 다 받은 패킷의 타임스탬프, 이벤트)
+    pending_events: HashMap<FlowId, VecDeque<(f64, SqlEvent)>>,
+    // 마지막으로 유휴 플로우를 찾은 패킷 타임스탬프
+    last_flow_sweep: f64,
 }
 
 impl Extractor {
-    pub fn new(_use_tds_parsing: bool) -> Self {
-        Self {
-            reassembler: TcpReassembler::new(),
-        }
+    /// 기본 설정으로 생성
+    /// use_tds_parsing이 false면 raw 모드 (ExtractorBuilder::tds_parsing 참고)
+    pub fn new(use_tds_parsing: bool) -> Self {
+        ExtractorBuilder::new().tds_parsing(use_tds_parsing).build()
+    }
+
+    /// 설정 빌더
+    pub fn builder() -> ExtractorBuilder {
+        ExtractorBuilder::new()
+    }
+
+    /// 이벤트마다 호출할 콜백 등록 (여러 개 등록 가능, 등록한 순서대로 호출)
+    /// 채널로 보내기 직전에 캡처 스레드에서 호출되므로 오래 걸리는 작업은 다른 스레드로 넘길 것
+    /// 샘플링 / 초당 제한으로 버린 이벤트에는 호출하지 않음
+    pub fn on_event(&mut self, callback: EventCallback) {
+        self.callbacks.push(callback);
+    }
+
+    /// 캡처 통계
+    pub fn stats(&self) -> Arc<CaptureStats> {
+        Arc::clone(&self.stats)
     }
 
     /// 네트워크 인터페이스 목록 가져오기
@@ -24,158 +739,102 @@ impl Extractor {
         Ok(devices
             .into_iter()
             .map(|d| {
-                let desc = d.desc.unwrap_or_else(|| "No description".to_string());
+                // Npcap 루프백 어댑터는 설명만으로 용도를 알기 어려우므로 명시
+                let is_loopback = d.flags.is_loopback() || d.name.ends_with(NPCAP_LOOPBACK_SUFFIX);
+                let desc = match (is_loopback, d.desc) {
+                    (true, Some(desc)) => format!("{} {}", LOOPBACK_LABEL, desc),
+                    (true, None) => LOOPBACK_LABEL.to_string(),
+                    (false, desc) => desc.unwrap_or_else(|| "No description".to_string()),
+                };
                 (d.name, desc)
             })
             .collect())
     }
 
+    /// 인터페이스를 캡처할 수 있는지 확인 (장치를 잠깐 열었다 닫음)
+    /// 권한이 없으면 캡처 스레드를 시작하기 전에 알 수 있음
+    pub fn can_capture(interface: &str) -> Result<(), ExtractorError> {
+        pcap::Capture::from_device(interface)?.timeout(1).open()?;
+        Ok(())
+    }
+
+    /// ============================================
+    /// 인터페이스 테스트
+    /// ============================================
+    /// 캡처와 같은 설정으로 장치를 열어 duration 동안 받은 패킷 수와 SQL Server 포트 패킷 수를 셈
+    /// (디코딩은 하지 않음, 인터페이스 / 미러링 설정 확인용)
+    pub fn test_interface(
+        &self,
+        interface: &str,
+        duration: Duration,
+    ) -> Result<InterfaceTestResult, Box<dyn std::error::Error>> {
+        let mut cap = self
+            .capture_options()
+            .apply(pcap::Capture::from_device(interface)?)
+            .open()?;
+        let link_type = self
+            .link_type
+            .or_else(|| LinkType::from_pcap(cap.get_datalink()))
+            .unwrap_or(LinkType::Ethernet);
+
+        let mut result = InterfaceTestResult::default();
+        let started = Instant::now();
+        while started.elapsed() < duration {
+            match cap.next_packet() {
+                Ok(packet) => {
+                    result.total_packets += 1;
+                    let is_sql = Self::parse_packet_all(packet.data, link_type, 0.0)
+                        .is_some_and(|(flow_id, ..)| self.is_sql_flow(&flow_id));
+                    if is_sql {
+                        result.sql_packets += 1;
+                    }
+                }
+                Err(pcap::Error::TimeoutExpired) => continue,
+                Err(e) => return Err(Box::new(e)),
+            }
+        }
+
+        Ok(result)
+    }
+
     /// ============================================
     /// 실시간 네트워크 캡처 및 TDS 패킷 처리
     /// ============================================
-    /// 단계별 처리:
-    /// 1. 패킷 캡처 (Ethernet/IP/TCP 파싱)
-    /// 2. TDS 패킷 식별
-    /// 3. TCP 스트림 재조립
-    /// 4. TDS 데이터 디코딩
+    /// 캡처한 패킷을 process_raw_frame으로 처리하고 완성된 이벤트를 전송
+    /// 캡처 중 회복 가능한 장치 오류가 나면 대기 시간을 늘려가며 장치를 다시 열어 이어서 캡처
+    /// 캡처 시간을 설정했으면 그 시간이 지날 때 중지 신호를 받은 것처럼 끝냄 (CaptureStatus::DurationElapsed 전송)
     pub fn start_live_capture(
         &mut self,
         interface: &str,
         sender: mpsc::Sender<SqlEvent>,
         stop_rx: mpsc::Receiver<()>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let mut cap = pcap::Capture::from_device(interface)?
-            .promisc(true)
-            .snaplen(65535) // 전체 패킷 캡처
-            .timeout(100) // 100ms 타임아웃으로 중지 신호를 자주 확인
-            .open()?;
+        let mut cap = self.open_live(interface)?;
 
-        let mut flow_timestamps: std::collections::HashMap<FlowId, f64> =
-            std::collections::HashMap::new();
+        // 링크 타입이 지정되지 않았으면 캡처 장치에서 감지 (알 수 없으면 Ethernet)
+        if self.link_type.is_none() {
+            self.link_type =
+                Some(LinkType::from_pcap(cap.get_datalink()).unwrap_or(LinkType::Ethernet));
+        }
 
+        let started = Instant::now();
         loop {
-            // 중지 신호 확인
-            if stop_rx.try_recv().is_ok() {
+            if self.capture_finished(started, &stop_rx, &sender) {
                 break;
             }
 
+            // 요청 처리 (스트림 덤프 등)
+            while let Some(request) = self.requests.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                self.handle_request(request);
+            }
+
             match cap.next_packet() {
                 Ok(packet) => {
                     let timestamp = packet.header.ts.tv_sec as f64
                         + (packet.header.ts.tv_usec as f64 / 1_000_000.0);
 
-                    // ============================================
-                    // 1단계: 패킷 파싱 (Ethernet + IP + TCP)
-                    // ============================================
-                    if let Some((
-                        flow_id,
-                        seq,
-                        data,
-                        is_client,
-                        actual_src_ip,
-                        actual_src_port,
-                        actual_dst_ip,
-                        actual_dst_port,
-                    )) = Self::parse_packet_all(packet.data, timestamp)
-                    {
-                        // 첫 번째 패킷의 타임스탬프 저장
-                        flow_timestamps.entry(flow_id.clone()).or_insert(timestamp);
-
-                        // ============================================
-                        // 2단계: SQL Server 포트 필터링
-                        // ============================================
-                        // TCP 세그먼트가 쪼개져 있을 수 있으므로 재조립 전에 TDS 체크하지 않음
-                        // 대신 포트 기반으로 필터링 (SQL Server 기본 포트: 1433)
-                        // NOTE: 추가적으로 port 설정을 하고 있다면 추가해야할 수도 있음
-                        let sql_server_ports = [1433, 1434, 1436]; // 1434는 SQL Browser
-                        let is_sql_server_port = sql_server_ports.contains(&flow_id.src_port)
-                            || sql_server_ports.contains(&flow_id.dst_port);
-
-                        if !is_sql_server_port {
-                            continue;
-                        }
-
-                        // ============================================
-                        // 3단계: TCP 스트림 재조립
-                        // ============================================
-                        // TCP 시퀀스 번호를 기준으로 패킷 재조립
-                        // 페이로드가 비어있지 않은 경우에만 재조립
-                        if !data.is_empty() {
-                            self.reassembler.add_packet(
-                                flow_id.clone(),
-                                if is_client {
-                                    flow_id.src_ip
-                                } else {
-                                    flow_id.dst_ip
-                                },
-                                if is_client {
-                                    flow_id.src_port
-                                } else {
-                                    flow_id.dst_port
-                                },
-                                seq,
-                                data,
-                                timestamp,
-                            );
-                        }
-
-                        // ============================================
-                        // 4단계: 재조립된 스트림에서 TDS 데이터 디코딩
-                        // ============================================
-
-                        // NOTE: Dentweb SQL Batch only exists at client to server flow
-                        if is_client {
-                            if let Some(client_data) = self.reassembler.get_client_data(&flow_id) {
-                                // TDS 패킷인지 먼저 확인
-                                if TdsParser::looks_like_tds(&client_data) {
-                                    // 여러 TDS 패킷이 연속으로 붙어있을 수 있으므로 프레이밍 루프로 처리
-                                    let (decoded_texts, raw_packets) =
-                                        TdsParser::decode_tds_packets_with_raw(&client_data);
-
-                                    for (decoded_text, raw_data) in
-                                        decoded_texts.into_iter().zip(raw_packets.into_iter())
-                                    {
-                                        // 빈 텍스트나 너무 짧은 텍스트는 건너뛰기
-                                        let trimmed = decoded_text.trim();
-                                        if trimmed.len() < 3 {
-                                            continue;
-                                        }
-
-                                        let timestamp_sec =
-                                            flow_timestamps.get(&flow_id).copied().unwrap_or(0.0);
-                                        let timestamp = chrono::DateTime::from_timestamp(
-                                            timestamp_sec as i64,
-                                            ((timestamp_sec - timestamp_sec.floor())
-                                                * 1_000_000_000.0)
-                                                as u32,
-                                        )
-                                        .unwrap_or_default();
-
-                                        // 실제 패킷 정보
-                                        let event = SqlEvent {
-                                            timestamp,
-                                            flow_id: format!(
-                                                "{}:{}->{}:{}",
-                                                actual_src_ip,
-                                                actual_src_port,
-                                                actual_dst_ip,
-                                                actual_dst_port
-                                            ),
-                                            sql_text: trimmed.to_string(),
-                                            tables: Vec::new(),
-                                            operation: "TDS".to_string(),
-                                            label: None,
-                                            raw_data: Some(raw_data),
-                                        };
-
-                                        // 실시간으로 이벤트 전송
-                                        if sender.send(event).is_err() {
-                                            break; // 수신자가 없으면 종료
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                    if !self.process_raw_frame(packet.data, timestamp, &sender) {
+                        return Ok(()); // 수신자가 없으면 종료
                     }
                 }
                 Err(pcap::Error::TimeoutExpired) => {
@@ -183,7 +842,18 @@ impl Extractor {
                     continue;
                 }
                 Err(e) => {
-                    return Err(Box::new(e));
+                    let error = ExtractorError::from(e);
+                    if !error.is_recoverable() {
+                        return Err(Box::new(error));
+                    }
+                    match self.reconnect(interface, error, &stop_rx)? {
+                        Some(reopened) => cap = reopened,
+                        None => {
+                            // 다시 여는 중에 중지 신호를 받음
+                            self.finish_events(&sender);
+                            break;
+                        }
+                    }
                 }
             }
         }
@@ -192,88 +862,2033 @@ impl Extractor {
     }
 
     /// ============================================
-    /// 패킷 파싱: Ethernet + IP + TCP
+    /// pcap 파일 처리
     /// ============================================
-    /// 모든 TCP 패킷을 처리 (TDS 필터링 없음)
-    /// 반환값: (FlowId, 시퀀스 번호, 페이로드, 클라이언트→서버 여부, 실제 src_ip, 실제 src_port, 실제 dst_ip, 실제 dst_port)
-    #[allow(clippy::type_complexity)]
-    fn parse_packet_all(
-        data: &[u8],
-        _timestamp: f64,
-    ) -> Option<(FlowId, u32, Vec<u8>, bool, IpAddr, u16, IpAddr, u16)> {
-        // Ethernet 헤더 (14 bytes) 건너뛰기
-        if data.len() < 14 {
-            return None;
-        }
+    /// 저장된 pcap / pcapng 파일의 패킷을 실시간 캡처와 같은 과정으로 처리해 이벤트 전송
+    /// 진행 상황은 ExtractorBuilder::progress로 PROGRESS_INTERVAL 패킷마다, 끝날 때 한 번 더 전송
+    /// 중지 신호를 받으면 그때까지 처리한 부분만으로 끝냄 (응답을 기다리던 이벤트도 전송)
+    pub fn process_file(
+        &mut self,
+        path: &Path,
+        sender: mpsc::Sender<SqlEvent>,
+        stop_rx: mpsc::Receiver<()>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let total_bytes = std::fs::metadata(path)?.len();
+        let mut cap = pcap::Capture::from_file(path).map_err(ExtractorError::from)?;
 
-        let ip_start = 14;
-        if data.len() < ip_start + 20 {
-            return None;
+        // 링크 타입이 지정되지 않았으면 파일 헤더에서 감지 (알 수 없으면 Ethernet)
+        if self.link_type.is_none() {
+            self.link_type =
+                Some(LinkType::from_pcap(cap.get_datalink()).unwrap_or(LinkType::Ethernet));
         }
 
-        // IP 헤더 확인
-        let version = (data[ip_start] >> 4) & 0x0F;
-        if version != 4 {
-            return None; // IPv4만 지원
+        let mut progress = FileProgress {
+            bytes: PCAP_GLOBAL_HEADER_LEN.min(total_bytes),
+            total_bytes,
+            packets: 0,
+        };
+        loop {
+            if stop_rx.try_recv().is_ok() {
+                tracing::info!("pcap 파일 처리 취소 ({}개 패킷 처리)", progress.packets);
+                break;
+            }
+
+            // 요청 처리 (스트림 덤프 등)
+            while let Some(request) = self.requests.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                self.handle_request(request);
+            }
+
+            match cap.next_packet() {
+                Ok(packet) => {
+                    let timestamp = packet.header.ts.tv_sec as f64
+                        + (packet.header.ts.tv_usec as f64 / 1_000_000.0);
+                    progress.bytes += PCAP_RECORD_HEADER_LEN + u64::from(packet.header.caplen);
+                    progress.packets += 1;
+
+                    if !self.process_raw_frame(packet.data, timestamp, &sender) {
+                        return Ok(()); // 수신자가 없으면 종료
+                    }
+                    if progress.packets.is_multiple_of(PROGRESS_INTERVAL) {
+                        self.report_progress(progress);
+                    }
+                }
+                Err(pcap::Error::NoMorePackets) => {
+                    progress.bytes = total_bytes;
+                    break;
+                }
+                Err(e) => return Err(Box::new(ExtractorError::from(e))),
+            }
         }
 
-        // IP 헤더 길이 계산 (IHL * 4)
-        let ip_header_len = ((data[ip_start] & 0x0F) * 4) as usize;
-        let protocol = data[ip_start + 9];
+        // 응답을 받지 못한 이벤트도 모두 전송
+        self.finish_events(&sender);
+        self.report_progress(progress);
+        Ok(())
+    }
 
-        if protocol != 6 {
-            return None; // TCP만 처리
+    /// 파일 처리 진행 상황 전송 (받는 쪽이 없으면 무시)
+    fn report_progress(&self, progress: FileProgress) {
+        if let Some(ref sender) = self.progress {
+            let _ = sender.send(progress);
         }
+    }
 
-        // IP 주소 추출
-        let src_ip = IpAddr::V4(std::net::Ipv4Addr::new(
-            data[ip_start + 12],
-            data[ip_start + 13],
-            data[ip_start + 14],
-            data[ip_start + 15],
-        ));
-        let dst_ip = IpAddr::V4(std::net::Ipv4Addr::new(
-            data[ip_start + 16],
-            data[ip_start + 17],
-            data[ip_start + 18],
-            data[ip_start + 19],
-        ));
+    /// 실시간 캡처용으로 장치 열기
+    fn open_live(&self, interface: &str) -> Result<pcap::Capture<pcap::Active>, ExtractorError> {
+        Ok(self
+            .capture_options()
+            .apply(pcap::Capture::from_device(interface)?)
+            .open()?)
+    }
 
-        // TCP 헤더 파싱
-        let tcp_start = ip_start + ip_header_len;
-        if data.len() < tcp_start + 20 {
-            return None;
+    /// 장치를 열 때 적용할 설정 (100ms 타임아웃으로 중지 신호를 자주 확인)
+    fn capture_options(&self) -> CaptureOptions {
+        CaptureOptions {
+            promisc: self.promisc,
+            snaplen: self.snaplen,
+            timeout_ms: 100,
         }
+    }
 
-        let src_port = u16::from_be_bytes([data[tcp_start], data[tcp_start + 1]]);
-        let dst_port = u16::from_be_bytes([data[tcp_start + 2], data[tcp_start + 3]]);
+    /// 장치 오류 후 장치를 다시 열기 (최대 MAX_RECONNECT_ATTEMPTS번, 시도마다 대기 시간 두 배)
+    /// 재조립 중인 플로우 상태는 그대로 두고 이어서 캡처
+    /// 반환값: 다시 연 장치, 기다리는 중에 중지 신호를 받으면 None
+    fn reconnect(
+        &self,
+        interface: &str,
+        mut error: ExtractorError,
+        stop_rx: &mpsc::Receiver<()>,
+    ) -> Result<Option<pcap::Capture<pcap::Active>>, ExtractorError> {
+        let mut delay = RECONNECT_DELAY;
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            tracing::warn!(
+                "캡처 장치 오류, 다시 여는 중 ({}/{}): {}",
+                attempt,
+                MAX_RECONNECT_ATTEMPTS,
+                error
+            );
+            self.report_status(CaptureStatus::Reconnecting {
+                attempt,
+                max_attempts: MAX_RECONNECT_ATTEMPTS,
+                error: error.to_string(),
+            });
 
-        // TCP 시퀀스 번호 추출
-        let seq = u32::from_be_bytes([
-            data[tcp_start + 4],
-            data[tcp_start + 5],
+            // 대기 중에도 중지 신호에 바로 반응
+            match stop_rx.recv_timeout(delay) {
+                Ok(()) | Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(None),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+            }
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+
+            match self.open_live(interface) {
+                Ok(cap) => {
+                    tracing::info!("캡처 장치를 다시 열었음: {}", interface);
+                    self.report_status(CaptureStatus::Reconnected);
+                    return Ok(Some(cap));
+                }
+                // 권한 문제는 다시 시도해도 같음
+                Err(e @ ExtractorError::PermissionDenied(_)) => return Err(e),
+                Err(e) => error = e,
+            }
+        }
+
+        Err(error)
+    }
+
+    /// 중지 신호를 받았거나 캡처 시간(capture_duration)이 지났는지 확인
+    /// 끝낼 때는 응답을 받지 못한 이벤트도 모두 전송하고, 캡처 시간이 지난 경우 DurationElapsed 알림
+    /// 반환값: 캡처 루프를 끝내야 하면 true
+    fn capture_finished(
+        &mut self,
+        started: Instant,
+        stop_rx: &mpsc::Receiver<()>,
+        sender: &mpsc::Sender<SqlEvent>,
+    ) -> bool {
+        let stopped = stop_rx.try_recv().is_ok();
+        let elapsed = !stopped
+            && self
+                .capture_duration
+                .is_some_and(|duration| started.elapsed() >= duration);
+        if !stopped && !elapsed {
+            return false;
+        }
+
+        self.finish_events(sender);
+        if elapsed {
+            tracing::info!("캡처 시간이 지나 캡처 종료");
+            self.report_status(CaptureStatus::DurationElapsed);
+        }
+        true
+    }
+
+    /// 캡처를 끝낼 때 호출
+    /// 응답을 받지 못한 이벤트를 모두 전송하고, 마지막 구간에 제한으로 버린 이벤트 요약 기록
+    fn finish_events(&mut self, sender: &mpsc::Sender<SqlEvent>) {
+        for event in self.take_pending_events() {
+            self.emit(event, sender);
+        }
+        if let Some(limiter) = self.rate_limiter.as_mut() {
+            limiter.flush_summary();
+        }
+    }
+
+    /// 캡처 상태 알림 전송 (받는 쪽이 없으면 무시)
+    fn report_status(&self, status: CaptureStatus) {
+        if let Some(ref sender) = self.status {
+            let _ = sender.send(status);
+        }
+    }
+
+    /// 캡처한 프레임 하나를 처리하고 완성된 이벤트 중 샘플링 / 초당 제한을 통과한 것을 전송
+    /// start_live_capture가 패킷마다 호출하며, pcap 없이 프레임을 직접 넣어
+    /// 파싱 → 재조립 → 디코딩 → 전송 전체 과정을 확인할 때도 사용
+    /// 반환값: 이벤트 수신자가 없으면 false (캡처를 끝내야 함)
+    pub fn process_raw_frame(
+        &mut self,
+        data: &[u8],
+        timestamp: f64,
+        sender: &mpsc::Sender<SqlEvent>,
+    ) -> bool {
+        for event in self.process_frame_guarded(data, timestamp) {
+            if !self.admit(&event, timestamp) {
+                continue;
+            }
+            if !self.emit(event, sender) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 등록한 콜백을 호출한 뒤 이벤트 전송
+    /// 반환값: 이벤트 수신자가 없으면 false
+    fn emit(&mut self, event: SqlEvent, sender: &mpsc::Sender<SqlEvent>) -> bool {
+        for callback in &mut self.callbacks {
+            callback(&event);
+        }
+        sender.send(event).is_ok()
+    }
+
+    /// 샘플링과 초당 이벤트 제한을 통과해 보낼 이벤트인지 (버리는 이벤트는 개수만 집계)
+    fn admit(&mut self, event: &SqlEvent, timestamp: f64) -> bool {
+        if let Some(ref mut sampler) = self.sampler {
+            if !sampler.keep() {
+                self.stats.record_sampled_out_event();
+                return false;
+            }
+        }
+        if let Some(ref mut limiter) = self.rate_limiter {
+            if !limiter.allow(event, timestamp) {
+                self.stats.record_rate_limited_event();
+                return false;
+            }
+        }
+        true
+    }
+
+    /// extract_frame과 같지만 처리 중 panic이 나면 그 패킷만 건너뜀
+    /// 잘못된 형식의 패킷 하나 때문에 캡처 스레드가 죽지 않도록 함 (통계의 parse_panics 증가)
+    pub fn process_frame_guarded(&mut self, frame: &[u8], timestamp: f64) -> Vec<SqlEvent> {
+        match panic::catch_unwind(AssertUnwindSafe(|| self.extract_frame(frame, timestamp))) {
+            Ok(events) => events,
+            Err(_) => {
+                self.stats.record_parse_panic();
+                tracing::error!("패킷 처리 중 panic, 패킷을 건너뜀:\n{}", hex_dump(frame));
+                Vec::new()
+            }
+        }
+    }
+
+    /// 테스트용 진입점: 캡처 장치 없이 프레임 하나로 파싱 → 재조립 → 디코딩 전체 과정을 실행
+    /// 샘플링 / 초당 제한과 panic 처리 없이 이 프레임으로 완성된 이벤트를 그대로 반환
+    #[cfg(test)]
+    pub fn process_frame(&mut self, frame: &[u8], timestamp: f64) -> Vec<SqlEvent> {
+        self.extract_frame(frame, timestamp)
+    }
+
+    /// 캡처 스레드 요청 처리
+    pub fn handle_request(&self, request: CaptureRequest) {
+        match request {
+            CaptureRequest::ClientStream { flow_id, reply } => {
+                let _ = reply.send(self.client_stream(&flow_id));
+            }
+            CaptureRequest::FlowStats { reply } => {
+                let _ = reply.send(self.reassembler.flow_stats());
+            }
+        }
+    }
+
+    /// 플로우의 재조립된 클라이언트 → 서버 스트림 중 아직 디코딩하지 않은 부분
+    /// (완전하지 않은 패킷, EOM을 기다리는 메시지, TDS 요청으로 시작하지 않아 디코딩하지 못한 바이트)
+    pub fn client_stream(&self, flow_id: &FlowId) -> Option<Vec<u8>> {
+        self.reassembler.get_client_data(flow_id)
+    }
+
+    /// 플로우의 연결별 상태 초기화 (같은 4-tuple로 새 연결이 시작된 경우)
+    /// 이전 연결에서 응답을 기다리던 이벤트는 바로 반환
+    fn reset_flow(&mut self, flow_id: &FlowId) -> Vec<SqlEvent> {
+        self.stream_decoders.remove(flow_id);
+        self.response_decoders.remove(flow_id);
+        self.reverse_decoders.remove(flow_id);
+        self.current_databases.remove(flow_id);
+        self.logins.remove(flow_id);
+        self.pending_events
+            .remove(flow_id)
+            .map(|queue| queue.into_iter().map(|(_, event)| event).collect())
+            .unwrap_or_default()
+    }
+
+    /// 서버 응답을 기다리는 이벤트를 모두 꺼내기 (캡처 종료 시)
+    /// 나머지 바이트를 받지 못한 채 스트림 끝에 남은 패킷도 잘린 이벤트로 포함
+    pub fn take_pending_events(&mut self) -> Vec<SqlEvent> {
+        // 응답 스트림에 빈틈이 있으면 응답을 끝까지 받지 못한 것이므로 손실 표시
+        let reassembler = &self.reassembler;
+        let mut events: Vec<SqlEvent> = self
+            .pending_events
+            .drain()
+            .flat_map(|(flow_id, queue)| {
+                let server_gap = reassembler
+                    .gap_offset(&flow_id, Direction::ServerToClient)
+                    .is_some();
+                queue.into_iter().map(move |(_, mut event)| {
+                    event.packet_loss |= server_gap;
+                    event
+                })
+            })
+            .collect();
+        for flow_id in self.reassembler.get_flows() {
+            events.extend(self.take_truncated_events(&flow_id));
+        }
+        events
+    }
+
+    /// 클라이언트 스트림 끝의 완전하지 않은 요청 패킷을 잘린 이벤트로 만듦 (MARS 연결은 세션마다 하나씩)
+    /// 연결이 끝났거나 캡처를 중지해 나머지 바이트가 더 오지 않을 때만 호출
+    /// (디코더에 남은 바이트를 꺼내므로 다시 호출하면 빈 목록)
+    fn take_truncated_events(&mut self, flow_id: &FlowId) -> Vec<SqlEvent> {
+        if !self.use_tds_parsing {
+            return Vec::new();
+        }
+        let Some(decoder) = self.stream_decoders.get_mut(flow_id) else {
+            return Vec::new();
+        };
+
+        let timestamp = self.last_packet_times.get(flow_id).copied().unwrap_or(0.0);
+        let mut events = Vec::new();
+        for (decoded_text, raw_data) in decoder.finish() {
+            if decoded_text.is_none() && !self.diagnostics {
+                continue;
+            }
+            let trimmed = decoded_text.as_ref().map(|(text, _)| text.trim());
+            if trimmed.is_some_and(TdsParser::is_too_short) {
+                continue;
+            }
+
+            tracing::debug!(
+                flow = %flow_id,
+                packet_type = raw_data.first().copied().unwrap_or_default(),
+                len = raw_data.len(),
+                decode_result = if trimmed.is_some() { "decoded" } else { "undecoded" },
+                "truncated request packet"
+            );
+            let mut event = self.request_event(
+                flow_id,
+                timestamp,
+                trimmed,
+                decoded_text.as_ref().map(|(_, encoding)| *encoding),
+                raw_data,
+            );
+            event.truncated = true;
+            events.push(event);
+        }
+        events
+    }
+
+    /// 끝난 연결의 상태를 재조립기와 플로우별 맵에서 모두 정리 (FIN / RST, 유휴 플로우)
+    /// 반환값: 응답을 기다리던 이벤트와 끝에 잘린 요청 이벤트
+    fn evict_flow(&mut self, flow_id: &FlowId) -> Vec<SqlEvent> {
+        let truncated = self.take_truncated_events(flow_id);
+        let mut events = self.reset_flow(flow_id);
+        events.extend(truncated);
+
+        self.reassembler.remove(flow_id);
+        self.flow_generations.remove(flow_id);
+        self.last_packet_times.remove(flow_id);
+        events
+    }
+
+    /// FLOW_IDLE_TIMEOUT 동안 패킷이 없는 플로우 정리 (FLOW_SWEEP_INTERVAL마다 한 번)
+    fn evict_idle_flows(&mut self, timestamp: f64) -> Vec<SqlEvent> {
+        if timestamp - self.last_flow_sweep < FLOW_SWEEP_INTERVAL {
+            return Vec::new();
+        }
+        self.last_flow_sweep = timestamp;
+
+        let idle: Vec<FlowId> = self
+            .last_packet_times
+            .iter()
+            .filter(|(_, &last)| timestamp - last > FLOW_IDLE_TIMEOUT)
+            .map(|(flow_id, _)| flow_id.clone())
+            .collect();
+        let mut events = Vec::new();
+        for flow_id in idle {
+            tracing::debug!(flow = %flow_id, "idle flow evicted");
+            events.extend(self.evict_flow(&flow_id));
+        }
+        events
+    }
+
+    /// 클라이언트 요청 패킷 하나의 이벤트 (text가 None이면 디코딩하지 못한 패킷)
+    /// timestamp_sec: 요청의 마지막 패킷(EOM)을 받은 시각 (연결이 재사용되어도 요청마다 다름)
+    fn request_event(
+        &self,
+        flow_id: &FlowId,
+        timestamp_sec: f64,
+        text: Option<&str>,
+        encoding: Option<TextEncoding>,
+        raw_data: Vec<u8>,
+    ) -> SqlEvent {
+        let timestamp = chrono::DateTime::from_timestamp(
+            timestamp_sec as i64,
+            ((timestamp_sec - timestamp_sec.floor()) * 1_000_000_000.0) as u32,
+        )
+        .unwrap_or_default();
+        let login = self.logins.get(flow_id).and_then(Option::as_ref);
+        let rpc_calls = if raw_data.first() == Some(&0x03) && self.use_tds_parsing {
+            TdsParser::rpc_calls(&raw_data)
+        } else {
+            Vec::new()
+        };
+        let (sql_text, params) = split_rpc_params(text, &rpc_calls);
+        let label = text
+            .filter(|_| self.use_tds_parsing)
+            .map(|_| event_label(&sql_text, &rpc_calls));
+
+        SqlEvent {
+            timestamp,
+            flow_id: flow_id.to_string(),
+            sql_text,
+            params,
+            tables: Vec::new(),
+            operation: if text.is_none() {
+                UNDECODED_OPERATION.to_string()
+            } else if self.use_tds_parsing {
+                "TDS".to_string()
+            } else {
+                "RAW".to_string()
+            },
+            label,
+            rpc_flags: TdsParser::rpc_option_flags(&raw_data),
+            reset_connection: TdsParser::parse_header(&raw_data)
+                .is_some_and(|header| header.is_session_reset()),
+            raw_data: Some(raw_data),
+            result_sets: Vec::new(),
+            result_columns: Vec::new(),
+            database: self.current_databases.get(flow_id).cloned(),
+            app_name: login_field(login, |login| &login.app_name),
+            client_host: login_field(login, |login| &login.host_name),
+            client_library: login_field(login, |login| &login.library_name),
+            encoding,
+            latency_ms: None,
+            truncated: false,
+            packet_loss: self
+                .reassembler
+                .gap_offset(flow_id, Direction::ClientToServer)
+                .is_some(),
+        }
+    }
+
+    /// ============================================
+    /// 프레임 하나 처리
+    /// ============================================
+    /// 단계별 처리:
+    /// 1. 패킷 파싱 (링크 헤더/IP/TCP)
+    /// 2. SQL Server 포트 필터링
+    /// 3. TCP 스트림 재조립
+    /// 4. TDS 데이터 디코딩
+    /// 5. 서버 응답 토큰 처리
+    ///
+    /// 반환값: 이 프레임으로 완성된 이벤트들 (서버 응답을 기다리는 이벤트는 제외)
+    /// 연결이 끝났거나 오랫동안 패킷이 없는 플로우는 정리하면서 남은 이벤트도 함께 반환
+    fn extract_frame(&mut self, frame: &[u8], timestamp: f64) -> Vec<SqlEvent> {
+        let mut events = self.evict_idle_flows(timestamp);
+        let link_type = self.link_type.unwrap_or(LinkType::Ethernet);
+
+        // ============================================
+        // 1단계: 패킷 파싱 (Ethernet + IP + TCP)
+        // ============================================
+        let Some((flow_id, seq, data, tcp_flags, ..)) =
+            Self::parse_packet_all(frame, link_type, timestamp)
+        else {
+            return events;
+        };
+
+        // ============================================
+        // 2단계: SQL Server 포트 필터링
+        // ============================================
+        // TCP 세그먼트가 쪼개져 있을 수 있으므로 재조립 전에 TDS 체크하지 않음
+        // 대신 포트 기반으로 필터링 (SQL Server 기본 포트: 1433)
+        // NOTE: 다른 포트를 사용하는 경우 ExtractorBuilder::ports / port_ranges로 설정
+        // 서버 인스턴스 하나만 볼 때는 ExtractorBuilder::target_server (IP와 포트가 모두 같은 쪽만)
+        if !self.is_sql_flow(&flow_id) {
+            return events;
+        }
+
+        // 서버 → 클라이언트 패킷은 클라이언트 → 서버 기준의 FlowId로 정규화
+        // (양방향 세그먼트를 하나의 TcpStream에 모으기 위함)
+        // 방향은 포트로만 판단하므로 캡처를 연결 도중에 시작해 서버 패킷을 먼저 받아도 같음
+        let from_server = self.is_from_server(&flow_id);
+        let flow_id = if from_server {
+            flow_id.reversed()
+        } else {
+            flow_id
+        };
+        let is_client = !from_server;
+
+        // 클라이언트 허용 목록이 있으면 해당 클라이언트의 플로우만 처리
+        if !self.client_allowlist.is_empty() && !self.client_allowlist.contains(&flow_id.src_ip) {
+            return events;
+        }
+
+        // 마지막 패킷의 타임스탬프 저장
+        self.last_packet_times.insert(flow_id.clone(), timestamp);
+
+        // ============================================
+        // 3단계: TCP 스트림 재조립
+        // ============================================
+        // TCP 시퀀스 번호를 기준으로 패킷 재조립
+        let direction = if is_client {
+            Direction::ClientToServer
+        } else {
+            Direction::ServerToClient
+        };
+        if tcp_flags & TCP_SYN != 0 {
+            self.reassembler.syn(&flow_id, direction, seq);
+        }
+
+        // 페이로드가 비어있지 않은 경우에만 재조립 (중복 패킷이면 None)
+        let new_bytes = if data.is_empty() {
+            Some(Vec::new())
+        } else {
+            self.reassembler.add_packet(
+                flow_id.clone(),
+                if is_client {
+                    flow_id.src_ip
+                } else {
+                    flow_id.dst_ip
+                },
+                if is_client {
+                    flow_id.src_port
+                } else {
+                    flow_id.dst_port
+                },
+                seq,
+                data,
+                timestamp,
+            )
+        };
+
+        // 같은 4-tuple로 새 연결이 시작되었으면 이전 연결의 디코딩 상태 정리
+        let generation = self.reassembler.generation(&flow_id);
+        if self
+            .flow_generations
+            .insert(flow_id.clone(), generation)
+            .is_some_and(|previous| previous != generation)
+        {
+            events.extend(self.reset_flow(&flow_id));
+        }
+
+        // 중복 패킷이면 새 데이터가 없으므로 디코딩 생략
+        let Some(new_bytes) = new_bytes else {
+            self.stats
+                .set_duplicate_packets(self.reassembler.duplicate_count());
+            return events;
+        };
+
+        events.extend(self.decode_segment(&flow_id, is_client, &new_bytes, timestamp));
+
+        // 연결이 끝나면 나머지 바이트를 기다리던 마지막 요청 패킷도 있는 만큼 디코딩하고 플로우 상태를 모두 정리
+        // (이후 같은 4-tuple의 패킷은 새 플로우로 시작)
+        if tcp_flags & (TCP_FIN | TCP_RST) != 0 {
+            events.extend(self.evict_flow(&flow_id));
+        }
+
+        events
+    }
+
+    /// 재조립기가 이번 세그먼트로 새로 이어 붙인 바이트 디코딩 (extract_frame의 4, 5단계)
+    /// 디코딩을 마친 바이트는 재조립 버퍼에서 버림
+    fn decode_segment(
+        &mut self,
+        flow_id: &FlowId,
+        is_client: bool,
+        new_bytes: &[u8],
+        timestamp: f64,
+    ) -> Vec<SqlEvent> {
+        let mut events = Vec::new();
+
+        // ============================================
+        // 4단계: 재조립된 스트림에서 TDS 데이터 디코딩
+        // ============================================
+
+        // NOTE: SQL Batch / RPC only exist in the client to server direction
+        // (the side that is not a SQL Server port, see is_from_server)
+        if is_client {
+            let _span = tracing::debug_span!("decode", flow = %flow_id).entered();
+            let Some(stream) = self.reassembler.client_data(flow_id) else {
+                return events;
+            };
+
+            // 연결 시작의 LOGIN7에서 애플리케이션 / 호스트 / 라이브러리 이름 기록 (연결당 한 번)
+            // 스트림 앞부분에서 찾지 못하면 (연결 도중부터 캡처, 암호화된 로그인) 더 찾지 않음
+            if !self.logins.contains_key(flow_id) {
+                let head = &stream[..stream.len().min(LOGIN_SCAN_LIMIT)];
+                if let Some(login) = TdsParser::find_login7(head) {
+                    self.logins.insert(flow_id.clone(), Some(login));
+                } else if stream.len() >= LOGIN_SCAN_LIMIT {
+                    self.logins.insert(flow_id.clone(), None);
+                }
+            }
+
+            // 처음에는 스트림이 TDS 요청으로 시작하는지 확인하고 디코더 생성
+            // 연결 시작부터 캡처했으면 로그인 단계 패킷 이후부터 처리 (raw 모드는 모든 패킷 표시)
+            // MARS 연결은 로그인 이후 TDS 패킷 앞에 SMUX 헤더가 붙으므로 세션별 디코더 사용
+            // 디코더가 생긴 뒤에는 새로 이어진 바이트만 넘겨서 이번에 완성된 메시지만 받음
+            let messages = match self.stream_decoders.get_mut(flow_id) {
+                Some(decoder) => decoder.feed(new_bytes),
+                None => {
+                    let start = self
+                        .reassembler
+                        .discarded(flow_id, Direction::ClientToServer);
+                    let (mut decoder, request_data) = if self.use_tds_parsing {
+                        let client_data = TdsParser::skip_login_packets(stream);
+                        let start = start + stream.len() - client_data.len();
+                        if smux::looks_like_smux(client_data) {
+                            tracing::debug!("MARS (SMUX) 연결");
+                            let decoder = SmuxStreamDecoder::new(self.detect_encoding);
+                            (FlowDecoder::mars(decoder, start), client_data)
+                        } else if TdsParser::looks_like_tds(client_data) {
+                            let decoder = TdsStreamDecoder::new(self.detect_encoding);
+                            (FlowDecoder::new(decoder, start), client_data)
+                        } else {
+                            // TDS 요청으로 시작하지 않는 스트림 (암호화된 연결, 패킷 중간부터 캡처 등)
+                            // 로그인 정보를 확정한 뒤에는 LOGIN_SCAN_LIMIT까지만 보관하고 버림
+                            // (이후 세그먼트가 패킷 경계에서 시작하면 그때부터 디코딩)
+                            if self.logins.contains_key(flow_id) && stream.len() >= LOGIN_SCAN_LIMIT
+                            {
+                                self.reassembler.discard(flow_id, Direction::ClientToServer);
+                            }
+                            return events;
+                        }
+                    } else {
+                        (FlowDecoder::new(TdsStreamDecoder::raw(), start), stream)
+                    };
+                    // 요청 패킷이 나왔으면 로그인 단계는 끝났으므로 LOGIN7을 더 찾지 않음
+                    if self.use_tds_parsing {
+                        self.logins.entry(flow_id.clone()).or_insert(None);
+                    }
+                    let messages = decoder.feed(request_data);
+                    self.stream_decoders.insert(flow_id.clone(), decoder);
+                    messages
+                }
+            };
+            let Some(decoder) = self.stream_decoders.get(flow_id) else {
+                return events;
+            };
+            let is_mars = decoder.is_mars();
+
+            // 디코딩을 마친 바이트는 재조립 버퍼에서 버림
+            // 로그인 정보를 찾는 동안은 스트림 앞부분이 필요하므로 남겨 둠
+            if self.logins.contains_key(flow_id) {
+                self.reassembler
+                    .consume(flow_id, Direction::ClientToServer, decoder.consumed());
+            }
+
+            // (디코딩한 텍스트, 인코딩) 목록과 원본 패킷 목록
+            let (decoded_texts, raw_packets): (Vec<_>, Vec<_>) = if self.use_tds_parsing {
+                // 진단 모드가 아니면 디코딩하지 못한 메시지는 제외
+                messages
+                    .into_iter()
+                    .filter(|(decoded, _)| self.diagnostics || decoded.is_some())
+                    .map(|(decoded, raw)| {
+                        (decoded.map(|(text, encoding)| (text, Some(encoding))), raw)
+                    })
+                    .unzip()
+            } else {
+                // raw 모드: SQL 추출 없이 패킷 원본을 hex 덤프로 전달 (hex 뷰어와 같은 형식)
+                messages
+                    .into_iter()
+                    .map(|(_, packet)| (Some((hex_dump(&packet), None)), packet))
+                    .unzip()
+            };
+
+            if decoded_texts.iter().any(Option::is_some) {
+                self.reassembler.mark_decoded(flow_id);
+            }
+
+            for (decoded_text, raw_data) in decoded_texts.into_iter().zip(raw_packets) {
+                // 빈 텍스트나 너무 짧은 텍스트는 건너뛰기 (디코딩 실패는 진단 모드에서만 포함됨)
+                let trimmed = decoded_text.as_ref().map(|(text, _)| text.trim());
+                tracing::debug!(
+                    packet_type = raw_data.first().copied().unwrap_or_default(),
+                    len = raw_data.len(),
+                    decode_result = match trimmed {
+                        None => "undecoded",
+                        Some(text) if TdsParser::is_too_short(text) => "too_short",
+                        Some(_) => "decoded",
+                    },
+                    "request packet"
+                );
+                if trimmed.is_some_and(TdsParser::is_too_short) {
+                    continue;
+                }
+                if trimmed.is_some_and(|text| self.is_relayed_request(flow_id, text, timestamp)) {
+                    continue;
+                }
+
+                // 이벤트 시각은 이번 패킷으로 완성된 요청 메시지의 완료 시각 (응답 시간 기준)
+                let event = self.request_event(
+                    flow_id,
+                    timestamp,
+                    trimmed,
+                    decoded_text.as_ref().and_then(|(_, encoding)| *encoding),
+                    raw_data,
+                );
+
+                if !self.capture_responses || is_mars {
+                    // 서버 응답을 기다리지 않고 실시간으로 이벤트 전송
+                    // MARS는 여러 세션의 요청이 동시에 진행되어 응답을 요청 순서로 맞출 수 없음
+                    events.push(event);
+                    continue;
+                }
+
+                // 새 요청이 오면 응답을 기다리던 이전 이벤트는 바로 전송
+                let queue = self.pending_events.entry(flow_id.clone()).or_default();
+                events.extend(queue.drain(..).map(|(_, event)| event));
+                queue.push_back((timestamp, event));
+            }
+        } else if self.bidirectional && self.is_reverse_request_stream(flow_id) {
+            // 양방향 디코딩: 서버 포트 쪽에서 요청을 보내는 구간 (프록시)
+            // 응답은 반대 방향이므로 응답 시간 없이 바로 전송
+            let _span = tracing::debug_span!("decode", flow = %flow_id.reversed()).entered();
+            events.extend(self.decode_reverse_requests(flow_id, new_bytes, timestamp));
+        } else if !self.capture_responses {
+            // 서버 응답은 파싱하지 않으므로 재조립한 바이트도 보관하지 않음
+            // (양방향 디코딩 모드는 서버 포트 쪽이 요청 스트림인지 판단할 수 있도록 남겨 둠)
+            if !self.bidirectional {
+                self.reassembler.discard(flow_id, Direction::ServerToClient);
+            }
+        } else if !new_bytes.is_empty() {
+            // ============================================
+            // 5단계: 서버 응답 토큰 처리
+            // ============================================
+            // - ENVCHANGE(데이터베이스): 이후 요청에 현재 데이터베이스 기록
+            // - DONE: 결과 집합 행 수 기록, DONE_MORE가 없으면 이벤트 전송
+            let _span = tracing::debug_span!("response", flow = %flow_id).entered();
+            // 새로 이어진 바이트만 디코딩하고, 응답을 끝까지 읽었으면 재조립 버퍼를 비움
+            // MARS 연결은 SMUX 헤더를 뗀 응답 (세션 구분 없이 받은 순서대로, 응답을 기다리는 이벤트가 없어 ENVCHANGE만 반영)
+            let decoder = self.response_decoders.entry(flow_id.clone()).or_default();
+            let tokens = decoder.push(new_bytes);
+            if decoder.is_idle() {
+                self.reassembler.discard(flow_id, Direction::ServerToClient);
+            }
+            tracing::trace!(tokens = tokens.len(), "response tokens");
+
+            for token in tokens {
+                match token {
+                    ResponseToken::EnvChange(change) if change.env_type == EnvChange::DATABASE => {
+                        self.current_databases
+                            .insert(flow_id.clone(), change.new_value);
+                    }
+                    ResponseToken::ColMetadata(columns) if self.result_columns => {
+                        // 응답을 기다리는 가장 오래된 요청의 결과 집합
+                        if let Some((_, event)) = self
+                            .pending_events
+                            .get_mut(flow_id)
+                            .and_then(|queue| queue.front_mut())
+                        {
+                            event.result_columns.push(TdsParser::column_names(&columns));
+                        }
+                    }
+                    ResponseToken::Done(done) => {
+                        let Some(queue) = self.pending_events.get_mut(flow_id) else {
+                            continue;
+                        };
+                        let Some((request_timestamp, event)) = queue.front_mut() else {
+                            continue;
+                        };
+
+                        if done.has_count() {
+                            event.result_sets.push(done.row_count);
+                        }
+
+                        // DONE_MORE가 없으면 해당 요청의 응답이 끝난 것
+                        // 응답 시간: 요청을 다 받은 패킷부터 마지막 DONE이 든 패킷까지
+                        if !done.has_more() {
+                            event.latency_ms =
+                                Some(((timestamp - *request_timestamp) * 1000.0).max(0.0));
+                            events.extend(queue.pop_front().map(|(_, event)| event));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        events
+    }
+
+    /// 서버 포트 쪽 스트림이 응답이 아니라 SQL Batch / RPC 요청으로 시작하는지 (로그인 단계 패킷 이후)
+    /// 응답을 읽고 앞부분을 버린 스트림은 응답 스트림으로 확정
+    fn is_reverse_request_stream(&self, flow_id: &FlowId) -> bool {
+        self.use_tds_parsing
+            && (self.reverse_decoders.contains_key(flow_id)
+                || (self
+                    .reassembler
+                    .discarded(flow_id, Direction::ServerToClient)
+                    == 0
+                    && self.reassembler.server_data(flow_id).is_some_and(|stream| {
+                        TdsParser::looks_like_tds(TdsParser::skip_login_packets(stream))
+                    })))
+    }
+
+    /// 서버 포트 쪽 스트림의 요청 패킷 디코딩 (양방향 디코딩 모드)
+    /// 이벤트의 flow_id는 실제 보낸 쪽 기준 (서버 포트 → 클라이언트 포트)
+    fn decode_reverse_requests(
+        &mut self,
+        flow_id: &FlowId,
+        new_bytes: &[u8],
+        timestamp: f64,
+    ) -> Vec<SqlEvent> {
+        let packets = match self.reverse_decoders.get_mut(flow_id) {
+            Some(decoder) => decoder.feed(new_bytes),
+            None => {
+                let Some(stream) = self.reassembler.server_data(flow_id) else {
+                    return Vec::new();
+                };
+                let request_data = TdsParser::skip_login_packets(stream);
+                let start = self
+                    .reassembler
+                    .discarded(flow_id, Direction::ServerToClient)
+                    + stream.len()
+                    - request_data.len();
+                let mut decoder =
+                    FlowDecoder::new(TdsStreamDecoder::new(self.detect_encoding), start);
+                let packets = decoder.feed(request_data);
+                self.reverse_decoders.insert(flow_id.clone(), decoder);
+                packets
+            }
+        };
+        // 디코딩을 마친 바이트는 재조립 버퍼에서 버림
+        if let Some(decoder) = self.reverse_decoders.get(flow_id) {
+            self.reassembler
+                .consume(flow_id, Direction::ServerToClient, decoder.consumed());
+        }
+
+        let mut events = Vec::new();
+        for (decoded, raw_data) in packets {
+            let trimmed = decoded.as_ref().map(|(text, _)| text.trim());
+            if trimmed.is_none() && !self.diagnostics {
+                continue;
+            }
+            if trimmed.is_some_and(|text| {
+                TdsParser::is_too_short(text) || self.is_relayed_request(flow_id, text, timestamp)
+            }) {
+                continue;
+            }
+
+            let mut event = self.request_event(
+                flow_id,
+                timestamp,
+                trimmed,
+                decoded.as_ref().map(|(_, encoding)| *encoding),
+                raw_data,
+            );
+            event.flow_id = flow_id.reversed().to_string();
+            events.push(event);
+        }
+        if !events.is_empty() {
+            self.reassembler.mark_decoded(flow_id);
+        }
+        events
+    }
+
+    /// 다른 연결에서 RELAY_DEDUP_WINDOW 안에 같은 SQL이 나왔는지 (양방향 디코딩 모드에서만)
+    /// 처음 나온 요청은 기록해 두고 false
+    fn is_relayed_request(&mut self, flow_id: &FlowId, text: &str, timestamp: f64) -> bool {
+        if !self.bidirectional {
+            return false;
+        }
+
+        while self
+            .relayed_requests
+            .front()
+            .is_some_and(|(seen, ..)| timestamp - seen > RELAY_DEDUP_WINDOW)
+        {
+            self.relayed_requests.pop_front();
+        }
+        if self
+            .relayed_requests
+            .iter()
+            .any(|(_, seen_flow, seen_text)| seen_flow != flow_id && seen_text == text)
+        {
+            tracing::debug!(flow = %flow_id, "relayed request from another leg, skipped");
+            return true;
+        }
+
+        self.relayed_requests
+            .push_back((timestamp, flow_id.clone(), text.to_string()));
+        false
+    }
+
+    /// SQL Server 플로우인지 (한쪽이 대상 서버이거나, 대상 서버가 없으면 서버 포트)
+    fn is_sql_flow(&self, flow_id: &FlowId) -> bool {
+        match self.target_server {
+            Some(target) => {
+                target == (flow_id.src_ip, flow_id.src_port)
+                    || target == (flow_id.dst_ip, flow_id.dst_port)
+            }
+            None => self.is_server_port(flow_id.src_port) || self.is_server_port(flow_id.dst_port),
+        }
+    }
+
+    /// 서버 포트 목록이나 서버 포트 범위에 있는 포트인지
+    /// 클라이언트 포트 범위에 있는 포트는 서버 포트로 보지 않음 (방향 판단과 플로우 필터에 같이 적용)
+    fn is_server_port(&self, port: u16) -> bool {
+        (self.server_ports.contains(&port) || port_in_ranges(&self.server_port_ranges, port))
+            && !port_in_ranges(&self.client_port_ranges, port)
+    }
+
+    /// 패킷이 서버 → 클라이언트 방향인지 (SQL Server 포트가 아닌 쪽이 클라이언트)
+    /// 양쪽 모두 서버 포트이면 (클라이언트 임시 포트가 1434 등과 겹친 경우) 작은 포트를 서버로 판단
+    /// 대상 서버를 지정했으면 보낸 쪽이 대상 서버인지로만 판단
+    fn is_from_server(&self, flow_id: &FlowId) -> bool {
+        if let Some(target) = self.target_server {
+            return target == (flow_id.src_ip, flow_id.src_port);
+        }
+        let src_is_server = self.is_server_port(flow_id.src_port);
+        let dst_is_server = self.is_server_port(flow_id.dst_port);
+        match (src_is_server, dst_is_server) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => flow_id.src_port < flow_id.dst_port,
+        }
+    }
+
+    /// ============================================
+    /// 패킷 파싱: 링크 헤더 + IP + TCP
+    /// ============================================
+    /// 모든 TCP 패킷을 처리 (TDS 필터링 없음)
+    /// 반환값: (FlowId, 시퀀스 번호, 페이로드, TCP 플래그, 실제 src_ip, 실제 src_port, 실제 dst_ip, 실제 dst_port)
+    #[allow(clippy::type_complexity)]
+    fn parse_packet_all(
+        data: &[u8],
+        link_type: LinkType,
+        _timestamp: f64,
+    ) -> Option<(FlowId, u32, Vec<u8>, u8, IpAddr, u16, IpAddr, u16)> {
+        // 링크 헤더 (Ethernet 14 bytes 등) 건너뛰기
+        let ip_start = link_type.ip_offset(data)?;
+
+        // IP 헤더 (IPv4 / IPv6, TCP만 처리)
+        let (src_ip, dst_ip, tcp_start, ip_end) = Self::parse_ip_header(data, ip_start)?;
+
+        // TCP 헤더 파싱
+        if ip_end < tcp_start + 20 {
+            return None;
+        }
+
+        let src_port = u16::from_be_bytes([data[tcp_start], data[tcp_start + 1]]);
+        let dst_port = u16::from_be_bytes([data[tcp_start + 2], data[tcp_start + 3]]);
+
+        // TCP 시퀀스 번호 추출
+        let seq = u32::from_be_bytes([
+            data[tcp_start + 4],
+            data[tcp_start + 5],
             data[tcp_start + 6],
             data[tcp_start + 7],
         ]);
 
+        // TCP 플래그 (SYN / FIN / RST로 연결 시작과 종료 감지)
+        let tcp_flags = data[tcp_start + 13];
+
         // TCP 헤더 길이 계산 (Data Offset * 4)
         let tcp_header_len = ((data[tcp_start + 12] >> 4) * 4) as usize;
         let payload_start = tcp_start + tcp_header_len;
 
-        if data.len() < payload_start {
+        if ip_end < payload_start {
             return None;
         }
 
-        // TCP 페이로드 추출
-        let payload = data[payload_start..].to_vec();
+        // TCP 페이로드 추출 (IP 길이 뒤의 Ethernet 패딩 제외)
+        let payload = data[payload_start..ip_end].to_vec();
 
-        // Flow ID 생성 및 방향 확인
+        // Flow ID 생성
         let flow_id = FlowId::new(src_ip, src_port, dst_ip, dst_port);
-        let is_client = flow_id.is_client_to_server(src_ip, src_port);
 
         // 실제 패킷 방향 정보도 함께 반환 (GUI 표시용)
         Some((
-            flow_id, seq, payload, is_client, src_ip, src_port, dst_ip, dst_port,
+            flow_id, seq, payload, tcp_flags, src_ip, src_port, dst_ip, dst_port,
         ))
     }
+
+    /// IP 헤더 파싱
+    /// 반환값: (src_ip, dst_ip, TCP 헤더 시작 위치, IP 패킷 끝 위치)
+    /// localhost 연결은 IPv6(::1)를 쓰는 경우가 많으므로 IPv6도 처리 (Npcap 루프백 어댑터)
+    fn parse_ip_header(data: &[u8], ip_start: usize) -> Option<(IpAddr, IpAddr, usize, usize)> {
+        let version = data.get(ip_start)? >> 4;
+        match version {
+            4 => {
+                let header = data.get(ip_start..ip_start + 20)?;
+                // IP 헤더 길이 계산 (IHL * 4)
+                let ip_header_len = ((header[0] & 0x0F) * 4) as usize;
+                if header[9] != 6 || ip_header_len < 20 {
+                    return None; // TCP만 처리
+                }
+
+                let src_ip = IpAddr::from(<[u8; 4]>::try_from(&header[12..16]).ok()?);
+                let dst_ip = IpAddr::from(<[u8; 4]>::try_from(&header[16..20]).ok()?);
+
+                // 전체 길이가 0이면 (TSO 등) 캡처된 끝까지 사용
+                let total_len = u16::from_be_bytes([header[2], header[3]]) as usize;
+                let ip_end = if total_len == 0 {
+                    data.len()
+                } else {
+                    (ip_start + total_len).min(data.len())
+                };
+                Some((src_ip, dst_ip, ip_start + ip_header_len, ip_end))
+            }
+            6 => {
+                let header = data.get(ip_start..ip_start + 40)?;
+                let src_ip = IpAddr::from(<[u8; 16]>::try_from(&header[8..24]).ok()?);
+                let dst_ip = IpAddr::from(<[u8; 16]>::try_from(&header[24..40]).ok()?);
+
+                let payload_len = u16::from_be_bytes([header[4], header[5]]) as usize;
+                let ip_end = if payload_len == 0 {
+                    data.len()
+                } else {
+                    (ip_start + 40 + payload_len).min(data.len())
+                };
+
+                // 확장 헤더 건너뛰기 (Hop-by-Hop, Routing, Destination Options)
+                let mut next_header = header[6];
+                let mut offset = ip_start + 40;
+                while matches!(next_header, 0 | 43 | 60) {
+                    let extension = data.get(offset..offset + 2)?;
+                    next_header = extension[0];
+                    offset += (extension[1] as usize + 1) * 8;
+                }
+                if next_header != 6 {
+                    return None; // TCP만 처리 (조각난 패킷 포함)
+                }
+                Some((src_ip, dst_ip, offset, ip_end))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 이벤트에 넣을 LOGIN7 문자열 필드 (로그인 정보가 없거나 빈 문자열이면 None)
+fn login_field(login: Option<&Login7>, field: impl Fn(&Login7) -> &String) -> Option<String> {
+    login.map(field).filter(|value| !value.is_empty()).cloned()
+}
+
+/// sp_executesql 요청 하나면 (SQL 문장, 파라미터 목록)으로 나눔
+/// 배치로 이어진 여러 요청이나 저장 프로시저 직접 호출, 파라미터 없는 요청은 디코딩한 텍스트 그대로
+/// rpc_calls는 RPC 요청 메시지의 요청들 (SQL Batch면 비어 있음)
+fn split_rpc_params(text: Option<&str>, rpc_calls: &[RpcCall]) -> (String, Vec<(String, String)>) {
+    let text = text.unwrap_or_default().to_string();
+    if text.is_empty() {
+        return (text, Vec::new());
+    }
+
+    match rpc_calls {
+        [RpcCall {
+            statement: Some(statement),
+            params,
+            ..
+        }] if !params.is_empty() => (statement.clone(), params.clone()),
+        _ => (text, Vec::new()),
+    }
+}
+
+/// 이벤트 라벨: 요청 종류 / 문장 수 / 위험한 문장 (예: "sp_getPatient / 1 stmt", "batch / 3 stmts / WHERE 없음 - 위험")
+/// 요청 종류는 RPC면 호출한 프로시저 이름 (여러 요청이면 ", "로 나열), SQL Batch면 "batch"
+/// 문장 수는 SQL 본문을 split_batch로 나눈 수 (SQL 본문 없이 프로시저를 호출한 요청은 1)
+fn event_label(sql_text: &str, rpc_calls: &[RpcCall]) -> String {
+    let statement_count = |sql: &str| split_batch(sql).len().max(1);
+    let (kind, count) = if rpc_calls.is_empty() {
+        ("batch".to_string(), statement_count(sql_text))
+    } else {
+        let procedures: Vec<&str> = rpc_calls
+            .iter()
+            .map(|call| call.procedure.as_deref().unwrap_or("RPC"))
+            .collect();
+        let count = rpc_calls
+            .iter()
+            .map(|call| call.statement.as_deref().map_or(1, statement_count))
+            .sum();
+        (procedures.join(", "), count)
+    };
+
+    let mut label = format!(
+        "{} / {} {}",
+        kind,
+        count,
+        if count == 1 { "stmt" } else { "stmts" }
+    );
+    if let Some(danger) = dangerous_statement_label(sql_text) {
+        label.push_str(" / ");
+        label.push_str(danger);
+    }
+    label
+}
+
+/// 포트가 범위 목록 중 하나에 들어가는지 ((시작, 끝), 양 끝 포함)
+fn port_in_ranges(ranges: &[(u16, u16)], port: u16) -> bool {
+    ranges
+        .iter()
+        .any(|&(start, end)| (start..=end).contains(&port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tds::test_packets::{sql_batch, tds_packet, utf16};
+
+    const CLIENT_SEQ: u32 = 1000;
+    const SERVER_SEQ: u32 = 5000;
+
+    fn done(status: u16, row_count: u64) -> Vec<u8> {
+        let mut token = vec![0xFD];
+        token.extend(status.to_le_bytes());
+        token.extend(0xC1u16.to_le_bytes());
+        token.extend(row_count.to_le_bytes());
+        token
+    }
+
+    /// 10.0.0.5:50000 (클라이언트) <-> 10.0.0.1:1433 (서버) 사이의 Ethernet + IPv4 + TCP 프레임 (ACK는 항상 설정)
+    fn frame(from_client: bool, seq: u32, flags: u8, payload: &[u8]) -> Vec<u8> {
+        let (src, dst, src_port, dst_port) = if from_client {
+            ([10, 0, 0, 5], [10, 0, 0, 1], 50000u16, 1433u16)
+        } else {
+            ([10, 0, 0, 1], [10, 0, 0, 5], 1433, 50000)
+        };
+        let mut frame = vec![0u8; 12];
+        frame.extend([0x08, 0x00]);
+        frame.extend([0x45, 0]);
+        frame.extend(((40 + payload.len()) as u16).to_be_bytes());
+        frame.extend([0, 0, 0x40, 0, 64, 6, 0, 0]);
+        frame.extend(src);
+        frame.extend(dst);
+        frame.extend(src_port.to_be_bytes());
+        frame.extend(dst_port.to_be_bytes());
+        frame.extend(seq.to_be_bytes());
+        frame.extend([0; 4]);
+        frame.extend([0x50, flags | 0x10]);
+        frame.extend([0xFF, 0xFF, 0, 0, 0, 0]);
+        frame.extend(payload);
+        frame
+    }
+
+    fn flow_stats(extractor: &Extractor) -> Vec<FlowStat> {
+        let (reply, receiver) = mpsc::channel();
+        extractor.handle_request(CaptureRequest::FlowStats { reply });
+        receiver.recv().unwrap()
+    }
+
+    #[test]
+    fn decodes_sql_batch_from_single_frame() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let events = extractor.process_frame(
+            &frame(true, CLIENT_SEQ, 0, &sql_batch("SELECT * FROM TB_환자")),
+            10.5,
+        );
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.sql_text, "SELECT * FROM TB_환자");
+        assert_eq!(event.flow_id, "10.0.0.5:50000->10.0.0.1:1433");
+        assert_eq!(event.operation, "TDS");
+        assert_eq!(event.timestamp.timestamp_millis(), 10_500);
+        assert!(!event.truncated);
+    }
+
+    #[test]
+    fn reset_connection_request_is_marked_on_event() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let mut request = sql_batch("SELECT 1 FROM TB_A");
+        // EOM | RESETCONNECTION (연결 풀에서 재사용한 연결의 첫 요청)
+        request[1] = 0x09;
+        let second = sql_batch("SELECT 2 FROM TB_A");
+        let events = extractor.process_frame(&frame(true, CLIENT_SEQ, 0, &request), 1.0);
+        let later = extractor.process_frame(
+            &frame(true, CLIENT_SEQ + request.len() as u32, 0, &second),
+            1.1,
+        );
+
+        assert!(events[0].reset_connection);
+        assert!(!later[0].reset_connection);
+    }
+
+    #[test]
+    fn raw_mode_sends_packet_hex_dump() {
+        let mut extractor = Extractor::builder()
+            .tds_parsing(false)
+            .capture_responses(false)
+            .build();
+        let packet = sql_batch("SELECT * FROM TB_환자");
+        let events = extractor.process_frame(&frame(true, CLIENT_SEQ, 0, &packet), 1.0);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sql_text, hex_dump(&packet));
+        assert!(events[0].sql_text.starts_with("00000000:  01 01 00"));
+        assert_eq!(events[0].raw_data.as_deref(), Some(&packet[..]));
+    }
+
+    #[test]
+    fn ignores_frames_on_other_ports() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let mut other = frame(true, CLIENT_SEQ, 0, &sql_batch("SELECT 1 FROM TB_A"));
+        // 목적지 포트를 80으로
+        other[36..38].copy_from_slice(&80u16.to_be_bytes());
+        assert!(extractor.process_frame(&other, 1.0).is_empty());
+    }
+
+    #[test]
+    fn reassembles_request_split_across_segments() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let packet = sql_batch("UPDATE TB_진료내역 SET 상태 = 1 WHERE ID = 7");
+        let (first, second) = packet.split_at(20);
+        let first = frame(true, CLIENT_SEQ, 0, first);
+        let second = frame(true, CLIENT_SEQ + 20, 0, second);
+
+        // SYN으로 시작 seq를 알면 뒷부분이 먼저 도착해도 빈틈이 채워질 때 디코딩
+        extractor.process_frame(&frame(true, CLIENT_SEQ - 1, TCP_SYN, &[]), 1.0);
+        assert!(extractor.process_frame(&second, 1.1).is_empty());
+        let events = extractor.process_frame(&first, 1.2);
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].sql_text,
+            "UPDATE TB_진료내역 SET 상태 = 1 WHERE ID = 7"
+        );
+
+        // 재전송은 이벤트를 다시 만들지 않음
+        assert!(extractor.process_frame(&second, 1.3).is_empty());
+        assert!(extractor.process_frame(&first, 1.4).is_empty());
+    }
+
+    #[test]
+    fn multi_packet_message_keeps_all_packets_as_raw_data() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let sql = "SELECT * FROM TB_진료내역 WHERE ID = 7";
+        let body = &sql_batch(sql)[8..];
+        let first = tds_packet(0x01, 0x00, &body[..30]);
+        let second = tds_packet(0x01, 0x01, &body[30..]);
+
+        assert!(extractor
+            .process_frame(&frame(true, CLIENT_SEQ, 0, &first), 1.0)
+            .is_empty());
+        let events = extractor.process_frame(
+            &frame(true, CLIENT_SEQ + first.len() as u32, 0, &second),
+            1.1,
+        );
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sql_text, sql);
+        assert_eq!(
+            events[0].raw_data,
+            Some([first.clone(), second.clone()].concat())
+        );
+        assert_eq!(
+            TdsParser::message_packets(events[0].raw_data.as_ref().unwrap()),
+            vec![&first[..], &second[..]]
+        );
+    }
+
+    #[test]
+    fn requests_on_one_connection_keep_their_own_timestamps() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let request = sql_batch("SELECT * FROM TB_Users");
+
+        let first = extractor.process_frame(&frame(true, CLIENT_SEQ, 0, &request), 10.0);
+        let second = extractor.process_frame(
+            &frame(true, CLIENT_SEQ + request.len() as u32, 0, &request),
+            20.0,
+        );
+
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        assert_eq!(first[0].timestamp.timestamp(), 10);
+        assert_eq!(second[0].timestamp.timestamp(), 20);
+    }
+
+    #[test]
+    fn client_stream_request_returns_undecoded_bytes() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let packet = sql_batch("SELECT * FROM TB_Users");
+        let (first, second) = packet.split_at(20);
+        extractor.process_frame(&frame(true, CLIENT_SEQ, 0, first), 1.0);
+
+        // 나머지 바이트를 기다리는 패킷은 재조립 버퍼에 남아 있음
+        let flow_id: FlowId = "10.0.0.5:50000->10.0.0.1:1433".parse().unwrap();
+        let (reply, receiver) = mpsc::channel();
+        extractor.handle_request(CaptureRequest::ClientStream {
+            flow_id: flow_id.clone(),
+            reply,
+        });
+        let stream = receiver.recv().unwrap().unwrap();
+        assert_eq!(stream, first);
+
+        // 16바이트마다 한 줄
+        let dump = hex_dump(&stream);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("00000000:  01 01 00"));
+        assert!(lines[1].starts_with("00000010:  "));
+
+        // 디코딩을 마친 바이트는 버림
+        let events = extractor.process_frame(&frame(true, CLIENT_SEQ + 20, 0, second), 1.1);
+        assert_eq!(events.len(), 1);
+        assert_eq!(extractor.client_stream(&flow_id), None);
+        assert_eq!(extractor.client_stream(&flow_id.reversed()), None);
+    }
+
+    #[test]
+    fn decoded_requests_are_dropped_from_reassembly_buffer() {
+        let mut extractor = Extractor::builder().build();
+        let mut client_seq = CLIENT_SEQ;
+        let mut server_seq = SERVER_SEQ;
+        let response = tds_packet(0x04, 0x01, &done(0x10, 1));
+        for i in 0..3 {
+            // 두 패킷으로 나뉜 메시지: 첫 패킷은 EOM을 기다리는 동안 남아 있음
+            let first = tds_packet(
+                0x01,
+                0x00,
+                &sql_batch(&format!("SELECT {} FROM TB_A", i))[8..],
+            );
+            let second = tds_packet(0x01, 0x01, &utf16(" WHERE 1 = 1"));
+            extractor.process_frame(&frame(true, client_seq, 0, &first), 1.0 + i as f64);
+            client_seq += first.len() as u32;
+            assert_eq!(
+                extractor.reassembler.flow_stats()[0].client_bytes,
+                first.len()
+            );
+
+            extractor.process_frame(&frame(true, client_seq, 0, &second), 1.1 + i as f64);
+            client_seq += second.len() as u32;
+            let events =
+                extractor.process_frame(&frame(false, server_seq, 0, &response), 1.2 + i as f64);
+            server_seq += response.len() as u32;
+            assert_eq!(events.len(), 1);
+            assert_eq!(
+                events[0].sql_text,
+                format!("SELECT {} FROM TB_A WHERE 1 = 1", i)
+            );
+
+            let stats = extractor.reassembler.flow_stats();
+            assert_eq!((stats[0].client_bytes, stats[0].server_bytes), (0, 0));
+        }
+    }
+
+    #[test]
+    fn idle_flows_are_evicted() {
+        let mut extractor = Extractor::builder().build();
+        assert!(extractor
+            .process_frame(
+                &frame(true, CLIENT_SEQ, 0, &sql_batch("SELECT 1 FROM TB_A")),
+                1.0
+            )
+            .is_empty());
+
+        // 다른 플로우의 패킷이 FLOW_IDLE_TIMEOUT 뒤에 오면 응답을 기다리던 이벤트를 내보내고 정리
+        let later = 1.0 + FLOW_IDLE_TIMEOUT + FLOW_SWEEP_INTERVAL;
+        let events = extractor.process_frame(&frame_from([10, 0, 0, 6], CLIENT_SEQ, 0, &[]), later);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sql_text, "SELECT 1 FROM TB_A");
+        assert_eq!(events[0].latency_ms, None);
+
+        let flow_id: FlowId = "10.0.0.5:50000->10.0.0.1:1433".parse().unwrap();
+        assert!(!extractor.reassembler.get_flows().contains(&flow_id));
+        assert!(!extractor.stream_decoders.contains_key(&flow_id));
+        assert!(!extractor.last_packet_times.contains_key(&flow_id));
+        assert!(extractor.take_pending_events().is_empty());
+    }
+
+    /// 10.0.0.5 / 10.0.0.6 클라이언트 (포트 50000) -> 10.0.0.1:1433 프레임
+    fn frame_from(client: [u8; 4], seq: u32, flags: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = frame(true, seq, flags, payload);
+        frame[26..30].copy_from_slice(&client);
+        frame
+    }
+
+    #[test]
+    fn events_carry_login7_app_name_of_their_flow() {
+        use crate::tds::login::tests::login7_packet;
+
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let mut events = Vec::new();
+        for (client, app) in [([10, 0, 0, 5], "Alpha"), ([10, 0, 0, 6], "Beta")] {
+            let login = login7_packet(["host", "user", app, "srv", "lib", "", "db"]);
+            extractor.process_frame(&frame_from(client, CLIENT_SEQ - 1, TCP_SYN, &[]), 1.0);
+            extractor.process_frame(&frame_from(client, CLIENT_SEQ, 0, &login), 1.1);
+            events.extend(extractor.process_frame(
+                &frame_from(
+                    client,
+                    CLIENT_SEQ + login.len() as u32,
+                    0,
+                    &sql_batch("SELECT * FROM TB_Users"),
+                ),
+                1.2,
+            ));
+        }
+
+        let apps: Vec<Option<&str>> = events.iter().map(|e| e.app_name.as_deref()).collect();
+        assert_eq!(apps, vec![Some("Alpha"), Some("Beta")]);
+        assert_eq!(events[0].client_host.as_deref(), Some("host"));
+        assert_eq!(events[0].client_library.as_deref(), Some("lib"));
+    }
+
+    #[test]
+    fn malformed_frames_do_not_panic() {
+        let stats = Arc::new(CaptureStats::default());
+        let mut extractor = Extractor::builder()
+            .diagnostics(true)
+            .result_columns(true)
+            .stats(stats.clone())
+            .build();
+
+        // 잘린 프레임, 잘못된 IP / TCP 헤더 길이
+        let valid = frame(true, CLIENT_SEQ, 0, &sql_batch("SELECT 1"));
+        let mut bad_ihl = valid.clone();
+        bad_ihl[14] = 0x4F;
+        let mut bad_data_offset = valid.clone();
+        bad_data_offset[46] = 0xF0;
+        for (i, malformed) in [
+            &valid[..10],
+            &valid[..30],
+            &valid[..50],
+            &bad_ihl,
+            &bad_data_offset,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            extractor.process_frame_guarded(malformed, i as f64);
+        }
+
+        // 잘못된 길이 필드를 가진 TDS 패킷들 (RPC / LOGIN7 / 응답)
+        let malformed_payloads: Vec<Vec<u8>> = vec![
+            vec![0x01, 0x01, 0x00, 0x02, 0, 0, 1, 0],
+            vec![0x03, 0x01, 0x00, 0x0C, 0, 0, 1, 0, 0xFF, 0xFF, 0x0A, 0x00],
+            tds_packet(
+                0x03,
+                0x01,
+                &[
+                    4, 0, 0, 0, 0xFF, 0xFF, 0x0A, 0, 0, 0, 1, b'@', 0, 0, 0xE7, 0xFF, 0xFF,
+                ],
+            ),
+            tds_packet(0x10, 0x01, &[0xFF, 0xFF, 0, 0, 0x04, 0, 0, 0x74]),
+            tds_packet(0x04, 0x01, &[0x81, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0xE7]),
+        ];
+        for (i, payload) in malformed_payloads.iter().enumerate() {
+            let mut request = frame(true, CLIENT_SEQ, 0, payload);
+            request[34..36].copy_from_slice(&(40000 + i as u16).to_be_bytes());
+            extractor.process_frame_guarded(&request, 10.0);
+            let mut response = frame(false, SERVER_SEQ, 0, payload);
+            response[36..38].copy_from_slice(&(40000 + i as u16).to_be_bytes());
+            extractor.process_frame_guarded(&response, 10.1);
+        }
+
+        // 임의 바이트 (고정 시드)
+        let mut seed = 0x2545_F491_4F6C_DD1Du64;
+        for i in 0..500u16 {
+            let payload: Vec<u8> = (0..(i % 97 + 8))
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    seed as u8
+                })
+                .collect();
+            let mut request = frame(i % 2 == 0, CLIENT_SEQ, 0, &payload);
+            let port_offset = if i % 2 == 0 { 34 } else { 36 };
+            request[port_offset..port_offset + 2].copy_from_slice(&(20000 + i).to_be_bytes());
+            // 절반은 TDS 패킷 타입으로 시작
+            if i % 4 < 2 {
+                request[54] = [0x01, 0x03, 0x04, 0x10][usize::from(i / 4 % 4)];
+            }
+            extractor.process_frame_guarded(&request, 20.0);
+        }
+        extractor.take_pending_events();
+
+        assert_eq!(stats.parse_panics(), 0);
+    }
+
+    #[test]
+    fn decodes_requests_when_capture_starts_with_server_packet() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        // 캡처를 연결 도중에 시작해 이전 요청의 응답을 먼저 받음
+        let response = tds_packet(0x04, 0x01, &done(0x10, 1));
+        assert!(extractor
+            .process_frame(&frame(false, SERVER_SEQ, 0, &response), 1.0)
+            .is_empty());
+
+        let events = extractor.process_frame(
+            &frame(true, CLIENT_SEQ, 0, &sql_batch("SELECT * FROM TB_A")),
+            1.1,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sql_text, "SELECT * FROM TB_A");
+        assert_eq!(events[0].flow_id, "10.0.0.5:50000->10.0.0.1:1433");
+    }
+
+    #[test]
+    fn lower_port_is_server_when_both_ports_are_server_ports() {
+        let mut extractor = Extractor::builder()
+            .ports(&[1433, 1434])
+            .capture_responses(false)
+            .build();
+        // 클라이언트 임시 포트가 1434와 겹친 경우
+        let mut request = frame(true, CLIENT_SEQ, 0, &sql_batch("SELECT 1 FROM TB_A"));
+        request[34..36].copy_from_slice(&1434u16.to_be_bytes());
+
+        let events = extractor.process_frame(&request, 1.0);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].flow_id, "10.0.0.5:1434->10.0.0.1:1433");
+    }
+
+    #[test]
+    fn events_decoded_before_a_gap_are_flagged() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let first = sql_batch("SELECT 1 FROM TB_A");
+        let lost = sql_batch("SELECT 2 FROM TB_A");
+        let third = sql_batch("SELECT 3 FROM TB_A");
+        let third_seq = CLIENT_SEQ + (first.len() + lost.len()) as u32;
+
+        extractor.process_frame(&frame(true, CLIENT_SEQ - 1, TCP_SYN, &[]), 1.0);
+        // 두 번째 요청이 빠진 채 세 번째 요청이 먼저 도착
+        assert!(extractor
+            .process_frame(&frame(true, third_seq, 0, &third), 1.1)
+            .is_empty());
+        let events = extractor.process_frame(&frame(true, CLIENT_SEQ, 0, &first), 1.2);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sql_text, "SELECT 1 FROM TB_A");
+        assert!(events[0].packet_loss);
+        assert_eq!(flow_stats(&extractor)[0].client_gap, Some(first.len()));
+
+        // 빠졌던 세그먼트가 재전송되면 빈틈이 없어짐
+        let events =
+            extractor.process_frame(&frame(true, CLIENT_SEQ + first.len() as u32, 0, &lost), 1.3);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|event| !event.packet_loss));
+    }
+
+    /// frame과 같지만 클라이언트 / 서버 포트를 지정
+    fn frame_on_ports(
+        from_client: bool,
+        (client_port, server_port): (u16, u16),
+        seq: u32,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut frame = frame(from_client, seq, 0, payload);
+        let (src, dst) = if from_client {
+            (client_port, server_port)
+        } else {
+            (server_port, client_port)
+        };
+        frame[34..36].copy_from_slice(&src.to_be_bytes());
+        frame[36..38].copy_from_slice(&dst.to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn server_port_range_decides_direction() {
+        let mut extractor = Extractor::builder().port_ranges(&[(5000, 5010)]).build();
+        let ports = (50000, 5000);
+
+        // 서버 쪽 패킷을 먼저 받아도 5000 쪽이 서버
+        extractor.process_frame(&frame_on_ports(false, ports, SERVER_SEQ, &[]), 1.0);
+        let request = sql_batch("SELECT * FROM TB_A");
+        assert!(extractor
+            .process_frame(&frame_on_ports(true, ports, CLIENT_SEQ, &request), 1.1)
+            .is_empty());
+        let response = tds_packet(0x04, 0x01, &done(0x10, 1));
+        let events =
+            extractor.process_frame(&frame_on_ports(false, ports, SERVER_SEQ, &response), 1.2);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].flow_id, "10.0.0.5:50000->10.0.0.1:5000");
+        assert_eq!(events[0].result_sets, vec![1]);
+    }
+
+    #[test]
+    fn client_port_range_overrides_server_ports() {
+        // 서버 포트 범위가 애플리케이션 서버의 임시 포트 범위와 겹치는 경우
+        let mut extractor = Extractor::builder()
+            .port_ranges(&[(5000, 60000)])
+            .client_port_ranges(&[(49152, 65535)])
+            .capture_responses(false)
+            .build();
+
+        let request = sql_batch("SELECT * FROM TB_A");
+        let events = extractor.process_frame(
+            &frame_on_ports(true, (50000, 5000), CLIENT_SEQ, &request),
+            1.0,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].flow_id, "10.0.0.5:50000->10.0.0.1:5000");
+
+        // 어느 쪽도 서버 포트가 아니면 무시
+        let other = frame_on_ports(true, (50001, 4000), CLIENT_SEQ, &request);
+        assert!(extractor.process_frame(&other, 1.1).is_empty());
+    }
+
+    #[test]
+    fn pairs_request_with_server_done() {
+        let mut extractor = Extractor::builder().build();
+        let request = sql_batch("SELECT * FROM TB_Users");
+        assert!(extractor
+            .process_frame(&frame(true, CLIENT_SEQ, 0, &request), 10.0)
+            .is_empty());
+
+        // DONE_MORE | DONE_COUNT 뒤에 DONE_COUNT로 끝나는 응답이 두 패킷에 나뉘어 옴
+        let mut body = done(0x11, 3);
+        body.extend(done(0x10, 5));
+        let first = tds_packet(0x04, 0x00, &body[..10]);
+        let second = tds_packet(0x04, 0x01, &body[10..]);
+        assert!(extractor
+            .process_frame(&frame(false, SERVER_SEQ, 0, &first), 10.1)
+            .is_empty());
+        let events = extractor.process_frame(
+            &frame(false, SERVER_SEQ + first.len() as u32, 0, &second),
+            10.25,
+        );
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sql_text, "SELECT * FROM TB_Users");
+        assert_eq!(events[0].result_sets, vec![3, 5]);
+        assert_eq!(events[0].latency_ms, Some(250.0));
+        // 응답을 끝까지 읽었으므로 재조립 버퍼는 비어 있음
+        assert_eq!(flow_stats(&extractor)[0].server_bytes, 0);
+    }
+
+    #[test]
+    fn result_columns_are_attached_to_pending_request() {
+        let mut extractor = Extractor::builder().result_columns(true).build();
+        let request = sql_batch("SELECT id FROM TB_A");
+        extractor.process_frame(&frame(true, CLIENT_SEQ, 0, &request), 1.0);
+
+        // COLMETADATA (int 컬럼 id) + DONE
+        let mut body = vec![0x81, 1, 0, 0, 0, 0, 0, 0x09, 0x00, 0x38, 2];
+        body.extend(utf16("id"));
+        body.extend(done(0x10, 0));
+        let events = extractor.process_frame(
+            &frame(false, SERVER_SEQ, 0, &tds_packet(0x04, 0x01, &body)),
+            1.1,
+        );
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].result_columns,
+            vec![vec![("id".to_string(), "int".to_string())]]
+        );
+    }
+
+    #[test]
+    fn latency_is_measured_from_request_to_final_done() {
+        let mut extractor = Extractor::builder().build();
+        let first = sql_batch("SELECT * FROM TB_A");
+        let second = sql_batch("SELECT * FROM TB_B");
+
+        extractor.process_frame(&frame(true, CLIENT_SEQ, 0, &first), 5.0);
+        let events = extractor.process_frame(
+            &frame(
+                false,
+                SERVER_SEQ,
+                0,
+                &tds_packet(0x04, 0x01, &done(0x10, 1)),
+            ),
+            5.75,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].latency_ms, Some(750.0));
+
+        // 응답 전에 다음 요청이 오면 이전 요청은 응답 시간 없이 전송
+        extractor.process_frame(
+            &frame(true, CLIENT_SEQ + first.len() as u32, 0, &second),
+            6.0,
+        );
+        let events = extractor.process_frame(
+            &frame(
+                true,
+                CLIENT_SEQ + (first.len() + second.len()) as u32,
+                0,
+                &first,
+            ),
+            6.5,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sql_text, "SELECT * FROM TB_B");
+        assert_eq!(events[0].latency_ms, None);
+    }
+
+    #[test]
+    fn syn_after_fin_starts_new_connection() {
+        let mut extractor = Extractor::builder().build();
+        let first = sql_batch("SELECT * FROM TB_Old");
+        extractor.process_frame(&frame(true, CLIENT_SEQ - 1, TCP_SYN, &[]), 1.0);
+        assert!(extractor
+            .process_frame(&frame(true, CLIENT_SEQ, 0, &first), 1.1)
+            .is_empty());
+
+        // 연결이 끝나면 응답을 기다리던 이벤트는 바로 나오고 플로우 상태는 정리됨
+        let events = extractor.process_frame(
+            &frame(true, CLIENT_SEQ + first.len() as u32, TCP_FIN, &[]),
+            1.2,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sql_text, "SELECT * FROM TB_Old");
+        assert_eq!(events[0].latency_ms, None);
+        assert!(extractor.reassembler.get_flows().is_empty());
+
+        // 같은 4-tuple의 새 연결
+        let events = extractor.process_frame(&frame(true, 90_000, TCP_SYN, &[]), 2.0);
+        assert!(events.is_empty());
+
+        // 새 연결의 요청은 새 스트림으로 디코딩
+        let events = extractor.process_frame(
+            &frame(true, 90_001, 0, &sql_batch("SELECT * FROM TB_New")),
+            2.1,
+        );
+        assert!(events.is_empty());
+        let events = extractor.process_frame(
+            &frame(
+                false,
+                SERVER_SEQ,
+                0,
+                &tds_packet(0x04, 0x01, &done(0x10, 1)),
+            ),
+            2.2,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sql_text, "SELECT * FROM TB_New");
+        assert_eq!(events[0].result_sets, vec![1]);
+    }
+
+    #[test]
+    fn process_raw_frame_sends_decoded_event() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let (sender, receiver) = mpsc::channel();
+        let sql = "DELETE FROM TB_예약 WHERE ID = 3";
+
+        assert!(extractor.process_raw_frame(
+            &frame(true, CLIENT_SEQ, 0, &sql_batch(sql)),
+            3.0,
+            &sender
+        ));
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(event.sql_text, sql);
+        assert_eq!(event.flow_id, "10.0.0.5:50000->10.0.0.1:1433");
+        assert!(receiver.try_recv().is_err());
+
+        // 이벤트가 없는 프레임(서버 ACK)은 아무것도 보내지 않음
+        assert!(extractor.process_raw_frame(&frame(false, SERVER_SEQ, 0, &[]), 3.1, &sender));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn process_raw_frame_calls_callbacks_before_sending() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let callback_seen = Arc::clone(&seen);
+        extractor.on_event(Box::new(move |event: &SqlEvent| {
+            callback_seen.lock().unwrap().push(event.sql_text.clone());
+        }));
+        let (sender, receiver) = mpsc::channel();
+
+        extractor.process_raw_frame(
+            &frame(true, CLIENT_SEQ, 0, &sql_batch("SELECT 1 FROM TB_A")),
+            1.0,
+            &sender,
+        );
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec!["SELECT 1 FROM TB_A".to_string()]
+        );
+        assert_eq!(receiver.try_recv().unwrap().sql_text, "SELECT 1 FROM TB_A");
+    }
+
+    #[test]
+    fn process_raw_frame_reports_closed_receiver() {
+        let mut extractor = Extractor::builder().capture_responses(false).build();
+        let (sender, receiver) = mpsc::channel();
+        drop(receiver);
+
+        // 보낼 이벤트가 없으면 수신자가 없어도 계속
+        assert!(extractor.process_raw_frame(&frame(false, SERVER_SEQ, 0, &[]), 1.0, &sender));
+        assert!(!extractor.process_raw_frame(
+            &frame(true, CLIENT_SEQ, 0, &sql_batch("SELECT 1 FROM TB_A")),
+            1.1,
+            &sender
+        ));
+    }
+
+    /// 한 연결에서 쿼리를 count개 연속으로 보내고 수신한 이벤트의 SQL 목록 반환
+    fn send_queries(extractor: &mut Extractor, count: usize, interval: f64) -> Vec<String> {
+        let (sender, receiver) = mpsc::channel();
+        let mut seq = CLIENT_SEQ;
+        for i in 0..count {
+            let request = sql_batch(&format!("SELECT {} FROM TB_A", i));
+            assert!(extractor.process_raw_frame(
+                &frame(true, seq, 0, &request),
+                1.0 + i as f64 * interval,
+                &sender
+            ));
+            seq += request.len() as u32;
+        }
+        receiver.try_iter().map(|event| event.sql_text).collect()
+    }
+
+    #[test]
+    fn sampling_keeps_every_nth_event_and_counts_the_rest() {
+        let stats = Arc::new(CaptureStats::default());
+        let mut extractor = Extractor::builder()
+            .capture_responses(false)
+            .sample_every(10)
+            .stats(stats.clone())
+            .build();
+
+        let received = send_queries(&mut extractor, 100, 0.001);
+        assert_eq!(received.len(), 10);
+        assert_eq!(received[0], "SELECT 0 FROM TB_A");
+        assert_eq!(received[1], "SELECT 10 FROM TB_A");
+        assert_eq!(stats.sampled_out_events(), 90);
+        assert_eq!(stats.rate_limited_events(), 0);
+    }
+
+    #[test]
+    fn rate_limit_caps_events_per_second() {
+        let stats = Arc::new(CaptureStats::default());
+        let mut extractor = Extractor::builder()
+            .capture_responses(false)
+            .rate_limit(5, false)
+            .stats(stats.clone())
+            .build();
+
+        // 0.1초 간격 20개 = 2초 구간, 구간마다 5개
+        let received = send_queries(&mut extractor, 20, 0.1);
+        assert_eq!(received.len(), 10);
+        assert_eq!(stats.rate_limited_events(), 10);
+        assert_eq!(stats.sampled_out_events(), 0);
+    }
+
+    #[test]
+    fn rate_limit_summary_is_flushed_when_capture_finishes() {
+        let mut extractor = Extractor::builder()
+            .capture_responses(false)
+            .rate_limit(5, false)
+            .build();
+
+        // 마지막 구간(1초)에서 3개를 버린 채 캡처 종료
+        assert_eq!(send_queries(&mut extractor, 8, 0.1).len(), 5);
+        let limiter = extractor.rate_limiter.as_ref().unwrap();
+        assert_eq!(limiter.dropped_in_window, 3);
+
+        let (sender, _receiver) = mpsc::channel();
+        let (stop, stop_rx) = mpsc::channel();
+        stop.send(()).unwrap();
+        assert!(extractor.capture_finished(Instant::now(), &stop_rx, &sender));
+        let limiter = extractor.rate_limiter.as_mut().unwrap();
+        assert_eq!(limiter.dropped_in_window, 0);
+        assert_eq!(limiter.flush_summary(), 0);
+    }
+
+    #[test]
+    fn capture_ends_when_duration_elapses() {
+        let (status, status_receiver) = mpsc::channel();
+        let mut extractor = Extractor::builder()
+            .capture_duration(Some(Duration::from_millis(50)))
+            .status(status)
+            .build();
+        let (sender, receiver) = mpsc::channel();
+        let (_stop, stop_rx) = mpsc::channel();
+
+        // 응답을 기다리는 요청
+        assert!(extractor.process_raw_frame(
+            &frame(true, CLIENT_SEQ, 0, &sql_batch("SELECT 1 FROM TB_A")),
+            1.0,
+            &sender
+        ));
+        let started = Instant::now();
+        assert!(!extractor.capture_finished(started, &stop_rx, &sender));
+        assert!(receiver.try_recv().is_err());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(extractor.capture_finished(started, &stop_rx, &sender));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(receiver.try_recv().unwrap().sql_text, "SELECT 1 FROM TB_A");
+        assert!(matches!(
+            status_receiver.try_recv(),
+            Ok(CaptureStatus::DurationElapsed)
+        ));
+    }
+
+    #[test]
+    fn stop_signal_ends_capture_without_duration_status() {
+        let (status, status_receiver) = mpsc::channel();
+        let mut extractor = Extractor::builder().status(status).build();
+        let (sender, _receiver) = mpsc::channel();
+        let (stop, stop_rx) = mpsc::channel();
+
+        let started = Instant::now();
+        assert!(!extractor.capture_finished(started, &stop_rx, &sender));
+        stop.send(()).unwrap();
+        assert!(extractor.capture_finished(started, &stop_rx, &sender));
+        assert!(status_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn capture_options_follow_builder_and_config() {
+        assert_eq!(
+            Extractor::builder().build().capture_options(),
+            CaptureOptions {
+                promisc: true,
+                snaplen: 65535,
+                timeout_ms: 100,
+            }
+        );
+
+        let extractor = Extractor::builder().promisc(false).snaplen(256).build();
+        assert!(!extractor.capture_options().promisc);
+        assert_eq!(extractor.capture_options().snaplen, 256);
+
+        let config = crate::Config {
+            promisc: false,
+            snaplen: 1500,
+            ..crate::Config::default()
+        };
+        let options = config.extractor_builder().build().capture_options();
+        assert!(!options.promisc);
+        assert_eq!(options.snaplen, 1500);
+    }
+
+    /// 프레임들을 Ethernet pcap 파일로 저장 (프레임마다 1초 간격)
+    fn write_pcap_file(name: &str, frames: &[Vec<u8>]) -> std::path::PathBuf {
+        let mut pcap = Vec::new();
+        pcap.extend(0xa1b2_c3d4u32.to_le_bytes());
+        pcap.extend([2, 0, 4, 0]);
+        pcap.extend([0; 8]);
+        pcap.extend(65535u32.to_le_bytes());
+        pcap.extend(1u32.to_le_bytes());
+        for (i, frame) in frames.iter().enumerate() {
+            pcap.extend((i as u32).to_le_bytes());
+            pcap.extend(0u32.to_le_bytes());
+            pcap.extend((frame.len() as u32).to_le_bytes());
+            pcap.extend((frame.len() as u32).to_le_bytes());
+            pcap.extend(frame);
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("tds-sniffer-{}-{}.pcap", name, std::process::id()));
+        std::fs::write(&path, pcap).unwrap();
+        path
+    }
+
+    #[test]
+    fn file_progress_increases_until_the_whole_file() {
+        let frames: Vec<Vec<u8>> = (0..2500)
+            .map(|i| frame(true, CLIENT_SEQ + i, 0, &[]))
+            .collect();
+        let path = write_pcap_file("progress", &frames);
+        let (progress, progress_receiver) = mpsc::channel();
+        let mut extractor = Extractor::builder().progress(progress).build();
+        let (sender, _receiver) = mpsc::channel();
+        let (_stop, stop_rx) = mpsc::channel();
+
+        let result = extractor.process_file(&path, sender, stop_rx);
+        let total_bytes = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let reports: Vec<FileProgress> = progress_receiver.try_iter().collect();
+        assert_eq!(
+            reports.iter().map(|p| p.packets).collect::<Vec<_>>(),
+            vec![1000, 2000, 2500]
+        );
+        assert!(reports.windows(2).all(|pair| pair[0].bytes < pair[1].bytes));
+        assert!(reports.iter().all(|p| p.total_bytes == total_bytes));
+        assert_eq!(reports.last().unwrap().bytes, total_bytes);
+    }
+
+    #[test]
+    fn stop_signal_halts_file_processing_partway() {
+        let mut frames = Vec::new();
+        let mut seq = CLIENT_SEQ;
+        for i in 0..10 {
+            let request = sql_batch(&format!("SELECT {} FROM TB_A", i));
+            frames.push(frame(true, seq, 0, &request));
+            seq += request.len() as u32;
+        }
+        let path = write_pcap_file("cancel", &frames);
+        let (progress, progress_receiver) = mpsc::channel();
+        let mut extractor = Extractor::builder()
+            .capture_responses(false)
+            .progress(progress)
+            .build();
+        // 첫 이벤트를 받으면 취소
+        let (stop, stop_rx) = mpsc::channel();
+        extractor.on_event(Box::new(move |_: &SqlEvent| {
+            let _ = stop.send(());
+        }));
+        let (sender, receiver) = mpsc::channel();
+
+        let result = extractor.process_file(&path, sender, stop_rx);
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let events: Vec<SqlEvent> = receiver.try_iter().collect();
+        assert_eq!(events.len(), 1);
+        let last = progress_receiver.try_iter().last().unwrap();
+        assert_eq!(last.packets, 1);
+        assert!(last.bytes < last.total_bytes);
+    }
+
+    #[test]
+    fn pcap_errors_are_mapped_to_capture_errors() {
+        let map = |error: pcap::Error| ExtractorError::from(error);
+
+        assert!(matches!(
+            map(pcap::Error::IoError(std::io::ErrorKind::PermissionDenied)),
+            ExtractorError::PermissionDenied(_)
+        ));
+        // libpcap / Npcap 메시지는 대소문자와 관계없이 구분
+        assert!(matches!(
+            map(pcap::Error::PcapError(
+                "eth0: You don't have permission to capture on that device".to_string()
+            )),
+            ExtractorError::PermissionDenied(_)
+        ));
+        assert!(matches!(
+            map(pcap::Error::PcapError(
+                "socket: Operation not permitted".to_string()
+            )),
+            ExtractorError::PermissionDenied(_)
+        ));
+        assert!(matches!(
+            map(pcap::Error::PcapError(
+                "Error opening adapter: Access is denied.".to_string()
+            )),
+            ExtractorError::PermissionDenied(_)
+        ));
+        assert!(matches!(
+            map(pcap::Error::PcapError(
+                "SIOCGIFHWADDR: No such device".to_string()
+            )),
+            ExtractorError::NoSuchDevice(_)
+        ));
+        assert!(matches!(
+            map(pcap::Error::PcapError("snaplen too large".to_string())),
+            ExtractorError::Pcap(_)
+        ));
+    }
 }
@@ -1,64 +1,478 @@
+use crate::alert::{default_alert_rules, matching_rules, AlertRule};
+use crate::config::{Config, DedupScope, DisplayTimezone, Theme};
+#[cfg(feature = "control-api")]
+use crate::control::{ControlCommand, ControlRequest};
+use crate::diff::{changed_hunks, diff_sql, DiffKind, DiffSpan};
+use crate::export::{
+    auto_export_path, export_csv, export_json, export_session, export_table_graph, SessionSummary,
+};
+use crate::extractor::UNDECODED_OPERATION;
+use crate::normalize::{to_nfc, to_parameterized_script, to_sp_executesql};
+use crate::pcap_export::export_pcap;
+use crate::query::EventPredicate;
+use crate::resolve::HostResolver;
+use crate::tcp::{FlowId, FlowStat};
+use crate::tds::{TdsPacketType, TdsParser};
+#[cfg(feature = "webhook")]
+use crate::webhook::WebhookNotifier;
 use crate::{
-    extract_operations, extract_table_name, extract_tables_from_sql, Extractor, SqlEvent, SqlLogger,
+    extract_exec_target, extract_operations, extract_table_name, extract_tables_from_sql, hex_dump,
+    hex_rows, is_temp_table, parse_hex_dump, split_batch, to_rust_test, truncate_str,
+    CaptureRequest, CaptureStats, CaptureStatus, Extractor, ExtractorError, FileProgress,
+    InterfaceTestResult, QueryKind, SqlEvent, SqlLogger,
+};
+use chrono::{DateTime, Utc};
+use egui::text::LayoutJob;
+use egui::{
+    CentralPanel, Color32, FontId, Key, Modifiers, RichText, ScrollArea, SidePanel, TextEdit,
+    TextFormat, TopBottomPanel,
 };
-use egui::{CentralPanel, Color32, RichText, ScrollArea, SidePanel, TextEdit, TopBottomPanel};
-use std::collections::HashMap;
-use std::sync::mpsc;
+use egui_extras::{Column, TableBuilder};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// hex 디코딩 결과 (패킷별 SQL, 원본 패킷)
+type DecodedPackets = Vec<(String, Vec<u8>)>;
+
+/// 수신한 이벤트를 시간순으로 정렬하기 위해 보류하는 시간
+const REORDER_WINDOW: Duration = Duration::from_millis(200);
+
+/// 실시간 로그에 보관하는 최근 이벤트 수 (표시 개수 설정의 최대값)
+const MAX_RECENT_EVENTS: usize = 1000;
+
+/// 캡처 중 새 이벤트를 확인하는 주기 (이벤트가 없을 때 다시 그리는 간격)
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 인터페이스 테스트에서 패킷을 세는 시간
+const INTERFACE_TEST_DURATION: Duration = Duration::from_secs(5);
+
+/// 통계의 플로우 상태 갱신 간격
+const FLOW_STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 초당 쿼리 그래프에 보관하는 시간 (초)
+const THROUGHPUT_WINDOW_SECS: usize = 60;
 
 /// 뷰 모드
 #[derive(Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 enum ViewMode {
     ByTable,
     BySql,
+    ByFlow,
+    ByApp,
+    ByKind,
+}
+
+/// SQL 목록 정렬 기준
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortColumn {
+    Time,
+    Operation,
+    Latency,
+    Tables,
+    Sql,
+}
+
+/// 애플리케이션 이름을 모르는 이벤트의 그룹 (LOGIN7을 캡처하지 못한 연결)
+const UNKNOWN_APP: &str = "(알 수 없음)";
+
+/// 테이블을 찾지 못한 SQL(SET 문, SELECT 1, 변수 대입 등)의 그룹
+const OTHER_TABLE_GROUP: &str = "기타";
+
+/// 임시 테이블(#tmp, ##tmp)과 테이블 변수(@tv)를 사용하는 SQL의 그룹 (이름별로 나누지 않음)
+const TEMP_TABLE_GROUP: &str = "임시 테이블";
+
+/// 캡처 시간 선택지 (분, None이면 중지할 때까지)
+const CAPTURE_MINUTES_PRESETS: [Option<u32>; 5] = [None, Some(5), Some(10), Some(30), Some(60)];
+
+/// SQL 목록 위의 작업 필터 칩 (Alt+1 ~ Alt+5로 켜고 끔)
+/// EXEC 칩은 EXEC / EXECUTE 작업 그룹을 함께 다룸
+const OPERATION_CHIPS: [&str; 5] = ["SELECT", "INSERT", "UPDATE", "DELETE", "EXEC"];
+
+/// 최근 THROUGHPUT_WINDOW_SECS초 동안의 초당 수신 이벤트 수 (링 버퍼, 마지막이 현재 초)
+struct Throughput {
+    counts: VecDeque<u32>,
+    // 현재 초(counts의 마지막 칸)의 시작 시각
+    current_start: Instant,
+}
+
+impl Throughput {
+    fn new(now: Instant) -> Self {
+        Self {
+            counts: VecDeque::from([0]),
+            current_start: now,
+        }
+    }
+
+    /// 지난 초들을 0으로 채워 현재 초까지 진행한 뒤 count개 기록
+    fn record(&mut self, now: Instant, count: usize) {
+        while now.duration_since(self.current_start) >= Duration::from_secs(1) {
+            self.current_start += Duration::from_secs(1);
+            self.counts.push_back(0);
+            if self.counts.len() > THROUGHPUT_WINDOW_SECS {
+                self.counts.pop_front();
+            }
+            // 오래 멈췄다가 다시 그리면 창 전체가 0이므로 더 돌지 않음
+            if self.counts.iter().all(|&count| count == 0) {
+                self.current_start = now;
+            }
+        }
+        if let Some(current) = self.counts.back_mut() {
+            *current = current.saturating_add(count as u32);
+        }
+    }
+
+    /// 마지막으로 다 채운 초의 이벤트 수 (진행 중인 현재 초 제외)
+    fn last_second(&self) -> u32 {
+        self.counts.iter().rev().nth(1).copied().unwrap_or(0)
+    }
+
+    fn peak(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// 진행 중인 pcap 파일 처리 (처리 스레드와 주고받는 채널)
+struct FileReplay {
+    path: PathBuf,
+    events: mpsc::Receiver<SqlEvent>,
+    progress: mpsc::Receiver<FileProgress>,
+    result: mpsc::Receiver<Result<(), String>>,
+    stop: mpsc::Sender<()>,
+    latest: FileProgress,
+    cancelled: bool,
 }
 
 /// GUI 상태
 pub struct GuiState {
     events: Vec<SqlEvent>,
     // 중복 제거를 위한 SQL 텍스트 -> 이벤트 인덱스 매핑
-    unique_sql_map: HashMap<String, usize>, // sql_text -> 첫 번째 이벤트 인덱스
+    unique_sql_map: HashMap<String, usize>, // 중복 제거 키 (DedupScope::key) -> 첫 번째 이벤트 인덱스
+    last_seen: Vec<DateTime<Utc>>,          // 고유 SQL 인덱스 -> 마지막 실행 시각
+    table_names: Vec<String>,               // 고유 SQL 인덱스 -> 목록에 표시할 테이블들
     // 테이블별 그룹화 (TB_ 다음 부분이 테이블명)
     table_groups: HashMap<String, Vec<usize>>, // 테이블명 -> 고유 SQL 인덱스들
     // SQL별 그룹화
     operation_groups: HashMap<String, Vec<usize>>, // operation -> 고유 SQL 인덱스들
+    // Flow별 그룹화
+    flow_groups: HashMap<String, Vec<usize>>, // flow_id -> 고유 SQL 인덱스들
+    // 앱별 그룹화
+    app_groups: HashMap<String, Vec<usize>>, // app_name -> 고유 SQL 인덱스들
+    // 쿼리 작성 방식별 그룹화 (Parameterized / Ad-hoc / Static)
+    kind_groups: HashMap<String, Vec<usize>>, // QueryKind::label -> 고유 SQL 인덱스들
     view_mode: ViewMode,
     selected_table: Option<String>,
     selected_operation: Option<String>,
+    selected_flow: Option<String>,
+    selected_app: Option<String>,
+    selected_kind: Option<String>,
+    // 켜진 작업 필터 칩 (OPERATION_CHIPS 중, 기본값은 모두 켜짐)
+    active_operations: HashSet<&'static str>,
     show_details: Option<usize>,
+    // 상세 패널에 표시 중인 SQL 길이 (고유 SQL 인덱스, 바이트), 다른 이벤트를 선택하면 설정값부터 다시 시작
+    sql_display_len: Option<(usize, usize)>,
     show_raw: Option<usize>,
+    hex_selection: Option<(usize, usize)>, // 원본 hex 뷰어에서 선택한 바이트 범위 (처음 누른 바이트, 마지막 바이트)
+    hex_highlight_headers: bool,           // 원본 hex 뷰어에서 TDS 헤더 강조
+    scroll_to_details: bool,               // 다음 프레임에 상세 보기 중인 행으로 스크롤
+    sort_column: SortColumn,
+    sort_ascending: bool,
     pub is_capturing: bool,
     pub capture_started: bool,
+    capture_started_at: Option<DateTime<Utc>>, // 현재(마지막) 캡처 시작 시각 (자동 내보내기 요약용)
     processing_status: String,
     pub selected_interface: Option<String>, // 인터페이스 이름만 저장
+    interface_error: Option<ExtractorError>, // 선택한 인터페이스를 캡처할 수 없는 이유 (권한 등)
     available_interfaces: Vec<(String, String)>, // (이름, 설명)
     event_receiver: Option<mpsc::Receiver<SqlEvent>>,
+    reorder_buffer: Vec<(Instant, SqlEvent)>, // (수신 시각, 이벤트)
     stop_sender: Option<mpsc::Sender<()>>,
-    logger: SqlLogger, // SQL 이벤트 로거
+    capture_thread: Option<thread::JoinHandle<()>>, // 실행 중인 캡처 스레드 (중지할 때 끝날 때까지 기다림)
+    request_sender: Option<mpsc::Sender<CaptureRequest>>, // 캡처 스레드 조회 요청
+    capture_status_receiver: Option<mpsc::Receiver<CaptureStatus>>, // 캡처 스레드 상태 (장치 오류 등)
+    stream_dump_receiver: Option<(String, mpsc::Receiver<Option<Vec<u8>>>)>, // (flow_id, 응답)
+    stream_dump: Option<(String, Option<Vec<u8>>)>, // 표시 중인 스트림 덤프 (flow_id, 데이터)
+    flow_stats_receiver: Option<mpsc::Receiver<Vec<FlowStat>>>, // 플로우 상태 응답
+    flow_stats: Vec<FlowStat>,                      // 마지막으로 받은 플로우 상태
+    flow_stats_requested: Option<Instant>,          // 마지막 플로우 상태 요청 시각
+    // 진행 중인 인터페이스 테스트 결과
+    interface_test_receiver: Option<mpsc::Receiver<Result<InterfaceTestResult, String>>>,
+    file_replay: Option<FileReplay>, // 진행 중인 pcap 파일 처리
+    logger: SqlLogger,               // SQL 이벤트 로거
+    config: Config,                  // 기본 캡처 설정 (설정 파일)
+    stats: Arc<CaptureStats>,        // 현재 캡처의 통계 (캡처 스레드와 공유)
+    alert_rules: Vec<AlertRule>,
+    alerts: Vec<usize>, // 경고 규칙에 걸린 고유 SQL 인덱스들
+    show_alerts: bool,
+    recent: VecDeque<usize>, // 수신 순서대로 최근 이벤트의 고유 SQL 인덱스 (중복 실행 포함)
+    throughput: Throughput,  // 최근 1분간 초당 수신 이벤트 수 (통계의 그래프)
+    show_tail: bool,
+    show_hex_decode: bool,
+    hex_input: String, // hex 디코딩 창에 붙여넣은 문자열
+    hex_decode_result: Option<Result<DecodedPackets, String>>, // 디코딩 결과 또는 hex 파싱 오류
+    hex_packet_type: Option<TdsPacketType>, // hex 디코딩의 패킷 타입 (None이면 헤더로 판단)
+    diff_marks: Vec<usize>, // 비교 대상으로 표시한 고유 SQL 인덱스 (최대 2개, 먼저 표시한 것이 앞)
+    sql_diff: Option<Vec<DiffSpan>>, // 두 SQL의 비교 결과 (표시 중일 때만)
+    diff_side_by_side: bool, // SQL 비교 창에서 A / B를 나란히 표시 (false면 한 덩어리로)
+    search_query: String, // SQL 목록 / 실시간 로그 검색어
+    // 검색어를 해석한 조건식 (비어있으면 None) 및 조건식 오류 (오류면 검색어 전체를 SQL 검색어로 사용)
+    search_predicate: Option<EventPredicate>,
+    search_error: Option<String>,
+    resolver: HostResolver, // Flow 표시용 역방향 DNS 조회 (캐시)
+    #[cfg(feature = "webhook")]
+    webhook: Option<WebhookNotifier>, // 경고 웹훅 전송기
+    #[cfg(feature = "control-api")]
+    control_receiver: Option<mpsc::Receiver<ControlRequest>>, // 제어 API 명령
 }
 
 impl GuiState {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         let interfaces = Extractor::list_interfaces().unwrap_or_default();
+        let config = Config::load();
+
+        // 설정 파일의 인터페이스가 목록에 있으면 우선 선택
+        let selected_interface = config
+            .interface
+            .as_ref()
+            .filter(|saved| interfaces.iter().any(|(name, _)| name == *saved))
+            .cloned()
+            .or_else(|| interfaces.first().map(|(name, _)| name.clone()));
+        let interface_error = selected_interface
+            .as_deref()
+            .and_then(|name| Extractor::can_capture(name).err());
+
+        let mut logger = SqlLogger::new();
+        logger.set_log_dir(&config.log_dir);
+
         Self {
             events: Vec::new(),
             unique_sql_map: HashMap::new(),
+            last_seen: Vec::new(),
+            table_names: Vec::new(),
             table_groups: HashMap::new(),
             operation_groups: HashMap::new(),
+            flow_groups: HashMap::new(),
+            app_groups: HashMap::new(),
+            kind_groups: HashMap::new(),
             view_mode: ViewMode::ByTable,
             selected_table: None,
             selected_operation: None,
+            selected_flow: None,
+            selected_app: None,
+            selected_kind: None,
+            active_operations: OPERATION_CHIPS.into_iter().collect(),
             show_details: None,
+            sql_display_len: None,
             show_raw: None,
+            hex_selection: None,
+            hex_highlight_headers: true,
+            scroll_to_details: false,
+            sort_column: SortColumn::Time,
+            sort_ascending: true,
             is_capturing: false,
             capture_started: false,
+            capture_started_at: None,
             processing_status: String::new(),
-            selected_interface: interfaces.first().map(|(name, _)| name.clone()),
+            selected_interface,
+            interface_error,
             available_interfaces: interfaces,
             event_receiver: None,
+            reorder_buffer: Vec::new(),
             stop_sender: None,
-            logger: SqlLogger::new(),
+            capture_thread: None,
+            request_sender: None,
+            capture_status_receiver: None,
+            stream_dump_receiver: None,
+            stream_dump: None,
+            flow_stats_receiver: None,
+            flow_stats: Vec::new(),
+            flow_stats_requested: None,
+            interface_test_receiver: None,
+            file_replay: None,
+            logger,
+            stats: Arc::default(),
+            alert_rules: default_alert_rules(),
+            alerts: Vec::new(),
+            show_alerts: true,
+            recent: VecDeque::new(),
+            throughput: Throughput::new(Instant::now()),
+            show_tail: false,
+            show_hex_decode: false,
+            hex_input: String::new(),
+            hex_decode_result: None,
+            hex_packet_type: None,
+            diff_marks: Vec::new(),
+            sql_diff: None,
+            diff_side_by_side: false,
+            search_query: String::new(),
+            search_predicate: None,
+            search_error: None,
+            resolver: HostResolver::new(),
+            #[cfg(feature = "webhook")]
+            webhook: config.webhook_url.as_deref().map(WebhookNotifier::new),
+            #[cfg(feature = "control-api")]
+            control_receiver: None,
+            config,
+        }
+    }
+
+    /// 화면에 표시할 Flow 문자열
+    /// 호스트 이름 표시 설정이면 조회가 끝난 IP를 이름으로 바꿔 "client:port->dbhost:1433" 형식으로 표시
+    /// (조회 중이거나 이름이 없는 IP는 그대로 표시)
+    pub fn display_flow(&self, flow_id: &str) -> String {
+        if !self.config.resolve_hosts {
+            return flow_id.to_string();
+        }
+        let Ok(flow) = flow_id.parse::<FlowId>() else {
+            return flow_id.to_string();
+        };
+
+        let host = |ip: IpAddr| {
+            self.resolver
+                .resolve_host(ip)
+                .unwrap_or_else(|| ip.to_string())
+        };
+        format!(
+            "{}:{}->{}:{}",
+            host(flow.src_ip),
+            flow.src_port,
+            host(flow.dst_ip),
+            flow.dst_port
+        )
+    }
+
+    /// 현재 설정
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// 현재 설정을 설정 파일에 저장
+    pub fn save_config(&mut self) {
+        self.config.interface = self.selected_interface.clone();
+        self.processing_status = match self.config.save() {
+            Ok(path) => format!("설정 저장됨 ({})", path.display()),
+            Err(e) => format!("설정 저장 실패: {}", e),
+        };
+    }
+
+    /// 화면에 표시 중인 이벤트 (선택한 그룹과 검색어 적용, 목록 정렬 순서)
+    /// 내보내기는 이 이벤트만 저장
+    pub fn visible_events(&self) -> Vec<SqlEvent> {
+        self.sorted_events()
+            .into_iter()
+            .map(|idx| self.events[idx].clone())
+            .collect()
+    }
+
+    /// 고유 SQL을 비교 대상으로 표시하거나 해제
+    /// 세 번째를 표시하면 가장 먼저 표시한 것은 해제되며, 두 개가 되면 비교 창을 엶
+    fn toggle_diff_mark(&mut self, idx: usize) {
+        if let Some(pos) = self.diff_marks.iter().position(|&marked| marked == idx) {
+            self.diff_marks.remove(pos);
+        } else {
+            self.diff_marks.push(idx);
+            if self.diff_marks.len() > 2 {
+                self.diff_marks.remove(0);
+            }
         }
+        self.update_sql_diff();
+    }
+
+    /// 비교 대상이 두 개면 비교 결과 계산 (아니면 비교 창 닫음)
+    fn update_sql_diff(&mut self) {
+        self.sql_diff = match self.diff_marks[..] {
+            [a, b] => Some(diff_sql(
+                &self.events[a].full_text(),
+                &self.events[b].full_text(),
+            )),
+            _ => None,
+        };
+    }
+
+    /// 표시 중인 이벤트의 원본 TDS 패킷을 pcap 파일로 내보내기
+    pub fn export_pcap(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("pcap", &["pcap"])
+            .set_file_name("sql_capture.pcap")
+            .save_file()
+        else {
+            return;
+        };
+
+        self.processing_status = match export_pcap(&path, &self.visible_events()) {
+            Ok(count) => format!("pcap 내보내기 완료 ({}개 패킷, {})", count, path.display()),
+            Err(e) => format!("pcap 내보내기 실패: {}", e),
+        };
+    }
+
+    /// 표시 중인 이벤트를 JSON 파일로 내보내기
+    pub fn export_json(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("sql_capture.json")
+            .save_file()
+        else {
+            return;
+        };
+
+        self.processing_status = match export_json(&path, &self.visible_events(), None) {
+            Ok(count) => format!(
+                "JSON 내보내기 완료 ({}개 이벤트, {})",
+                count,
+                path.display()
+            ),
+            Err(e) => format!("JSON 내보내기 실패: {}", e),
+        };
+    }
+
+    /// 표시 중인 이벤트를 CSV 파일로 내보내기
+    pub fn export_csv(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("sql_capture.csv")
+            .save_file()
+        else {
+            return;
+        };
+
+        self.processing_status = match export_csv(&path, &self.visible_events(), None) {
+            Ok(count) => format!("CSV 내보내기 완료 ({}개 이벤트, {})", count, path.display()),
+            Err(e) => format!("CSV 내보내기 실패: {}", e),
+        };
+    }
+
+    /// 캡처한 모든 이벤트의 테이블 관계 그래프(같은 SQL에 함께 등장한 테이블)를 파일로 저장
+    /// 확장자가 .dot / .gv면 GraphViz DOT, 그 외에는 JSON 간선 목록
+    /// 반환값: 기록한 간선 수
+    pub fn export_table_graph(&self, path: &Path) -> Result<usize, String> {
+        export_table_graph(path, &self.events)
+    }
+
+    /// 테이블 관계 그래프 저장 (파일 선택 창)
+    fn save_table_graph(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("GraphViz", &["dot", "gv"])
+            .add_filter("JSON", &["json"])
+            .set_file_name("table_graph.dot")
+            .save_file()
+        else {
+            return;
+        };
+
+        self.processing_status = match self.export_table_graph(&path) {
+            Ok(count) => format!(
+                "테이블 관계 그래프 저장 완료 ({}개 관계, {})",
+                count,
+                path.display()
+            ),
+            Err(e) => format!("테이블 관계 그래프 저장 실패: {}", e),
+        };
     }
 
     /// 이벤트 수신기 설정
@@ -71,23 +485,409 @@ impl GuiState {
         self.stop_sender = Some(sender);
     }
 
-    /// 캡처 시작
-    pub fn start_capture(&mut self) {
-        if self.is_capturing || self.selected_interface.is_none() {
+    /// 캡처 스레드 설정 (캡처를 시작할 때마다)
+    pub fn set_capture_thread(&mut self, handle: thread::JoinHandle<()>) {
+        self.capture_thread = Some(handle);
+    }
+
+    /// 현재 캡처의 통계 (캡처 스레드의 Extractor에 전달)
+    pub fn capture_stats(&self) -> Arc<CaptureStats> {
+        Arc::clone(&self.stats)
+    }
+
+    /// 캡처 스레드 요청 sender 설정
+    pub fn set_request_sender(&mut self, sender: mpsc::Sender<CaptureRequest>) {
+        self.request_sender = Some(sender);
+    }
+
+    /// 캡처 스레드 상태 receiver 설정 (캡처를 시작할 때마다 새 채널)
+    pub fn set_capture_status_receiver(&mut self, receiver: mpsc::Receiver<CaptureStatus>) {
+        self.capture_status_receiver = Some(receiver);
+    }
+
+    /// 캡처 스레드 상태 확인
+    /// 장치를 다시 여는 중이면 상태 표시줄에 표시하고, 인터페이스가 사라지는 등 캡처를 시작하지 못했거나
+    /// 다시 열지 못해 캡처가 끝나면 캡처 중지 상태로 되돌림
+    fn poll_capture_status(&mut self) {
+        let Some(ref receiver) = self.capture_status_receiver else {
             return;
+        };
+        let statuses: Vec<CaptureStatus> = receiver.try_iter().collect();
+
+        for status in statuses {
+            if !self.is_capturing {
+                break;
+            }
+            match status {
+                CaptureStatus::Reconnecting {
+                    attempt,
+                    max_attempts,
+                    error,
+                } => {
+                    self.processing_status = format!(
+                        "캡처 장치 다시 연결 중 ({}/{}): {}",
+                        attempt, max_attempts, error
+                    );
+                }
+                CaptureStatus::Reconnected => {
+                    self.processing_status = "캡처 장치 다시 연결됨, 캡처 계속".to_string();
+                }
+                CaptureStatus::DurationElapsed => {
+                    // 캡처 스레드가 끝나기 전에 보낸 이벤트까지 받은 뒤 중지 (로그 파일 종료 메시지도 기록)
+                    self.stop_capture();
+                    self.capture_status_receiver = None;
+                    self.processing_status = format!("캡처 시간 종료 - {}", self.processing_status);
+                }
+                CaptureStatus::Failed(error) => {
+                    // 이미 받은 이벤트는 반영하고 로그 파일 마무리
+                    self.flush_reorder_buffer();
+                    self.logger.stop_capture(self.events.len());
+                    self.is_capturing = false;
+                    self.capture_started = false;
+                    self.stop_sender = None;
+                    self.capture_thread = None;
+                    self.capture_status_receiver = None;
+                    self.processing_status = format!("캡처 실패: {}", error);
+                }
+            }
         }
+    }
 
-        // 기존 데이터 초기화
+    /// 붙여넣은 hex 문자열을 TDS 패킷으로 디코딩 (캡처 없이 파서만 확인)
+    /// 패킷 타입을 지정하면 헤더 확인 없이 패킷 하나로 보고 그 타입으로 디코딩
+    fn decode_hex_input(&mut self) {
+        let packet_type = self.hex_packet_type;
+        self.hex_decode_result = Some(parse_hex_dump(&self.hex_input).map(|bytes| {
+            if let Some(packet_type) = packet_type {
+                return TdsParser::decode_tds_packet_unchecked(&bytes, packet_type)
+                    .map(|sql| (sql, bytes))
+                    .into_iter()
+                    .collect();
+            }
+            let (decoded, raw) = TdsParser::decode_tds_packets_with_raw(&bytes);
+            decoded.into_iter().zip(raw).collect()
+        }));
+    }
+
+    /// 플로우의 재조립된 클라이언트 스트림 중 아직 디코딩하지 않은 부분 요청 (응답은 poll_stream_dump에서 수신)
+    pub fn request_stream_dump(&mut self, flow_id: &str) {
+        let flow = match flow_id.parse::<FlowId>() {
+            Ok(flow) => flow,
+            Err(e) => {
+                self.processing_status = format!("스트림 덤프 실패: {}", e);
+                return;
+            }
+        };
+
+        let Some(ref sender) = self.request_sender else {
+            self.processing_status = "스트림 덤프 실패: 캡처 중에만 가능합니다".to_string();
+            return;
+        };
+
+        let (reply, receiver) = mpsc::channel();
+        let request = CaptureRequest::ClientStream {
+            flow_id: flow,
+            reply,
+        };
+        if sender.send(request).is_err() {
+            self.processing_status = "스트림 덤프 실패: 캡처 중에만 가능합니다".to_string();
+            return;
+        }
+        self.stream_dump_receiver = Some((flow_id.to_string(), receiver));
+    }
+
+    /// 스트림 덤프 응답 확인
+    fn poll_stream_dump(&mut self) {
+        let Some((ref flow_id, ref receiver)) = self.stream_dump_receiver else {
+            return;
+        };
+
+        match receiver.try_recv() {
+            Ok(data) => {
+                self.stream_dump = Some((flow_id.clone(), data));
+                self.stream_dump_receiver = None;
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => {
+                // 캡처 스레드가 종료됨
+                self.processing_status = "스트림 덤프 실패: 캡처 중에만 가능합니다".to_string();
+                self.stream_dump_receiver = None;
+            }
+        }
+    }
+
+    /// 캡처 스레드에 플로우 재조립 상태 요청 (FLOW_STATS_INTERVAL마다 한 번, 응답은 poll_flow_stats에서 수신)
+    fn request_flow_stats(&mut self) {
+        if self.flow_stats_receiver.is_some()
+            || self
+                .flow_stats_requested
+                .is_some_and(|requested| requested.elapsed() < FLOW_STATS_INTERVAL)
+        {
+            return;
+        }
+        let Some(ref sender) = self.request_sender else {
+            return;
+        };
+
+        let (reply, receiver) = mpsc::channel();
+        if sender.send(CaptureRequest::FlowStats { reply }).is_ok() {
+            self.flow_stats_receiver = Some(receiver);
+            self.flow_stats_requested = Some(Instant::now());
+        }
+    }
+
+    /// 선택한 인터페이스에서 INTERFACE_TEST_DURATION 동안 패킷 수신 확인 (결과는 poll_interface_test에서 수신)
+    pub fn start_interface_test(&mut self) {
+        if self.is_capturing || self.interface_test_receiver.is_some() {
+            return;
+        }
+        let Some(interface) = self.selected_interface.clone() else {
+            return;
+        };
+
+        let extractor = self.config.extractor_builder().build();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = extractor
+                .test_interface(&interface, INTERFACE_TEST_DURATION)
+                .map_err(|e| e.to_string());
+            let _ = sender.send(result);
+        });
+
+        self.interface_test_receiver = Some(receiver);
+        self.processing_status = format!(
+            "인터페이스 테스트 중... ({}초)",
+            INTERFACE_TEST_DURATION.as_secs()
+        );
+    }
+
+    /// 인터페이스 테스트 결과 확인
+    fn poll_interface_test(&mut self) {
+        let Some(ref receiver) = self.interface_test_receiver else {
+            return;
+        };
+
+        match receiver.try_recv() {
+            Ok(Ok(result)) => {
+                self.processing_status = format!("인터페이스 테스트: {}", result.summary());
+                self.interface_test_receiver = None;
+            }
+            Ok(Err(e)) => {
+                self.processing_status = format!("인터페이스 테스트 실패: {}", e);
+                self.interface_test_receiver = None;
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => self.interface_test_receiver = None,
+        }
+    }
+
+    /// pcap 파일을 골라서 처리 시작
+    pub fn open_pcap_file(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("pcap", &["pcap", "pcapng", "cap"])
+            .pick_file()
+        else {
+            return;
+        };
+        self.start_file_replay(path);
+    }
+
+    /// 저장된 pcap 파일을 별도 스레드에서 처리 (캡처 중이거나 이미 처리 중이면 무시)
+    /// 기존 이벤트는 지우고 실시간 캡처와 같은 설정으로 처리하며, 진행 표시줄의 취소로 중간에 멈출 수 있음
+    pub fn start_file_replay(&mut self, path: PathBuf) {
+        if self.is_capturing || self.file_replay.is_some() {
+            return;
+        }
+
+        self.clear_events();
+        self.stats = Arc::default();
+
+        let (event_sender, events) = mpsc::channel();
+        let (progress_sender, progress) = mpsc::channel();
+        let (result_sender, result) = mpsc::channel();
+        let (stop, stop_receiver) = mpsc::channel();
+        let mut extractor = self
+            .config
+            .extractor_builder()
+            .stats(self.capture_stats())
+            .progress(progress_sender)
+            .build();
+        let file = path.clone();
+        std::thread::spawn(move || {
+            let outcome = extractor
+                .process_file(&file, event_sender, stop_receiver)
+                .map_err(|e| e.to_string());
+            let _ = result_sender.send(outcome);
+        });
+
+        self.processing_status = format!("파일 처리 중... ({})", path.display());
+        self.file_replay = Some(FileReplay {
+            path,
+            events,
+            progress,
+            result,
+            stop,
+            latest: FileProgress::default(),
+            cancelled: false,
+        });
+    }
+
+    /// 진행 중인 pcap 파일 처리 취소 (그때까지 받은 이벤트는 유지)
+    pub fn cancel_file_replay(&mut self) {
+        if let Some(ref mut replay) = self.file_replay {
+            let _ = replay.stop.send(());
+            replay.cancelled = true;
+        }
+    }
+
+    /// 파일 처리 진행 상황 (처리 중이 아니면 None)
+    pub fn file_progress(&self) -> Option<FileProgress> {
+        self.file_replay.as_ref().map(|replay| replay.latest)
+    }
+
+    /// pcap 파일 처리 스레드의 이벤트와 진행 상황 반영
+    /// 반환값: 이번에 받은 이벤트가 있으면 true
+    fn poll_file_replay(&mut self) -> bool {
+        let Some(ref mut replay) = self.file_replay else {
+            return false;
+        };
+        if let Some(progress) = replay.progress.try_iter().last() {
+            replay.latest = progress;
+        }
+        let new_events: Vec<SqlEvent> = replay.events.try_iter().collect();
+        let outcome = match replay.result.try_recv() {
+            Ok(outcome) => Some(outcome),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err("처리 스레드 종료".to_string())),
+        };
+
+        let received = !new_events.is_empty();
+        if received {
+            self.commit_events(new_events);
+        }
+
+        let Some(outcome) = outcome else {
+            let latest = self.file_progress().unwrap_or_default();
+            self.processing_status = format!(
+                "파일 처리 중... {:.0}% ({}개 패킷, {}개 이벤트)",
+                latest.fraction() * 100.0,
+                latest.packets,
+                self.events.len()
+            );
+            return received;
+        };
+        let Some(replay) = self.file_replay.take() else {
+            return received;
+        };
+
+        // 처리 스레드가 끝나기 전에 보낸 이벤트까지 반영
+        self.commit_events(replay.events.try_iter().collect());
+        self.processing_status = match outcome {
+            Err(e) => format!("파일 처리 실패: {} ({})", e, replay.path.display()),
+            _ if replay.cancelled => format!(
+                "파일 처리 취소됨 ({}개 패킷까지, 총 {}개 이벤트)",
+                replay.latest.packets,
+                self.events.len()
+            ),
+            _ => format!(
+                "파일 처리 완료 ({}개 패킷, 총 {}개 이벤트, {})",
+                replay.latest.packets,
+                self.events.len(),
+                replay.path.display()
+            ),
+        };
+        true
+    }
+
+    /// 플로우 상태 응답 확인
+    fn poll_flow_stats(&mut self) {
+        let Some(ref receiver) = self.flow_stats_receiver else {
+            return;
+        };
+
+        match receiver.try_recv() {
+            Ok(mut flow_stats) => {
+                // 최근에 패킷을 받은 플로우가 위로
+                flow_stats.sort_by(|a, b| b.last_timestamp.total_cmp(&a.last_timestamp));
+                self.flow_stats = flow_stats;
+                self.flow_stats_receiver = None;
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => self.flow_stats_receiver = None,
+        }
+    }
+
+    /// 중복 제거된 이벤트 수
+    pub fn event_count(&self) -> usize {
+        self.events.len()
+    }
+
+    /// 인터페이스 선택 (캡처 권한을 미리 확인해 캡처할 수 없으면 시작 버튼 비활성화)
+    pub fn select_interface(&mut self, name: String) {
+        self.interface_error = Extractor::can_capture(&name).err();
+        if let Some(ref error) = self.interface_error {
+            self.processing_status = error.to_string();
+        }
+        self.selected_interface = Some(name);
+    }
+
+    /// 수집한 이벤트와 그룹, 선택 상태 초기화
+    pub fn clear_events(&mut self) {
         self.events.clear();
+        self.reorder_buffer.clear();
         self.unique_sql_map.clear();
+        self.last_seen.clear();
+        self.table_names.clear();
         self.table_groups.clear();
         self.operation_groups.clear();
+        self.flow_groups.clear();
+        self.app_groups.clear();
+        self.kind_groups.clear();
+        self.alerts.clear();
+        self.recent.clear();
+        self.throughput = Throughput::new(Instant::now());
+        self.selected_table = None;
+        self.selected_operation = None;
+        self.selected_flow = None;
+        self.selected_app = None;
+        self.selected_kind = None;
+        self.show_details = None;
+        self.show_raw = None;
+        self.stream_dump = None;
+        self.diff_marks.clear();
+        self.sql_diff = None;
+        self.flow_stats.clear();
+    }
+
+    /// 보기 모드 전환 (모든 그룹 선택과 상세 보기를 초기화)
+    fn set_view_mode(&mut self, mode: ViewMode) {
+        self.view_mode = mode;
         self.selected_table = None;
         self.selected_operation = None;
+        self.selected_flow = None;
+        self.selected_app = None;
+        self.selected_kind = None;
         self.show_details = None;
         self.show_raw = None;
+    }
+
+    /// 캡처 시작
+    pub fn start_capture(&mut self) {
+        if self.is_capturing
+            || self.file_replay.is_some()
+            || self.selected_interface.is_none()
+            || self.interface_error.is_some()
+        {
+            return;
+        }
+
+        // 기존 데이터 초기화
+        self.clear_events();
+        self.stats = Arc::default();
 
         // 로그 파일 생성
+        self.logger.set_anonymize(self.config.anonymize);
+        self.logger.set_timezone(self.config.timezone);
         match self.logger.start_capture(self.selected_interface.as_ref()) {
             Ok(log_filename) => {
                 self.processing_status = format!("캡처 시작 중... (로그: {})", log_filename);
@@ -99,6 +899,7 @@ impl GuiState {
 
         self.is_capturing = true;
         self.capture_started = false;
+        self.capture_started_at = Some(Utc::now());
     }
 
     /// 캡처 중지
@@ -111,6 +912,18 @@ impl GuiState {
             let _ = sender.send(());
         }
 
+        // 캡처 스레드가 중지 신호를 받고 보낸 마지막 이벤트(응답을 기다리던 요청)까지 받은 뒤 반영
+        // (로그 파일 종료 메시지와 자동 내보내기에 포함되도록)
+        if let Some(handle) = self.capture_thread.take() {
+            let _ = handle.join();
+        }
+        if let Some(ref receiver) = self.event_receiver {
+            let now = Instant::now();
+            self.reorder_buffer
+                .extend(receiver.try_iter().map(|event| (now, event)));
+        }
+        self.flush_reorder_buffer();
+
         // 로그 파일에 종료 메시지 작성
         self.logger.stop_capture(self.events.len());
 
@@ -123,57 +936,250 @@ impl GuiState {
         self.is_capturing = false;
         self.capture_started = false;
         self.processing_status = format!(
-            "캡처 중지됨 (총 {}개 이벤트){}",
+            "캡처 중지됨 (총 {}개 이벤트){}{}",
             self.events.len(),
-            log_file_info
+            log_file_info,
+            self.auto_export()
         );
     }
 
-    /// 새 이벤트 추가 (중복 제거 및 그룹화)
-    pub fn add_event(&mut self, event: SqlEvent) {
-        // 중복 체크: 같은 SQL 텍스트가 이미 있으면 추가하지 않음
-        let sql_key = event.sql_text.trim().to_string();
-        let unique_idx = if let Some(&existing_idx) = self.unique_sql_map.get(&sql_key) {
-            // 이미 존재하는 SQL이면 기존 인덱스 사용
-            existing_idx
-        } else {
-            // 새로운 고유 SQL이면 추가
-            let idx = self.events.len();
-            self.events.push(event);
-            self.unique_sql_map.insert(sql_key, idx);
-            idx
+    /// 자동 내보내기 설정이면 세션을 JSONL로 저장 (중지 버튼, 자동 중지, 종료 모두)
+    /// 반환값: 상태 표시줄에 붙일 결과 (설정하지 않았으면 빈 문자열)
+    fn auto_export(&self) -> String {
+        let Some(ref target) = self.config.auto_export else {
+            return String::new();
         };
 
-        let event = &self.events[unique_idx];
-
-        // 새로운 고유 SQL이 추가되었을 때만 로깅
-        if unique_idx == self.events.len() - 1 {
-            self.logger.log_event(event);
+        let summary = SessionSummary {
+            interface: self.selected_interface.clone(),
+            started_at: self.capture_started_at,
+            stopped_at: Utc::now(),
+            events: self.events.len(),
+        };
+        let path = auto_export_path(target, &summary.stopped_at);
+        match export_session(&path, &summary, &self.events) {
+            Ok(_) => format!(" (내보내기: {})", path.display()),
+            Err(e) => {
+                tracing::warn!("자동 내보내기 실패: {}", e);
+                format!(" (자동 내보내기 실패: {})", e)
+            }
         }
+    }
 
-        // 테이블별 그룹화 (TB_ 다음 부분이 테이블명)
-        // event.tables가 비어있으면 SQL 텍스트에서 직접 추출
-        let tables = if event.tables.is_empty() {
-            extract_tables_from_sql(&event.sql_text)
-        } else {
-            event.tables.clone()
+    /// 제어 API 명령을 받을 채널 설정
+    #[cfg(feature = "control-api")]
+    pub fn set_control_receiver(&mut self, receiver: mpsc::Receiver<ControlRequest>) {
+        self.control_receiver = Some(receiver);
+    }
+
+    /// 제어 API로 받은 명령 처리
+    #[cfg(feature = "control-api")]
+    fn poll_control_requests(&mut self) {
+        let Some(ref receiver) = self.control_receiver else {
+            return;
         };
+        let requests: Vec<ControlRequest> = receiver.try_iter().collect();
+        for request in requests {
+            let response = self.handle_control_command(&request.command);
+            let _ = request.reply.send(response);
+        }
+    }
 
-        // 중복 체크: 이미 그룹에 있으면 추가하지 않음
-        if tables.is_empty() {
-            let group = self.table_groups.entry("기타".to_string()).or_default();
-            if !group.contains(&unique_idx) {
-                group.push(unique_idx);
+    /// 제어 API 명령 하나를 처리하고 JSON 응답 반환
+    /// ({"ok": true, "command": ..., ...} 또는 {"ok": false, "command": ..., "error": ...})
+    #[cfg(feature = "control-api")]
+    pub fn handle_control_command(&mut self, command: &ControlCommand) -> serde_json::Value {
+        use serde_json::json;
+
+        let result = match command {
+            ControlCommand::Start { interface } => {
+                if let Some(interface) = interface {
+                    if !self.is_capturing {
+                        self.select_interface(interface.clone());
+                    }
+                }
+                if self.is_capturing {
+                    Err("Already capturing".to_string())
+                } else {
+                    self.start_capture();
+                    if self.is_capturing {
+                        Ok(json!({
+                            "interface": self.selected_interface,
+                            "log": self.logger.get_file_path(),
+                        }))
+                    } else {
+                        // 인터페이스를 선택하지 않았거나 캡처할 수 없는 인터페이스
+                        Err(match self.interface_error {
+                            Some(ref error) => error.to_string(),
+                            None => "No interface selected".to_string(),
+                        })
+                    }
+                }
             }
-        } else {
-            for table in &tables {
-                let table_name = extract_table_name(table);
-                let group = self.table_groups.entry(table_name).or_default();
-                if !group.contains(&unique_idx) {
-                    group.push(unique_idx);
+            ControlCommand::Stop => {
+                if self.is_capturing {
+                    self.stop_capture();
+                    Ok(json!({ "events": self.events.len() }))
+                } else {
+                    Err("Not capturing".to_string())
                 }
             }
-        }
+            ControlCommand::Clear => {
+                let cleared = self.events.len();
+                self.clear_events();
+                Ok(json!({ "cleared": cleared }))
+            }
+            ControlCommand::GetEvents { since } => {
+                let events = self.events.get(*since..).unwrap_or_default();
+                Ok(json!({
+                    "capturing": self.is_capturing,
+                    "events": events,
+                    "next": self.events.len(),
+                }))
+            }
+        };
+
+        match result {
+            Ok(mut value) => {
+                value["ok"] = json!(true);
+                value["command"] = json!(command.name());
+                value
+            }
+            Err(e) => json!({ "ok": false, "command": command.name(), "error": e }),
+        }
+    }
+
+    /// 새 이벤트 추가 (중복 제거 및 그룹화)
+    pub fn add_event(&mut self, mut event: SqlEvent) {
+        // NFC/NFD로 다르게 온 같은 한글 SQL·테이블명이 하나로 묶이도록 정규화
+        if let Cow::Owned(sql_text) = to_nfc(&event.sql_text) {
+            event.sql_text = sql_text;
+        }
+        for table in &mut event.tables {
+            if let Cow::Owned(normalized) = to_nfc(table) {
+                *table = normalized;
+            }
+        }
+
+        // 중복 체크: 같은 SQL 텍스트가 이미 있으면 추가하지 않음 (설정에서 끌 수 있음)
+        // 연결별 범위면 같은 연결에서 보낸 같은 SQL만 하나로 묶음
+        let sql_key = self
+            .config
+            .dedup_scope
+            .key(&event.flow_id, &event.full_text());
+        // 디코딩하지 못한 패킷(빈 SQL)은 각각 따로 표시
+        let existing = if self.config.dedup && !event.sql_text.trim().is_empty() {
+            self.unique_sql_map.get(&sql_key).copied()
+        } else {
+            None
+        };
+        let (unique_idx, is_new) = if let Some(existing_idx) = existing {
+            // 이미 존재하는 SQL이면 기존 인덱스 사용 (마지막 실행 시각과 응답 시간만 갱신)
+            let last_seen = &mut self.last_seen[existing_idx];
+            *last_seen = (*last_seen).max(event.timestamp);
+            if event.latency_ms.is_some() {
+                self.events[existing_idx].latency_ms = event.latency_ms;
+            }
+            (existing_idx, false)
+        } else {
+            // 새로운 고유 SQL이면 추가
+            let idx = self.events.len();
+            self.last_seen.push(event.timestamp);
+            self.events.push(event);
+            self.unique_sql_map.insert(sql_key, idx);
+            (idx, true)
+        };
+
+        // 실시간 로그 (중복 제거와 관계없이 수신 순서대로)
+        self.recent.push_back(unique_idx);
+        if self.recent.len() > MAX_RECENT_EVENTS {
+            self.recent.pop_front();
+        }
+
+        let event = &self.events[unique_idx];
+
+        // 새로운 고유 SQL이 추가되었을 때만 로깅 및 경고 규칙 검사
+        // (마지막 고유 SQL이 반복된 경우에도 경고·웹훅이 다시 나가지 않도록 인덱스가 아닌 플래그로 판단)
+        if is_new {
+            self.logger.log_event(event);
+
+            let matched = matching_rules(&self.alert_rules, event);
+            if !matched.is_empty() {
+                #[cfg(feature = "webhook")]
+                if let Some(ref webhook) = self.webhook {
+                    webhook.notify(event, &matched);
+                }
+                self.alerts.push(unique_idx);
+            }
+        }
+
+        // 테이블별 그룹화 (TB_ 다음 부분이 테이블명)
+        // event.tables가 비어있으면 SQL 텍스트에서 직접 추출
+        let tables = if event.tables.is_empty() {
+            extract_tables_from_sql(&event.sql_text)
+        } else {
+            event.tables.clone()
+        };
+        if unique_idx == self.table_names.len() {
+            self.table_names.push(tables.join(", "));
+        }
+
+        // EXEC 호출은 프로시저별 그룹으로 (예: "PROC: usp_Foo")
+        let exec_target = extract_exec_target(&event.sql_text);
+        if let Some(ref proc_name) = exec_target {
+            let short_name = proc_name.rsplit('.').next().unwrap_or(proc_name);
+            let group = self
+                .table_groups
+                .entry(format!("PROC: {}", short_name))
+                .or_default();
+            if !group.contains(&unique_idx) {
+                group.push(unique_idx);
+            }
+        }
+
+        // 중복 체크: 이미 그룹에 있으면 추가하지 않음
+        if event.operation == UNDECODED_OPERATION {
+            // 진단 모드의 디코딩하지 못한 패킷은 별도 그룹
+            let group = self
+                .table_groups
+                .entry("미해석 패킷".to_string())
+                .or_default();
+            group.push(unique_idx);
+        } else if tables.is_empty() && exec_target.is_none() {
+            let group = self
+                .table_groups
+                .entry(OTHER_TABLE_GROUP.to_string())
+                .or_default();
+            if !group.contains(&unique_idx) {
+                group.push(unique_idx);
+            }
+        } else {
+            for table in &tables {
+                if is_temp_table(table) {
+                    let group = self
+                        .table_groups
+                        .entry(TEMP_TABLE_GROUP.to_string())
+                        .or_default();
+                    if !group.contains(&unique_idx) {
+                        group.push(unique_idx);
+                    }
+                    continue;
+                }
+
+                // 대소문자만 다른 테이블은 먼저 생긴 그룹에 포함
+                let table_name = extract_table_name(table);
+                let table_name = self
+                    .table_groups
+                    .keys()
+                    .find(|key| key.to_lowercase() == table_name.to_lowercase())
+                    .cloned()
+                    .unwrap_or(table_name);
+                let group = self.table_groups.entry(table_name).or_default();
+                if !group.contains(&unique_idx) {
+                    group.push(unique_idx);
+                }
+            }
+        }
 
         // SQL별 그룹화 (한 쿼리에 여러 operation이 있으면 각 그룹에 포함)
         let operations = extract_operations(&event.sql_text);
@@ -195,21 +1201,120 @@ impl GuiState {
                 }
             }
         }
+
+        // Flow별 그룹화
+        let group = self.flow_groups.entry(event.flow_id.clone()).or_default();
+        if !group.contains(&unique_idx) {
+            group.push(unique_idx);
+        }
+
+        // 앱별 그룹화 (LOGIN7의 Application Name)
+        let app_name = event.app_name.as_deref().unwrap_or(UNKNOWN_APP);
+        let group = self.app_groups.entry(app_name.to_string()).or_default();
+        if !group.contains(&unique_idx) {
+            group.push(unique_idx);
+        }
+
+        // 쿼리 작성 방식별 그룹화 (디코딩하지 못한 패킷은 제외)
+        if let Some(kind) = event.query_kind() {
+            let group = self
+                .kind_groups
+                .entry(kind.label().to_string())
+                .or_default();
+            if !group.contains(&unique_idx) {
+                group.push(unique_idx);
+            }
+        }
     }
 
     /// 실시간 이벤트 수신 처리
-    pub fn process_received_events(&mut self) {
-        let mut new_events = Vec::new();
+    /// 새로 추가된 이벤트가 있으면 true (화면을 바로 다시 그릴지 판단)
+    pub fn process_received_events(&mut self) -> bool {
+        let now = Instant::now();
 
-        // 먼저 모든 이벤트를 수집
+        // 먼저 모든 이벤트를 재정렬 버퍼에 수집 (수신 시각 기록)
         if let Some(receiver) = &mut self.event_receiver {
             while let Ok(event) = receiver.try_recv() {
-                new_events.push(event);
+                self.reorder_buffer.push((now, event));
             }
         }
 
-        // 수집한 이벤트들을 추가
+        // 재정렬 대기 시간이 지난 이벤트만 꺼내기
+        // (여러 캡처 스레드에서 섞여 들어온 이벤트를 시간순으로 정렬하기 위함)
+        let (ready, waiting): (Vec<_>, Vec<_>) = self
+            .reorder_buffer
+            .drain(..)
+            .partition(|(received_at, _)| now.duration_since(*received_at) >= REORDER_WINDOW);
+        self.reorder_buffer = waiting;
+
+        let received = !ready.is_empty();
+        // 중복 제거 전 수신 개수 기준 (같은 쿼리를 반복 실행해도 부하로 셈)
+        self.throughput.record(now, ready.len());
+        self.commit_events(ready.into_iter().map(|(_, event)| event).collect());
+
+        // 자동 중지 개수에 도달하면 캡처 중지 (로그 파일 종료 메시지도 기록)
+        if self.is_capturing && self.stop_after_reached() {
+            self.stop_capture();
+            self.processing_status = format!("자동 중지됨 - {}", self.processing_status);
+        }
+        received
+    }
+
+    /// 이벤트 수가 자동 중지 개수(stop_after)에 도달했는지
+    fn stop_after_reached(&self) -> bool {
+        self.config
+            .stop_after
+            .is_some_and(|limit| self.events.len() >= limit)
+    }
+
+    /// 재정렬 버퍼에 남은 이벤트를 모두 추가
+    fn flush_reorder_buffer(&mut self) {
+        let buffered = self
+            .reorder_buffer
+            .drain(..)
+            .map(|(_, event)| event)
+            .collect();
+        self.commit_events(buffered);
+    }
+
+    /// 이벤트들을 타임스탬프 순으로 정렬하여 추가
+    fn commit_events(&mut self, mut new_events: Vec<SqlEvent>) {
+        new_events.sort_by_key(|event| event.timestamp);
+
+        // 익명화 설정이면 화면/로그/내보내기 모두 리터럴을 지운 SQL만 사용
+        if self.config.anonymize {
+            for event in &mut new_events {
+                event.anonymize();
+            }
+        }
+
+        // 배치 분리 설정이면 문장별 이벤트로 나누기
+        if self.config.split_batches {
+            new_events = new_events
+                .into_iter()
+                .flat_map(|event| {
+                    let statements = split_batch(&event.sql_text);
+                    if statements.len() <= 1 {
+                        return vec![event];
+                    }
+                    statements
+                        .into_iter()
+                        .map(|statement| SqlEvent {
+                            // 테이블도 문장별로 다시 추출 (배치 전체의 테이블이 아님)
+                            tables: extract_tables_from_sql(&statement),
+                            sql_text: statement,
+                            ..event.clone()
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+
+        // 수집한 이벤트들을 추가 (자동 중지 개수를 넘는 이벤트는 버림)
         for event in new_events {
+            if self.stop_after_reached() {
+                break;
+            }
             self.add_event(event);
             if !self.capture_started {
                 self.capture_started = true;
@@ -218,15 +1323,146 @@ impl GuiState {
         }
     }
 
-    /// 선택된 그룹의 고유 SQL 인덱스 가져오기
+    /// 조건식에 맞는 고유 SQL 인덱스 (수신 순서)
+    pub fn query(&self, predicate: &EventPredicate) -> Vec<usize> {
+        (0..self.events.len())
+            .filter(|&idx| predicate.matches(&self.events[idx]))
+            .collect()
+    }
+
+    /// 검색어가 바뀌었을 때 조건식 다시 해석
+    /// 조건식 오류면 기존처럼 검색어 전체를 SQL 검색어로 사용하고 오류는 검색창 옆에 표시
+    fn update_search(&mut self) {
+        match EventPredicate::from_search(&self.search_query) {
+            Ok(predicate) => {
+                self.search_predicate = predicate;
+                self.search_error = None;
+            }
+            Err(e) => {
+                self.search_predicate = Some(EventPredicate::SqlContains(
+                    self.search_query.trim().to_string(),
+                ));
+                self.search_error = Some(e);
+            }
+        }
+    }
+
+    /// 고유 SQL이 검색어에 맞는지 (검색어가 비어있으면 모두 포함)
+    fn matches_search(&self, idx: usize) -> bool {
+        self.search_predicate
+            .as_ref()
+            .is_none_or(|predicate| predicate.matches(&self.events[idx]))
+    }
+
+    /// 최근 수신한 이벤트 n개의 고유 SQL 인덱스 (오래된 것부터, 검색어 적용)
+    /// 같은 SQL이 여러 번 실행되었으면 실행할 때마다 포함
+    pub fn recent_events(&self, n: usize) -> Vec<usize> {
+        let mut recent: Vec<usize> = self
+            .recent
+            .iter()
+            .rev()
+            .copied()
+            .filter(|&idx| self.matches_search(idx))
+            .take(n)
+            .collect();
+        recent.reverse();
+        recent
+    }
+
+    /// 목록의 시간 열에 표시하는 시각 (설정에 따라 처음 또는 마지막 실행 시각)
+    fn display_timestamp(&self, idx: usize) -> DateTime<Utc> {
+        if self.config.show_last_seen {
+            self.last_seen[idx]
+        } else {
+            self.events[idx].timestamp
+        }
+    }
+
+    /// 선택된 그룹의 고유 SQL 인덱스를 목록 정렬 기준으로 정렬 (검색어 적용)
+    fn sorted_events(&self) -> Vec<usize> {
+        let mut indices = self.get_selected_events();
+        indices.retain(|&idx| self.matches_search(idx));
+        match self.sort_column {
+            SortColumn::Time => indices.sort_by_key(|&idx| self.display_timestamp(idx)),
+            SortColumn::Operation => {
+                indices.sort_by(|&a, &b| self.events[a].operation.cmp(&self.events[b].operation))
+            }
+            // 응답 시간이 없는 이벤트는 가장 짧은 것으로 취급
+            SortColumn::Latency => indices.sort_by(|&a, &b| {
+                let latency = |idx: usize| self.events[idx].latency_ms.unwrap_or(-1.0);
+                latency(a).total_cmp(&latency(b))
+            }),
+            SortColumn::Tables => {
+                indices.sort_by(|&a, &b| self.table_names[a].cmp(&self.table_names[b]))
+            }
+            SortColumn::Sql => {
+                indices.sort_by(|&a, &b| self.events[a].sql_text.cmp(&self.events[b].sql_text))
+            }
+        }
+        if !self.sort_ascending {
+            indices.reverse();
+        }
+        indices
+    }
+
+    /// 테이블별 보기의 전체 목록: 중복 제거된 모든 이벤트 ("기타"를 숨기면 그 그룹의 SQL 제외)
+    fn all_table_view_events(&self) -> Vec<usize> {
+        let hidden = self
+            .table_groups
+            .get(OTHER_TABLE_GROUP)
+            .filter(|_| self.config.hide_other_tables);
+        (0..self.events.len())
+            .filter(|idx| hidden.is_none_or(|group| !group.contains(idx)))
+            .collect()
+    }
+
+    /// 작업 필터 칩 켜고 끄기
+    pub fn toggle_operation_chip(&mut self, chip: &'static str) {
+        if !self.active_operations.remove(chip) {
+            self.active_operations.insert(chip);
+        }
+    }
+
+    /// 꺼진 작업 필터 칩의 SQL 제외
+    /// 켜진 칩의 작업이 하나라도 있는 SQL(INSERT ... SELECT에서 SELECT만 켠 경우 등)과
+    /// 칩에 해당하는 작업이 없는 SQL(SET 문 등)은 남김
+    fn filter_operations(&self, mut indices: Vec<usize>) -> Vec<usize> {
+        if self.active_operations.len() == OPERATION_CHIPS.len() {
+            return indices;
+        }
+
+        let chip_groups = |active: bool| -> HashSet<usize> {
+            OPERATION_CHIPS
+                .into_iter()
+                .filter(|chip| self.active_operations.contains(chip) == active)
+                .flat_map(|chip| match chip {
+                    "EXEC" => vec!["EXEC", "EXECUTE"],
+                    chip => vec![chip],
+                })
+                .filter_map(|operation| self.operation_groups.get(operation))
+                .flatten()
+                .copied()
+                .collect()
+        };
+        let shown = chip_groups(true);
+        let hidden = chip_groups(false);
+        indices.retain(|idx| shown.contains(idx) || !hidden.contains(idx));
+        indices
+    }
+
+    /// 선택된 그룹의 고유 SQL 인덱스 가져오기 (작업 필터 칩 적용)
     fn get_selected_events(&self) -> Vec<usize> {
+        self.filter_operations(self.group_events())
+    }
+
+    /// 선택된 그룹의 고유 SQL 인덱스
+    fn group_events(&self) -> Vec<usize> {
         match self.view_mode {
             ViewMode::ByTable => {
                 if let Some(ref table) = self.selected_table {
                     self.table_groups.get(table).cloned().unwrap_or_default()
                 } else {
-                    // 중복 제거된 모든 이벤트
-                    (0..self.events.len()).collect()
+                    self.all_table_view_events()
                 }
             }
             ViewMode::BySql => {
@@ -240,14 +1476,262 @@ impl GuiState {
                     (0..self.events.len()).collect()
                 }
             }
+            ViewMode::ByFlow => {
+                if let Some(ref flow) = self.selected_flow {
+                    self.flow_groups.get(flow).cloned().unwrap_or_default()
+                } else {
+                    // 중복 제거된 모든 이벤트
+                    (0..self.events.len()).collect()
+                }
+            }
+            ViewMode::ByApp => {
+                if let Some(ref app) = self.selected_app {
+                    self.app_groups.get(app).cloned().unwrap_or_default()
+                } else {
+                    // 중복 제거된 모든 이벤트
+                    (0..self.events.len()).collect()
+                }
+            }
+            ViewMode::ByKind => {
+                if let Some(ref kind) = self.selected_kind {
+                    self.kind_groups.get(kind).cloned().unwrap_or_default()
+                } else {
+                    // 중복 제거된 모든 이벤트
+                    (0..self.events.len()).collect()
+                }
+            }
+        }
+    }
+}
+
+/// 시간대 선택 목록에 표시할 이름
+fn timezone_label(timezone: DisplayTimezone) -> String {
+    match timezone {
+        DisplayTimezone::Local => "로컬".to_string(),
+        DisplayTimezone::Utc => "UTC".to_string(),
+        DisplayTimezone::Offset(540) => "KST (+09:00)".to_string(),
+        DisplayTimezone::Offset(minutes) => format!(
+            "UTC{}{:02}:{:02}",
+            if minutes < 0 { '-' } else { '+' },
+            minutes.abs() / 60,
+            minutes.abs() % 60
+        ),
+    }
+}
+
+/// TDS 헤더 강조 배경색
+const HEX_HEADER_COLOR: Color32 = Color32::from_rgb(50, 70, 120);
+
+/// ============================================
+/// 원본 데이터 hex 뷰어
+/// ============================================
+/// 오프셋 / hex 16바이트 / ASCII 열로 표시 (보이는 줄만 그림)
+/// - 바이트를 누르면 선택, Shift+클릭으로 처음 누른 바이트부터 범위 선택
+/// - 마우스를 올리면 오프셋과 값(hex / 10진수 / 문자)을 표시
+/// - highlight_headers면 메시지를 이루는 각 TDS 패킷의 헤더(8바이트)를 다른 배경색으로 표시
+fn hex_view(
+    ui: &mut egui::Ui,
+    data: &[u8],
+    selection: &mut Option<(usize, usize)>,
+    highlight_headers: bool,
+) {
+    // 각 패킷 헤더의 시작 오프셋
+    let header_starts: Vec<usize> = if highlight_headers {
+        TdsParser::message_packets(data)
+            .iter()
+            .scan(0, |offset, packet| {
+                let start = *offset;
+                *offset += packet.len();
+                Some(start)
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let is_header = |offset: usize| {
+        header_starts
+            .iter()
+            .any(|&start| (start..start + 8).contains(&offset))
+    };
+    let selected = selection.map(|(start, end)| start.min(end)..=start.max(end));
+    let selection_color = ui.visuals().selection.bg_fill;
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let total_rows = data.len().div_ceil(16);
+    let mut clicked = None;
+    ScrollArea::both()
+        .max_height(320.0)
+        .auto_shrink([false, true])
+        .id_source("hex_view_scroll")
+        .show_rows(ui, row_height, total_rows, |ui, row_range| {
+            let start = row_range.start * 16;
+            let end = (row_range.end * 16).min(data.len());
+            for row in hex_rows(&data[start..end], start) {
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    ui.label(
+                        RichText::new(format!("{:08x}  ", row.offset))
+                            .monospace()
+                            .weak(),
+                    );
+
+                    // hex 열과 ASCII 열의 같은 바이트는 같은 선택 / 강조 / 툴팁
+                    let columns = [
+                        row.hex.split(' ').map(str::to_string).collect::<Vec<_>>(),
+                        row.ascii.chars().map(String::from).collect(),
+                    ];
+                    for (column, cells) in columns.iter().enumerate() {
+                        for (i, cell) in cells.iter().enumerate() {
+                            let offset = row.offset + i;
+                            let mut text = RichText::new(cell).monospace();
+                            if selected
+                                .as_ref()
+                                .is_some_and(|range| range.contains(&offset))
+                            {
+                                text = text.background_color(selection_color);
+                            } else if is_header(offset) {
+                                text = text.background_color(HEX_HEADER_COLOR);
+                            }
+                            let byte = data[offset];
+                            let response = ui
+                                .add(egui::Label::new(text).sense(egui::Sense::click()))
+                                .on_hover_text(format!(
+                                    "오프셋 0x{:x} ({})\n값 0x{:02x} = {}{}",
+                                    offset,
+                                    offset,
+                                    byte,
+                                    byte,
+                                    if byte.is_ascii_graphic() {
+                                        format!(" '{}'", byte as char)
+                                    } else {
+                                        String::new()
+                                    }
+                                ));
+                            if response.clicked() {
+                                clicked = Some(offset);
+                            }
+                            if column == 0 {
+                                ui.label(RichText::new(" ").monospace());
+                            }
+                        }
+                        if column == 0 {
+                            // 마지막 줄이 16바이트보다 짧아도 ASCII 열 위치를 맞춤
+                            let padding = 16 - cells.len();
+                            ui.label(RichText::new(" ".repeat(padding * 3 + 1)).monospace());
+                        }
+                    }
+                });
+            }
+        });
+
+    if let Some(offset) = clicked {
+        let shift = ui.input(|i| i.modifiers.shift);
+        *selection = match *selection {
+            Some((anchor, _)) if shift => Some((anchor, offset)),
+            _ => Some((offset, offset)),
+        };
+    }
+}
+
+/// 초당 쿼리 수 꺾은선 그래프 (오른쪽 끝이 현재, 최대값 기준으로 높이 맞춤)
+fn draw_throughput(ui: &mut egui::Ui, throughput: &Throughput) {
+    let size = egui::vec2(ui.available_width().min(400.0), 40.0);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    let peak = throughput.peak().max(1) as f32;
+    let step = rect.width() / (THROUGHPUT_WINDOW_SECS - 1) as f32;
+    let offset = THROUGHPUT_WINDOW_SECS - throughput.counts.len();
+    let points: Vec<egui::Pos2> = throughput
+        .counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            egui::pos2(
+                rect.left() + (offset + i) as f32 * step,
+                rect.bottom() - 2.0 - (rect.height() - 4.0) * count as f32 / peak,
+            )
+        })
+        .collect();
+    if points.len() > 1 {
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(1.5, Color32::from_rgb(100, 180, 255)),
+        ));
+    }
+}
+
+/// SQL 비교 창의 삭제(A에만 있음) / 추가(B에만 있음) 배경색
+const DIFF_DELETE_COLOR: Color32 = Color32::from_rgb(120, 40, 40);
+const DIFF_INSERT_COLOR: Color32 = Color32::from_rgb(40, 100, 40);
+
+/// 비교 결과를 한 덩어리 텍스트로 (삭제는 빨간 배경 + 취소선, 추가는 초록 배경)
+/// skip 종류의 조각은 빼고 표시 (나란히 보기에서 A는 Insert, B는 Delete를 뺌)
+fn diff_layout(spans: &[DiffSpan], skip: Option<DiffKind>, text_color: Color32) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    for span in spans.iter().filter(|span| Some(span.kind) != skip) {
+        let mut format = TextFormat {
+            font_id: FontId::monospace(13.0),
+            color: text_color,
+            ..Default::default()
+        };
+        match span.kind {
+            DiffKind::Equal => {}
+            DiffKind::Delete => {
+                format.color = Color32::WHITE;
+                format.background = DIFF_DELETE_COLOR;
+                format.strikethrough = egui::Stroke::new(1.0, Color32::WHITE);
+            }
+            DiffKind::Insert => {
+                format.color = Color32::WHITE;
+                format.background = DIFF_INSERT_COLOR;
+            }
         }
+        job.append(&span.text, 0.0, format);
+    }
+    job
+}
+
+/// 작업 타입 색상
+fn operation_color(operation: &str) -> Color32 {
+    match operation {
+        "SELECT" => Color32::from_rgb(100, 200, 100),
+        "INSERT" => Color32::from_rgb(100, 150, 255),
+        "UPDATE" => Color32::from_rgb(255, 200, 100),
+        "DELETE" => Color32::from_rgb(255, 100, 100),
+        "EXEC" => Color32::from_rgb(200, 100, 255),
+        _ => Color32::GRAY,
     }
 }
 
 /// GUI 렌더링
 pub fn show_gui(ctx: &egui::Context, state: &mut GuiState) {
     // 실시간 이벤트 처리
-    state.process_received_events();
+    let received = state.process_received_events() | state.poll_file_replay();
+    state.poll_stream_dump();
+    state.poll_flow_stats();
+    state.poll_interface_test();
+    state.poll_capture_status();
+    #[cfg(feature = "control-api")]
+    state.poll_control_requests();
+
+    // 작업 필터 칩 단축키 (Alt+1 ~ Alt+5)
+    let chip_keys = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5];
+    for (chip, key) in OPERATION_CHIPS.into_iter().zip(chip_keys) {
+        if ctx.input_mut(|input| input.consume_key(Modifiers::ALT, key)) {
+            state.toggle_operation_chip(chip);
+        }
+    }
+
+    // 시각 표시 시간대
+    let timezone = state.config.timezone;
+
+    // 테마 적용
+    ctx.set_visuals(match state.config.theme {
+        Theme::Dark => egui::Visuals::dark(),
+        Theme::Light => egui::Visuals::light(),
+    });
 
     // 제어 영역 (상단에 고정)
     TopBottomPanel::top("control_panel").show(ctx, |ui| {
@@ -272,6 +1756,7 @@ pub fn show_gui(ctx: &egui::Context, state: &mut GuiState) {
             egui::ComboBox::from_id_source("interface_select")
                 .selected_text(&selected_text)
                 .show_ui(ui, |ui| {
+                    let mut clicked = None;
                     for (name, desc) in &state.available_interfaces {
                         let display_text = format!("{} - {}", name, desc);
                         let is_selected = state.selected_interface.as_ref() == Some(name);
@@ -279,66 +1764,517 @@ pub fn show_gui(ctx: &egui::Context, state: &mut GuiState) {
                         if ui.selectable_label(is_selected, &display_text).clicked()
                             && !state.is_capturing
                         {
-                            state.selected_interface = Some(name.clone());
+                            clicked = Some(name.clone());
                         }
                     }
+                    if let Some(name) = clicked {
+                        state.select_interface(name);
+                    }
                 });
 
             ui.separator();
 
             if !state.is_capturing {
-                let can_start = state.selected_interface.is_some();
+                let can_start = state.selected_interface.is_some()
+                    && state.interface_error.is_none()
+                    && state.file_replay.is_none();
+                let mut start = ui.add_enabled(can_start, egui::Button::new("시작"));
+                if let Some(ref error) = state.interface_error {
+                    start = start.on_disabled_hover_text(error.to_string());
+                }
+                if start.clicked() {
+                    state.start_capture();
+                }
+
+                // 자동 중지 개수 (체크하면 이벤트가 이 개수에 도달할 때 중지)
+                let mut auto_stop = state.config.stop_after.is_some();
                 if ui
-                    .add_enabled(can_start, egui::Button::new("시작"))
-                    .clicked()
+                    .checkbox(&mut auto_stop, "자동 중지")
+                    .on_hover_text("이벤트(중복 제거 시 고유 SQL)가 지정한 개수에 도달하면 캡처 중지")
+                    .changed()
                 {
-                    state.start_capture();
+                    state.config.stop_after = auto_stop.then_some(100);
                 }
+                if let Some(ref mut limit) = state.config.stop_after {
+                    ui.add(egui::DragValue::new(limit).clamp_range(1..=1_000_000).suffix("개"));
+                }
+
+                // 캡처 시간 (지나면 자동 중지)
+                let minutes_label = |minutes: Option<u32>| match minutes {
+                    Some(minutes) => format!("{}분 캡처", minutes),
+                    None => "시간 제한 없음".to_string(),
+                };
+                egui::ComboBox::from_id_source("capture_minutes")
+                    .selected_text(minutes_label(state.config.capture_minutes))
+                    .show_ui(ui, |ui| {
+                        for minutes in CAPTURE_MINUTES_PRESETS {
+                            ui.selectable_value(
+                                &mut state.config.capture_minutes,
+                                minutes,
+                                minutes_label(minutes),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text("지정한 시간이 지나면 캡처를 자동으로 중지하고 로그 파일을 마무리");
             } else {
                 if ui.button("중지").clicked() {
                     state.stop_capture();
                 }
+                if ui
+                    .button("새 로그")
+                    .on_hover_text("캡처를 멈추지 않고 새 로그 파일에 이어서 기록")
+                    .clicked()
+                {
+                    state.processing_status = match state.logger.roll() {
+                        Ok(path) => format!("새 로그 파일에 기록 중 ({})", path),
+                        Err(e) => format!("새 로그 파일 생성 실패: {}", e),
+                    };
+                }
                 ui.spinner();
             }
-        });
 
-        if !state.processing_status.is_empty() {
-            ui.label(&state.processing_status);
-        }
+            // 저장된 pcap 파일 처리 (처리 중에는 진행 표시줄과 취소 버튼)
+            if let Some(progress) = state.file_progress() {
+                ui.add(
+                    egui::ProgressBar::new(progress.fraction())
+                        .desired_width(160.0)
+                        .show_percentage(),
+                );
+                if ui.button("취소").clicked() {
+                    state.cancel_file_replay();
+                }
+            } else if ui
+                .add_enabled(!state.is_capturing, egui::Button::new("파일 열기"))
+                .on_hover_text("저장된 pcap 파일의 SQL을 캡처 설정 그대로 추출 (기존 이벤트는 지움)")
+                .clicked()
+            {
+                state.open_pcap_file();
+            }
+
+            // 캡처 전 인터페이스 / 미러링 설정 확인
+            let testing = state.interface_test_receiver.is_some();
+            if ui
+                .add_enabled(
+                    !state.is_capturing
+                        && !testing
+                        && state.selected_interface.is_some()
+                        && state.interface_error.is_none(),
+                    egui::Button::new("인터페이스 테스트"),
+                )
+                .on_hover_text("선택한 인터페이스에서 5초 동안 받은 패킷과 SQL 포트 패킷 수를 확인")
+                .clicked()
+            {
+                state.start_interface_test();
+            }
+            if testing {
+                ui.spinner();
+            }
 
-        // 뷰 모드 탭 (데이터가 있을 때만 표시)
-        if !state.events.is_empty() {
             ui.separator();
-            ui.horizontal(|ui| {
-                ui.label("보기 모드:");
-                if ui
-                    .selectable_label(state.view_mode == ViewMode::ByTable, "테이블별")
-                    .clicked()
-                {
-                    state.view_mode = ViewMode::ByTable;
-                    state.selected_table = None;
-                    state.selected_operation = None;
-                    state.show_details = None;
-                    state.show_raw = None;
-                }
+            if ui.selectable_label(state.show_tail, "실시간 로그").clicked() {
+                state.show_tail = !state.show_tail;
+            }
+            if ui
+                .selectable_label(state.show_hex_decode, "hex 디코딩")
+                .on_hover_text("다른 도구의 TDS 패킷 hex 덤프를 붙여넣어 디코딩")
+                .clicked()
+            {
+                state.show_hex_decode = !state.show_hex_decode;
+            }
+
+            // 경고 배지 (클릭하면 경고 패널 열기/닫기)
+            if !state.alerts.is_empty() {
+                ui.separator();
+                let badge = RichText::new(format!("경고 {}", state.alerts.len()))
+                    .color(Color32::WHITE)
+                    .strong();
                 if ui
-                    .selectable_label(state.view_mode == ViewMode::BySql, "SQL별")
+                    .add(egui::Button::new(badge).fill(Color32::from_rgb(200, 40, 40)))
                     .clicked()
                 {
-                    state.view_mode = ViewMode::BySql;
-                    state.selected_table = None;
-                    state.selected_operation = None;
-                    state.show_details = None;
-                    state.show_raw = None;
+                    state.show_alerts = !state.show_alerts;
                 }
+            }
+        });
+
+        // 설정
+        ui.horizontal(|ui| {
+            ui.label("설정:");
+            ui.add_enabled(
+                !state.is_capturing,
+                egui::Checkbox::new(&mut state.config.dedup, "중복 제거"),
+            );
+            ui.add_enabled_ui(!state.is_capturing && state.config.dedup, |ui| {
+                ui.selectable_value(&mut state.config.dedup_scope, DedupScope::Global, "전체")
+                    .on_hover_text("모든 연결에서 같은 SQL을 하나로 표시");
+                ui.selectable_value(&mut state.config.dedup_scope, DedupScope::PerFlow, "연결별")
+                    .on_hover_text("같은 SQL도 보낸 연결(Flow)이 다르면 따로 표시");
+            });
+            ui.add_enabled(
+                !state.is_capturing,
+                egui::Checkbox::new(&mut state.config.split_batches, "배치 분리"),
+            );
+            ui.add_enabled(
+                !state.is_capturing,
+                egui::Checkbox::new(&mut state.config.promisc, "Promiscuous"),
+            )
+            .on_hover_text("다른 호스트로 가는 패킷도 캡처 (NIC 정책상 허용하지 않으면 해제)");
+            ui.add_enabled(
+                !state.is_capturing,
+                egui::Checkbox::new(&mut state.config.diagnostics, "진단 모드"),
+            )
+            .on_hover_text("디코딩하지 못한 TDS 패킷을 '미해석 패킷' 그룹에 표시");
+            ui.add_enabled(
+                !state.is_capturing,
+                egui::Checkbox::new(&mut state.config.anonymize, "익명화"),
+            )
+            .on_hover_text("SQL의 문자열/숫자 값을 ?로 바꿔 표시하고 로그에 기록 (원본 패킷은 보관하지 않음)");
+            ui.add_enabled(
+                !state.is_capturing,
+                egui::Checkbox::new(&mut state.config.detect_encoding, "인코딩 자동 감지"),
+            )
+            .on_hover_text("UTF-16LE로 읽히지 않는 SQL Batch를 UTF-8 / windows-1252로 디코딩");
+            ui.add_enabled(
+                !state.is_capturing,
+                egui::Checkbox::new(&mut state.config.bidirectional, "양방향 디코딩"),
+            )
+            .on_hover_text("프록시를 거치는 구간처럼 서버 포트 쪽에서 보내는 SQL도 디코딩 (두 구간의 같은 쿼리는 하나로 표시)");
+            ui.add_enabled(
+                !state.is_capturing,
+                egui::Checkbox::new(&mut state.config.result_columns, "결과 컬럼"),
+            )
+            .on_hover_text("서버 응답에서 결과 집합의 컬럼 이름과 타입을 읽어 상세 패널에 표시 (스키마 파악용)");
+            ui.checkbox(&mut state.config.resolve_hosts, "호스트 이름 표시")
+                .on_hover_text("Flow의 IP를 역방향 DNS로 조회한 호스트 이름으로 표시 (해제하면 IP 그대로)");
+            ui.checkbox(&mut state.config.show_last_seen, "마지막 실행 시각")
+                .on_hover_text("목록의 시간 열에 같은 SQL이 마지막으로 실행된 시각을 표시 (해제하면 처음 실행 시각)");
+            ui.separator();
+            ui.label("테마:");
+            ui.selectable_value(&mut state.config.theme, Theme::Dark, "다크");
+            ui.selectable_value(&mut state.config.theme, Theme::Light, "라이트");
+            ui.separator();
+            ui.label("시간대:");
+            egui::ComboBox::from_id_source("timezone_select")
+                .selected_text(timezone_label(state.config.timezone))
+                .show_ui(ui, |ui| {
+                    for timezone in [
+                        DisplayTimezone::Local,
+                        DisplayTimezone::Utc,
+                        DisplayTimezone::Offset(9 * 60),
+                    ] {
+                        ui.selectable_value(
+                            &mut state.config.timezone,
+                            timezone,
+                            timezone_label(timezone),
+                        );
+                    }
+                });
+            ui.separator();
+            if ui.button("설정 저장").clicked() {
+                state.save_config();
+            }
+            ui.separator();
+            // 화면에 표시 중인 이벤트만 내보냄 (선택한 그룹, 검색어)
+            ui.add_enabled_ui(!state.is_capturing && !state.events.is_empty(), |ui| {
+                ui.menu_button("내보내기", |ui| {
+                    if ui.button("JSON").clicked() {
+                        ui.close_menu();
+                        state.export_json();
+                    }
+                    if ui.button("CSV").clicked() {
+                        ui.close_menu();
+                        state.export_csv();
+                    }
+                    if ui.button("pcap").clicked() {
+                        ui.close_menu();
+                        state.export_pcap();
+                    }
+                    ui.separator();
+                    // 검색어, 그룹 선택과 관계없이 전체 이벤트 기준
+                    if ui.button("테이블 관계 그래프").clicked() {
+                        ui.close_menu();
+                        state.save_table_graph();
+                    }
+                });
             });
+        });
+
+        if !state.processing_status.is_empty() {
+            ui.label(&state.processing_status);
         }
-    });
 
-    // 데이터가 있을 때만 표시
-    if !state.events.is_empty() {
-        // 왼쪽 패널: 그룹 목록
-        SidePanel::left("group_panel")
+        // 파싱 중 panic으로 건너뛴 패킷이 있으면 표시 (캡처는 계속됨)
+        let parse_panics = state.stats.parse_panics();
+        if parse_panics > 0 {
+            ui.label(
+                RichText::new(format!(
+                    "파싱 오류 {}건: 해당 패킷은 건너뛰었습니다 (패킷 hex는 콘솔 오류 로그 참고)",
+                    parse_panics
+                ))
+                .color(Color32::from_rgb(230, 60, 60)),
+            );
+        }
+
+        // 캡처 통계
+        ui.collapsing("통계", |ui| {
+            ui.label(format!("고유 SQL: {}개", state.events.len()));
+
+            ui.label(format!(
+                "초당 쿼리 (최근 1분): {}개 / 최대 {}개",
+                state.throughput.last_second(),
+                state.throughput.peak()
+            ));
+            draw_throughput(ui, &state.throughput);
+
+            let duplicates = state.stats.duplicate_packets();
+            ui.label(format!("중복 패킷 제거: {}개", duplicates));
+            ui.label(format!("파싱 오류: {}건", parse_panics));
+
+            let rate_limited = state.stats.rate_limited_events();
+            if let Some(max_per_sec) = state.config.max_events_per_sec {
+                ui.label(format!(
+                    "초당 제한({}개)으로 버린 이벤트: {}개",
+                    max_per_sec, rate_limited
+                ));
+            }
+            if let Some(every) = state.config.sample_every.filter(|&every| every > 1) {
+                ui.label(format!(
+                    "샘플링(1/{})으로 건너뛴 이벤트: {}개",
+                    every,
+                    state.stats.sampled_out_events()
+                ));
+            }
+            if rate_limited > 0 {
+                ui.label(
+                    RichText::new("이벤트가 폭주하고 있습니다. 같은 쿼리를 반복 실행하는 애플리케이션이 있는지 확인하세요.")
+                        .small()
+                        .color(Color32::from_rgb(255, 200, 100)),
+                );
+            }
+            if duplicates > 0 {
+                ui.label(
+                    RichText::new(
+                        "같은 패킷이 반복 수신되고 있습니다. SPAN 포트가 양방향 트래픽을 중복 미러링하는지 확인하세요.",
+                    )
+                    .small()
+                    .color(Color32::from_rgb(255, 200, 100)),
+                );
+            }
+
+            // 재조립 중인 플로우 (통계를 펼친 동안 주기적으로 갱신, SQL 검색어로 IP 필터)
+            if state.is_capturing {
+                state.request_flow_stats();
+            }
+            let query = state.search_query.trim().to_string();
+            let flows: Vec<&FlowStat> = state
+                .flow_stats
+                .iter()
+                .filter(|stat| query.is_empty() || stat.flow_id.to_string().contains(&query))
+                .collect();
+            ui.label(format!("재조립 중인 Flow: {}개", flows.len()));
+            if !flows.is_empty() {
+                ScrollArea::vertical()
+                    .max_height(200.0)
+                    .id_source("flow_stats_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("flow_stats_grid")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("Flow");
+                                ui.strong("클라이언트 (바이트 / 세그먼트)");
+                                ui.strong("서버 (바이트 / 세그먼트)");
+                                ui.strong("마지막 패킷");
+                                ui.strong("상태");
+                                ui.end_row();
+
+                                for stat in flows {
+                                    let last_seen = chrono::DateTime::from_timestamp(
+                                        stat.last_timestamp as i64,
+                                        ((stat.last_timestamp - stat.last_timestamp.floor())
+                                            * 1_000_000_000.0)
+                                            as u32,
+                                    )
+                                    .unwrap_or_default();
+
+                                    ui.label(state.display_flow(&stat.flow_id.to_string()));
+                                    ui.label(format!(
+                                        "{} / {}",
+                                        stat.client_bytes, stat.client_segments
+                                    ));
+                                    ui.label(format!(
+                                        "{} / {}",
+                                        stat.server_bytes, stat.server_segments
+                                    ));
+                                    ui.label(timezone.format(&last_seen, "%H:%M:%S%.3f"));
+                                    let status = match (stat.decoded, stat.closed) {
+                                        (true, true) => "디코딩됨, 종료",
+                                        (true, false) => "디코딩됨",
+                                        (false, true) => "종료",
+                                        (false, false) => "디코딩 전",
+                                    };
+                                    if stat.client_gap.is_some() || stat.server_gap.is_some() {
+                                        ui.label(format!("{}, 패킷 손실", status));
+                                    } else {
+                                        ui.label(status);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            }
+        });
+
+        // 뷰 모드 탭 (데이터가 있을 때만 표시)
+        if !state.events.is_empty() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("보기 모드:");
+                if ui
+                    .selectable_label(state.view_mode == ViewMode::ByTable, "테이블별")
+                    .clicked()
+                {
+                    state.set_view_mode(ViewMode::ByTable);
+                }
+                if ui
+                    .selectable_label(state.view_mode == ViewMode::BySql, "SQL별")
+                    .clicked()
+                {
+                    state.set_view_mode(ViewMode::BySql);
+                }
+                if ui
+                    .selectable_label(state.view_mode == ViewMode::ByFlow, "Flow별")
+                    .clicked()
+                {
+                    state.set_view_mode(ViewMode::ByFlow);
+                }
+                if ui
+                    .selectable_label(state.view_mode == ViewMode::ByApp, "앱별")
+                    .clicked()
+                {
+                    state.set_view_mode(ViewMode::ByApp);
+                }
+                if ui
+                    .selectable_label(state.view_mode == ViewMode::ByKind, "쿼리 유형별")
+                    .on_hover_text("Parameterized: RPC / sp_executesql, Ad-hoc: 리터럴을 직접 넣은 SQL Batch, Static: 리터럴이 없는 SQL Batch")
+                    .clicked()
+                {
+                    state.set_view_mode(ViewMode::ByKind);
+                }
+            });
+        }
+    });
+
+    // 실시간 로그 패널 (하단): 그룹 선택과 관계없이 최근 이벤트를 수신 순서대로
+    if state.show_tail {
+        TopBottomPanel::bottom("tail_panel")
+            .resizable(true)
+            .default_height(180.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("실시간 로그");
+                    ui.label("표시 개수:");
+                    ui.add(
+                        egui::DragValue::new(&mut state.config.tail_size)
+                            .clamp_range(1..=MAX_RECENT_EVENTS),
+                    );
+                    if ui.button("닫기").clicked() {
+                        state.show_tail = false;
+                    }
+                });
+
+                // 새 이벤트가 오면 맨 아래로 자동 스크롤
+                ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .stick_to_bottom(true)
+                    .id_source("tail_scroll")
+                    .show(ui, |ui| {
+                        for idx in state.recent_events(state.config.tail_size) {
+                            let event = &state.events[idx];
+                            ui.horizontal(|ui| {
+                                ui.label(timezone.format(&event.timestamp, "%H:%M:%S%.3f"));
+                                ui.label(
+                                    RichText::new(&event.operation)
+                                        .color(operation_color(&event.operation))
+                                        .strong(),
+                                );
+
+                                let preview: String = event
+                                    .full_text()
+                                    .split_whitespace()
+                                    .collect::<Vec<_>>()
+                                    .join(" ")
+                                    .chars()
+                                    .take(200)
+                                    .collect();
+                                // 클릭하면 상세 보기
+                                if ui.link(preview).clicked() {
+                                    state.show_details = Some(idx);
+                                    state.scroll_to_details = true;
+                                }
+                            });
+                        }
+                    });
+            });
+    }
+
+    // 경고 패널 (하단): 경고 규칙에 걸린 SQL 목록
+    if state.show_alerts && !state.alerts.is_empty() {
+        TopBottomPanel::bottom("alert_panel")
+            .resizable(true)
+            .default_height(150.0)
+            .show(ctx, |ui| {
+                ui.heading(
+                    RichText::new(format!("경고 ({})", state.alerts.len()))
+                        .color(Color32::from_rgb(230, 60, 60))
+                        .strong(),
+                );
+                ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .id_source("alert_list_scroll")
+                    .show(ui, |ui| {
+                        // 최근 경고가 위로
+                        for &idx in state.alerts.iter().rev() {
+                            let event = &state.events[idx];
+                            let rules = matching_rules(&state.alert_rules, event).join(", ");
+
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    RichText::new(rules)
+                                        .color(Color32::from_rgb(230, 60, 60))
+                                        .strong(),
+                                );
+                                ui.separator();
+                                ui.label(
+                                    timezone.format(&event.timestamp, "%Y-%m-%d %H:%M:%S%.3f"),
+                                );
+                                ui.separator();
+
+                                let preview = if event.sql_text.chars().count() > 100 {
+                                    event.sql_text.chars().take(100).collect::<String>() + "..."
+                                } else {
+                                    event.sql_text.clone()
+                                };
+                                // 클릭하면 전체 목록에서 상세 보기
+                                if ui.link(preview).clicked() {
+                                    state.selected_table = None;
+                                    state.selected_operation = None;
+                                    state.selected_flow = None;
+                                    state.selected_app = None;
+                                    state.selected_kind = None;
+                                    state.show_details = Some(idx);
+                                    state.scroll_to_details = true;
+                                }
+                            });
+                        }
+                    });
+            });
+    }
+
+    // 데이터가 있을 때만 표시
+    if !state.events.is_empty() {
+        // 왼쪽 패널: 그룹 목록
+        SidePanel::left("group_panel")
             .resizable(true)
             .default_width(300.0)
             .min_width(200.0)
@@ -347,12 +2283,32 @@ pub fn show_gui(ctx: &egui::Context, state: &mut GuiState) {
                 match state.view_mode {
                     ViewMode::ByTable => {
                         ui.heading("테이블 목록");
+                        if ui
+                            .checkbox(&mut state.config.hide_other_tables, "기타 숨기기")
+                            .on_hover_text(
+                                "테이블을 찾지 못한 SQL(SET 문, SELECT 1 등)을 목록과 전체 개수에서 제외",
+                            )
+                            .changed()
+                            && state.config.hide_other_tables
+                            && state.selected_table.as_deref() == Some(OTHER_TABLE_GROUP)
+                        {
+                            state.selected_table = None;
+                            state.show_details = None;
+                            state.show_raw = None;
+                        }
                         ScrollArea::vertical()
                             .auto_shrink([false; 2])
                             .id_source("table_list_scroll")
                             .show(ui, |ui| {
-                                let mut tables: Vec<String> =
-                                    state.table_groups.keys().cloned().collect();
+                                let mut tables: Vec<String> = state
+                                    .table_groups
+                                    .keys()
+                                    .filter(|table| {
+                                        !(state.config.hide_other_tables
+                                            && table.as_str() == OTHER_TABLE_GROUP)
+                                    })
+                                    .cloned()
+                                    .collect();
                                 tables.sort();
 
                                 for table in &tables {
@@ -379,7 +2335,7 @@ pub fn show_gui(ctx: &egui::Context, state: &mut GuiState) {
 
                                 // 전체 보기
                                 ui.separator();
-                                let total_count = state.events.len();
+                                let total_count = state.all_table_view_events().len();
                                 let is_all_selected = state.selected_table.is_none();
                                 if ui
                                     .selectable_label(
@@ -447,9 +2403,445 @@ pub fn show_gui(ctx: &egui::Context, state: &mut GuiState) {
                                 }
                             });
                     }
+                    ViewMode::ByFlow => {
+                        ui.heading("Flow 목록");
+                        ScrollArea::vertical()
+                            .auto_shrink([false; 2])
+                            .id_source("flow_list_scroll")
+                            .show(ui, |ui| {
+                                let mut flows: Vec<String> =
+                                    state.flow_groups.keys().cloned().collect();
+                                flows.sort();
+
+                                for flow in &flows {
+                                    let count =
+                                        state.flow_groups.get(flow).map(|v| v.len()).unwrap_or(0);
+                                    let is_selected = state.selected_flow.as_ref() == Some(flow);
+
+                                    if ui
+                                        .selectable_label(
+                                            is_selected,
+                                            format!("{} ({})", state.display_flow(flow), count),
+                                        )
+                                        .clicked()
+                                    {
+                                        state.selected_flow = if is_selected {
+                                            None
+                                        } else {
+                                            Some(flow.clone())
+                                        };
+                                        state.show_details = None;
+                                        state.show_raw = None;
+                                    }
+                                }
+
+                                // 전체 보기
+                                ui.separator();
+                                let total_count = state.events.len();
+                                let is_all_selected = state.selected_flow.is_none();
+                                if ui
+                                    .selectable_label(
+                                        is_all_selected,
+                                        format!("전체 ({})", total_count),
+                                    )
+                                    .clicked()
+                                {
+                                    state.selected_flow = None;
+                                    state.show_details = None;
+                                    state.show_raw = None;
+                                }
+                            });
+                    }
+                    ViewMode::ByApp => {
+                        ui.heading("애플리케이션 목록");
+                        ScrollArea::vertical()
+                            .auto_shrink([false; 2])
+                            .id_source("app_list_scroll")
+                            .show(ui, |ui| {
+                                let mut apps: Vec<String> =
+                                    state.app_groups.keys().cloned().collect();
+                                apps.sort();
+
+                                for app in &apps {
+                                    let count =
+                                        state.app_groups.get(app).map(|v| v.len()).unwrap_or(0);
+                                    let is_selected = state.selected_app.as_ref() == Some(app);
+
+                                    if ui
+                                        .selectable_label(
+                                            is_selected,
+                                            format!("{} ({})", app, count),
+                                        )
+                                        .clicked()
+                                    {
+                                        state.selected_app =
+                                            if is_selected { None } else { Some(app.clone()) };
+                                        state.show_details = None;
+                                        state.show_raw = None;
+                                    }
+                                }
+
+                                // 전체 보기
+                                ui.separator();
+                                let total_count = state.events.len();
+                                let is_all_selected = state.selected_app.is_none();
+                                if ui
+                                    .selectable_label(
+                                        is_all_selected,
+                                        format!("전체 ({})", total_count),
+                                    )
+                                    .clicked()
+                                {
+                                    state.selected_app = None;
+                                    state.show_details = None;
+                                    state.show_raw = None;
+                                }
+                            });
+                    }
+                    ViewMode::ByKind => {
+                        ui.heading("쿼리 유형");
+                        ScrollArea::vertical()
+                            .auto_shrink([false; 2])
+                            .id_source("kind_list_scroll")
+                            .show(ui, |ui| {
+                                for kind in [
+                                    QueryKind::Parameterized,
+                                    QueryKind::AdHoc,
+                                    QueryKind::Static,
+                                ] {
+                                    let label = kind.label();
+                                    let count =
+                                        state.kind_groups.get(label).map(|v| v.len()).unwrap_or(0);
+                                    let is_selected = state.selected_kind.as_deref() == Some(label);
+
+                                    if ui
+                                        .selectable_label(
+                                            is_selected,
+                                            format!("{} ({})", label, count),
+                                        )
+                                        .clicked()
+                                    {
+                                        state.selected_kind = if is_selected {
+                                            None
+                                        } else {
+                                            Some(label.to_string())
+                                        };
+                                        state.show_details = None;
+                                        state.show_raw = None;
+                                    }
+                                }
+
+                                // 전체 보기
+                                ui.separator();
+                                let total_count = state.events.len();
+                                let is_all_selected = state.selected_kind.is_none();
+                                if ui
+                                    .selectable_label(
+                                        is_all_selected,
+                                        format!("전체 ({})", total_count),
+                                    )
+                                    .clicked()
+                                {
+                                    state.selected_kind = None;
+                                    state.show_details = None;
+                                    state.show_raw = None;
+                                }
+                            });
+                    }
                 }
             });
 
+        // 오른쪽 패널: 선택한 SQL의 상세 정보 / 원본 데이터
+        if let Some(idx) = state.show_details.filter(|&idx| idx < state.events.len()) {
+            let mut toggle_diff = false;
+            SidePanel::right("detail_panel")
+                .resizable(true)
+                .default_width(450.0)
+                .min_width(300.0)
+                .show(ctx, |ui| {
+                    let event = &state.events[idx];
+                    let flow = state.display_flow(&event.flow_id);
+
+                    ui.horizontal(|ui| {
+                        ui.heading("상세 정보");
+                        if ui.button("닫기").clicked() {
+                            state.show_details = None;
+                            state.show_raw = None;
+                        }
+                    });
+
+                    ScrollArea::vertical()
+                        .auto_shrink([false; 2])
+                        .id_source("detail_scroll")
+                        .show(ui, |ui| {
+                            ui.label(
+                                RichText::new(&event.operation)
+                                    .color(operation_color(&event.operation))
+                                    .strong(),
+                            );
+                            if let Some(ref label) = event.label {
+                                ui.label(RichText::new(label).small().weak());
+                            }
+                            ui.label(format!(
+                                "처음: {} / 마지막: {}",
+                                timezone.format(&event.timestamp, "%Y-%m-%d %H:%M:%S%.3f"),
+                                timezone.format(&state.last_seen[idx], "%Y-%m-%d %H:%M:%S%.3f")
+                            ));
+                            ui.label(format!("Flow: {}", flow));
+                            if let Some(ref database) = event.database {
+                                ui.label(format!("DB: {}", database));
+                            }
+                            if !state.table_names[idx].is_empty() {
+                                ui.label(format!("테이블: {}", state.table_names[idx]));
+                            }
+                            if let Some(latency) = event.latency_ms {
+                                ui.label(format!("응답 시간: {:.1} ms", latency));
+                            }
+                            if !event.result_sets.is_empty() {
+                                let rows: Vec<String> = event
+                                    .result_sets
+                                    .iter()
+                                    .map(|count| count.to_string())
+                                    .collect();
+                                ui.label(format!("결과 행 수: {}", rows.join(", ")));
+                            }
+                            if !event.result_columns.is_empty() {
+                                ui.collapsing("결과 컬럼", |ui| {
+                                    for (set, columns) in event.result_columns.iter().enumerate() {
+                                        if event.result_columns.len() > 1 {
+                                            ui.label(
+                                                RichText::new(format!("결과 집합 {}", set + 1))
+                                                    .strong(),
+                                            );
+                                        }
+                                        egui::Grid::new(("result_columns", idx, set))
+                                            .striped(true)
+                                            .show(ui, |ui| {
+                                                for (name, type_name) in columns {
+                                                    ui.label(name);
+                                                    ui.label(type_name);
+                                                    ui.end_row();
+                                                }
+                                            });
+                                    }
+                                });
+                            }
+                            // 첫 TDS 패킷의 헤더 필드
+                            if let Some(header) =
+                                event.raw_data.as_deref().and_then(TdsParser::parse_header)
+                            {
+                                ui.collapsing("헤더", |ui| {
+                                    egui::Grid::new(("tds_header", idx)).striped(true).show(
+                                        ui,
+                                        |ui| {
+                                            for (name, value) in header.fields() {
+                                                ui.label(name);
+                                                ui.label(value);
+                                                ui.end_row();
+                                            }
+                                        },
+                                    );
+                                });
+                            }
+                            if let Some(ref app_name) = event.app_name {
+                                ui.label(format!("애플리케이션: {}", app_name));
+                            }
+                            if let Some(ref client_host) = event.client_host {
+                                ui.label(format!("클라이언트 호스트: {}", client_host));
+                            }
+                            if let Some(ref client_library) = event.client_library {
+                                ui.label(format!("클라이언트 라이브러리: {}", client_library));
+                            }
+                            if let Some(encoding) = event.encoding {
+                                ui.label(format!("인코딩: {}", encoding.name()));
+                            }
+                            if let Some(kind) = event.query_kind() {
+                                ui.label(format!("쿼리 유형: {}", kind.label()));
+                            }
+                            if let Some(danger) = event.danger_label() {
+                                ui.label(
+                                    RichText::new(format!("⚠ {}", danger))
+                                        .color(Color32::from_rgb(255, 100, 100))
+                                        .strong(),
+                                );
+                            }
+                            if event.truncated {
+                                ui.label(
+                                    RichText::new("잘린 패킷: 연결 종료 또는 캡처 중지로 패킷을 끝까지 받지 못함 (SQL 일부만 표시)")
+                                        .color(Color32::from_rgb(255, 180, 0)),
+                                );
+                            }
+                            if event.packet_loss {
+                                ui.label(
+                                    RichText::new("패킷 손실: 스트림에 빈틈이 있어 SQL이나 응답 정보가 불완전할 수 있음")
+                                        .color(Color32::from_rgb(255, 180, 0)),
+                                );
+                            }
+                            if event.reset_connection {
+                                ui.label("세션: 연결 초기화 (연결 풀에서 재사용한 연결의 첫 요청)");
+                            }
+                            if let Some(flags) = event.rpc_flags {
+                                ui.label(format!(
+                                    "RPC 옵션: 0x{:04X} ({})",
+                                    flags,
+                                    TdsParser::describe_rpc_flags(flags)
+                                ));
+                            }
+
+                            ui.separator();
+                            ui.horizontal(|ui| {
+                                ui.label("전체 SQL:");
+                                if ui.button("복사").clicked() {
+                                    ctx.copy_text(event.full_text().into_owned());
+                                }
+                                // sp_executesql의 문장만 (파라미터 값 제외)
+                                if !event.params.is_empty() && ui.button("문장만 복사").clicked() {
+                                    ctx.copy_text(event.sql_text.clone());
+                                }
+                                // SQL Batch만 (RPC는 이미 파라미터화된 호출)
+                                let is_rpc = event
+                                    .raw_data
+                                    .as_ref()
+                                    .is_some_and(|data| data.first() == Some(&0x03));
+                                if !is_rpc && ui.button("sp_executesql로 복사").clicked() {
+                                    ctx.copy_text(to_sp_executesql(&event.sql_text));
+                                }
+                                if !is_rpc && ui.button("파라미터화 복사").clicked() {
+                                    ctx.copy_text(to_parameterized_script(&event.sql_text));
+                                }
+                                // 원본 패킷과 지금 표시한 SQL로 회귀 테스트 코드 생성
+                                if let Some(ref raw_data) = event.raw_data {
+                                    if ui
+                                        .button("테스트 코드 복사")
+                                        .on_hover_text("원본 패킷을 디코딩해 이 SQL이 나오는지 확인하는 Rust #[test] 코드")
+                                        .clicked()
+                                    {
+                                        ctx.copy_text(to_rust_test(raw_data, &event.full_text()));
+                                    }
+                                }
+                                let marked = state.diff_marks.contains(&idx);
+                                if ui
+                                    .selectable_label(marked, "비교")
+                                    .on_hover_text("두 SQL을 표시하면 다른 부분을 비교하는 창이 열림")
+                                    .clicked()
+                                {
+                                    toggle_diff = true;
+                                }
+                            });
+                            // 아주 긴 SQL은 일부만 표시 (전체를 TextEdit에 넣으면 화면이 멈춤)
+                            // 복사 버튼은 항상 전체 SQL을 복사
+                            let display_len = match state.sql_display_len {
+                                Some((shown_idx, len)) if shown_idx == idx => len,
+                                _ => state.config.max_sql_display,
+                            };
+                            let mut sql_text = truncate_str(&event.sql_text, display_len);
+                            let shown_len = sql_text.len();
+                            ui.add(
+                                TextEdit::multiline(&mut sql_text)
+                                    .desired_width(f32::INFINITY)
+                                    .interactive(true),
+                            );
+                            if shown_len < event.sql_text.len() {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!(
+                                        "{} / {} 바이트 표시 중",
+                                        shown_len,
+                                        event.sql_text.len()
+                                    ));
+                                    if ui.button("더 보기").clicked() {
+                                        state.sql_display_len = Some((
+                                            idx,
+                                            display_len + state.config.max_sql_display.max(1),
+                                        ));
+                                    }
+                                });
+                            }
+
+                            // sp_executesql 파라미터 (이름, 값)
+                            if !event.params.is_empty() {
+                                ui.separator();
+                                ui.label(format!("파라미터 ({}개):", event.params.len()));
+                                egui::Grid::new(("rpc_params", idx)).striped(true).show(
+                                    ui,
+                                    |ui| {
+                                        for (name, value) in &event.params {
+                                            ui.label(RichText::new(name).monospace());
+                                            ui.label(value);
+                                            ui.end_row();
+                                        }
+                                    },
+                                );
+                            }
+
+                            // 원본 데이터 (Hex)
+                            if let Some(ref raw_data) = event.raw_data {
+                                ui.separator();
+                                let show_raw = state.show_raw == Some(idx);
+                                if ui
+                                    .button(if show_raw {
+                                        "원본 숨기기"
+                                    } else {
+                                        "원본 보기"
+                                    })
+                                    .clicked()
+                                {
+                                    state.show_raw = if show_raw { None } else { Some(idx) };
+                                    state.hex_selection = None;
+                                }
+
+                                if show_raw {
+                                    // Hex 문자열 생성 (16바이트씩 줄바꿈)
+                                    let hex_string = hex_dump(raw_data);
+
+                                    // 여러 패킷으로 나뉜 메시지는 모든 패킷을 이어 붙인 바이트
+                                    let packet_count = TdsParser::message_packets(raw_data).len();
+                                    ui.horizontal(|ui| {
+                                        ui.label(if packet_count > 1 {
+                                            format!(
+                                                "원본 데이터 (Hex, 패킷 {}개 / {}바이트):",
+                                                packet_count,
+                                                raw_data.len()
+                                            )
+                                        } else {
+                                            "원본 데이터 (Hex):".to_string()
+                                        });
+                                        if ui.button("복사").clicked() {
+                                            ctx.copy_text(hex_string);
+                                        }
+                                        if let Some((start, end)) = state.hex_selection {
+                                            let range = start.min(end)..=start.max(end);
+                                            if let Some(selected) = raw_data.get(range) {
+                                                if ui
+                                                    .button(format!("선택 복사 ({}바이트)", selected.len()))
+                                                    .clicked()
+                                                {
+                                                    ctx.copy_text(
+                                                        selected
+                                                            .iter()
+                                                            .map(|b| format!("{:02x}", b))
+                                                            .collect::<Vec<_>>()
+                                                            .join(" "),
+                                                    );
+                                                }
+                                            }
+                                        }
+                                        ui.checkbox(&mut state.hex_highlight_headers, "TDS 헤더 강조");
+                                    });
+                                    hex_view(
+                                        ui,
+                                        raw_data,
+                                        &mut state.hex_selection,
+                                        state.hex_highlight_headers,
+                                    );
+                                }
+                            }
+                        });
+                });
+            if toggle_diff {
+                state.toggle_diff_mark(idx);
+            }
+        }
+
         // 오른쪽 중앙 패널: SQL 목록
         CentralPanel::default().show(ctx, |ui| {
             ui.push_id("sql_panel", |ui| {
@@ -462,7 +2854,10 @@ pub fn show_gui(ctx: &egui::Context, state: &mut GuiState) {
                                 state.get_selected_events().len()
                             )
                         } else {
-                            format!("전체 SQL 목록 ({}개)", state.events.len())
+                            format!(
+                                "전체 SQL 목록 ({}개)",
+                                state.all_table_view_events().len()
+                            )
                         }
                     }
                     ViewMode::BySql => {
@@ -476,146 +2871,252 @@ pub fn show_gui(ctx: &egui::Context, state: &mut GuiState) {
                             format!("전체 SQL 목록 ({}개)", state.events.len())
                         }
                     }
+                    ViewMode::ByFlow => {
+                        if let Some(ref flow) = state.selected_flow {
+                            format!(
+                                "Flow: {} ({}개)",
+                                state.display_flow(flow),
+                                state.get_selected_events().len()
+                            )
+                        } else {
+                            format!("전체 SQL 목록 ({}개)", state.events.len())
+                        }
+                    }
+                    ViewMode::ByApp => {
+                        if let Some(ref app) = state.selected_app {
+                            format!("앱: {} ({}개)", app, state.get_selected_events().len())
+                        } else {
+                            format!("전체 SQL 목록 ({}개)", state.events.len())
+                        }
+                    }
+                    ViewMode::ByKind => {
+                        if let Some(ref kind) = state.selected_kind {
+                            format!("쿼리 유형: {} ({}개)", kind, state.get_selected_events().len())
+                        } else {
+                            format!("전체 SQL 목록 ({}개)", state.events.len())
+                        }
+                    }
                 };
-                ui.heading(&title);
-
-                // heading을 그린 후 남은 높이 계산
-                let sql_scroll_height = ui.available_height();
-
-                ScrollArea::vertical()
-                    .auto_shrink([false; 2])
-                    .max_height(sql_scroll_height)
-                    .id_source("sql_list_scroll")
-                    .show(ui, |ui| {
-                        let event_indices = state.get_selected_events();
-
-                        for &idx in &event_indices {
-                            let event = &state.events[idx];
+                ui.horizontal(|ui| {
+                    ui.heading(&title);
+                    let search = ui
+                        .add(
+                            TextEdit::singleline(&mut state.search_query)
+                                .hint_text("SQL 검색 (예: op=SELECT and table~진료)")
+                                .desired_width(200.0),
+                        )
+                        .on_hover_text(
+                            "필드 조건: op=, table= / table~, sql= / sql~(정규식), time>= / time<=\n\
+                             and / or / not과 괄호로 조합 (필드 조건이 없으면 SQL 텍스트 검색)",
+                        );
+                    if search.changed() {
+                        state.update_search();
+                    }
+                    if let Some(ref error) = state.search_error {
+                        ui.colored_label(Color32::from_rgb(255, 100, 100), "⚠ 조건식 오류")
+                            .on_hover_text(error);
+                    }
 
-                            ui.group(|ui| {
-                                ui.horizontal(|ui| {
-                                    // 작업 타입 색상
-                                    let color = match event.operation.as_str() {
-                                        "SELECT" => Color32::from_rgb(100, 200, 100),
-                                        "INSERT" => Color32::from_rgb(100, 150, 255),
-                                        "UPDATE" => Color32::from_rgb(255, 200, 100),
-                                        "DELETE" => Color32::from_rgb(255, 100, 100),
-                                        "EXEC" => Color32::from_rgb(200, 100, 255),
-                                        _ => Color32::GRAY,
-                                    };
+                    // 선택한 Flow의 재조립된 클라이언트 스트림 중 아직 디코딩하지 않은 부분 보기 (캡처 중에만)
+                    if state.view_mode == ViewMode::ByFlow {
+                        if let Some(flow) = state.selected_flow.clone() {
+                            if ui
+                                .add_enabled(state.is_capturing, egui::Button::new("스트림 덤프"))
+                                .clicked()
+                            {
+                                state.request_stream_dump(&flow);
+                            }
+                        }
+                    }
+                });
 
-                                    ui.label(RichText::new(&event.operation).color(color).strong());
-                                    ui.separator();
-                                    ui.label(format!(
-                                        "{}",
-                                        event.timestamp.format("%Y-%m-%d %H:%M:%S%.3f")
-                                    ));
-                                    ui.separator();
-                                    ui.label(&event.flow_id);
+                // 작업 필터 칩: 끈 작업의 SQL은 목록에서 숨김
+                ui.horizontal(|ui| {
+                    for (i, chip) in OPERATION_CHIPS.into_iter().enumerate() {
+                        let active = state.active_operations.contains(chip);
+                        if ui
+                            .selectable_label(active, chip)
+                            .on_hover_text(format!("Alt+{}", i + 1))
+                            .clicked()
+                        {
+                            state.toggle_operation_chip(chip);
+                        }
+                    }
+                });
 
-                                    if !event.tables.is_empty() {
-                                        ui.separator();
-                                        ui.label(format!("테이블: {}", event.tables.join(", ")));
-                                    }
-                                });
+                // 행 클릭은 표에서 처리하므로 라벨 텍스트 선택은 끔
+                ui.style_mut().interaction.selectable_labels = false;
 
-                                // SQL 미리보기
-                                let sql_preview = if event.sql_text.chars().count() > 200 {
-                                    event.sql_text.chars().take(200).collect::<String>() + "..."
-                                } else {
-                                    event.sql_text.clone()
-                                };
-                                ui.label(sql_preview);
+                let event_indices = state.sorted_events();
+                let scroll_row = if state.scroll_to_details {
+                    state.scroll_to_details = false;
+                    state
+                        .show_details
+                        .and_then(|idx| event_indices.iter().position(|&i| i == idx))
+                } else {
+                    None
+                };
 
-                                ui.horizontal(|ui| {
-                                    // 상세 보기 버튼
-                                    if ui.button("상세 보기").clicked() {
-                                        state.show_details = if state.show_details == Some(idx) {
-                                            None
-                                        } else {
-                                            Some(idx)
-                                        };
-                                    }
+                let mut clicked_sort = None;
+                let mut clicked_row = None;
+                let mut clicked_mark = None;
+                let mut table = TableBuilder::new(ui)
+                    .striped(true)
+                    .sense(egui::Sense::click())
+                    .auto_shrink([false; 2])
+                    .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                    .column(Column::exact(20.0))
+                    .column(Column::auto().at_least(170.0))
+                    .column(Column::auto().at_least(60.0))
+                    .column(Column::auto().at_least(70.0))
+                    .column(
+                        Column::initial(200.0)
+                            .at_least(80.0)
+                            .resizable(true)
+                            .clip(true),
+                    )
+                    .column(Column::remainder().clip(true));
+                if let Some(row) = scroll_row {
+                    table = table.scroll_to_row(row, Some(egui::Align::Center));
+                }
 
-                                    // 원본 보기 버튼
-                                    if event.raw_data.is_some() && ui.button("원본 보기").clicked()
-                                    {
-                                        state.show_raw = if state.show_raw == Some(idx) {
-                                            None
-                                        } else {
-                                            Some(idx)
-                                        };
-                                    }
-                                });
+                table
+                    .header(24.0, |mut header| {
+                        let time_label = if state.config.show_last_seen {
+                            "마지막 실행"
+                        } else {
+                            "시간"
+                        };
+                        header.col(|ui| {
+                            ui.label(RichText::new("비교").small().weak())
+                                .on_hover_text("두 SQL을 체크하면 다른 부분을 비교하는 창이 열림");
+                        });
+                        for (column, name) in [
+                            (SortColumn::Time, time_label),
+                            (SortColumn::Operation, "작업"),
+                            (SortColumn::Latency, "응답(ms)"),
+                            (SortColumn::Tables, "테이블"),
+                            (SortColumn::Sql, "SQL"),
+                        ] {
+                            header.col(|ui| {
+                                let arrow =
+                                    match (state.sort_column == column, state.sort_ascending) {
+                                        (true, true) => " ▲",
+                                        (true, false) => " ▼",
+                                        (false, _) => "",
+                                    };
+                                if ui
+                                    .add(
+                                        egui::Button::new(
+                                            RichText::new(format!("{}{}", name, arrow)).strong(),
+                                        )
+                                        .frame(false),
+                                    )
+                                    .clicked()
+                                {
+                                    clicked_sort = Some(column);
+                                }
+                            });
+                        }
+                    })
+                    .body(|body| {
+                        // 보이는 행만 그림
+                        body.rows(20.0, event_indices.len(), |mut row| {
+                            let idx = event_indices[row.index()];
+                            let event = &state.events[idx];
+                            row.set_selected(state.show_details == Some(idx));
 
-                                // 상세 정보
-                                if state.show_details == Some(idx) {
-                                    ui.separator();
-                                    ui.group(|ui| {
-                                        ui.horizontal(|ui| {
-                                            ui.label("전체 SQL:");
-                                            if ui.button("복사").clicked() {
-                                                ctx.copy_text(event.sql_text.clone());
-                                            }
-                                        });
-                                        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
-                                            let mut sql_text = event.sql_text.clone();
-                                            ui.add(
-                                                TextEdit::multiline(&mut sql_text)
-                                                    .desired_width(f32::INFINITY)
-                                                    .interactive(true),
-                                            );
-                                        });
+                            row.col(|ui| {
+                                let mut marked = state.diff_marks.contains(&idx);
+                                if ui.checkbox(&mut marked, "").changed() {
+                                    clicked_mark = Some(idx);
+                                }
+                            });
+                            row.col(|ui| {
+                                ui.label(timezone.format(
+                                    &state.display_timestamp(idx),
+                                    "%Y-%m-%d %H:%M:%S%.3f",
+                                ));
+                            });
+                            row.col(|ui| {
+                                ui.label(
+                                    RichText::new(&event.operation)
+                                        .color(operation_color(&event.operation))
+                                        .strong(),
+                                );
+                                // 연결 풀 재사용으로 세션이 새로 시작된 요청
+                                if event.reset_connection {
+                                    ui.label(RichText::new("RESET").small().weak())
+                                        .on_hover_text("연결 초기화 (새 세션의 첫 요청)");
+                                }
+                            });
+                            row.col(|ui| {
+                                if let Some(latency) = event.latency_ms {
+                                    let text = RichText::new(format!("{:.1}", latency));
+                                    ui.label(if latency > state.config.slow_query_ms {
+                                        text.color(Color32::from_rgb(255, 100, 100)).strong()
+                                    } else {
+                                        text
                                     });
                                 }
-
-                                // 원본 데이터 (Hex)
-                                if state.show_raw == Some(idx) {
-                                    if let Some(ref raw_data) = event.raw_data {
-                                        ui.separator();
-                                        ui.group(|ui| {
-                                            // Hex 문자열 생성 (16바이트씩 줄바꿈)
-                                            let hex_string: String = raw_data
-                                                .chunks(16)
-                                                .enumerate()
-                                                .map(|(i, chunk)| {
-                                                    let hex: String = chunk
-                                                        .iter()
-                                                        .map(|b| format!("{:02x}", b))
-                                                        .collect::<Vec<_>>()
-                                                        .join(" ");
-                                                    let offset = i * 16;
-                                                    format!("{:08x}:  {}", offset, hex)
-                                                })
-                                                .collect::<Vec<_>>()
-                                                .join("\n");
-
-                                            ui.horizontal(|ui| {
-                                                ui.label("원본 데이터 (Hex):");
-                                                if ui.button("복사").clicked() {
-                                                    ctx.copy_text(hex_string.clone());
-                                                }
-                                            });
-                                            ScrollArea::vertical().max_height(300.0).show(
-                                                ui,
-                                                |ui| {
-                                                    let mut hex_text = hex_string;
-                                                    ui.add(
-                                                        TextEdit::multiline(&mut hex_text)
-                                                            .desired_width(f32::INFINITY)
-                                                            .font(egui::TextStyle::Monospace)
-                                                            .interactive(true),
-                                                    );
-                                                },
-                                            );
-                                        });
-                                    }
+                            });
+                            row.col(|ui| {
+                                ui.label(&state.table_names[idx]);
+                            });
+                            row.col(|ui| {
+                                // SQL 미리보기 (한 줄로)
+                                let sql_preview: String = event
+                                    .full_text()
+                                    .split_whitespace()
+                                    .collect::<Vec<_>>()
+                                    .join(" ")
+                                    .chars()
+                                    .take(200)
+                                    .collect();
+                                // 위험한 문장 (WHERE 없는 DELETE / UPDATE, TRUNCATE, DROP)
+                                let response = if event.danger_label().is_some() {
+                                    ui.label(
+                                        RichText::new(format!("⚠ {}", sql_preview))
+                                            .color(Color32::from_rgb(255, 100, 100)),
+                                    )
+                                } else {
+                                    ui.label(sql_preview)
+                                };
+                                if let Some(ref label) = event.label {
+                                    response.on_hover_text(label);
                                 }
                             });
 
-                            ui.add_space(5.0);
-                        }
+                            if row.response().clicked() {
+                                clicked_row = Some(idx);
+                            }
+                        });
                     });
+
+                // 같은 열을 다시 누르면 정렬 방향 반전
+                if let Some(column) = clicked_sort {
+                    if state.sort_column == column {
+                        state.sort_ascending = !state.sort_ascending;
+                    } else {
+                        state.sort_column = column;
+                        state.sort_ascending = true;
+                    }
+                }
+
+                // 비교 체크 (세 번째를 체크하면 가장 먼저 체크한 것은 해제)
+                if let Some(idx) = clicked_mark {
+                    state.toggle_diff_mark(idx);
+                }
+
+                // 선택한 행을 다시 누르면 상세 패널 닫기
+                if let Some(idx) = clicked_row {
+                    if state.show_details == Some(idx) {
+                        state.show_details = None;
+                    } else {
+                        state.show_details = Some(idx);
+                    }
+                    state.show_raw = None;
+                }
             });
         });
     } else {
@@ -637,4 +3138,576 @@ pub fn show_gui(ctx: &egui::Context, state: &mut GuiState) {
             });
         });
     }
+
+    // 스트림 덤프 창
+    if let Some((flow_id, data)) = state.stream_dump.clone() {
+        let mut open = true;
+        egui::Window::new("스트림 덤프")
+            .open(&mut open)
+            .default_width(700.0)
+            .default_height(500.0)
+            .show(ctx, |ui| {
+                ui.label(format!("Flow: {}", state.display_flow(&flow_id)));
+
+                let Some(data) = data else {
+                    ui.label("디코딩하지 않고 남은 클라이언트 데이터가 없습니다.");
+                    return;
+                };
+
+                let hex_string = hex_dump(&data);
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "디코딩하지 않은 클라이언트 → 서버 스트림 ({} bytes)",
+                        data.len()
+                    ));
+                    if ui.button("복사").clicked() {
+                        ctx.copy_text(hex_string.clone());
+                    }
+                    if ui.button("저장").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("bin", &["bin"])
+                            .set_file_name("stream_dump.bin")
+                            .save_file()
+                        {
+                            state.processing_status = match std::fs::write(&path, &data) {
+                                Ok(()) => format!("스트림 덤프 저장됨 ({})", path.display()),
+                                Err(e) => format!("스트림 덤프 저장 실패: {}", e),
+                            };
+                        }
+                    }
+                });
+
+                ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .id_source("stream_dump_scroll")
+                    .show(ui, |ui| {
+                        let mut hex_text = hex_string;
+                        ui.add(
+                            TextEdit::multiline(&mut hex_text)
+                                .desired_width(f32::INFINITY)
+                                .font(egui::TextStyle::Monospace)
+                                .interactive(true),
+                        );
+                    });
+            });
+
+        if !open {
+            state.stream_dump = None;
+        }
+    }
+
+    // SQL 비교 창 (비교 대상으로 표시한 두 SQL)
+    if let (Some(spans), &[a, b]) = (state.sql_diff.as_ref(), &state.diff_marks[..]) {
+        let mut open = true;
+        let mut swap = false;
+        let mut side_by_side = state.diff_side_by_side;
+        egui::Window::new("SQL 비교")
+            .open(&mut open)
+            .default_width(700.0)
+            .default_height(400.0)
+            .show(ctx, |ui| {
+                for (name, idx) in [("A", a), ("B", b)] {
+                    let event = &state.events[idx];
+                    ui.label(format!(
+                        "{}: {}  {}  {}",
+                        name,
+                        state
+                            .config
+                            .timezone
+                            .format(&event.timestamp, "%Y-%m-%d %H:%M:%S%.3f"),
+                        event.operation,
+                        state.display_flow(&event.flow_id)
+                    ));
+                }
+                let hunks = changed_hunks(spans);
+                ui.horizontal(|ui| {
+                    if hunks.is_empty() {
+                        ui.label("두 SQL이 같습니다");
+                    } else {
+                        ui.label(format!("다른 부분 {}곳", hunks.len()));
+                    }
+                    ui.label(
+                        RichText::new("A에만 있음")
+                            .background_color(DIFF_DELETE_COLOR)
+                            .strikethrough(),
+                    );
+                    ui.label(RichText::new("B에만 있음").background_color(DIFF_INSERT_COLOR));
+                    if ui.button("A ↔ B").clicked() {
+                        swap = true;
+                    }
+                    ui.checkbox(&mut side_by_side, "나란히 보기");
+                });
+                ui.separator();
+
+                let text_color = ui.visuals().text_color();
+                if side_by_side {
+                    ui.columns(2, |columns| {
+                        for (column, (name, skip)) in columns
+                            .iter_mut()
+                            .zip([("A", DiffKind::Insert), ("B", DiffKind::Delete)])
+                        {
+                            column.label(RichText::new(name).strong());
+                            ScrollArea::vertical()
+                                .auto_shrink([false; 2])
+                                .id_source(("sql_diff_scroll", name))
+                                .show(column, |ui| {
+                                    ui.label(diff_layout(spans, Some(skip), text_color));
+                                });
+                        }
+                    });
+                } else {
+                    ScrollArea::vertical()
+                        .auto_shrink([false; 2])
+                        .id_source("sql_diff_scroll")
+                        .show(ui, |ui| {
+                            ui.label(diff_layout(spans, None, text_color));
+                        });
+                }
+            });
+
+        state.diff_side_by_side = side_by_side;
+        if swap {
+            state.diff_marks.swap(0, 1);
+            state.update_sql_diff();
+        }
+        if !open {
+            state.diff_marks.clear();
+            state.sql_diff = None;
+        }
+    }
+
+    // hex 디코딩 창
+    if state.show_hex_decode {
+        let mut open = true;
+        egui::Window::new("hex 디코딩")
+            .open(&mut open)
+            .default_width(700.0)
+            .default_height(500.0)
+            .show(ctx, |ui| {
+                ui.label("TDS 패킷의 hex 덤프를 붙여넣으세요 (공백, 줄 앞 오프셋, 줄 끝 ASCII 열은 무시)");
+                ui.add(
+                    TextEdit::multiline(&mut state.hex_input)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(8)
+                        .font(egui::TextStyle::Monospace)
+                        .hint_text("04 01 00 2a 00 00 01 00 ..."),
+                );
+                ui.horizontal(|ui| {
+                    ui.label("패킷 타입:");
+                    ui.selectable_value(&mut state.hex_packet_type, None, "자동")
+                        .on_hover_text("헤더의 타입으로 SQL Batch / RPC 패킷만 디코딩");
+                    ui.selectable_value(
+                        &mut state.hex_packet_type,
+                        Some(TdsPacketType::SqlBatch),
+                        "SQL Batch",
+                    );
+                    ui.selectable_value(
+                        &mut state.hex_packet_type,
+                        Some(TdsPacketType::RpcRequest),
+                        "RPC",
+                    );
+                    ui.separator();
+                    if ui.button("디코딩").clicked() {
+                        state.decode_hex_input();
+                    }
+                    if ui.button("지우기").clicked() {
+                        state.hex_input.clear();
+                        state.hex_decode_result = None;
+                    }
+                });
+                ui.separator();
+
+                match state.hex_decode_result {
+                    None => {}
+                    Some(Err(ref error)) => {
+                        ui.label(RichText::new(error).color(Color32::from_rgb(255, 100, 100)));
+                    }
+                    Some(Ok(ref packets)) if packets.is_empty() => {
+                        ui.label("디코딩된 SQL이 없습니다 (헤더를 포함한 완전한 SQL Batch / RPC 패킷인지 확인하세요)");
+                    }
+                    Some(Ok(ref packets)) => {
+                        ScrollArea::vertical()
+                            .auto_shrink([false; 2])
+                            .id_source("hex_decode_scroll")
+                            .show(ui, |ui| {
+                                for (i, (sql, raw)) in packets.iter().enumerate() {
+                                    let summary = TdsParser::parse_header(raw)
+                                        .map(|header| {
+                                            header
+                                                .fields()
+                                                .into_iter()
+                                                .take(3)
+                                                .map(|(name, value)| format!("{}: {}", name, value))
+                                                .collect::<Vec<_>>()
+                                                .join(", ")
+                                        })
+                                        .unwrap_or_default();
+                                    ui.label(
+                                        RichText::new(format!("패킷 {} ({})", i + 1, summary))
+                                            .strong(),
+                                    );
+                                    let mut sql_text = sql.as_str();
+                                    ui.add(
+                                        TextEdit::multiline(&mut sql_text)
+                                            .desired_width(f32::INFINITY)
+                                            .font(egui::TextStyle::Monospace),
+                                    );
+                                    ui.add_space(8.0);
+                                }
+                            });
+                    }
+                }
+            });
+
+        if !open {
+            state.show_hex_decode = false;
+        }
+    }
+
+    // 다시 그리기 예약 (이번 프레임에서 캡처를 시작/중지했을 수 있으므로 마지막에)
+    // 새 이벤트가 있으면 바로, 캡처 중이거나 기다리는 응답이 있으면 주기적으로 확인,
+    // 그 외에는 입력이 있을 때만 다시 그림 (유휴 시 CPU 사용 방지)
+    if received {
+        ctx.request_repaint();
+    } else if state.is_capturing
+        || state.interface_test_receiver.is_some()
+        || state.file_replay.is_some()
+        || !state.reorder_buffer.is_empty()
+        || (state.config.resolve_hosts && state.resolver.is_resolving())
+    {
+        ctx.request_repaint_after(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 사용자 설정 파일과 관계없이 기본 설정을 쓰는 상태
+    fn state() -> GuiState {
+        let mut state = GuiState::new();
+        state.config = Config::default();
+        state
+    }
+
+    #[test]
+    fn exec_calls_are_grouped_by_procedure() {
+        let mut state = state();
+        state.add_event(SqlEvent::for_test("EXEC usp_Foo", 1));
+        state.add_event(SqlEvent::for_test("EXECUTE dbo.usp_Foo 1, 2", 2));
+        state.add_event(SqlEvent::for_test("EXEC dbo.usp_Bar @id=5", 3));
+
+        assert_eq!(state.table_groups["PROC: usp_Foo"], vec![0, 1]);
+        assert_eq!(state.table_groups["PROC: usp_Bar"], vec![2]);
+        assert!(!state.table_groups.contains_key(OTHER_TABLE_GROUP));
+    }
+
+    #[test]
+    fn duplicate_sql_records_first_and_last_seen() {
+        let mut state = state();
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_Users", 10));
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_Users", 20));
+        // 늦게 도착한 예전 이벤트는 마지막 실행 시각을 되돌리지 않음
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_Users", 15));
+
+        assert_eq!(state.events.len(), 1);
+        assert_eq!(state.events[0].timestamp.timestamp(), 10);
+        assert_eq!(state.last_seen[0].timestamp(), 20);
+    }
+
+    #[test]
+    fn last_seen_toggle_changes_time_column_and_sort_order() {
+        let mut state = state();
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_A", 10));
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_B", 20));
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_A", 30));
+        state.view_mode = ViewMode::BySql;
+        state.sort_column = SortColumn::Time;
+        state.sort_ascending = false;
+
+        assert_eq!(state.display_timestamp(0).timestamp(), 10);
+        assert_eq!(state.sorted_events(), vec![1, 0]);
+
+        // 마지막 실행 시각 기준이면 최근에 다시 실행된 쿼리가 위로
+        state.config.show_last_seen = true;
+        assert_eq!(state.display_timestamp(0).timestamp(), 30);
+        assert_eq!(state.sorted_events(), vec![0, 1]);
+    }
+
+    #[test]
+    fn split_batches_adds_one_event_per_statement() {
+        let mut state = state();
+        state.config.split_batches = true;
+        state.commit_events(vec![SqlEvent::for_test(
+            "UPDATE TB_A SET X = ';' WHERE ID = 1; SELECT * FROM TB_B",
+            1,
+        )]);
+
+        let texts: Vec<&str> = state.events.iter().map(|e| e.sql_text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["UPDATE TB_A SET X = ';' WHERE ID = 1", "SELECT * FROM TB_B"]
+        );
+        assert_eq!(state.table_groups["A"], vec![0]);
+        assert_eq!(state.table_groups["B"], vec![1]);
+    }
+
+    #[test]
+    fn tables_differing_only_in_case_share_a_group() {
+        let mut state = state();
+        state.add_event(SqlEvent::for_test("SELECT * FROM dbo.TB_Users", 1));
+        state.add_event(SqlEvent::for_test("SELECT * FROM tb_users WHERE id = 1", 2));
+
+        assert_eq!(state.table_groups["Users"], vec![0, 1]);
+        assert!(!state.table_groups.contains_key("users"));
+    }
+
+    #[test]
+    fn nfd_and_nfc_korean_table_names_share_a_group() {
+        use unicode_normalization::UnicodeNormalization;
+
+        let nfc_sql = "SELECT * FROM dbo.TB_진료내역";
+        let nfd_sql: String = nfc_sql.nfd().collect();
+        assert_ne!(nfd_sql, nfc_sql);
+
+        let mut state = state();
+        state.add_event(SqlEvent::for_test(nfc_sql, 1));
+        // 클라이언트가 NFD로 보낸 SQL과 테이블명
+        state.add_event(SqlEvent {
+            tables: vec![format!("dbo.TB_{}", "진료내역".nfd().collect::<String>())],
+            ..SqlEvent::for_test(&nfd_sql, 2)
+        });
+
+        assert_eq!(state.events.len(), 1);
+        assert_eq!(state.table_groups.len(), 1);
+        assert_eq!(state.table_groups["진료내역"], vec![0]);
+    }
+
+    #[test]
+    fn events_from_different_apps_group_separately() {
+        let mut state = state();
+        state.config.dedup_scope = DedupScope::PerFlow;
+        for (flow_id, app_name) in [
+            ("10.0.0.5:50000->10.0.0.1:1433", Some("Alpha")),
+            ("10.0.0.6:50000->10.0.0.1:1433", Some("Beta")),
+            ("10.0.0.7:50000->10.0.0.1:1433", None),
+        ] {
+            state.add_event(SqlEvent {
+                flow_id: flow_id.to_string(),
+                app_name: app_name.map(str::to_string),
+                ..SqlEvent::for_test("SELECT * FROM TB_Users", 1)
+            });
+        }
+
+        assert_eq!(state.app_groups["Alpha"], vec![0]);
+        assert_eq!(state.app_groups["Beta"], vec![1]);
+        assert_eq!(state.app_groups[UNKNOWN_APP], vec![2]);
+    }
+
+    #[test]
+    fn recent_events_are_in_arrival_order_with_repeats() {
+        let mut state = state();
+        for (i, sql) in [
+            "SELECT 1 FROM TB_A",
+            "SELECT 2 FROM TB_B",
+            "SELECT 1 FROM TB_A",
+            "SELECT 3 FROM TB_C",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            state.add_event(SqlEvent::for_test(sql, i as i64));
+        }
+
+        assert_eq!(state.recent_events(10), vec![0, 1, 0, 2]);
+        assert_eq!(state.recent_events(2), vec![0, 2]);
+        assert!(state.recent_events(0).is_empty());
+
+        // 검색어를 적용한 뒤의 최근 n개
+        state.search_query = "TB_A".to_string();
+        state.update_search();
+        assert_eq!(state.recent_events(10), vec![0, 0]);
+        assert_eq!(state.recent_events(1), vec![0]);
+    }
+
+    #[test]
+    fn latest_latency_is_kept_and_sortable() {
+        let mut state = state();
+        let with_latency = |sql: &str, latency_ms: Option<f64>| SqlEvent {
+            latency_ms,
+            ..SqlEvent::for_test(sql, 1)
+        };
+        state.add_event(with_latency("SELECT * FROM TB_A", Some(300.0)));
+        state.add_event(with_latency("SELECT * FROM TB_B", None));
+        state.add_event(with_latency("SELECT * FROM TB_C", Some(20.0)));
+        // 중복 SQL의 응답 시간은 마지막 값, 응답이 없었던 실행은 갱신하지 않음
+        state.add_event(with_latency("SELECT * FROM TB_A", Some(5.0)));
+        state.add_event(with_latency("SELECT * FROM TB_A", None));
+        assert_eq!(state.events[0].latency_ms, Some(5.0));
+
+        state.view_mode = ViewMode::BySql;
+        state.sort_column = SortColumn::Latency;
+        state.sort_ascending = true;
+        assert_eq!(state.sorted_events(), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn matching_event_is_added_to_alerts_once() {
+        let mut state = state();
+        state.add_event(SqlEvent::for_test("DELETE FROM TB_x WHERE id=1", 1));
+        state.add_event(SqlEvent::for_test("DELETE FROM TB_x", 2));
+        state.add_event(SqlEvent::for_test("DELETE FROM TB_x", 3));
+
+        assert_eq!(state.alerts, vec![1]);
+    }
+
+    #[test]
+    fn operation_chips_hide_switched_off_operations() {
+        let mut state = state();
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_A", 1));
+        state.add_event(SqlEvent::for_test("DELETE FROM TB_A WHERE id=1", 2));
+        state.add_event(SqlEvent::for_test("INSERT INTO TB_B SELECT * FROM TB_A", 3));
+        state.add_event(SqlEvent::for_test("EXECUTE usp_Foo", 4));
+        state.add_event(SqlEvent::for_test("SET NOCOUNT ON", 5));
+        state.view_mode = ViewMode::BySql;
+        assert_eq!(state.get_selected_events(), vec![0, 1, 2, 3, 4]);
+
+        state.toggle_operation_chip("SELECT");
+        state.toggle_operation_chip("EXEC");
+        // INSERT ... SELECT는 INSERT가 켜져 있어 남고, 칩에 없는 SET 문도 남음
+        assert_eq!(state.get_selected_events(), vec![1, 2, 4]);
+
+        state.toggle_operation_chip("SELECT");
+        assert_eq!(state.get_selected_events(), vec![0, 1, 2, 4]);
+    }
+
+    #[cfg(feature = "control-api")]
+    #[test]
+    fn control_commands_read_and_clear_events() {
+        let mut state = state();
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_A", 1));
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_B", 2));
+
+        let response = state.handle_control_command(&ControlCommand::GetEvents { since: 1 });
+        assert_eq!(response["ok"], true);
+        assert_eq!(response["command"], "get_events");
+        assert_eq!(response["capturing"], false);
+        assert_eq!(response["next"], 2);
+        assert_eq!(response["events"].as_array().unwrap().len(), 1);
+        assert_eq!(response["events"][0]["sql_text"], "SELECT * FROM TB_B");
+        // 범위를 넘는 since는 빈 목록
+        let response = state.handle_control_command(&ControlCommand::GetEvents { since: 5 });
+        assert!(response["events"].as_array().unwrap().is_empty());
+
+        let response = state.handle_control_command(&ControlCommand::Clear);
+        assert_eq!(response["cleared"], 2);
+        assert!(state.events.is_empty());
+        assert!(state.table_groups.is_empty());
+    }
+
+    #[cfg(feature = "control-api")]
+    #[test]
+    fn control_start_and_stop_report_errors() {
+        let mut state = state();
+        state.selected_interface = None;
+
+        let response = state.handle_control_command(&ControlCommand::Start { interface: None });
+        assert_eq!(response["ok"], false);
+        assert_eq!(response["command"], "start");
+        assert_eq!(response["error"], "No interface selected");
+        assert!(!state.is_capturing);
+
+        let response = state.handle_control_command(&ControlCommand::Stop);
+        assert_eq!(response["ok"], false);
+        assert_eq!(response["error"], "Not capturing");
+
+        state.is_capturing = true;
+        let response = state.handle_control_command(&ControlCommand::Start { interface: None });
+        assert_eq!(response["error"], "Already capturing");
+        let response = state.handle_control_command(&ControlCommand::Stop);
+        assert_eq!(response["ok"], true);
+        assert_eq!(response["events"], 0);
+        assert!(!state.is_capturing);
+    }
+
+    #[test]
+    fn capture_failure_leaves_capturing_state() {
+        let mut state = state();
+        let (status_sender, status_receiver) = mpsc::channel();
+        state.set_capture_status_receiver(status_receiver);
+        state.is_capturing = true;
+        state.capture_started = true;
+        // 아직 재정렬 대기 중인 이벤트
+        state
+            .reorder_buffer
+            .push((Instant::now(), SqlEvent::for_test("SELECT * FROM TB_A", 1)));
+
+        state.poll_capture_status();
+        assert!(state.is_capturing);
+
+        status_sender
+            .send(CaptureStatus::Failed("eth0: No such device".to_string()))
+            .unwrap();
+        state.poll_capture_status();
+
+        assert!(!state.is_capturing);
+        assert!(!state.capture_started);
+        assert_eq!(state.processing_status, "캡처 실패: eth0: No such device");
+        assert_eq!(state.events.len(), 1);
+        assert!(state.capture_status_receiver.is_none());
+    }
+
+    #[test]
+    fn temp_tables_share_one_group_apart_from_real_tables() {
+        let mut state = state();
+        state.add_event(SqlEvent::for_test(
+            "SELECT * FROM #tmp JOIN TB_A ON 1 = 1",
+            1,
+        ));
+        state.add_event(SqlEvent::for_test(
+            "INSERT INTO @tv SELECT * FROM ##other",
+            2,
+        ));
+
+        assert_eq!(state.table_groups[TEMP_TABLE_GROUP], vec![0, 1]);
+        assert_eq!(state.table_groups["A"], vec![0]);
+        assert_eq!(state.table_groups.len(), 2);
+    }
+
+    #[test]
+    fn events_are_grouped_by_query_kind() {
+        let mut state = state();
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_A WHERE id=5", 1));
+        state.add_event(SqlEvent::for_test("EXEC sp_executesql N'SELECT 1'", 2));
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_A", 3));
+        state.add_event(SqlEvent::for_test("SELECT * FROM TB_A WHERE id=6", 4));
+
+        state.view_mode = ViewMode::ByKind;
+        state.selected_kind = Some(QueryKind::AdHoc.label().to_string());
+        assert_eq!(state.get_selected_events(), vec![0, 3]);
+        state.selected_kind = Some(QueryKind::Parameterized.label().to_string());
+        assert_eq!(state.get_selected_events(), vec![1]);
+        state.selected_kind = None;
+        assert_eq!(state.get_selected_events(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn received_events_are_reported_once_committed() {
+        let mut state = state();
+        let (sender, receiver) = mpsc::channel();
+        state.event_receiver = Some(receiver);
+        assert!(!state.process_received_events());
+
+        // 재정렬 대기 시간 동안은 버퍼에만 있고 새 이벤트로 보고하지 않음
+        sender
+            .send(SqlEvent::for_test("SELECT * FROM TB_A", 1))
+            .unwrap();
+        assert!(!state.process_received_events());
+        assert!(state.events.is_empty());
+
+        std::thread::sleep(REORDER_WINDOW);
+        assert!(state.process_received_events());
+        assert_eq!(state.events.len(), 1);
+        assert!(!state.process_received_events());
+    }
 }
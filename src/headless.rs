@@ -0,0 +1,503 @@
+use crate::export::{auto_export_path, export_session, SessionSummary};
+use crate::{CaptureStats, Config, Extractor, SqlEvent, SqlLogger};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+/// 명령을 기다리는 동안 캡처 이벤트를 수집하는 간격
+const COLLECT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 실행 중인 캡처 (캡처 스레드와 통신하는 채널)
+struct RunningCapture {
+    interface: String,
+    started_at: DateTime<Utc>,
+    event_receiver: mpsc::Receiver<SqlEvent>,
+    stop_sender: mpsc::Sender<()>,
+    handle: thread::JoinHandle<()>,
+    stats: Arc<CaptureStats>,
+}
+
+/// ============================================
+/// 헤드리스 명령 인터페이스
+/// ============================================
+/// GUI 없이 한 줄에 하나씩 명령을 받아 캡처를 제어 (스크립트 자동화용)
+/// 모든 응답은 한 줄 JSON ({"ok": true, ...} 또는 {"ok": false, "error": ...})
+///
+/// 명령:
+/// - `interfaces`: 네트워크 인터페이스 목록
+/// - `start <iface>`: 캡처 시작
+/// - `stop`: 캡처 중지
+/// - `dump <path>`: 지금까지 수집한 이벤트를 JSON 파일로 저장
+/// - `stats`: 캡처 상태 및 통계
+/// - `quit`: 캡처를 중지하고 종료 (입력이 끝나도 종료)
+///
+/// 명령을 기다리는 동안에도 이벤트를 수집해 로그 파일에 기록하며,
+/// 설정의 stop_after 개수에 도달하면 바로 캡처를 자동 중지하고 로그를 마무리 (넘는 이벤트는 버림)
+pub struct HeadlessSession {
+    config: Config,
+    logger: SqlLogger,
+    events: Vec<SqlEvent>,
+    unique_sql: HashSet<String>, // 자동 중지 개수 확인용 (중복 제거 설정일 때 고유 SQL 수)
+    capture: Option<RunningCapture>,
+}
+
+impl HeadlessSession {
+    pub fn new(config: Config) -> Self {
+        let mut logger = SqlLogger::new();
+        logger.set_log_dir(&config.log_dir);
+        logger.set_anonymize(config.anonymize);
+        logger.set_timezone(config.timezone);
+
+        Self {
+            config,
+            logger,
+            events: Vec::new(),
+            unique_sql: HashSet::new(),
+            capture: None,
+        }
+    }
+
+    /// 입력이 끝나거나 quit 명령을 받을 때까지 명령 처리
+    /// 입력은 별도 스레드에서 읽고, 명령이 없는 동안 COLLECT_INTERVAL마다 이벤트 수집
+    pub fn run<R, W>(&mut self, input: R, mut output: W) -> io::Result<()>
+    where
+        R: BufRead + Send + 'static,
+        W: Write,
+    {
+        let (line_sender, line_receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in input.lines() {
+                if line_sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        loop {
+            let line = match line_receiver.recv_timeout(COLLECT_INTERVAL) {
+                Ok(line) => line?,
+                Err(RecvTimeoutError::Timeout) => {
+                    self.collect_events();
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let quit = line == "quit";
+            let response = if quit {
+                self.stop_capture();
+                json!({ "ok": true, "command": "quit", "events": self.events.len() })
+            } else {
+                self.handle_command(line)
+            };
+
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+
+            if quit {
+                return Ok(());
+            }
+        }
+
+        // 입력이 끝나면 캡처 정리
+        self.stop_capture();
+        Ok(())
+    }
+
+    /// 명령 한 줄 처리
+    pub fn handle_command(&mut self, line: &str) -> Value {
+        self.collect_events();
+
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line, ""),
+        };
+
+        let result = match command {
+            "interfaces" => self.interfaces(),
+            "start" => self.start(arg),
+            "stop" => self.stop(),
+            "dump" => self.dump(arg),
+            "stats" => Ok(self.stats()),
+            _ => Err(format!("Unknown command: {}", command)),
+        };
+
+        match result {
+            Ok(mut value) => {
+                value["ok"] = json!(true);
+                value["command"] = json!(command);
+                value
+            }
+            Err(e) => json!({ "ok": false, "command": command, "error": e }),
+        }
+    }
+
+    fn interfaces(&self) -> Result<Value, String> {
+        let interfaces = Extractor::list_interfaces().map_err(|e| e.to_string())?;
+        let list: Vec<Value> = interfaces
+            .into_iter()
+            .map(|(name, desc)| json!({ "name": name, "description": desc }))
+            .collect();
+        Ok(json!({ "interfaces": list }))
+    }
+
+    fn start(&mut self, interface: &str) -> Result<Value, String> {
+        if interface.is_empty() {
+            return Err("Usage: start <iface>".to_string());
+        }
+        if let Some(ref capture) = self.capture {
+            return Err(format!("Already capturing on {}", capture.interface));
+        }
+
+        let stats = Arc::new(CaptureStats::default());
+        let mut extractor = self.config.extractor_builder().stats(stats.clone()).build();
+        let thread_interface = interface.to_string();
+        self.begin_capture(interface, stats, move |event_sender, stop_receiver| {
+            if let Err(e) =
+                extractor.start_live_capture(&thread_interface, event_sender, stop_receiver)
+            {
+                tracing::error!("캡처 오류: {}", e);
+            }
+        })
+    }
+
+    /// 로그 파일을 열고 캡처 스레드 시작 (capture는 이벤트 송신기와 중지 신호 수신기를 받아 캡처 수행)
+    fn begin_capture<F>(
+        &mut self,
+        interface: &str,
+        stats: Arc<CaptureStats>,
+        capture: F,
+    ) -> Result<Value, String>
+    where
+        F: FnOnce(mpsc::Sender<SqlEvent>, mpsc::Receiver<()>) + Send + 'static,
+    {
+        self.events.clear();
+        self.unique_sql.clear();
+        let interface = interface.to_string();
+        let log_file = self.logger.start_capture(Some(&interface))?;
+
+        let (event_sender, event_receiver) = mpsc::channel();
+        let (stop_sender, stop_receiver) = mpsc::channel();
+        let handle = thread::spawn(move || capture(event_sender, stop_receiver));
+
+        self.capture = Some(RunningCapture {
+            interface: interface.clone(),
+            started_at: Utc::now(),
+            event_receiver,
+            stop_sender,
+            handle,
+            stats,
+        });
+
+        Ok(json!({
+            "interface": interface,
+            "log": log_file,
+            "stop_after": self.config.stop_after,
+        }))
+    }
+
+    fn stop(&mut self) -> Result<Value, String> {
+        if self.capture.is_none() {
+            return Err("Not capturing".to_string());
+        }
+        self.stop_capture();
+        Ok(json!({ "events": self.events.len() }))
+    }
+
+    fn dump(&mut self, path: &str) -> Result<Value, String> {
+        if path.is_empty() {
+            return Err("Usage: dump <path>".to_string());
+        }
+
+        let text = serde_json::to_string_pretty(&self.events)
+            .map_err(|e| format!("Failed to serialize events: {}", e))?;
+        std::fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+        Ok(json!({ "path": path, "events": self.events.len() }))
+    }
+
+    fn stats(&self) -> Value {
+        match self.capture {
+            // 캡처 스레드가 오류로 끝났으면 capturing은 false
+            Some(ref capture) => json!({
+                "capturing": !capture.handle.is_finished(),
+                "interface": capture.interface,
+                "events": self.events.len(),
+                "stop_after": self.config.stop_after,
+                "duplicate_packets": capture.stats.duplicate_packets(),
+                "parse_panics": capture.stats.parse_panics(),
+                "rate_limited_events": capture.stats.rate_limited_events(),
+                "sampled_out_events": capture.stats.sampled_out_events(),
+            }),
+            None => json!({
+                "capturing": false,
+                "events": self.events.len(),
+            }),
+        }
+    }
+
+    /// 캡처 스레드에서 받은 이벤트 수집 (로그 파일에도 기록)
+    /// 명령을 처리하기 전과 명령을 기다리는 동안 주기적으로 호출
+    fn collect_events(&mut self) {
+        let Some(ref capture) = self.capture else {
+            return;
+        };
+
+        let received: Vec<SqlEvent> = capture.event_receiver.try_iter().collect();
+        for event in received {
+            if self.stop_after_reached() {
+                break;
+            }
+            self.add_event(event);
+        }
+
+        // 자동 중지 개수에 도달하면 캡처 중지 (로그 파일 종료 메시지도 기록)
+        if self.stop_after_reached() {
+            self.stop_capture();
+        }
+    }
+
+    /// 이벤트 수가 자동 중지 개수(stop_after)에 도달했는지
+    /// 중복 제거 설정이면 GUI와 같이 고유 SQL 수로 셈
+    fn stop_after_reached(&self) -> bool {
+        let count = if self.config.dedup {
+            self.unique_sql.len()
+        } else {
+            self.events.len()
+        };
+        self.config.stop_after.is_some_and(|limit| count >= limit)
+    }
+
+    /// 이벤트 기록 (익명화 설정이면 리터럴을 지운 뒤 보관)
+    fn add_event(&mut self, mut event: SqlEvent) {
+        if self.config.anonymize {
+            event.anonymize();
+        }
+        self.logger.log_event(&event);
+        self.unique_sql.insert(
+            self.config
+                .dedup_scope
+                .key(&event.flow_id, &event.full_text()),
+        );
+        self.events.push(event);
+    }
+
+    /// 캡처 중지 (캡처 스레드가 끝날 때까지 기다린 후 남은 이벤트 수집)
+    fn stop_capture(&mut self) {
+        let Some(capture) = self.capture.take() else {
+            return;
+        };
+
+        let _ = capture.stop_sender.send(());
+        let _ = capture.handle.join();
+
+        for event in capture.event_receiver.try_iter() {
+            if self.stop_after_reached() {
+                break;
+            }
+            self.add_event(event);
+        }
+        self.logger.stop_capture(self.events.len());
+
+        // 자동 내보내기 설정이면 세션 저장
+        if let Some(ref target) = self.config.auto_export {
+            let summary = SessionSummary {
+                interface: Some(capture.interface),
+                started_at: Some(capture.started_at),
+                stopped_at: Utc::now(),
+                events: self.events.len(),
+            };
+            let path = auto_export_path(target, &summary.stopped_at);
+            if let Err(e) = export_session(&path, &summary, &self.events) {
+                tracing::warn!("자동 내보내기 실패: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// 테스트마다 다른 임시 로그 폴더를 쓰는 세션
+    fn session(name: &str, stop_after: Option<usize>) -> (HeadlessSession, PathBuf) {
+        let log_dir = std::env::temp_dir().join(format!(
+            "tds-sniffer-headless-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let config = Config {
+            log_dir: log_dir.to_str().unwrap().to_string(),
+            stop_after,
+            ..Config::default()
+        };
+        (HeadlessSession::new(config), log_dir)
+    }
+
+    /// 이벤트를 보낸 뒤 중지 신호를 기다리는 가짜 캡처 (중지 신호를 받으면 stopped 송신)
+    fn fake_capture(
+        session: &mut HeadlessSession,
+        sql_texts: &[&str],
+        stopped: mpsc::Sender<()>,
+    ) -> Value {
+        let events: Vec<SqlEvent> = sql_texts
+            .iter()
+            .enumerate()
+            .map(|(i, sql)| SqlEvent::for_test(sql, i as i64))
+            .collect();
+        session
+            .begin_capture("fake0", Arc::default(), move |sender, stop_receiver| {
+                for event in events {
+                    sender.send(event).unwrap();
+                }
+                let _ = stop_receiver.recv();
+                let _ = stopped.send(());
+            })
+            .unwrap()
+    }
+
+    /// 캡처 스레드가 보낸 이벤트가 모두 모일 때까지 stats 명령 반복
+    fn wait_for_events(session: &mut HeadlessSession, count: u64) -> Value {
+        for _ in 0..500 {
+            let stats = session.handle_command("stats");
+            if stats["events"] == count {
+                return stats;
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("events did not arrive");
+    }
+
+    fn responses(output: &[u8]) -> Vec<Value> {
+        std::str::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn commands_without_capture_report_errors() {
+        let (mut session, _) = session("idle", None);
+        let input = "bogus\n\nstart\nstop\ndump\nstats\nquit\nstats\n";
+        let mut output = Vec::new();
+        session.run(input.as_bytes(), &mut output).unwrap();
+
+        // 빈 줄은 무시하고 quit 뒤의 명령은 처리하지 않음
+        let responses = responses(&output);
+        let summary: Vec<(&str, bool)> = responses
+            .iter()
+            .map(|r| (r["command"].as_str().unwrap(), r["ok"].as_bool().unwrap()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("bogus", false),
+                ("start", false),
+                ("stop", false),
+                ("dump", false),
+                ("stats", true),
+                ("quit", true),
+            ]
+        );
+        assert_eq!(responses[0]["error"], "Unknown command: bogus");
+        assert_eq!(responses[1]["error"], "Usage: start <iface>");
+        assert_eq!(responses[2]["error"], "Not capturing");
+        assert_eq!(responses[4]["capturing"], false);
+    }
+
+    #[test]
+    fn stop_collects_events_and_dump_writes_them() {
+        let (mut session, log_dir) = session("dump", None);
+        let (stopped_sender, stopped) = mpsc::channel();
+        let started = fake_capture(
+            &mut session,
+            &["SELECT * FROM TB_A", "UPDATE TB_B SET x = 1 WHERE id = 2"],
+            stopped_sender,
+        );
+        let log_path = started["log"].as_str().unwrap().to_string();
+
+        let stats = wait_for_events(&mut session, 2);
+        assert_eq!(stats["capturing"], true);
+        assert_eq!(stats["interface"], "fake0");
+        assert_eq!(
+            session.handle_command("start eth0")["error"],
+            "Already capturing on fake0"
+        );
+
+        let stop = session.handle_command("stop");
+        assert_eq!(stop["ok"], true);
+        assert_eq!(stop["events"], 2);
+        assert!(stopped.try_recv().is_ok());
+        assert_eq!(session.handle_command("stats")["capturing"], false);
+
+        let dump_path = log_dir.join("events.json");
+        let dump = session.handle_command(&format!("dump {}", dump_path.display()));
+        assert_eq!(dump["events"], 2);
+        let dumped: Vec<SqlEvent> =
+            serde_json::from_str(&std::fs::read_to_string(&dump_path).unwrap()).unwrap();
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        std::fs::remove_dir_all(&log_dir).unwrap();
+
+        assert_eq!(dumped[1].sql_text, "UPDATE TB_B SET x = 1 WHERE id = 2");
+        assert!(log.contains("TB_A") && log.contains("Total Events: 2"));
+    }
+
+    #[test]
+    fn stop_after_stops_capture_without_commands() {
+        let (mut session, log_dir) = session("stop-after", Some(2));
+        let (stopped_sender, stopped) = mpsc::channel();
+        let started = fake_capture(
+            &mut session,
+            &[
+                "SELECT 1 FROM TB_A",
+                "SELECT 2 FROM TB_B",
+                "SELECT 3 FROM TB_C",
+            ],
+            stopped_sender,
+        );
+        let log_path = started["log"].as_str().unwrap().to_string();
+
+        let (reader, mut writer) = io::pipe().unwrap();
+        let handle = thread::spawn(move || {
+            let mut output = Vec::new();
+            session
+                .run(io::BufReader::new(reader), &mut output)
+                .unwrap();
+            output
+        });
+
+        // 명령을 보내지 않아도 중지 신호가 캡처 스레드에 전달되고 로그가 마무리됨
+        stopped.recv_timeout(Duration::from_secs(5)).unwrap();
+        let mut log = String::new();
+        for _ in 0..500 {
+            log = std::fs::read_to_string(&log_path).unwrap();
+            if log.contains("Capture Stopped") {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(log.contains("TB_A") && log.contains("TB_B"));
+        assert!(!log.contains("TB_C"));
+        assert!(log.contains("Total Events: 2"));
+
+        writeln!(writer, "stats").unwrap();
+        drop(writer);
+        let output = handle.join().unwrap();
+        std::fs::remove_dir_all(&log_dir).unwrap();
+
+        let stats = &responses(&output)[0];
+        assert_eq!(stats["capturing"], false);
+        assert_eq!(stats["events"], 2);
+    }
+}
@@ -1,11 +1,42 @@
+pub mod alert;
+pub mod config;
+#[cfg(feature = "control-api")]
+pub mod control;
+pub mod diff;
+pub mod export;
 pub mod extractor;
 pub mod gui;
+pub mod headless;
 pub mod log;
+pub mod normalize;
 pub mod output;
+pub mod pcap_export;
+pub mod query;
+pub mod resolve;
+pub mod stats;
 pub mod tcp;
 pub mod tds;
+pub mod tray;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
-pub use extractor::Extractor;
+pub use alert::{default_alert_rules, AlertRule};
+pub use config::Config;
+pub use export::ExportFilter;
+pub use extractor::{
+    CaptureRequest, CaptureStatus, EventCallback, Extractor, ExtractorBuilder, ExtractorError,
+    FileProgress, InterfaceTestResult, LinkType,
+};
 pub use gui::{show_gui, GuiState};
 pub use log::SqlLogger;
-pub use output::{extract_operations, extract_table_name, extract_tables_from_sql, SqlEvent};
+pub use normalize::{
+    anonymize_sql, normalize_sql, to_nfc, to_parameterized_script, to_sp_executesql,
+};
+pub use output::{
+    dangerous_statement_label, dangerous_statements, extract_exec_target, extract_operations,
+    extract_table_name, extract_tables_from_sql, hex_dump, hex_rows, is_temp_table, parse_hex_dump,
+    split_batch, strip_leading_comments, to_rust_test, truncate_str, DangerousStatement, HexRow,
+    QueryKind, SqlEvent,
+};
+pub use query::EventPredicate;
+pub use stats::CaptureStats;
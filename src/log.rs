@@ -1,10 +1,12 @@
-use crate::{extract_tables_from_sql, SqlEvent};
+use crate::config::DisplayTimezone;
+use crate::{extract_tables_from_sql, hex_dump, SqlEvent};
 use chrono::Utc;
-use log::info;
-use std::fs::OpenOptions;
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use tracing::info;
 
 /// SQL Event Logger
 /// Logs SQL events to files and console.
@@ -16,6 +18,10 @@ pub struct SqlLogger {
     log_file_path: Option<String>,
     raw_log_file: Option<Arc<Mutex<std::fs::File>>>, // SQL text + raw data
     raw_log_file_path: Option<String>,
+    log_dir: String,           // base directory (basic/, raw/ are created under it)
+    interface: String,         // capture interface written in file headers
+    anonymize: bool,           // mask literals and drop raw data before writing
+    timezone: DisplayTimezone, // timezone of logged timestamps
 }
 
 impl SqlLogger {
@@ -26,24 +32,107 @@ impl SqlLogger {
             log_file_path: None,
             raw_log_file: None,
             raw_log_file_path: None,
+            log_dir: "log".to_string(),
+            interface: "N/A".to_string(),
+            anonymize: false,
+            timezone: DisplayTimezone::default(),
         }
     }
 
+    /// Set base log directory
+    pub fn set_log_dir(&mut self, log_dir: &str) {
+        self.log_dir = log_dir.to_string();
+    }
+
+    /// Mask literal values (and drop raw packet data) in logged events
+    /// so the log files can be shared without the captured data values
+    pub fn set_anonymize(&mut self, anonymize: bool) {
+        self.anonymize = anonymize;
+    }
+
+    /// Set the timezone used for timestamps in log messages (stored events stay UTC)
+    pub fn set_timezone(&mut self, timezone: DisplayTimezone) {
+        self.timezone = timezone;
+    }
+
     /// Start capture - Create log files and write headers
     pub fn start_capture(&mut self, interface: Option<&String>) -> Result<String, String> {
+        self.interface = interface.cloned().unwrap_or_else(|| "N/A".to_string());
+        let (file, log_path, raw_file, raw_log_path) = self.create_log_files()?;
+        self.set_files(file, log_path, raw_file, raw_log_path);
+
+        // Write header
+        let header = format!(
+            "\n{}\nCapture Started: {}\nInterface: {}\n{}\n\n",
+            "=".repeat(80),
+            self.timezone.format(&Utc::now(), "%Y-%m-%d %H:%M:%S%.3f"),
+            self.interface,
+            "=".repeat(80)
+        );
+        self.write_to_files(&header);
+
+        self.log_file_path
+            .clone()
+            .ok_or_else(|| "Log file is not open".to_string())
+    }
+
+    /// Start new log files without stopping the capture (e.g. at a shift change)
+    /// Writes a footer pointing to the new files, then a header to the new files.
+    /// The current files are kept if the new ones cannot be created.
+    /// Returns the new SQL text log file path
+    pub fn roll(&mut self) -> Result<String, String> {
+        let Some(previous_path) = self.log_file_path.clone() else {
+            return Err("Capture is not started".to_string());
+        };
+        let (file, log_path, raw_file, raw_log_path) = self.create_log_files()?;
+
+        let now = self.timezone.format(&Utc::now(), "%Y-%m-%d %H:%M:%S%.3f");
+        let footer = format!(
+            "\n{}\nLog Rolled: {}\nContinued In: {}\n{}\n",
+            "=".repeat(80),
+            now,
+            log_path.display(),
+            "=".repeat(80)
+        );
+        self.write_to_files(&footer);
+
+        self.set_files(file, log_path, raw_file, raw_log_path);
+        let header = format!(
+            "\n{}\nCapture Continued: {}\nInterface: {}\nPrevious Log: {}\n{}\n\n",
+            "=".repeat(80),
+            now,
+            self.interface,
+            previous_path,
+            "=".repeat(80)
+        );
+        self.write_to_files(&header);
+
+        self.log_file_path
+            .clone()
+            .ok_or_else(|| "Log file is not open".to_string())
+    }
+
+    /// Create timestamped log files (log/basic/, log/raw/)
+    /// Adds a numeric suffix if a file with the same timestamp already exists
+    fn create_log_files(&self) -> Result<(File, PathBuf, File, PathBuf), String> {
         // Create log directories
-        std::fs::create_dir_all("log/basic")
-            .map_err(|e| format!("Failed to create log/basic directory: {}", e))?;
-        std::fs::create_dir_all("log/raw")
-            .map_err(|e| format!("Failed to create log/raw directory: {}", e))?;
+        let basic_dir = Path::new(&self.log_dir).join("basic");
+        let raw_dir = Path::new(&self.log_dir).join("raw");
+        std::fs::create_dir_all(&basic_dir)
+            .map_err(|e| format!("Failed to create {} directory: {}", basic_dir.display(), e))?;
+        std::fs::create_dir_all(&raw_dir)
+            .map_err(|e| format!("Failed to create {} directory: {}", raw_dir.display(), e))?;
 
-        let now = Utc::now();
-        let timestamp_str = now.format("%Y%m%d_%H%M%S").to_string();
+        let timestamp_str = Utc::now().format("%Y%m%d_%H%M%S").to_string();
+        let mut log_filename = format!("sql_capture_{}.log", timestamp_str);
+        let mut suffix = 2;
+        while basic_dir.join(&log_filename).exists() || raw_dir.join(&log_filename).exists() {
+            log_filename = format!("sql_capture_{}_{}.log", timestamp_str, suffix);
+            suffix += 1;
+        }
 
         // Log file with SQL text only (in log/basic/)
-        let log_filename = format!("sql_capture_{}.log", timestamp_str);
-        let log_path = Path::new("log/basic").join(&log_filename);
-
+        let log_path = basic_dir.join(&log_filename);
         let file = OpenOptions::new()
             .create(true)
             .append(true)
@@ -51,50 +140,47 @@ impl SqlLogger {
             .map_err(|e| format!("Failed to create log file: {}", e))?;
 
         // Log file with raw data included (in log/raw/)
-        let raw_log_filename = format!("sql_capture_{}.log", timestamp_str);
-        let raw_log_path = Path::new("log/raw").join(&raw_log_filename);
-
+        let raw_log_path = raw_dir.join(&log_filename);
         let raw_file = OpenOptions::new()
             .create(true)
             .append(true)
             .open(&raw_log_path)
             .map_err(|e| format!("Failed to create raw data log file: {}", e))?;
 
-        // Write header
-        let header = format!(
-            "\n{}\nCapture Started: {}\nInterface: {}\n{}\n\n",
-            "=".repeat(80),
-            now.format("%Y-%m-%d %H:%M:%S%.3f"),
-            interface.unwrap_or(&"N/A".to_string()),
-            "=".repeat(80)
-        );
-
-        // Write header to SQL text log file
-        if let Ok(mut f) = file.try_clone() {
-            let _ = f.write_all(header.as_bytes());
-            let _ = f.flush();
-        }
-
-        // Write header to raw data log file
-        if let Ok(mut f) = raw_file.try_clone() {
-            let _ = f.write_all(header.as_bytes());
-            let _ = f.flush();
-        }
+        Ok((file, log_path, raw_file, raw_log_path))
+    }
 
+    /// Replace the current log files
+    fn set_files(&mut self, file: File, log_path: PathBuf, raw_file: File, raw_log_path: PathBuf) {
         self.log_file = Some(Arc::new(Mutex::new(file)));
-        let log_file_path_str = format!("log/basic/{}", log_filename);
-        self.log_file_path = Some(log_file_path_str.clone());
-
+        self.log_file_path = Some(log_path.display().to_string());
         self.raw_log_file = Some(Arc::new(Mutex::new(raw_file)));
-        let raw_log_file_path_str = format!("log/raw/{}", raw_log_filename);
-        self.raw_log_file_path = Some(raw_log_file_path_str);
+        self.raw_log_file_path = Some(raw_log_path.display().to_string());
+    }
 
-        Ok(log_file_path_str)
+    /// Write the same text (header, footer) to both log files
+    fn write_to_files(&self, text: &str) {
+        for log_file in [&self.log_file, &self.raw_log_file].into_iter().flatten() {
+            if let Ok(mut file) = log_file.lock() {
+                let _ = file.write_all(text.as_bytes());
+                let _ = file.flush();
+            }
+        }
     }
 
     /// Log SQL event
     pub fn log_event(&self, event: &SqlEvent) {
-        let timestamp = event.timestamp.format("%Y-%m-%d %H:%M:%S%.3f");
+        let event = if self.anonymize {
+            let mut masked = event.clone();
+            masked.anonymize();
+            Cow::Owned(masked)
+        } else {
+            Cow::Borrowed(event)
+        };
+
+        let timestamp = self
+            .timezone
+            .format(&event.timestamp, "%Y-%m-%d %H:%M:%S%.3f");
 
         // Extract table information
         let tables = if event.tables.is_empty() {
@@ -116,27 +202,14 @@ impl SqlLogger {
             timestamp,
             event.flow_id,
             tables_str,
-            event.sql_text,
+            event.full_text(),
             "=".repeat(80)
         );
 
         // Log message with raw data included
         let raw_log_message = if let Some(ref raw_data) = event.raw_data {
             // Generate hex string (16 bytes per line)
-            let hex_string: String = raw_data
-                .chunks(16)
-                .enumerate()
-                .map(|(i, chunk)| {
-                    let hex: String = chunk
-                        .iter()
-                        .map(|b| format!("{:02x}", b))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let offset = i * 16;
-                    format!("{:08x}:  {}", offset, hex)
-                })
-                .collect::<Vec<_>>()
-                .join("\n");
+            let hex_string = hex_dump(raw_data);
 
             format!(
                 "\n{}\nTimestamp: {}\nFlow: {}\nTables: {}\nSQL:\n{}\n\nRaw Data (Hex):\n{}\n{}\n",
@@ -144,7 +217,7 @@ impl SqlLogger {
                 timestamp,
                 event.flow_id,
                 tables_str,
-                event.sql_text,
+                event.full_text(),
                 hex_string,
                 "=".repeat(80)
             )
@@ -179,26 +252,12 @@ impl SqlLogger {
         let footer = format!(
             "\n{}\nCapture Stopped: {}\nTotal Events: {}\n{}\n",
             "=".repeat(80),
-            now.format("%Y-%m-%d %H:%M:%S%.3f"),
+            self.timezone.format(&now, "%Y-%m-%d %H:%M:%S%.3f"),
             event_count,
             "=".repeat(80)
         );
 
-        // Write footer to SQL text log file
-        if let Some(ref log_file) = self.log_file {
-            if let Ok(mut file) = log_file.lock() {
-                let _ = file.write_all(footer.as_bytes());
-                let _ = file.flush();
-            }
-        }
-
-        // Write footer to raw data log file
-        if let Some(ref raw_log_file) = self.raw_log_file {
-            if let Ok(mut file) = raw_log_file.lock() {
-                let _ = file.write_all(footer.as_bytes());
-                let _ = file.flush();
-            }
-        }
+        self.write_to_files(&footer);
     }
 
     /// Get log file path
@@ -212,3 +271,39 @@ impl Default for SqlLogger {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roll_continues_logging_in_new_files() {
+        let log_dir = std::env::temp_dir().join(format!("tds-sniffer-roll-{}", std::process::id()));
+        let mut logger = SqlLogger::new();
+        logger.set_log_dir(log_dir.to_str().unwrap());
+        assert!(logger.roll().is_err());
+
+        let first_path = logger.start_capture(Some(&"eth0".to_string())).unwrap();
+        logger.log_event(&SqlEvent::for_test("SELECT * FROM TB_Before", 1));
+        // Rolling within the same second still opens separate files
+        let second_path = logger.roll().unwrap();
+        logger.log_event(&SqlEvent::for_test("SELECT * FROM TB_After", 2));
+        assert_ne!(first_path, second_path);
+        assert_eq!(logger.get_file_path(), Some(&second_path));
+
+        let first = std::fs::read_to_string(&first_path).unwrap();
+        let second = std::fs::read_to_string(&second_path).unwrap();
+        std::fs::remove_dir_all(&log_dir).unwrap();
+
+        assert!(first.contains("TB_Before"));
+        assert!(!first.contains("TB_After"));
+        assert!(first.contains(&format!("Continued In: {}", second_path)));
+        assert!(first.trim_end().ends_with(&"=".repeat(80)));
+
+        assert!(second.contains("Capture Continued:"));
+        assert!(second.contains("Interface: eth0"));
+        assert!(second.contains(&format!("Previous Log: {}", first_path)));
+        assert!(second.contains("TB_After"));
+        assert!(!second.contains("TB_Before"));
+    }
+}
@@ -1,6 +1,9 @@
 use rust_wireshark::gui::GuiState;
+use rust_wireshark::headless::HeadlessSession;
 use rust_wireshark::output::SqlEvent;
-use rust_wireshark::Extractor;
+#[cfg(windows)]
+use rust_wireshark::tray::{SystemTray, TrayCommand};
+use rust_wireshark::{CaptureStatus, Config};
 use std::sync::mpsc;
 use std::thread;
 
@@ -12,7 +15,34 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(1);
     }
 
-    env_logger::init();
+    // Log to stderr (stdout carries headless JSON responses), level from RUST_LOG
+    // e.g. RUST_LOG=rust_wireshark=debug shows per-flow decode spans, =trace adds every TCP segment
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    // Headless mode: drive the capture with line commands on stdin (JSON responses on stdout)
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--headless") {
+        let mut config = Config::load();
+        // --stop-after N: stop the capture after N events (overrides stop_after in the config file)
+        if let Some(pos) = args.iter().position(|arg| arg == "--stop-after") {
+            match args.get(pos + 1).and_then(|value| value.parse().ok()) {
+                Some(limit) => config.stop_after = Some(limit),
+                None => {
+                    eprintln!("Usage: --headless --stop-after <N>");
+                    std::process::exit(2);
+                }
+            }
+        }
+        let mut session = HeadlessSession::new(config);
+        session.run(
+            std::io::BufReader::new(std::io::stdin()),
+            std::io::stdout().lock(),
+        )?;
+        return Ok(());
+    }
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([1400.0, 900.0]),
@@ -62,10 +92,30 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let mut state = GuiState::new();
             state.set_event_receiver(event_rx);
             state.set_stop_sender(stop_tx);
+            // Local control server for test harnesses (control-api feature, off unless control_port is set)
+            #[cfg(feature = "control-api")]
+            if let Some(port) = state.config().control_port {
+                match rust_wireshark::control::start_server(port, &cc.egui_ctx) {
+                    Ok(receiver) => state.set_control_receiver(receiver),
+                    Err(e) => tracing::error!("제어 API 시작 실패 (포트 {}): {}", port, e),
+                }
+            }
+            // System tray (Windows only, capture keeps running while hidden)
+            #[cfg(windows)]
+            let tray = match SystemTray::new(&cc.egui_ctx) {
+                Ok(tray) => Some(tray),
+                Err(e) => {
+                    tracing::error!("트레이 아이콘 생성 실패: {}", e);
+                    None
+                }
+            };
+
             Box::new(GuiApp {
                 state,
                 event_sender: Some(event_tx),
                 stop_receiver: Some(stop_rx),
+                #[cfg(windows)]
+                tray,
             })
         }),
     )?;
@@ -77,10 +127,50 @@ struct GuiApp {
     state: GuiState,
     event_sender: Option<mpsc::Sender<SqlEvent>>,
     stop_receiver: Option<mpsc::Receiver<()>>,
+    #[cfg(windows)]
+    tray: Option<SystemTray>,
+}
+
+impl GuiApp {
+    /// Handle tray commands and minimize-to-tray
+    #[cfg(windows)]
+    fn handle_tray(&mut self, ctx: &egui::Context) {
+        let Some(tray) = &mut self.tray else {
+            return;
+        };
+
+        // Hide to tray when minimized
+        if ctx.input(|i| i.viewport().minimized) == Some(true) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+        }
+
+        while let Some(command) = tray.try_recv() {
+            match command {
+                // Window is already shown by the tray event handler
+                TrayCommand::ShowWindow => {}
+                TrayCommand::ToggleCapture => {
+                    if self.state.is_capturing {
+                        self.state.stop_capture();
+                    } else {
+                        self.state.start_capture();
+                    }
+                }
+                TrayCommand::Quit => {
+                    self.state.stop_capture();
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            }
+        }
+
+        tray.update(self.state.is_capturing, self.state.event_count());
+    }
 }
 
 impl eframe::App for GuiApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        #[cfg(windows)]
+        self.handle_tray(ctx);
+
         // Handle capture start request
         if self.state.is_capturing && !self.state.capture_started {
             // If stop_receiver is None, create a new channel (for restart)
@@ -96,23 +186,43 @@ impl eframe::App for GuiApp {
                 let interface = interface.clone();
                 let sender = sender.clone();
                 let stop_rx = self.stop_receiver.take();
-
-                thread::spawn(move || {
-                    let mut extractor = Extractor::new(true);
+                let config = self.state.config().clone();
+                let stats = self.state.capture_stats();
+                // Request channel for querying the capture thread (stream dump)
+                let (request_tx, request_rx) = mpsc::channel();
+                self.state.set_request_sender(request_tx);
+                // Status channel: device reconnect attempts, and a failed capture (e.g. the interface disappeared) resets the GUI
+                let (status_tx, status_rx) = mpsc::channel();
+                self.state.set_capture_status_receiver(status_rx);
+
+                let handle = thread::spawn(move || {
+                    let mut extractor = config
+                        .extractor_builder()
+                        .stats(stats)
+                        .requests(request_rx)
+                        .status(status_tx.clone())
+                        .build();
 
                     if let Some(stop_rx) = stop_rx {
                         // Start real-time capture (pass stop signal receiver)
                         if let Err(e) = extractor.start_live_capture(&interface, sender, stop_rx) {
-                            eprintln!("캡처 오류: {}", e);
+                            tracing::error!("캡처 오류: {}", e);
+                            let _ = status_tx.send(CaptureStatus::Failed(e.to_string()));
                         }
                     }
                 });
+                self.state.set_capture_thread(handle);
 
                 self.state.capture_started = true;
             }
         }
 
+        // show_gui schedules its own repaints (immediately on new events, periodically while capturing)
         rust_wireshark::gui::show_gui(ctx, &mut self.state);
-        ctx.request_repaint();
+    }
+
+    /// Stop a running capture on shutdown so the log is finalized and auto_export runs
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.state.stop_capture();
     }
 }
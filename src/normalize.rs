@@ -0,0 +1,469 @@
+use std::borrow::Cow;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+/// SQL 토큰 종류
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Whitespace,
+    /// -- 한 줄 주석 또는 /* */ 블록 주석 (중첩 허용)
+    Comment,
+    /// 키워드, 식별자, @변수, #임시테이블
+    Word,
+    /// [식별자] 또는 "식별자"
+    QuotedIdent,
+    /// '문자열'
+    String,
+    /// N'유니코드 문자열'
+    NString,
+    Number,
+    /// 0x 바이너리 리터럴
+    Hex,
+    Symbol,
+}
+
+/// SQL 토큰 (원문 조각을 그대로 가리킴)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SqlToken<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+}
+
+impl SqlToken<'_> {
+    /// 리터럴 값(문자열, 숫자, 바이너리)인지 여부
+    pub fn is_literal(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::String | TokenKind::NString | TokenKind::Number | TokenKind::Hex
+        )
+    }
+}
+
+/// ============================================
+/// SQL 토크나이저
+/// ============================================
+/// T-SQL 텍스트를 토큰으로 분리 (모든 토큰을 이어 붙이면 원문과 같음)
+/// 닫히지 않은 문자열/주석은 텍스트 끝까지 한 토큰으로 처리
+pub fn tokenize_sql(sql: &str) -> Vec<SqlToken<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < sql.len() {
+        let rest = &sql[pos..];
+        let c = rest.chars().next().unwrap_or_default();
+        let next = rest[c.len_utf8()..].chars().next();
+
+        let (kind, len) = if c.is_whitespace() {
+            (TokenKind::Whitespace, take_while(rest, char::is_whitespace))
+        } else if rest.starts_with("--") {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if rest.starts_with("/*") {
+            (TokenKind::Comment, block_comment_len(rest))
+        } else if (c == 'N' || c == 'n') && next == Some('\'') {
+            (TokenKind::NString, 1 + quoted_len(&rest[1..], '\''))
+        } else if c == '\'' {
+            (TokenKind::String, quoted_len(rest, '\''))
+        } else if c == '[' {
+            (TokenKind::QuotedIdent, quoted_len(rest, ']'))
+        } else if c == '"' {
+            (TokenKind::QuotedIdent, quoted_len(rest, '"'))
+        } else if c == '0'
+            && matches!(next, Some('x') | Some('X'))
+            && bytes.get(pos + 2).is_some_and(u8::is_ascii_hexdigit)
+        {
+            (
+                TokenKind::Hex,
+                2 + take_while(&rest[2..], |c| c.is_ascii_hexdigit()),
+            )
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            (TokenKind::Number, number_len(rest))
+        } else if is_word_start(c) {
+            (TokenKind::Word, take_while(rest, is_word_char))
+        } else {
+            (TokenKind::Symbol, c.len_utf8())
+        };
+
+        tokens.push(SqlToken {
+            kind,
+            text: &rest[..len],
+        });
+        pos += len;
+    }
+
+    tokens
+}
+
+/// 유니코드 NFC 정규화 (이미 NFC면 복사하지 않음)
+/// 클라이언트 OS에 따라 한글이 자모로 풀린 NFD 형태(macOS 등)로 올 수 있어,
+/// 눈으로는 같은 dbo.TB_진료내역이 다른 문자열로 그룹화/중복 제거되지 않도록 디코딩 직후와 그룹화 전에 적용
+pub fn to_nfc(text: &str) -> Cow<'_, str> {
+    if is_nfc_quick(text.chars()) == IsNormalized::Yes {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(text.nfc().collect())
+    }
+}
+
+/// 리터럴을 ?로 바꾸고 주석/공백을 공백 하나로 정리한 SQL
+/// 값만 다른 쿼리를 같은 형태로 묶을 때 사용
+pub fn normalize_sql(sql: &str) -> String {
+    let mut normalized = String::with_capacity(sql.len());
+    let mut pending_space = false;
+
+    for token in tokenize_sql(sql) {
+        match token.kind {
+            TokenKind::Whitespace | TokenKind::Comment => pending_space = true,
+            _ => {
+                if pending_space && !normalized.is_empty() {
+                    normalized.push(' ');
+                }
+                pending_space = false;
+                normalized.push_str(if token.is_literal() { "?" } else { token.text });
+            }
+        }
+    }
+
+    normalized
+}
+
+/// 리터럴을 ?로 바꾸고 주석을 지운 SQL (줄바꿈/들여쓰기는 유지)
+/// 환자 정보 등 값이 들어있는 리터럴과 주석(RPC 파라미터 값 포함)을 로그/화면에 남기지 않기 위해 사용
+pub fn anonymize_sql(sql: &str) -> String {
+    let mut anonymized = String::with_capacity(sql.len());
+
+    for token in tokenize_sql(sql) {
+        match token.kind {
+            // 주석 앞뒤 토큰이 붙지 않도록 공백으로
+            TokenKind::Comment => {
+                if !anonymized.ends_with(char::is_whitespace) {
+                    anonymized.push(' ');
+                }
+            }
+            _ if token.is_literal() => anonymized.push('?'),
+            _ => anonymized.push_str(token.text),
+        }
+    }
+
+    anonymized.trim_end().to_string()
+}
+
+/// 리터럴을 바꾼 파라미터
+struct Parameter {
+    name: String,
+    sql_type: String,
+    /// 원래 리터럴 텍스트
+    value: String,
+}
+
+/// ============================================
+/// sp_executesql 변환
+/// ============================================
+/// 리터럴을 @p0, @p1... 파라미터로 바꾼 EXEC sp_executesql 문 생성
+/// 파라미터 타입은 리터럴 형태로 추정 (정수 int/bigint, 소수 decimal(p,s),
+/// 지수 float, '문자열' varchar(n), N'문자열' nvarchar(n), 0x.. varbinary(n))
+/// 리터럴이 없으면 파라미터 없이 문장만 넘김
+pub fn to_sp_executesql(sql: &str) -> String {
+    let (statement, parameters) = parameterize(sql);
+
+    let mut exec = format!("EXEC sp_executesql {}", unicode_literal(&statement));
+    if !parameters.is_empty() {
+        let values: Vec<String> = parameters
+            .iter()
+            .map(|param| format!("{}={}", param.name, param.value))
+            .collect();
+        exec.push_str(&format!(
+            ", {}, {}",
+            unicode_literal(&declaration_list(&parameters)),
+            values.join(", ")
+        ));
+    }
+    exec
+}
+
+/// 리터럴을 @p0, @p1... 변수로 선언하고 sp_executesql로 실행하는 스크립트 생성
+/// SSMS에 붙여 넣어 값만 바꿔 가며 실행 계획을 확인할 수 있음 (파라미터 이름과 타입 추정은 to_sp_executesql과 같음)
+///
+/// DECLARE @p0 int = 5;
+/// EXEC sp_executesql N'SELECT * FROM T WHERE id = @p0', N'@p0 int', @p0 = @p0;
+pub fn to_parameterized_script(sql: &str) -> String {
+    let (statement, parameters) = parameterize(sql);
+
+    let mut script = String::new();
+    for param in &parameters {
+        script.push_str(&format!(
+            "DECLARE {} {} = {};\n",
+            param.name, param.sql_type, param.value
+        ));
+    }
+
+    script.push_str(&format!(
+        "EXEC sp_executesql {}",
+        unicode_literal(&statement)
+    ));
+    if !parameters.is_empty() {
+        let arguments: Vec<String> = parameters
+            .iter()
+            .map(|param| format!("{0} = {0}", param.name))
+            .collect();
+        script.push_str(&format!(
+            ", {}, {}",
+            unicode_literal(&declaration_list(&parameters)),
+            arguments.join(", ")
+        ));
+    }
+    script.push(';');
+    script
+}
+
+/// 리터럴을 @p0부터 번호를 붙인 파라미터로 바꾼 문장과 파라미터 목록
+fn parameterize(sql: &str) -> (String, Vec<Parameter>) {
+    let mut statement = String::with_capacity(sql.len());
+    let mut parameters = Vec::new();
+
+    for token in tokenize_sql(sql.trim()) {
+        if !token.is_literal() {
+            statement.push_str(token.text);
+            continue;
+        }
+
+        let name = format!("@p{}", parameters.len());
+        statement.push_str(&name);
+        parameters.push(Parameter {
+            name,
+            sql_type: literal_type(&token),
+            value: token.text.to_string(),
+        });
+    }
+
+    (statement, parameters)
+}
+
+/// sp_executesql 파라미터 정의 ("@p0 int, @p1 nvarchar(5)")
+fn declaration_list(parameters: &[Parameter]) -> String {
+    parameters
+        .iter()
+        .map(|param| format!("{} {}", param.name, param.sql_type))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// N'...' 유니코드 문자열 리터럴 (작은따옴표 이스케이프)
+fn unicode_literal(text: &str) -> String {
+    format!("N'{}'", text.replace('\'', "''"))
+}
+
+/// 리터럴 토큰의 SQL Server 타입
+fn literal_type(token: &SqlToken) -> String {
+    match token.kind {
+        TokenKind::String => sized_type("varchar", unquoted_len(token.text), 8000),
+        TokenKind::NString => sized_type("nvarchar", unquoted_len(&token.text[1..]), 4000),
+        TokenKind::Hex => sized_type("varbinary", (token.text.len() - 1) / 2, 8000),
+        _ => number_type(token.text),
+    }
+}
+
+/// 숫자 리터럴 타입 (SQL Server가 리터럴에 붙이는 타입 규칙을 따름)
+fn number_type(text: &str) -> String {
+    if text.contains(['e', 'E']) {
+        return "float".to_string();
+    }
+
+    match text.split_once('.') {
+        Some((int_part, frac_part)) => {
+            let int_digits = int_part.trim_start_matches('0').len();
+            let scale = frac_part.len().min(38);
+            let precision = (int_digits + frac_part.len()).clamp(1, 38).max(scale);
+            format!("decimal({},{})", precision, scale)
+        }
+        None => match text.parse::<i64>() {
+            Ok(value) if i32::try_from(value).is_ok() => "int".to_string(),
+            Ok(_) => "bigint".to_string(),
+            Err(_) => format!("decimal({},0)", text.len().min(38)),
+        },
+    }
+}
+
+/// 길이가 있는 타입 (길이 0은 1로, 최대 길이를 넘으면 max)
+fn sized_type(name: &str, len: usize, max_len: usize) -> String {
+    if len > max_len {
+        format!("{}(max)", name)
+    } else {
+        format!("{}({})", name, len.max(1))
+    }
+}
+
+/// '...' 문자열 리터럴의 실제 문자 수 ('' 이스케이프는 한 글자)
+fn unquoted_len(text: &str) -> usize {
+    let inner = text.strip_prefix('\'').unwrap_or(text);
+    let inner = inner.strip_suffix('\'').unwrap_or(inner);
+    inner.replace("''", "'").chars().count()
+}
+
+fn take_while(text: &str, pred: impl Fn(char) -> bool) -> usize {
+    text.find(|c: char| !pred(c)).unwrap_or(text.len())
+}
+
+fn is_word_start(c: char) -> bool {
+    c.is_alphabetic() || matches!(c, '_' | '@' | '#')
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '$')
+}
+
+/// 여는 문자로 시작하는 인용 구간 길이 (닫는 문자 두 번은 이스케이프)
+fn quoted_len(text: &str, close: char) -> usize {
+    let mut chars = text.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == close {
+            if chars.peek().map(|&(_, c)| c) == Some(close) {
+                chars.next();
+            } else {
+                return i + c.len_utf8();
+            }
+        }
+    }
+    text.len()
+}
+
+/// /* */ 블록 주석 길이 (중첩 허용)
+fn block_comment_len(text: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < text.len() {
+        if text[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if text[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    text.len()
+}
+
+/// 숫자 리터럴 길이 (정수부, 소수부, 지수부)
+fn number_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let digits = |mut i: usize| {
+        while bytes.get(i).is_some_and(u8::is_ascii_digit) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut end = digits(0);
+    if bytes.get(end) == Some(&b'.') {
+        end = digits(end + 1);
+    }
+    if matches!(bytes.get(end), Some(b'e') | Some(b'E')) {
+        let mut exp = end + 1;
+        if matches!(bytes.get(exp), Some(b'+') | Some(b'-')) {
+            exp += 1;
+        }
+        if bytes.get(exp).is_some_and(u8::is_ascii_digit) {
+            end = digits(exp);
+        }
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_nfd_hangul_to_nfc() {
+        let nfd: String = "TB_진료내역".nfd().collect();
+        // 진(ㅈㅣㄴ) 료(ㄹㅛ) 내(ㄴㅐ) 역(ㅇㅕㄱ)이 자모로 풀림
+        assert_eq!(nfd.chars().count(), 3 + 3 + 2 + 2 + 3);
+
+        let normalized = to_nfc(&nfd);
+        assert!(matches!(normalized, Cow::Owned(_)));
+        assert_eq!(normalized, "TB_진료내역");
+        // 이미 NFC면 복사하지 않음
+        assert!(matches!(to_nfc("TB_진료내역"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn tokens_concatenate_to_original_text() {
+        let sql =
+            "SELECT N'한글', 'a''b', 0x1F, 1.5e3 /* c /* d */ */ FROM [T 1] -- x\nWHERE id=@id";
+        let tokens = tokenize_sql(sql);
+        assert_eq!(tokens.iter().map(|t| t.text).collect::<String>(), sql);
+
+        let literals: Vec<(TokenKind, &str)> = tokens
+            .iter()
+            .filter(|t| t.is_literal())
+            .map(|t| (t.kind, t.text))
+            .collect();
+        assert_eq!(
+            literals,
+            vec![
+                (TokenKind::NString, "N'한글'"),
+                (TokenKind::String, "'a''b'"),
+                (TokenKind::Hex, "0x1F"),
+                (TokenKind::Number, "1.5e3"),
+            ]
+        );
+        assert!(tokens
+            .iter()
+            .any(|t| t.kind == TokenKind::Comment && t.text == "/* c /* d */ */"));
+        assert!(tokens
+            .iter()
+            .any(|t| t.kind == TokenKind::QuotedIdent && t.text == "[T 1]"));
+    }
+
+    #[test]
+    fn sp_executesql_declares_types_of_replaced_literals() {
+        assert_eq!(
+            to_sp_executesql("SELECT * FROM T WHERE id = 5 AND name = 'abc'"),
+            "EXEC sp_executesql N'SELECT * FROM T WHERE id = @p0 AND name = @p1', \
+             N'@p0 int, @p1 varchar(3)', @p0=5, @p1='abc'"
+        );
+        assert_eq!(
+            to_sp_executesql("SELECT 3000000000, 12.50, N'환자', 0xABCD, 1e5"),
+            "EXEC sp_executesql N'SELECT @p0, @p1, @p2, @p3, @p4', \
+             N'@p0 bigint, @p1 decimal(4,2), @p2 nvarchar(2), @p3 varbinary(2), @p4 float', \
+             @p0=3000000000, @p1=12.50, @p2=N'환자', @p3=0xABCD, @p4=1e5"
+        );
+    }
+
+    #[test]
+    fn sp_executesql_without_literals_has_no_parameters() {
+        assert_eq!(
+            to_sp_executesql("SELECT * FROM T"),
+            "EXEC sp_executesql N'SELECT * FROM T'"
+        );
+        // 문장 안의 작은따옴표는 이스케이프
+        assert_eq!(
+            to_sp_executesql("SELECT 'it''s'"),
+            "EXEC sp_executesql N'SELECT @p0', N'@p0 varchar(4)', @p0='it''s'"
+        );
+    }
+
+    #[test]
+    fn parameterized_script_declares_same_parameters_as_sp_executesql() {
+        let sql = "SELECT * FROM T WHERE id = 5 AND name = N'홍''길동'";
+        assert_eq!(
+            to_parameterized_script(sql),
+            "DECLARE @p0 int = 5;\n\
+             DECLARE @p1 nvarchar(4) = N'홍''길동';\n\
+             EXEC sp_executesql N'SELECT * FROM T WHERE id = @p0 AND name = @p1', \
+             N'@p0 int, @p1 nvarchar(4)', @p0 = @p0, @p1 = @p1;"
+        );
+        assert!(
+            to_sp_executesql(sql).contains("N'@p0 int, @p1 nvarchar(4)', @p0=5, @p1=N'홍''길동'")
+        );
+
+        assert_eq!(
+            to_parameterized_script("SELECT * FROM T"),
+            "EXEC sp_executesql N'SELECT * FROM T';"
+        );
+    }
+}
@@ -1,5 +1,9 @@
+use crate::extractor::UNDECODED_OPERATION;
+use crate::normalize::{anonymize_sql, to_nfc, tokenize_sql, TokenKind};
+use crate::tds::{TdsParser, TextEncoding};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashSet;
 
 /// SQL 이벤트
@@ -8,64 +12,539 @@ pub struct SqlEvent {
     pub timestamp: DateTime<Utc>,
     pub flow_id: String,
     pub sql_text: String,
+    /// sp_executesql 요청의 파라미터 (이름, 값) 목록 (sql_text에는 문장만 있음, 그 외 요청은 비어 있음)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<(String, String)>,
     pub tables: Vec<String>,
     pub operation: String,
+    /// 요청 요약 라벨 ("프로시저 / 문장 수 / 위험한 문장", 디코딩하지 못했거나 raw 모드면 None)
+    /// 예: "sp_getPatient / 1 stmt", "batch / 3 stmts / WHERE 없음 - 위험"
     pub label: Option<String>,
     /// 원본 TDS 패킷 바이트 데이터 (hex 표시용)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub raw_data: Option<Vec<u8>>,
+    /// 서버 응답의 DONE 토큰별 행 수 (결과 집합 단위)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub result_sets: Vec<u64>,
+    /// 서버 응답의 COLMETADATA 토큰별 컬럼 (이름, 타입명) 목록 (결과 컬럼 수집을 켠 경우만)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub result_columns: Vec<Vec<(String, String)>>,
+    /// 요청 시점의 현재 데이터베이스 (서버 ENVCHANGE 기준, 모르면 None)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// RPC 요청의 OptionFlags (0x01 WITH RECOMPILE 등, RPC가 아니면 None)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_flags: Option<u16>,
+    /// 연결의 애플리케이션 이름 (LOGIN7의 Application Name, 로그인을 캡처하지 못했으면 None)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_name: Option<String>,
+    /// 연결의 클라이언트 호스트 이름 (LOGIN7의 HostName)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_host: Option<String>,
+    /// 연결의 클라이언트 라이브러리 이름 (LOGIN7의 CltIntName, 예: ".Net SqlClient Data Provider")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_library: Option<String>,
+    /// SQL 텍스트를 디코딩한 인코딩 (raw 모드나 디코딩 실패면 None)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<TextEncoding>,
+    /// 응답 시간 (요청 메시지를 다 받은 시각부터 마지막 DONE을 받은 시각까지, 응답을 받지 못했으면 None)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    /// 요청 헤더에 연결 초기화 비트(RESETCONNECTION / RESETCONNECTIONSKIPTRAN)가 있었는지
+    /// 연결 풀에서 재사용한 연결의 첫 요청이므로 새 세션의 시작으로 볼 수 있음
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reset_connection: bool,
+    /// 연결 종료나 캡처 중지로 TDS 패킷을 끝까지 받지 못해 있는 바이트만으로 디코딩했는지 (SQL이 잘렸을 수 있음)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// 요청 또는 응답 스트림에 빈틈(패킷 손실)이 있는 상태에서 만든 이벤트인지
+    /// 빈틈 앞까지 받은 바이트로 디코딩했으므로 SQL, 결과 행 수, 응답 시간이 불완전할 수 있음
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub packet_loss: bool,
+}
+
+/// 쿼리 작성 방식 (실행 계획 캐시 재사용 관점)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryKind {
+    /// RPC 요청(sp_executesql, 저장 프로시저 호출) 또는 배치 안의 sp_executesql: 값은 파라미터로 전달
+    Parameterized,
+    /// 리터럴 값을 SQL에 직접 넣은 SQL Batch (값마다 다른 SQL이 되어 실행 계획 캐시를 재사용하지 못함)
+    AdHoc,
+    /// 리터럴이 없는 SQL Batch (SELECT * FROM T 처럼 항상 같은 텍스트)
+    Static,
+}
+
+impl QueryKind {
+    /// 목록/그룹에 표시할 이름
+    pub fn label(self) -> &'static str {
+        match self {
+            QueryKind::Parameterized => "Parameterized",
+            QueryKind::AdHoc => "Ad-hoc",
+            QueryKind::Static => "Static",
+        }
+    }
+
+    /// 이름으로 찾기 (대소문자, 하이픈 무시)
+    pub fn from_label(label: &str) -> Option<Self> {
+        [
+            QueryKind::Parameterized,
+            QueryKind::AdHoc,
+            QueryKind::Static,
+        ]
+        .into_iter()
+        .find(|kind| {
+            kind.label()
+                .replace('-', "")
+                .eq_ignore_ascii_case(&label.replace('-', ""))
+        })
+    }
+}
+
+impl SqlEvent {
+    /// 쿼리 작성 방식 분류 (디코딩하지 못한 패킷이나 raw 모드 이벤트는 None)
+    /// RPC는 항상 Parameterized, SQL Batch는 sp_executesql 호출이면 Parameterized,
+    /// 리터럴이 있으면 Ad-hoc (익명화로 ?가 된 리터럴 포함), 없으면 Static
+    pub fn query_kind(&self) -> Option<QueryKind> {
+        if self.sql_text.trim().is_empty()
+            || self.operation == UNDECODED_OPERATION
+            || self.operation == "RAW"
+        {
+            return None;
+        }
+        if self.rpc_flags.is_some() {
+            return Some(QueryKind::Parameterized);
+        }
+
+        let tokens = tokenize_sql(&self.sql_text);
+        if tokens.iter().any(|token| {
+            token.kind == TokenKind::Word && token.text.eq_ignore_ascii_case("sp_executesql")
+        }) {
+            Some(QueryKind::Parameterized)
+        } else if tokens.iter().any(|token| {
+            token.is_literal() || (token.kind == TokenKind::Symbol && token.text == "?")
+        }) {
+            Some(QueryKind::AdHoc)
+        } else {
+            Some(QueryKind::Static)
+        }
+    }
+
+    /// 라벨에 기록된 위험한 문장 표시 (dangerous_statement_label 참고, 없으면 None)
+    pub fn danger_label(&self) -> Option<&str> {
+        self.label
+            .as_deref()?
+            .split(" / ")
+            .find(|part| [LABEL_NO_WHERE, LABEL_TRUNCATE, LABEL_DROP].contains(part))
+    }
+
+    /// 파라미터까지 포함한 한 줄 SQL ("문장 -- @p1=값, @p2=값", 파라미터가 없으면 sql_text 그대로)
+    /// 중복 제거, 검색, 로그처럼 파라미터 값도 구분해야 하는 곳에서 사용
+    pub fn full_text(&self) -> Cow<'_, str> {
+        if self.params.is_empty() {
+            return Cow::Borrowed(&self.sql_text);
+        }
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        Cow::Owned(format!("{} -- {}", self.sql_text, params.join(", ")))
+    }
+
+    /// 개인정보가 남지 않도록 이벤트 익명화
+    /// SQL의 리터럴과 주석을 지우고 (anonymize_sql), 리터럴 값이 그대로 들어있는 원본 패킷과 파라미터 값은 버림
+    pub fn anonymize(&mut self) {
+        self.sql_text = anonymize_sql(&self.sql_text);
+        self.params.clear();
+        self.raw_data = None;
+    }
+}
+
+#[cfg(test)]
+impl SqlEvent {
+    /// 테스트용 SQL Batch 이벤트 (1970-01-01 + seconds, 테이블은 SQL에서 추출)
+    pub(crate) fn for_test(sql_text: &str, seconds: i64) -> Self {
+        Self {
+            timestamp: DateTime::from_timestamp(seconds, 0).unwrap(),
+            flow_id: "10.0.0.5:50000->10.0.0.1:1433".to_string(),
+            sql_text: sql_text.to_string(),
+            params: Vec::new(),
+            tables: extract_tables_from_sql(sql_text),
+            operation: "SQL_BATCH".to_string(),
+            label: None,
+            raw_data: None,
+            result_sets: Vec::new(),
+            result_columns: Vec::new(),
+            database: None,
+            rpc_flags: None,
+            app_name: None,
+            client_host: None,
+            client_library: None,
+            encoding: None,
+            latency_ms: None,
+            reset_connection: false,
+            truncated: false,
+            packet_loss: false,
+        }
+    }
 }
 
 /// ============================================
 /// SQL 파싱 유틸리티 함수들
 /// ============================================
+/// 식별자 한 부분 (대괄호로 감싼 이름 또는 한글/영문/숫자/언더스코어)
+const NAME_PART: &str = r"(?:\[[^\]]+\]|[a-zA-Z_가-힣][a-zA-Z0-9_가-힣]*)";
+
+/// 임시 테이블(#tmp, ##global)과 테이블 변수(@tv) 이름 (스키마 없이 한 부분)
+const TEMP_NAME: &str = r"(?:##?|@)[a-zA-Z0-9_가-힣]+";
+
+/// 임시 테이블 또는 테이블 변수인지 (#, ##, @로 시작)
+pub fn is_temp_table(table: &str) -> bool {
+    table.starts_with(['#', '@'])
+}
+
 /// SQL 텍스트에서 테이블명 추출
-/// FROM, UPDATE, INSERT INTO, JOIN 절에서 테이블명 찾기
+/// FROM, UPDATE, INSERT INTO, JOIN 절에서 테이블명 찾기 (FROM a, b 처럼 쉼표로 나열한 테이블 포함)
 /// 한글 테이블명도 지원 (예: dbo.TB_진료내역, DentWeb.dbo.TB_작업로그)
+/// 연결 서버의 네 부분 이름([LinkedSrv].[db].[dbo].[TB_X])도 지원하며 대괄호는 제거
+/// 임시 테이블(#tmp, ##tmp)과 테이블 변수(@tv)도 이름 그대로 추출 (is_temp_table로 구분)
+/// OPENQUERY / OPENROWSET은 연결 서버(또는 데이터 원본) 이름을 기록 (원격 쿼리 문자열 안의 테이블은 FROM 등으로 따로 추출)
+/// NFD로 풀린 한글도 NFC로 정규화해서 추출 (to_nfc 참고)
 pub fn extract_tables_from_sql(sql_text: &str) -> Vec<String> {
     use regex::Regex;
-    let mut tables = HashSet::new();
+    let normalized = to_nfc(sql_text);
+    let sql_text = normalized.as_ref();
+    let mut tables = Vec::new();
 
-    // 테이블명 패턴: database.schema.table 또는 schema.table 또는 table
-    // 한글, 영문, 숫자, 언더스코어, 점 허용
+    // 테이블명 패턴: server.database.schema.table 또는 database.schema.table 또는 schema.table 또는 table
+    // 한글, 영문, 숫자, 언더스코어, 점, 대괄호 허용
     // FROM, UPDATE, INSERT INTO, JOIN 뒤에 오는 테이블명 추출
-    // 최대 2개의 점 허용 (database.schema.table 형식 지원)
+    // 최대 3개의 점 허용 (연결 서버의 네 부분 이름 지원)
+    let name = format!(
+        r"(?:{temp}|{part}(?:\.{part}){{0,3}})",
+        temp = TEMP_NAME,
+        part = NAME_PART
+    );
     let patterns = vec![
+        (format!(r"(?i)\bFROM\s+({})", name), "FROM"),
+        (format!(r"(?i)\bUPDATE\s+({})", name), "UPDATE"),
+        (format!(r"(?i)\bINSERT\s+INTO\s+({})", name), "INSERT INTO"),
+        (format!(r"(?i)\bJOIN\s+({})", name), "JOIN"),
+        // OPENQUERY(LinkedSrv, '...')의 연결 서버 이름
+        (format!(r"(?i)\bOPENQUERY\s*\(\s*({})", NAME_PART), "OPENQUERY"),
+        // OPENROWSET('SQLNCLI', 'Server=Srv;...', '...')의 데이터 원본 이름
         (
-            r"(?i)\bFROM\s+([a-zA-Z_가-힣][a-zA-Z0-9_가-힣]*(?:\.[a-zA-Z_가-힣][a-zA-Z0-9_가-힣]*){0,2})",
-            "FROM",
-        ),
-        (
-            r"(?i)\bUPDATE\s+([a-zA-Z_가-힣][a-zA-Z0-9_가-힣]*(?:\.[a-zA-Z_가-힣][a-zA-Z0-9_가-힣]*){0,2})",
-            "UPDATE",
-        ),
-        (
-            r"(?i)\bINSERT\s+INTO\s+([a-zA-Z_가-힣][a-zA-Z0-9_가-힣]*(?:\.[a-zA-Z_가-힣][a-zA-Z0-9_가-힣]*){0,2})",
-            "INSERT INTO",
-        ),
-        (
-            r"(?i)\bJOIN\s+([a-zA-Z_가-힣][a-zA-Z0-9_가-힣]*(?:\.[a-zA-Z_가-힣][a-zA-Z0-9_가-힣]*){0,2})",
-            "JOIN",
+            r"(?i)\bOPENROWSET\s*\(\s*'[^']*'\s*,\s*'(?:[^']*?\b(?:Server|Data\s+Source)\s*=\s*)?([^';]+)"
+                .to_string(),
+            "OPENROWSET",
         ),
     ];
 
-    for (pattern, _) in patterns {
-        if let Ok(re) = Regex::new(pattern) {
+    // FROM a x, b y 형식 (쉼표로 나열한 옛 방식 조인)의 다음 테이블
+    // 별칭(AS 생략 가능)과 테이블 힌트 (NOLOCK), WITH (NOLOCK)는 건너뜀
+    let list_re = Regex::new(&format!(
+        r"^\s*(?:(?i:AS)\s+)?(?:[a-zA-Z_가-힣][a-zA-Z0-9_가-힣]*\s*)?(?:(?i:WITH)\s*)?(?:\([^)]*\)\s*)?,\s*({})",
+        name
+    ))
+    .ok();
+
+    for (pattern, keyword) in patterns {
+        if let Ok(re) = Regex::new(&pattern) {
             for cap in re.captures_iter(sql_text) {
-                if let Some(table) = cap.get(1) {
-                    tables.insert(table.as_str().to_string());
+                let Some(table) = cap.get(1) else {
+                    continue;
+                };
+                tables.push((table.start(), table.as_str()));
+
+                if keyword != "FROM" {
+                    continue;
+                }
+                let Some(ref list_re) = list_re else {
+                    continue;
+                };
+                let mut end = table.end();
+                while let Some(next) = list_re
+                    .captures(&sql_text[end..])
+                    .and_then(|cap| cap.get(1))
+                {
+                    tables.push((end + next.start(), next.as_str()));
+                    end += next.end();
                 }
             }
         }
     }
 
-    tables.into_iter().collect()
+    // SQL에 나온 순서대로 (표시 형태는 처음 나온 표기)
+    tables.sort_by_key(|(start, _)| *start);
+    // FROM OPENQUERY(...) 등은 함수 이름이 아니라 괄호 안의 연결 서버를 기록
+    let tables: Vec<String> = tables
+        .into_iter()
+        .filter(|(_, table)| {
+            !["OPENQUERY", "OPENROWSET", "OPENDATASOURCE"]
+                .iter()
+                .any(|function| table.eq_ignore_ascii_case(function))
+        })
+        .map(|(_, table)| table.trim().replace(['[', ']'], ""))
+        .collect();
+    dedup_tables(tables.iter().map(String::as_str))
+}
+
+/// 테이블명 정리 및 중복 제거
+/// 끝에 붙은 식별자 외 문자(쉼표, 괄호, 점 등)를 잘라내고 대소문자 무시로 비교
+/// 표시 형태는 처음 나온 표기를 유지 (TB_X 다음에 tb_x가 나오면 TB_X)
+fn dedup_tables<'a>(tables: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+
+    for table in tables {
+        let table = table.trim_end_matches(|c: char| !(c.is_alphanumeric() || c == '_'));
+        if !table.is_empty() && seen.insert(table.to_lowercase()) {
+            result.push(table.to_string());
+        }
+    }
+
+    result
+}
+
+/// EXEC / EXECUTE 대상 프로시저명 추출
+/// 예: "EXEC dbo.usp_Foo @id=5" -> "dbo.usp_Foo"
+/// "EXEC @ret = usp_Foo", "EXECUTE [dbo].[usp_Bar] 1, 2" 형식도 지원 (대괄호 제거)
+/// 동적 SQL (EXEC('...'), EXEC(@sql))은 None
+pub fn extract_exec_target(sql_text: &str) -> Option<String> {
+    use regex::Regex;
+    let normalized = to_nfc(sql_text);
+    let sql_text = normalized.as_ref();
+
+    let re = Regex::new(
+        r"(?i)\bEXEC(?:UTE)?\s+(?:@[a-zA-Z0-9_가-힣]+\s*=\s*)?((?:\[[^\]]+\]|[a-zA-Z_가-힣#][a-zA-Z0-9_가-힣#]*)(?:\.(?:\[[^\]]+\]|[a-zA-Z_가-힣#][a-zA-Z0-9_가-힣#]*)){0,3})",
+    )
+    .ok()?;
+
+    let name = re.captures(sql_text)?.get(1)?.as_str();
+    let name: String = name.chars().filter(|c| *c != '[' && *c != ']').collect();
+
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// SQL 앞부분의 공백과 주석 제거
+/// 예: "/* hint */ -- note\n SELECT 1" -> "SELECT 1"
+pub fn strip_leading_comments(sql_text: &str) -> &str {
+    let mut rest = sql_text.trim_start();
+
+    loop {
+        if let Some(after) = rest.strip_prefix("--") {
+            // 한 줄 주석: 줄 끝까지 건너뛰기
+            rest = match after.find('\n') {
+                Some(pos) => after[pos + 1..].trim_start(),
+                None => "",
+            };
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            // 블록 주석: */ 까지 건너뛰기 (닫히지 않으면 전부 주석)
+            rest = match after.find("*/") {
+                Some(pos) => after[pos + 2..].trim_start(),
+                None => "",
+            };
+        } else {
+            return rest;
+        }
+    }
+}
+
+/// 여러 문장이 들어있는 배치를 문장 단위로 분리
+/// `;` 와 단독 줄의 `GO`를 구분자로 사용하며, 문자열('...', "..."), [식별자], 주석 안의 구분자는 무시
+/// 빈 문장은 제외
+pub fn split_batch(sql_text: &str) -> Vec<String> {
+    let chars: Vec<char> = sql_text.chars().collect();
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut i = 0;
+
+    let mut push_statement = |current: &mut String| {
+        let statement = current.trim();
+        if !statement.is_empty() {
+            statements.push(statement.to_string());
+        }
+        current.clear();
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        match c {
+            // 문자열 / 인용 식별자: 닫는 문자까지 그대로 (연속 두 번은 이스케이프)
+            '\'' | '"' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                current.push(c);
+                i += 1;
+                while i < chars.len() {
+                    current.push(chars[i]);
+                    if chars[i] == close {
+                        if chars.get(i + 1) == Some(&close) {
+                            current.push(close);
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            // 한 줄 주석: 줄 끝까지
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    current.push(chars[i]);
+                    i += 1;
+                }
+            }
+            // 블록 주석 (중첩 허용)
+            '/' if next == Some('*') => {
+                let mut depth = 0;
+                while i < chars.len() {
+                    if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                        depth += 1;
+                        current.push_str("/*");
+                        i += 2;
+                    } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                        depth -= 1;
+                        current.push_str("*/");
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        current.push(chars[i]);
+                        i += 1;
+                    }
+                }
+            }
+            ';' => {
+                push_statement(&mut current);
+                i += 1;
+            }
+            _ => {
+                current.push(c);
+                i += 1;
+
+                // 줄 끝: 현재 줄이 GO 하나뿐이면 배치 구분자
+                if c == '\n' || i == chars.len() {
+                    let line_start = current.trim_end_matches(['\r', '\n']).rfind('\n');
+                    let line = match line_start {
+                        Some(pos) => &current[pos + 1..],
+                        None => &current[..],
+                    };
+                    if line.trim().eq_ignore_ascii_case("GO") {
+                        let keep = line_start.map(|pos| pos + 1).unwrap_or(0);
+                        current.truncate(keep);
+                        push_statement(&mut current);
+                    }
+                }
+            }
+        }
+    }
+
+    push_statement(&mut current);
+    statements
+}
+
+/// 위험한 문장 라벨: WHERE 없는 DELETE / UPDATE
+pub const LABEL_NO_WHERE: &str = "WHERE 없음 - 위험";
+/// 위험한 문장 라벨: TRUNCATE
+pub const LABEL_TRUNCATE: &str = "TRUNCATE - 위험";
+/// 위험한 문장 라벨: DROP
+pub const LABEL_DROP: &str = "DROP - 위험";
+
+/// 위험한 문장 종류 (경고 규칙과 라벨이 함께 사용)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DangerousStatement {
+    DeleteWithoutWhere,
+    UpdateWithoutWhere,
+    Truncate,
+    Drop,
+}
+
+impl DangerousStatement {
+    /// 이벤트 라벨에 붙이는 표시
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::DeleteWithoutWhere | Self::UpdateWithoutWhere => LABEL_NO_WHERE,
+            Self::Truncate => LABEL_TRUNCATE,
+            Self::Drop => LABEL_DROP,
+        }
+    }
+}
+
+/// ============================================
+/// 위험한 문장 감지
+/// ============================================
+/// 배치의 문장 중 실수로 전체 데이터를 바꾸거나 지울 수 있는 문장을 순서대로 반환
+/// 문장의 첫 키워드로 판단하며 문자열, 주석, [식별자] 안의 키워드는 무시
+/// (WITH CTE 뒤의 DELETE / UPDATE도 검사, 세미콜론이나 GO 없이 이어진 문장은 한 문장으로 봄)
+/// - WHERE 없는 DELETE / UPDATE (WHERE CURRENT OF 커서 포함 WHERE가 있으면 제외)
+/// - TRUNCATE, DROP (임시 테이블만 대상이면 제외)
+pub fn dangerous_statements(sql_text: &str) -> Vec<DangerousStatement> {
+    split_batch(sql_text)
+        .iter()
+        .filter_map(|statement| {
+            let words: Vec<&str> = tokenize_sql(statement)
+                .into_iter()
+                .filter(|token| token.kind == TokenKind::Word)
+                .map(|token| token.text)
+                .collect();
+            let is_word = |word: &str, keyword: &str| word.eq_ignore_ascii_case(keyword);
+            let first = words.first()?;
+
+            // WITH로 시작하면 CTE 뒤의 DELETE / UPDATE를 기준으로 판단
+            let command = if is_word(first, "WITH") {
+                words
+                    .iter()
+                    .find(|word| is_word(word, "DELETE") || is_word(word, "UPDATE"))?
+            } else {
+                first
+            };
+
+            // TRUNCATE TABLE / DROP TABLE [IF EXISTS] 뒤의 대상이 모두 임시 테이블인지
+            let only_temp = || {
+                words
+                    .iter()
+                    .skip(2)
+                    .filter(|word| !is_word(word, "IF") && !is_word(word, "EXISTS"))
+                    .all(|word| is_temp_table(word))
+            };
+            let has_where = || words.iter().any(|word| is_word(word, "WHERE"));
+
+            if is_word(command, "DELETE") {
+                (!has_where()).then_some(DangerousStatement::DeleteWithoutWhere)
+            } else if is_word(command, "UPDATE") {
+                (!has_where()).then_some(DangerousStatement::UpdateWithoutWhere)
+            } else if is_word(command, "TRUNCATE") {
+                (!only_temp()).then_some(DangerousStatement::Truncate)
+            } else if is_word(command, "DROP") {
+                (!only_temp()).then_some(DangerousStatement::Drop)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// 위험한 문장 라벨 (배치에서 처음 찾은 위험한 문장, dangerous_statements 참고)
+pub fn dangerous_statement_label(sql_text: &str) -> Option<&'static str> {
+    dangerous_statements(sql_text)
+        .first()
+        .map(|statement| statement.label())
 }
 
 /// SQL 텍스트에서 모든 operation 추출
 /// 한 쿼리에 여러 operation이 있을 수 있음
 pub fn extract_operations(sql_text: &str) -> Vec<String> {
     let mut operations = HashSet::new();
+    // 대소문자 구분 없이 비교하기 위해 대문자로 변환
     let upper_sql = sql_text.to_uppercase();
 
     // 각 operation 키워드 확인
@@ -81,7 +560,12 @@ pub fn extract_operations(sql_text: &str) -> Vec<String> {
 
 /// 테이블명에서 TB_ 다음 부분 추출
 /// 예: "dbo.TB_PI치료계획세부내역" -> "PI치료계획세부내역"
+/// 임시 테이블과 테이블 변수는 그대로 (예: "#TB_임시" -> "#TB_임시")
 pub fn extract_table_name(table: &str) -> String {
+    if is_temp_table(table) {
+        return table.to_string();
+    }
+
     // 스키마.테이블명 형식 처리
     let parts: Vec<&str> = table.split('.').collect();
     let table_part = if parts.len() > 1 {
@@ -90,10 +574,487 @@ pub fn extract_table_name(table: &str) -> String {
         table
     };
 
-    // TB_ 다음 부분 찾기
-    if let Some(pos) = table_part.find("TB_") {
+    // TB_ 다음 부분 찾기 (tb_도 허용, ASCII 대문자 변환은 바이트 위치를 바꾸지 않음)
+    if let Some(pos) = table_part.to_ascii_uppercase().find("TB_") {
         table_part[pos + 3..].to_string()
     } else {
         table_part.to_string()
     }
 }
+
+/// 문자열의 앞부분을 최대 max_len 바이트까지 자름 (UTF-8 문자 중간에서 자르지 않음)
+/// 긴 SQL을 화면에 나눠 표시할 때 사용하며, max_len보다 짧으면 그대로 반환
+pub fn truncate_str(text: &str, max_len: usize) -> &str {
+    if text.len() <= max_len {
+        return text;
+    }
+    let mut end = max_len;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// hex 덤프 / hex 뷰어의 한 줄 (최대 16바이트)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexRow {
+    /// 줄 첫 바이트의 오프셋
+    pub offset: usize,
+    /// 공백으로 구분한 hex 바이트 (예: "01 02 0a")
+    pub hex: String,
+    /// 바이트마다 한 글자 (출력 가능한 ASCII는 그대로, 나머지는 '.')
+    pub ascii: String,
+}
+
+/// 바이트 데이터를 16바이트씩 나눈 hex 덤프 줄 목록
+/// start_offset은 bytes 첫 바이트의 오프셋 (긴 데이터의 보이는 부분만 만들 때 사용, 16의 배수)
+pub fn hex_rows(bytes: &[u8], start_offset: usize) -> Vec<HexRow> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| HexRow {
+            offset: start_offset + i * 16,
+            hex: chunk
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" "),
+            ascii: chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// 바이트 데이터를 hex 덤프 문자열로 변환 (16바이트씩 줄바꿈, 앞에 오프셋)
+/// 예: "00000000:  01 02 03 ..."
+pub fn hex_dump(bytes: &[u8]) -> String {
+    hex_rows(bytes, 0)
+        .iter()
+        .map(|row| format!("{:08x}:  {}", row.offset, row.hex))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// ============================================
+/// 재현 테스트 코드 생성
+/// ============================================
+/// 캡처한 요청 메시지를 바이트 배열로 넣고 디코딩 결과가 expected_sql인지 확인하는 Rust #[test] 코드
+/// tests/ 아래 파일에 그대로 붙여 넣어 회귀 테스트로 사용 (TdsParser는 전체 경로로 참조)
+/// 패킷 하나면 decode_tds_packet, 여러 패킷으로 나뉜 메시지면 패킷을 모아 디코딩하는 decode_tds_packets_with_encoding 사용
+pub fn to_rust_test(raw_data: &[u8], expected_sql: &str) -> String {
+    let bytes = raw_data
+        .chunks(12)
+        .map(|chunk| {
+            let line: Vec<String> = chunk.iter().map(|b| format!("0x{:02x},", b)).collect();
+            format!("        {}", line.join(" "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let decode = if TdsParser::message_packets(raw_data).len() > 1 {
+        "    let messages = rust_wireshark::tds::TdsParser::decode_tds_packets_with_encoding(DATA, true);
+    let decoded = messages
+        .first()
+        .and_then(|(decoded, _)| decoded.as_ref())
+        .map(|(text, _)| text.clone())
+        .expect(\"TDS 메시지 디코딩 실패\");"
+    } else {
+        "    let decoded = rust_wireshark::tds::TdsParser::decode_tds_packet(DATA)
+        .expect(\"TDS 패킷 디코딩 실패\");"
+    };
+
+    format!(
+        "#[test]
+fn decodes_captured_packet() {{
+    const DATA: &[u8] = &[
+{}
+    ];
+{}
+    assert_eq!(decoded.trim(), {:?});
+}}
+",
+        bytes,
+        decode,
+        expected_sql.trim()
+    )
+}
+
+/// 다른 도구에서 복사한 hex 문자열을 바이트로 변환 (hex_dump의 반대)
+/// 공백으로 구분한 바이트("04 01 00 2a"), 이어진 문자열("0401002a"), 0x 접두사를 허용하고,
+/// 줄 앞의 오프셋("00000000:", Wireshark의 "0000   ")과 줄 끝의 ASCII 열은 무시
+pub fn parse_hex_dump(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+
+    for line in text.lines() {
+        let mut rest = line.trim();
+        if rest.is_empty() {
+            continue;
+        }
+
+        // 오프셋: ':'로 끝나거나, 뒤에 바이트(2자리)가 이어지는 4~8자리 hex 첫 토큰
+        if let Some((first, after)) = rest.split_once(char::is_whitespace) {
+            let is_offset = first.ends_with(':')
+                || ((4..=8).contains(&first.len())
+                    && first.chars().all(|c| c.is_ascii_hexdigit())
+                    && after
+                        .split_whitespace()
+                        .next()
+                        .is_some_and(|next| next.len() == 2));
+            if is_offset {
+                rest = after.trim_start();
+            }
+        }
+
+        // ASCII 열은 hex 바이트 뒤에 공백 세 칸 이상으로 구분됨
+        let hex_part = rest.split("   ").next().unwrap_or_default();
+
+        for token in hex_part.split_whitespace() {
+            let token = token.trim_start_matches("0x").trim_start_matches("0X");
+            if !token.chars().all(|c| c.is_ascii_hexdigit()) {
+                // 공백 한두 칸 뒤의 ASCII 열
+                break;
+            }
+            if token.len() % 2 != 0 {
+                return Err(format!("hex 자릿수가 홀수입니다: {}", token));
+            }
+            for i in (0..token.len()).step_by(2) {
+                let byte = u8::from_str_radix(&token[i..i + 2], 16)
+                    .map_err(|e| format!("잘못된 hex '{}': {}", token, e))?;
+                bytes.push(byte);
+            }
+        }
+    }
+
+    if bytes.is_empty() {
+        return Err("hex 바이트가 없습니다".to_string());
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tds::test_packets::sql_batch_packet;
+
+    #[test]
+    fn strips_leading_comments_and_whitespace() {
+        assert_eq!(strip_leading_comments("  SELECT 1"), "SELECT 1");
+        assert_eq!(strip_leading_comments("/* hint */ SELECT 1"), "SELECT 1");
+        assert_eq!(
+            strip_leading_comments("-- note\n  /* a */ /* b */\nselect 1"),
+            "select 1"
+        );
+        // 주석 안의 SELECT는 문장이 아님
+        assert_eq!(strip_leading_comments("-- SELECT 1"), "");
+        assert_eq!(strip_leading_comments("/* unterminated SELECT 1"), "");
+        // 문장 중간의 주석은 그대로
+        assert_eq!(
+            strip_leading_comments("SELECT /* x */ 1"),
+            "SELECT /* x */ 1"
+        );
+    }
+
+    #[test]
+    fn truncates_at_char_boundary() {
+        // "가"는 UTF-8로 3바이트
+        let text = "ab가나";
+        assert_eq!(truncate_str(text, 2), "ab");
+        assert_eq!(truncate_str(text, 3), "ab");
+        assert_eq!(truncate_str(text, 4), "ab");
+        assert_eq!(truncate_str(text, 5), "ab가");
+        assert_eq!(truncate_str(text, 100), text);
+
+        // 큰 문자열을 설정한 크기씩 나눠도 문자가 깨지지 않음
+        let large = "SELECT '진료' ".repeat(10_000);
+        let max_len = crate::Config::default().max_sql_display;
+        let first = truncate_str(&large, max_len);
+        assert!(first.len() <= max_len && first.len() > max_len - 4);
+        let second = truncate_str(&large, first.len() + max_len);
+        assert!(second.starts_with(first));
+        assert!(second.len() <= first.len() + max_len);
+    }
+
+    #[test]
+    fn classifies_query_kind() {
+        let kind = |sql: &str| SqlEvent::for_test(sql, 1).query_kind();
+
+        assert_eq!(
+            kind("EXEC sp_executesql N'SELECT * FROM TB_A WHERE id=@id', N'@id int', @id=5"),
+            Some(QueryKind::Parameterized)
+        );
+        assert_eq!(
+            kind("SELECT * FROM TB_A WHERE id=5"),
+            Some(QueryKind::AdHoc)
+        );
+        assert_eq!(
+            kind("SELECT * FROM TB_A WHERE name='kim'"),
+            Some(QueryKind::AdHoc)
+        );
+        // 익명화로 ?가 된 리터럴도 Ad-hoc
+        assert_eq!(
+            kind("SELECT * FROM TB_A WHERE id=?"),
+            Some(QueryKind::AdHoc)
+        );
+        assert_eq!(kind("SELECT * FROM TB_A"), Some(QueryKind::Static));
+
+        // RPC 요청은 리터럴이 있어도 Parameterized
+        let rpc = SqlEvent {
+            rpc_flags: Some(0),
+            ..SqlEvent::for_test("usp_GetPatient 5", 1)
+        };
+        assert_eq!(rpc.query_kind(), Some(QueryKind::Parameterized));
+
+        let undecoded = SqlEvent {
+            operation: UNDECODED_OPERATION.to_string(),
+            ..SqlEvent::for_test("SELECT 1", 1)
+        };
+        assert_eq!(undecoded.query_kind(), None);
+        assert_eq!(kind("  "), None);
+    }
+
+    #[test]
+    fn query_kind_labels_round_trip() {
+        for kind in [
+            QueryKind::Parameterized,
+            QueryKind::AdHoc,
+            QueryKind::Static,
+        ] {
+            assert_eq!(QueryKind::from_label(kind.label()), Some(kind));
+        }
+        assert_eq!(QueryKind::from_label("adhoc"), Some(QueryKind::AdHoc));
+        assert_eq!(
+            QueryKind::from_label("PARAMETERIZED"),
+            Some(QueryKind::Parameterized)
+        );
+        assert_eq!(QueryKind::from_label("dynamic"), None);
+    }
+
+    #[test]
+    fn extracts_temp_tables_and_table_variables() {
+        assert_eq!(
+            extract_tables_from_sql("SELECT * FROM #tmp t JOIN dbo.TB_A a ON a.id = t.id"),
+            vec!["#tmp", "dbo.TB_A"]
+        );
+        assert_eq!(
+            extract_tables_from_sql("INSERT INTO @tv (id) SELECT id FROM ##전역_임시"),
+            vec!["@tv", "##전역_임시"]
+        );
+        // 컬럼 값의 @파라미터는 테이블이 아님
+        assert_eq!(
+            extract_tables_from_sql("SELECT * FROM TB_A WHERE id = @id"),
+            vec!["TB_A"]
+        );
+
+        assert!(is_temp_table("#tmp"));
+        assert!(is_temp_table("##tmp"));
+        assert!(is_temp_table("@tv"));
+        assert!(!is_temp_table("dbo.TB_A"));
+        assert_eq!(extract_table_name("#TB_임시"), "#TB_임시");
+    }
+
+    #[test]
+    fn extracts_exec_target_procedure() {
+        assert_eq!(
+            extract_exec_target("EXEC usp_Foo").as_deref(),
+            Some("usp_Foo")
+        );
+        assert_eq!(
+            extract_exec_target("EXECUTE dbo.usp_Bar 1, 2").as_deref(),
+            Some("dbo.usp_Bar")
+        );
+        assert_eq!(
+            extract_exec_target("exec [dbo].[usp_환자조회] @id = 5").as_deref(),
+            Some("dbo.usp_환자조회")
+        );
+        assert_eq!(
+            extract_exec_target("EXEC @ret = usp_Foo @id=5").as_deref(),
+            Some("usp_Foo")
+        );
+        // 동적 SQL은 프로시저 이름이 없음
+        assert_eq!(extract_exec_target("EXEC ('SELECT 1')"), None);
+        assert_eq!(extract_exec_target("EXEC (@sql)"), None);
+        assert_eq!(extract_exec_target("SELECT * FROM TB_A"), None);
+    }
+
+    #[test]
+    fn hex_dump_puts_sixteen_bytes_per_line() {
+        let bytes: Vec<u8> = (0..20).collect();
+        assert_eq!(
+            hex_dump(&bytes),
+            "00000000:  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n\
+             00000010:  10 11 12 13"
+        );
+        assert_eq!(hex_dump(&[]), "");
+    }
+
+    #[test]
+    fn rust_test_embeds_bytes_and_expected_sql() {
+        let sql = "SELECT * FROM TB_환자 WHERE id = 1";
+        let packet = sql_batch_packet(sql, 0x01);
+        assert_eq!(TdsParser::decode_tds_packet(&packet).as_deref(), Some(sql));
+
+        let code = to_rust_test(&packet, &format!("  {}\n", sql));
+        assert!(code.starts_with("#[test]\nfn decodes_captured_packet() {"));
+        assert!(code.contains("const DATA: &[u8] = &[\n        0x01, 0x01, 0x00,"));
+        // 한 줄에 12바이트씩, 모든 바이트가 들어감
+        let literals: Vec<&str> = code
+            .lines()
+            .filter(|line| line.trim_start().starts_with("0x"))
+            .collect();
+        assert_eq!(literals.len(), packet.len().div_ceil(12));
+        assert!(literals.iter().all(|line| line.matches("0x").count() <= 12));
+        assert_eq!(
+            literals
+                .iter()
+                .map(|line| line.matches("0x").count())
+                .sum::<usize>(),
+            packet.len()
+        );
+        assert!(code.contains("rust_wireshark::tds::TdsParser::decode_tds_packet(DATA)"));
+        assert!(code.contains(&format!("assert_eq!(decoded.trim(), {:?});", sql)));
+    }
+
+    #[test]
+    fn rust_test_collects_split_messages() {
+        let first = sql_batch_packet("SELECT 1", 0x00);
+        let second = sql_batch_packet("SELECT 2", 0x01);
+        let code = to_rust_test(&[first, second].concat(), "SELECT 1");
+        assert!(code.contains("decode_tds_packets_with_encoding(DATA, true)"));
+        assert!(!code.contains("decode_tds_packet(DATA)"));
+        assert!(code.contains("assert_eq!(decoded.trim(), \"SELECT 1\");"));
+    }
+
+    #[test]
+    fn dangerous_statements_skip_comments_literals_and_temp_tables() {
+        assert_eq!(
+            dangerous_statements("DELETE FROM TB_x -- WHERE id = 1"),
+            vec![DangerousStatement::DeleteWithoutWhere]
+        );
+        assert_eq!(
+            dangerous_statements("DELETE FROM TB_x /* WHERE id = 1 */"),
+            vec![DangerousStatement::DeleteWithoutWhere]
+        );
+        assert_eq!(
+            dangerous_statements("UPDATE TB_x SET note = 'where'"),
+            vec![DangerousStatement::UpdateWithoutWhere]
+        );
+        assert!(dangerous_statements("SELECT 'drop table x'").is_empty());
+        assert!(dangerous_statements("DROP TABLE #tmp; TRUNCATE TABLE @tv").is_empty());
+        assert_eq!(
+            dangerous_statements(
+                "UPDATE TB_x SET a = 1 WHERE id = 1; DROP TABLE TB_y; TRUNCATE TABLE TB_z"
+            ),
+            vec![DangerousStatement::Drop, DangerousStatement::Truncate]
+        );
+        assert_eq!(
+            dangerous_statement_label("DELETE FROM TB_x; DROP TABLE TB_y"),
+            Some(LABEL_NO_WHERE)
+        );
+        assert_eq!(
+            dangerous_statement_label("DELETE FROM TB_x WHERE id = 1"),
+            None
+        );
+    }
+
+    #[test]
+    fn hex_rows_have_offset_hex_and_ascii_columns() {
+        let mut bytes = b"SELECT 1\r\n".to_vec();
+        bytes.extend([0x00, 0x7F, 0xFF, b'~', b' ', b'A', b'B', b'C', b'D']);
+        assert_eq!(
+            hex_rows(&bytes, 0),
+            vec![
+                HexRow {
+                    offset: 0,
+                    hex: "53 45 4c 45 43 54 20 31 0d 0a 00 7f ff 7e 20 41".to_string(),
+                    ascii: "SELECT 1.....~ A".to_string(),
+                },
+                HexRow {
+                    offset: 16,
+                    hex: "42 43 44".to_string(),
+                    ascii: "BCD".to_string(),
+                },
+            ]
+        );
+
+        // 긴 데이터의 보이는 부분만 만들 때는 시작 오프셋부터
+        let rows = hex_rows(&bytes[16..], 16);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].offset, 16);
+        assert!(hex_rows(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn splits_batch_on_semicolons_and_go() {
+        assert_eq!(
+            split_batch("SELECT 1; SELECT 2;\n\nSELECT 3"),
+            vec!["SELECT 1", "SELECT 2", "SELECT 3"]
+        );
+        assert_eq!(
+            split_batch("SELECT 1\nGO\nselect 2\r\ngo\r\n"),
+            vec!["SELECT 1", "select 2"]
+        );
+        // GO가 들어간 줄이라도 GO 하나뿐이 아니면 구분자가 아님
+        assert_eq!(split_batch("SELECT GO FROM T"), vec!["SELECT GO FROM T"]);
+        assert!(split_batch(" ; ;\n").is_empty());
+    }
+
+    #[test]
+    fn ignores_separators_in_literals_and_comments() {
+        assert_eq!(
+            split_batch("INSERT INTO TB_A VALUES ('a;b', 'it''s;'); SELECT 2"),
+            vec!["INSERT INTO TB_A VALUES ('a;b', 'it''s;')", "SELECT 2"]
+        );
+        assert_eq!(
+            split_batch("SELECT [a;b], \"c;d\" FROM T; SELECT 2"),
+            vec!["SELECT [a;b], \"c;d\" FROM T", "SELECT 2"]
+        );
+        assert_eq!(
+            split_batch("SELECT 1 -- a; b\n; /* c; /* d; */ e; */ SELECT 2"),
+            vec!["SELECT 1 -- a; b", "/* c; /* d; */ e; */ SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn extracts_comma_separated_from_list() {
+        assert_eq!(
+            extract_tables_from_sql("SELECT * FROM a, b, c WHERE a.id = b.id"),
+            vec!["a", "b", "c"]
+        );
+        // 별칭과 테이블 힌트가 있어도 다음 테이블을 찾음
+        assert_eq!(
+            extract_tables_from_sql(
+                "SELECT * FROM dbo.TB_A x WITH (NOLOCK), [dbo].[TB_B] AS y, TB_C"
+            ),
+            vec!["dbo.TB_A", "dbo.TB_B", "TB_C"]
+        );
+    }
+
+    #[test]
+    fn dedups_tables_case_insensitively_keeping_first_form() {
+        assert_eq!(
+            extract_tables_from_sql("SELECT * FROM TB_X JOIN tb_x ON 1 = 1; UPDATE Tb_X SET A = 1"),
+            vec!["TB_X"]
+        );
+        assert_eq!(
+            dedup_tables(["TB_A,", "tb_a)", "TB_B."]),
+            vec!["TB_A", "TB_B"]
+        );
+    }
+
+    #[test]
+    fn extracts_operations_in_any_case() {
+        assert_eq!(extract_operations("select * from TB_A"), vec!["SELECT"]);
+        assert_eq!(
+            extract_operations("/* hint */ Update TB_A set X = 1"),
+            vec!["UPDATE"]
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use crate::tcp::FlowId;
+use crate::SqlEvent;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+
+/// 합성 TCP 세그먼트 최대 페이로드 (큰 TDS 패킷은 나눠서 기록)
+const MAX_SEGMENT: usize = 1460;
+
+/// ============================================
+/// pcap 내보내기
+/// ============================================
+/// 이벤트의 raw_data(TDS 패킷)에 최소한의 Ethernet/IPv4/TCP 헤더를 붙여 pcap 파일로 저장
+/// Wireshark에서 TDS로 열어볼 수 있음 (타임스탬프는 이벤트 시각)
+/// raw_data가 없거나 Flow가 IPv4 형식이 아닌 이벤트는 건너뜀
+/// 반환값: 기록한 패킷 수
+pub fn export_pcap(path: &Path, events: &[SqlEvent]) -> Result<usize, String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create pcap file: {}", e))?;
+    let mut writer = BufWriter::new(file);
+    let count = write_pcap(&mut writer, events)
+        .and_then(|count| writer.flush().map(|_| count))
+        .map_err(|e| format!("Failed to write pcap file: {}", e))?;
+    Ok(count)
+}
+
+/// pcap 형식으로 기록 (파일 외 출력 대상에도 사용 가능)
+pub fn write_pcap<W: Write>(writer: &mut W, events: &[SqlEvent]) -> io::Result<usize> {
+    // 전역 헤더: magic, 버전 2.4, 시간대 0, 정확도 0, snaplen, 링크 타입 Ethernet
+    writer.write_all(&0xa1b2_c3d4u32.to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&4u16.to_le_bytes())?;
+    writer.write_all(&0i32.to_le_bytes())?;
+    writer.write_all(&0u32.to_le_bytes())?;
+    writer.write_all(&65535u32.to_le_bytes())?;
+    writer.write_all(&1u32.to_le_bytes())?;
+
+    // 플로우별 다음 TCP 시퀀스 번호
+    let mut next_seq: HashMap<String, u32> = HashMap::new();
+    let mut count = 0;
+
+    for event in events {
+        let Some(ref raw_data) = event.raw_data else {
+            continue;
+        };
+        let Some((src, dst)) = parse_flow(&event.flow_id) else {
+            continue;
+        };
+
+        let ts_sec = event.timestamp.timestamp() as u32;
+        let ts_usec = event.timestamp.timestamp_subsec_micros();
+
+        for segment in raw_data.chunks(MAX_SEGMENT) {
+            let seq = next_seq.entry(event.flow_id.clone()).or_insert(1);
+            let frame = build_frame(src, dst, *seq, segment);
+            *seq = seq.wrapping_add(segment.len() as u32);
+
+            // 레코드 헤더: 초, 마이크로초, 저장 길이, 원래 길이
+            writer.write_all(&ts_sec.to_le_bytes())?;
+            writer.write_all(&ts_usec.to_le_bytes())?;
+            writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+            writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+            writer.write_all(&frame)?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Flow 문자열에서 양 끝점 추출 (IPv4만)
+fn parse_flow(flow_id: &str) -> Option<((Ipv4Addr, u16), (Ipv4Addr, u16))> {
+    let flow: FlowId = flow_id.parse().ok()?;
+    match (flow.src_ip, flow.dst_ip) {
+        (IpAddr::V4(src_ip), IpAddr::V4(dst_ip)) => {
+            Some(((src_ip, flow.src_port), (dst_ip, flow.dst_port)))
+        }
+        _ => None,
+    }
+}
+
+/// Ethernet + IPv4 + TCP(PSH/ACK) 프레임 생성
+fn build_frame(src: (Ipv4Addr, u16), dst: (Ipv4Addr, u16), seq: u32, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(54 + payload.len());
+
+    // Ethernet: 목적지 MAC, 출발지 MAC, IPv4
+    frame.extend_from_slice(&[0x02, 0, 0, 0, 0, 0x02]);
+    frame.extend_from_slice(&[0x02, 0, 0, 0, 0, 0x01]);
+    frame.extend_from_slice(&[0x08, 0x00]);
+
+    // IPv4 헤더 (20바이트, 옵션 없음)
+    let total_len = (20 + 20 + payload.len()) as u16;
+    let mut ip = [0u8; 20];
+    ip[0] = 0x45;
+    ip[2..4].copy_from_slice(&total_len.to_be_bytes());
+    ip[6] = 0x40; // Don't Fragment
+    ip[8] = 64; // TTL
+    ip[9] = 6; // TCP
+    ip[12..16].copy_from_slice(&src.0.octets());
+    ip[16..20].copy_from_slice(&dst.0.octets());
+    let ip_checksum = checksum(&[&ip]);
+    ip[10..12].copy_from_slice(&ip_checksum.to_be_bytes());
+    frame.extend_from_slice(&ip);
+
+    // TCP 헤더 (20바이트, 옵션 없음)
+    let mut tcp = [0u8; 20];
+    tcp[0..2].copy_from_slice(&src.1.to_be_bytes());
+    tcp[2..4].copy_from_slice(&dst.1.to_be_bytes());
+    tcp[4..8].copy_from_slice(&seq.to_be_bytes());
+    tcp[12] = 0x50; // 데이터 오프셋 5 (20바이트)
+    tcp[13] = 0x18; // PSH | ACK
+    tcp[14..16].copy_from_slice(&0xffffu16.to_be_bytes());
+
+    // TCP 체크섬: 가상 헤더(출발지, 목적지, 프로토콜, TCP 길이) + 헤더 + 페이로드
+    let mut pseudo = [0u8; 12];
+    pseudo[0..4].copy_from_slice(&src.0.octets());
+    pseudo[4..8].copy_from_slice(&dst.0.octets());
+    pseudo[9] = 6;
+    pseudo[10..12].copy_from_slice(&((20 + payload.len()) as u16).to_be_bytes());
+    let tcp_checksum = checksum(&[&pseudo, &tcp, payload]);
+    tcp[16..18].copy_from_slice(&tcp_checksum.to_be_bytes());
+    frame.extend_from_slice(&tcp);
+
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// 인터넷 체크섬 (16비트 1의 보수 합)
+/// 각 조각은 짝수 길이여야 함 (마지막 조각만 홀수 허용)
+fn checksum(parts: &[&[u8]]) -> u16 {
+    let mut sum: u32 = 0;
+    for part in parts {
+        for chunk in part.chunks(2) {
+            let word = if chunk.len() == 2 {
+                u16::from_be_bytes([chunk[0], chunk[1]])
+            } else {
+                u16::from_be_bytes([chunk[0], 0])
+            };
+            sum += word as u32;
+        }
+    }
+
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// pcap 바이트에서 (초, 프레임) 레코드 목록 읽기
+    fn read_records(pcap: &[u8]) -> Vec<(u32, Vec<u8>)> {
+        assert_eq!(pcap[..4], 0xa1b2_c3d4u32.to_le_bytes());
+        assert_eq!(pcap[20..24], 1u32.to_le_bytes());
+
+        let mut records = Vec::new();
+        let mut rest = &pcap[24..];
+        while !rest.is_empty() {
+            let ts_sec = u32::from_le_bytes(rest[0..4].try_into().unwrap());
+            let incl_len = u32::from_le_bytes(rest[8..12].try_into().unwrap()) as usize;
+            let orig_len = u32::from_le_bytes(rest[12..16].try_into().unwrap()) as usize;
+            assert_eq!(incl_len, orig_len);
+            records.push((ts_sec, rest[16..16 + incl_len].to_vec()));
+            rest = &rest[16 + incl_len..];
+        }
+        records
+    }
+
+    fn event_with_raw(seconds: i64, raw_data: Vec<u8>) -> SqlEvent {
+        SqlEvent {
+            raw_data: Some(raw_data),
+            ..SqlEvent::for_test("SELECT 1", seconds)
+        }
+    }
+
+    #[test]
+    fn writes_one_record_per_event_with_payload() {
+        let events = [
+            event_with_raw(100, vec![0x01, 0x01, 0x00, 0x0A, 0, 0, 1, 0, 0xAA, 0xBB]),
+            event_with_raw(200, vec![0x03, 0x01, 0x00, 0x09, 0, 0, 1, 0, 0xCC]),
+            // raw_data가 없는 이벤트는 건너뜀
+            SqlEvent::for_test("SELECT 2", 300),
+        ];
+        let mut pcap = Vec::new();
+        assert_eq!(write_pcap(&mut pcap, &events).unwrap(), 2);
+
+        let records = read_records(&pcap);
+        assert_eq!(records.len(), 2);
+        for ((ts_sec, frame), event) in records.iter().zip(&events) {
+            assert_eq!(*ts_sec as i64, event.timestamp.timestamp());
+            assert_eq!(&frame[54..], event.raw_data.as_deref().unwrap());
+            // 출발지 10.0.0.5:50000 -> 목적지 10.0.0.1:1433
+            assert_eq!(frame[26..30], [10, 0, 0, 5]);
+            assert_eq!(frame[30..34], [10, 0, 0, 1]);
+            assert_eq!(frame[34..36], 50000u16.to_be_bytes());
+            assert_eq!(frame[36..38], 1433u16.to_be_bytes());
+            // IPv4 헤더 체크섬 검증 (체크섬 포함 합이 0)
+            assert_eq!(checksum(&[&frame[14..34]]), 0);
+        }
+    }
+
+    #[test]
+    fn splits_large_payload_into_sequential_segments() {
+        let raw_data: Vec<u8> = (0..3000).map(|i| i as u8).collect();
+        let mut pcap = Vec::new();
+        assert_eq!(
+            write_pcap(&mut pcap, &[event_with_raw(1, raw_data.clone())]).unwrap(),
+            3
+        );
+
+        let records = read_records(&pcap);
+        let seqs: Vec<u32> = records
+            .iter()
+            .map(|(_, frame)| u32::from_be_bytes(frame[38..42].try_into().unwrap()))
+            .collect();
+        assert_eq!(seqs, vec![1, 1461, 2921]);
+
+        let payload: Vec<u8> = records
+            .iter()
+            .flat_map(|(_, frame)| frame[54..].to_vec())
+            .collect();
+        assert_eq!(payload, raw_data);
+    }
+
+    #[test]
+    fn skips_events_with_non_ipv4_flow() {
+        let ip = IpAddr::V6(std::net::Ipv6Addr::LOCALHOST);
+        let flow_id = FlowId::new(ip, 50000, ip, 1433).to_string();
+        // IPv6 Flow 문자열도 파싱은 되지만 IPv4가 아니므로 건너뜀
+        assert!(flow_id.parse::<FlowId>().is_ok());
+
+        let mut event = event_with_raw(1, vec![0x01; 10]);
+        event.flow_id = flow_id;
+        let mut pcap = Vec::new();
+        assert_eq!(write_pcap(&mut pcap, &[event]).unwrap(), 0);
+        assert_eq!(pcap.len(), 24);
+    }
+
+    #[test]
+    fn skips_events_with_unparseable_flow() {
+        let mut event = event_with_raw(1, vec![0x01; 10]);
+        event.flow_id = "not a flow".to_string();
+        let mut pcap = Vec::new();
+        assert_eq!(write_pcap(&mut pcap, &[event]).unwrap(), 0);
+        assert_eq!(pcap.len(), 24);
+    }
+}
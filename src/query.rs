@@ -0,0 +1,554 @@
+use crate::{extract_operations, extract_table_name, extract_tables_from_sql, QueryKind, SqlEvent};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use regex::{Regex, RegexBuilder};
+
+/// ============================================
+/// 이벤트 조건식
+/// ============================================
+/// 캡처한 이벤트에 대한 조건 (검색, 내보내기 필터, 경고 규칙이 같은 평가기를 사용)
+#[derive(Debug, Clone)]
+pub enum EventPredicate {
+    /// 작업 유형이 같음 (한 쿼리의 여러 operation 중 하나라도 같으면 참, 대소문자 무시)
+    Operation(String),
+    /// 테이블명이 같음 (TB_ 다음 부분 기준 비교, 예: "TB_진료내역"과 "dbo.TB_진료내역"은 같음)
+    Table(String),
+    /// 테이블명에 문자열이 포함됨 (대소문자 무시)
+    TableContains(String),
+    /// SQL 텍스트에 문자열이 포함됨 (대소문자 무시, 빈 문자열이면 항상 참)
+    SqlContains(String),
+    /// SQL 텍스트가 정규식에 맞음
+    SqlMatches(Regex),
+    /// 쿼리 작성 방식이 같음 (SqlEvent::query_kind)
+    Kind(QueryKind),
+    /// 애플리케이션 이름이 같음 (LOGIN7의 Application Name, 대소문자 무시)
+    App(String),
+    /// 애플리케이션 이름에 문자열이 포함됨 (대소문자 무시)
+    AppContains(String),
+    /// 이벤트 시각이 범위 안에 있음 (경계 포함, None이면 그쪽은 제한 없음)
+    TimeBetween(Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+    Not(Box<EventPredicate>),
+    And(Vec<EventPredicate>),
+    Or(Vec<EventPredicate>),
+}
+
+impl EventPredicate {
+    /// 이벤트가 조건을 만족하는지
+    pub fn matches(&self, event: &SqlEvent) -> bool {
+        match self {
+            EventPredicate::Operation(operation) => {
+                event.operation.eq_ignore_ascii_case(operation)
+                    || extract_operations(&event.sql_text)
+                        .iter()
+                        .any(|op| op.eq_ignore_ascii_case(operation))
+            }
+            EventPredicate::Table(table) => {
+                let table_name = extract_table_name(table).to_lowercase();
+                event_tables(event)
+                    .iter()
+                    .any(|t| extract_table_name(t).to_lowercase() == table_name)
+            }
+            EventPredicate::TableContains(text) => {
+                let text = text.to_lowercase();
+                event_tables(event)
+                    .iter()
+                    .any(|t| t.to_lowercase().contains(&text))
+            }
+            EventPredicate::SqlContains(text) => {
+                text.is_empty()
+                    || event
+                        .full_text()
+                        .to_lowercase()
+                        .contains(&text.to_lowercase())
+            }
+            EventPredicate::SqlMatches(regex) => regex.is_match(&event.full_text()),
+            EventPredicate::Kind(kind) => event.query_kind() == Some(*kind),
+            EventPredicate::App(app) => event
+                .app_name
+                .as_ref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(app)),
+            EventPredicate::AppContains(text) => {
+                let text = text.to_lowercase();
+                event
+                    .app_name
+                    .as_ref()
+                    .is_some_and(|name| name.to_lowercase().contains(&text))
+            }
+            EventPredicate::TimeBetween(from, to) => {
+                from.is_none_or(|from| event.timestamp >= from)
+                    && to.is_none_or(|to| event.timestamp <= to)
+            }
+            EventPredicate::Not(inner) => !inner.matches(event),
+            EventPredicate::And(predicates) => predicates.iter().all(|p| p.matches(event)),
+            EventPredicate::Or(predicates) => predicates.iter().any(|p| p.matches(event)),
+        }
+    }
+
+    /// 조건식 문자열 파싱
+    ///
+    /// 필드 조건 (값에 공백이나 괄호, 연산자 문자가 있으면 큰따옴표로 감쌈):
+    /// - `op=SELECT` 작업 유형
+    /// - `table=TB_진료내역` 테이블명 일치, `table~진료` 테이블명에 포함
+    /// - `sql~"DELETE\s+FROM"` SQL 정규식 (대소문자 무시), `sql=WHERE` SQL에 포함
+    /// - `time>=2024-01-01T09:00` / `time<="2024-01-01 18:00"` 시각 범위 (경계 포함, 시간대가 없으면 로컬 시각)
+    /// - `time=2024-01-01` 그 날 하루
+    /// - `kind=adhoc` 쿼리 작성 방식 (parameterized / adhoc / static)
+    ///
+    /// 필드가 없는 단어는 SQL에 포함되는지 검사
+    /// 조건은 `and` / `or` / `not`과 괄호로 조합하며 연산자가 없으면 and (and가 or보다 우선)
+    /// 예: `op=SELECT and table~진료`, `(op=DELETE or op=UPDATE) not table~로그`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty() {
+            return Err("조건식이 비어 있음".to_string());
+        }
+
+        let mut parser = Parser { tokens, pos: 0 };
+        let predicate = parser.parse_or()?;
+        match parser.tokens.get(parser.pos) {
+            Some(token) => Err(format!("예상하지 못한 {}", token.describe())),
+            None => Ok(predicate),
+        }
+    }
+
+    /// 검색창 문자열 해석
+    /// 필드 조건(`op=` 등)이 있으면 조건식으로 파싱하고, 없으면 문자열 전체를 SQL 검색어로 사용
+    /// (기존처럼 공백이 있는 SQL 조각도 그대로 검색, 빈 문자열이면 None)
+    pub fn from_search(text: &str) -> Result<Option<Self>, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+
+        let has_condition = tokenize(text).is_ok_and(|tokens| {
+            tokens.windows(2).any(|pair| {
+                matches!(pair, [Token::Word(field), Token::Op(_)] if Field::parse(field).is_some())
+            })
+        });
+        if has_condition {
+            Self::parse(text).map(Some)
+        } else {
+            Ok(Some(EventPredicate::SqlContains(text.to_string())))
+        }
+    }
+}
+
+/// 이벤트의 테이블 목록 (event.tables가 비어있으면 SQL 텍스트에서 직접 추출)
+fn event_tables(event: &SqlEvent) -> Vec<String> {
+    if event.tables.is_empty() {
+        extract_tables_from_sql(&event.sql_text)
+    } else {
+        event.tables.clone()
+    }
+}
+
+/// ============================================
+/// 조건식 파서
+/// ============================================
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(String),
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(word) => format!("단어 '{}'", word),
+            Token::Quoted(text) => format!("문자열 \"{}\"", text),
+            Token::Op(op) => format!("연산자 '{}'", op),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+        }
+    }
+}
+
+/// 공백, 괄호, 큰따옴표, 연산자(= ~ < > <= >=) 기준으로 나눔
+/// 큰따옴표 안에서는 \" 와 \\ 만 이스케이프로 처리 (정규식의 \s 등은 그대로)
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if matches!(chars.peek(), Some('"') | Some('\\')) => {
+                            value.extend(chars.next());
+                        }
+                        Some(c) => value.push(c),
+                        None => return Err("닫는 큰따옴표가 없음".to_string()),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            '=' | '~' => {
+                chars.next();
+                tokens.push(Token::Op(c.to_string()));
+            }
+            '<' | '>' => {
+                chars.next();
+                let mut op = c.to_string();
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                    op.push('=');
+                }
+                tokens.push(Token::Op(op));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()\"=~<>".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// 조건식의 필드 이름
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Operation,
+    Table,
+    Sql,
+    Time,
+    Kind,
+    App,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "op" | "operation" => Some(Field::Operation),
+            "table" => Some(Field::Table),
+            "sql" => Some(Field::Sql),
+            "time" => Some(Field::Time),
+            "kind" => Some(Field::Kind),
+            "app" => Some(Field::App),
+            _ => None,
+        }
+    }
+}
+
+/// 재귀 하강 파서
+/// or_expr := and_expr ("or" and_expr)*
+/// and_expr := unary ("and"? unary)*
+/// unary := "not" unary | "(" or_expr ")" | 필드 연산자 값 | 단어
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// 다음 토큰이 키워드(and / or / not, 대소문자 무시)인지
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<EventPredicate, String> {
+        let mut predicates = vec![self.parse_and()?];
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            predicates.push(self.parse_and()?);
+        }
+        Ok(combine(predicates, EventPredicate::Or))
+    }
+
+    fn parse_and(&mut self) -> Result<EventPredicate, String> {
+        let mut predicates = vec![self.parse_unary()?];
+        loop {
+            if self.peek_keyword("and") {
+                self.pos += 1;
+            } else if self.peek().is_none()
+                || self.peek() == Some(&Token::RParen)
+                || self.peek_keyword("or")
+            {
+                break;
+            }
+            predicates.push(self.parse_unary()?);
+        }
+        Ok(combine(predicates, EventPredicate::And))
+    }
+
+    fn parse_unary(&mut self) -> Result<EventPredicate, String> {
+        if self.peek_keyword("not") {
+            self.pos += 1;
+            return Ok(EventPredicate::Not(Box::new(self.parse_unary()?)));
+        }
+
+        match self.next() {
+            Some(Token::LParen) => {
+                let predicate = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(predicate),
+                    _ => Err("닫는 괄호가 없음".to_string()),
+                }
+            }
+            Some(Token::Word(word)) => {
+                if let Some(Token::Op(op)) = self.peek().cloned() {
+                    let field =
+                        Field::parse(&word).ok_or_else(|| format!("알 수 없는 필드 '{}'", word))?;
+                    self.pos += 1;
+                    let value = match self.next() {
+                        Some(Token::Word(value)) | Some(Token::Quoted(value)) => value,
+                        _ => return Err(format!("'{}{}' 뒤에 값이 없음", word, op)),
+                    };
+                    condition(field, &op, &value)
+                } else {
+                    Ok(EventPredicate::SqlContains(word))
+                }
+            }
+            Some(Token::Quoted(text)) => Ok(EventPredicate::SqlContains(text)),
+            Some(token) => Err(format!("예상하지 못한 {}", token.describe())),
+            None => Err("조건식이 중간에 끝남".to_string()),
+        }
+    }
+}
+
+/// 조건이 하나면 그대로, 여러 개면 And / Or로 묶음
+fn combine(
+    mut predicates: Vec<EventPredicate>,
+    group: fn(Vec<EventPredicate>) -> EventPredicate,
+) -> EventPredicate {
+    if predicates.len() == 1 {
+        predicates.remove(0)
+    } else {
+        group(predicates)
+    }
+}
+
+/// 필드 조건 하나를 조건식으로
+fn condition(field: Field, op: &str, value: &str) -> Result<EventPredicate, String> {
+    match (field, op) {
+        (Field::Operation, "=") => Ok(EventPredicate::Operation(value.to_uppercase())),
+        (Field::Table, "=") => Ok(EventPredicate::Table(value.to_string())),
+        (Field::Table, "~") => Ok(EventPredicate::TableContains(value.to_string())),
+        (Field::Sql, "=") => Ok(EventPredicate::SqlContains(value.to_string())),
+        (Field::App, "=") => Ok(EventPredicate::App(value.to_string())),
+        (Field::App, "~") => Ok(EventPredicate::AppContains(value.to_string())),
+        (Field::Sql, "~") => RegexBuilder::new(value)
+            .case_insensitive(true)
+            .build()
+            .map(EventPredicate::SqlMatches)
+            .map_err(|e| format!("잘못된 정규식 '{}': {}", value, e)),
+        (Field::Kind, "=") => QueryKind::from_label(value)
+            .map(EventPredicate::Kind)
+            .ok_or_else(|| {
+                format!(
+                    "알 수 없는 쿼리 유형 '{}' (parameterized / adhoc / static)",
+                    value
+                )
+            }),
+        (Field::Time, ">=" | ">") => {
+            Ok(EventPredicate::TimeBetween(Some(parse_time(value)?), None))
+        }
+        (Field::Time, "<=" | "<") => {
+            Ok(EventPredicate::TimeBetween(None, Some(parse_time(value)?)))
+        }
+        (Field::Time, "=") => {
+            // 날짜만 쓰면 그 날 하루 전체
+            let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map_err(|_| format!("time= 에는 날짜(YYYY-MM-DD)만 쓸 수 있음: '{}'", value))?;
+            let from = local_to_utc(date.and_hms_opt(0, 0, 0).unwrap_or_default())?;
+            let to = from + chrono::Duration::days(1) - chrono::Duration::nanoseconds(1);
+            Ok(EventPredicate::TimeBetween(Some(from), Some(to)))
+        }
+        _ => Err(format!("지원하지 않는 연산자 '{}' ({:?} 필드)", op, field)),
+    }
+}
+
+/// 조건식의 시각 값 파싱
+/// RFC 3339 (시간대 포함), 또는 시간대 없는 "YYYY-MM-DD[ T]HH:MM[:SS[.fff]]" / "YYYY-MM-DD" (로컬 시각)
+fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    const FORMATS: [&str; 4] = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ];
+    let naive = FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(|| format!("잘못된 시각 '{}'", value))?;
+    local_to_utc(naive)
+}
+
+/// 로컬 시각을 UTC로 (일광 절약 시간으로 두 번 있는 시각은 앞의 것)
+fn local_to_utc(naive: NaiveDateTime) -> Result<DateTime<Utc>, String> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| format!("로컬 시간대에 없는 시각 '{}'", naive))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> EventPredicate {
+        EventPredicate::parse(text).unwrap()
+    }
+
+    #[test]
+    fn field_conditions_match_events() {
+        let select = SqlEvent::for_test("SELECT * FROM dbo.TB_진료내역 WHERE id=1", 10);
+        let delete = SqlEvent::for_test("delete from TB_환자 where id=1", 20);
+
+        assert!(parse("op=select").matches(&select));
+        assert!(!parse("op=SELECT").matches(&delete));
+        assert!(parse("op=DELETE").matches(&delete));
+        assert!(parse("table=TB_진료내역").matches(&select));
+        assert!(!parse("table=진료").matches(&select));
+        assert!(parse("table~진료").matches(&select));
+        assert!(parse(r#"sql~"delete\s+from""#).matches(&delete));
+        assert!(!parse(r#"sql~"delete\s+from""#).matches(&select));
+        assert!(parse("sql=WHERE").matches(&delete));
+
+        let from = DateTime::from_timestamp(15, 0);
+        assert!(!EventPredicate::TimeBetween(from, None).matches(&select));
+        assert!(EventPredicate::TimeBetween(from, None).matches(&delete));
+        assert!(
+            EventPredicate::TimeBetween(None, DateTime::from_timestamp(10, 0)).matches(&select)
+        );
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let select = SqlEvent::for_test("SELECT * FROM TB_진료내역", 1);
+        let update = SqlEvent::for_test("UPDATE TB_로그 SET a=1", 2);
+        let delete = SqlEvent::for_test("DELETE FROM TB_진료내역", 3);
+
+        let predicate = parse("op=SELECT or op=DELETE and table~로그");
+        assert!(predicate.matches(&select));
+        assert!(!predicate.matches(&delete));
+
+        let predicate = parse("(op=DELETE or op=UPDATE) not table~로그");
+        assert!(predicate.matches(&delete));
+        assert!(!predicate.matches(&update));
+        assert!(!predicate.matches(&select));
+
+        // 연산자가 없으면 and, 키워드는 대소문자 무시
+        let predicate = parse("op=DELETE 진료내역");
+        assert!(predicate.matches(&delete));
+        assert!(!parse("op=DELETE AND NOT 진료내역").matches(&delete));
+    }
+
+    #[test]
+    fn parse_errors_are_reported() {
+        for text in [
+            "",
+            "op=",
+            "foo=bar",
+            "(op=SELECT",
+            "op=SELECT)",
+            r#"sql="unterminated"#,
+            "sql~(",
+            "kind=weird",
+            "time>=yesterday",
+            "table<TB_A",
+        ] {
+            assert!(EventPredicate::parse(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn quoted_values_keep_operators_and_escapes() {
+        assert_eq!(
+            tokenize(r#"sql="a = \"b\" \s""#).unwrap(),
+            vec![
+                Token::Word("sql".to_string()),
+                Token::Op("=".to_string()),
+                Token::Quoted(r#"a = "b" \s"#.to_string()),
+            ]
+        );
+        assert_eq!(
+            tokenize("time>=2024-01-01").unwrap(),
+            vec![
+                Token::Word("time".to_string()),
+                Token::Op(">=".to_string()),
+                Token::Word("2024-01-01".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn time_equals_covers_the_whole_local_day() {
+        let EventPredicate::TimeBetween(Some(from), Some(to)) = parse("time=2024-03-01") else {
+            panic!("time= 는 시각 범위");
+        };
+        assert_eq!(
+            to - from,
+            chrono::Duration::days(1) - chrono::Duration::nanoseconds(1)
+        );
+        assert_eq!(from, parse_time("2024-03-01 00:00").unwrap());
+        assert_eq!(
+            parse_time("2024-03-01T09:00:00+09:00").unwrap(),
+            DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn search_without_conditions_is_plain_sql_text() {
+        assert!(EventPredicate::from_search("   ").unwrap().is_none());
+
+        let event = SqlEvent::for_test("SELECT a, b FROM TB_A", 1);
+        // 필드 조건이 없으면 공백이 있어도 문자열 전체로 검색
+        let predicate = EventPredicate::from_search("a, b FROM").unwrap().unwrap();
+        assert!(matches!(predicate, EventPredicate::SqlContains(_)));
+        assert!(predicate.matches(&event));
+        assert!(!EventPredicate::from_search("b, a")
+            .unwrap()
+            .unwrap()
+            .matches(&event));
+
+        assert!(EventPredicate::from_search("op=SELECT table=TB_A")
+            .unwrap()
+            .unwrap()
+            .matches(&event));
+        assert!(EventPredicate::from_search("op=SELECT (").is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// 캐시에 보관하는 최대 주소 수 (넘으면 가장 오래 사용하지 않은 주소부터 제거)
+const CACHE_CAPACITY: usize = 256;
+
+/// 역방향 DNS 조회 한 번의 최대 대기 시간 (넘으면 이름 없음으로 캐시)
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 최근 사용 순서를 유지하는 조회 결과 캐시
+/// 이름이 없는 주소(조회 실패, 시간 초과)도 None으로 캐시해 다시 조회하지 않음
+struct LruCache {
+    entries: HashMap<IpAddr, Option<String>>,
+    // 오래 사용하지 않은 주소가 앞
+    order: VecDeque<IpAddr>,
+}
+
+impl LruCache {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// 캐시된 결과 (없으면 None, 이름이 없는 주소면 Some(None))
+    fn get(&mut self, ip: &IpAddr) -> Option<Option<String>> {
+        let value = self.entries.get(ip)?.clone();
+        self.touch(ip);
+        Some(value)
+    }
+
+    fn insert(&mut self, ip: IpAddr, name: Option<String>) {
+        if self.entries.insert(ip, name).is_some() {
+            self.touch(&ip);
+            return;
+        }
+
+        self.order.push_back(ip);
+        if self.order.len() > CACHE_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// 가장 최근에 사용한 주소로 이동
+    fn touch(&mut self, ip: &IpAddr) {
+        if let Some(pos) = self.order.iter().position(|cached| cached == ip) {
+            self.order.remove(pos);
+            self.order.push_back(*ip);
+        }
+    }
+}
+
+/// 조회 스레드와 공유하는 상태
+struct ResolverState {
+    cache: LruCache,
+    // 조회를 요청했지만 아직 결과가 없는 주소 (중복 요청 방지)
+    pending: HashSet<IpAddr>,
+    cache_hits: u64,
+}
+
+/// ============================================
+/// 역방향 DNS 조회
+/// ============================================
+/// IP 주소를 호스트 이름으로 바꾸는 비동기 조회기
+/// resolve_host는 기다리지 않고 캐시된 결과만 반환하며, 처음 보는 주소는 별도 스레드에서 조회
+/// (캡처 스레드나 GUI를 멈추지 않음)
+pub struct HostResolver {
+    state: Arc<Mutex<ResolverState>>,
+    request_sender: mpsc::Sender<IpAddr>,
+}
+
+impl HostResolver {
+    pub fn new() -> Self {
+        let state = Arc::new(Mutex::new(ResolverState {
+            cache: LruCache::new(),
+            pending: HashSet::new(),
+            cache_hits: 0,
+        }));
+        let (request_sender, request_receiver) = mpsc::channel::<IpAddr>();
+
+        let worker_state = Arc::clone(&state);
+        thread::spawn(move || {
+            for ip in request_receiver {
+                let name = lookup_with_timeout(ip);
+                if let Ok(mut state) = worker_state.lock() {
+                    state.pending.remove(&ip);
+                    state.cache.insert(ip, name);
+                }
+            }
+        });
+
+        Self {
+            state,
+            request_sender,
+        }
+    }
+
+    /// IP 주소의 호스트 이름 (캐시에 있을 때만)
+    /// 캐시에 없으면 조회를 요청하고 None 반환, 조회가 끝난 뒤 다시 호출하면 결과 반환
+    pub fn resolve_host(&self, ip: IpAddr) -> Option<String> {
+        let Ok(mut state) = self.state.lock() else {
+            return None;
+        };
+
+        if let Some(name) = state.cache.get(&ip) {
+            state.cache_hits += 1;
+            return name;
+        }
+
+        if state.pending.insert(ip) {
+            let _ = self.request_sender.send(ip);
+        }
+        None
+    }
+
+    /// 조회 중인 주소가 있는지 (결과가 나오면 화면을 다시 그리기 위함)
+    pub fn is_resolving(&self) -> bool {
+        self.state
+            .lock()
+            .is_ok_and(|state| !state.pending.is_empty())
+    }
+
+    /// 캐시에서 바로 응답한 횟수
+    pub fn cache_hits(&self) -> u64 {
+        self.state.lock().map_or(0, |state| state.cache_hits)
+    }
+}
+
+impl Default for HostResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 역방향 DNS 조회 (LOOKUP_TIMEOUT 안에 끝나지 않으면 None)
+/// 시스템 조회 함수는 시간 제한이 없으므로 별도 스레드에서 실행하고 결과만 기다림
+fn lookup_with_timeout(ip: IpAddr) -> Option<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(dns_lookup::lookup_addr(&ip).ok());
+    });
+
+    receiver
+        .recv_timeout(LOOKUP_TIMEOUT)
+        .ok()
+        .flatten()
+        // 이름이 없으면 주소 문자열을 그대로 돌려주는 시스템이 있음
+        .filter(|name| name.parse::<IpAddr>().is_err())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([10, 0, 0, last])
+    }
+
+    #[test]
+    fn lru_cache_evicts_least_recently_used() {
+        let mut cache = LruCache::new();
+        for i in 0..CACHE_CAPACITY {
+            cache.insert(IpAddr::from([10, 1, (i / 256) as u8, i as u8]), None);
+        }
+        // 가장 오래된 주소를 사용하면 그 다음 주소가 먼저 제거됨
+        let oldest = IpAddr::from([10, 1, 0, 0]);
+        let second = IpAddr::from([10, 1, 0, 1]);
+        assert_eq!(cache.get(&oldest), Some(None));
+
+        cache.insert(ip(1), Some("dbhost".to_string()));
+        assert_eq!(cache.entries.len(), CACHE_CAPACITY);
+        assert_eq!(cache.get(&second), None);
+        assert_eq!(cache.get(&oldest), Some(None));
+        assert_eq!(cache.get(&ip(1)), Some(Some("dbhost".to_string())));
+    }
+
+    #[test]
+    fn second_lookup_hits_cache() {
+        let resolver = HostResolver::new();
+        let localhost = IpAddr::from([127, 0, 0, 1]);
+
+        // 처음에는 조회만 요청하고 기다리지 않음
+        assert_eq!(resolver.resolve_host(localhost), None);
+        assert_eq!(resolver.cache_hits(), 0);
+
+        let deadline = Instant::now() + LOOKUP_TIMEOUT * 2;
+        while resolver.is_resolving() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!resolver.is_resolving());
+
+        let first = resolver.resolve_host(localhost);
+        assert_eq!(resolver.cache_hits(), 1);
+        assert_eq!(resolver.resolve_host(localhost), first);
+        assert_eq!(resolver.cache_hits(), 2);
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 캡처 통계
+/// 캡처 스레드에서 갱신하고 GUI에서 읽음 (Arc로 공유)
+#[derive(Debug, Default)]
+pub struct CaptureStats {
+    /// 버린 중복 패킷 수 (SPAN 중복 미러링, TCP 재전송)
+    duplicate_packets: AtomicU64,
+    /// 처리 중 panic이 발생해 건너뛴 패킷 수 (잘못된 형식의 패킷)
+    parse_panics: AtomicU64,
+    /// 초당 이벤트 수 제한으로 버린 이벤트 수
+    rate_limited_events: AtomicU64,
+    /// 1/N 샘플링으로 건너뛴 이벤트 수
+    sampled_out_events: AtomicU64,
+}
+
+impl CaptureStats {
+    pub fn duplicate_packets(&self) -> u64 {
+        self.duplicate_packets.load(Ordering::Relaxed)
+    }
+
+    pub fn set_duplicate_packets(&self, count: u64) {
+        self.duplicate_packets.store(count, Ordering::Relaxed);
+    }
+
+    pub fn parse_panics(&self) -> u64 {
+        self.parse_panics.load(Ordering::Relaxed)
+    }
+
+    pub fn record_parse_panic(&self) {
+        self.parse_panics.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn rate_limited_events(&self) -> u64 {
+        self.rate_limited_events.load(Ordering::Relaxed)
+    }
+
+    pub fn record_rate_limited_event(&self) {
+        self.rate_limited_events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn sampled_out_events(&self) -> u64 {
+        self.sampled_out_events.load(Ordering::Relaxed)
+    }
+
+    pub fn record_sampled_out_event(&self) {
+        self.sampled_out_events.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -1,5 +1,16 @@
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::net::IpAddr;
+use std::str::FromStr;
+
+/// 중복 패킷 검사에 사용하는 최근 패킷 수
+const DUPLICATE_WINDOW: usize = 1024;
+
+/// 기대한 seq와 이보다 멀리 떨어진 세그먼트는 같은 4-tuple을 재사용한 새 연결로 판단
+/// (한 연결의 재전송/순서 뒤바뀜은 수신 윈도우 안에서만 일어남)
+const MAX_SEQ_JUMP: u32 = 64 * 1024 * 1024;
 
 /// TCP 플로우 식별자
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -20,39 +31,227 @@ impl FlowId {
         }
     }
 
+    /// Get the flow of the opposite direction
+    pub fn reversed(&self) -> Self {
+        Self::new(self.dst_ip, self.dst_port, self.src_ip, self.src_port)
+    }
+
     /// Check if the flow is client to server
     pub fn is_client_to_server(&self, src_ip: IpAddr, src_port: u16) -> bool {
         src_ip == self.src_ip && src_port == self.src_port
     }
 }
 
-/// TCP Segment
-#[derive(Debug, Clone)]
-pub struct TcpSegment {
-    pub seq: u32,
-    pub data: Vec<u8>,
-    pub timestamp: f64,
+impl fmt::Display for FlowId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}->{}:{}",
+            self.src_ip, self.src_port, self.dst_ip, self.dst_port
+        )
+    }
+}
+
+/// "ip:port->ip:port" 형식의 Flow 문자열 파싱 (SqlEvent::flow_id 형식)
+impl FromStr for FlowId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_endpoint = |endpoint: &str| -> Option<(IpAddr, u16)> {
+            let (ip, port) = endpoint.trim().rsplit_once(':')?;
+            Some((ip.parse().ok()?, port.parse().ok()?))
+        };
+
+        let (src, dst) = s
+            .split_once("->")
+            .ok_or_else(|| format!("Invalid flow: {}", s))?;
+        let (src_ip, src_port) =
+            parse_endpoint(src).ok_or_else(|| format!("Invalid flow source: {}", src))?;
+        let (dst_ip, dst_port) =
+            parse_endpoint(dst).ok_or_else(|| format!("Invalid flow destination: {}", dst))?;
+
+        Ok(Self::new(src_ip, src_port, dst_ip, dst_port))
+    }
+}
+
+/// 플로우 재조립 상태 (flow_stats 조회용)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowStat {
+    pub flow_id: FlowId,
+    /// 재조립 버퍼에 남아 있는 클라이언트 → 서버 바이트 수 (디코딩한 요청은 버림, TcpReassembler::consume)
+    pub client_bytes: usize,
+    /// 재조립 버퍼에 남아 있는 서버 → 클라이언트 바이트 수 (처리한 응답은 버림, TcpReassembler::discard)
+    pub server_bytes: usize,
+    pub client_segments: u64,
+    pub server_segments: u64,
+    /// 마지막 패킷 타임스탬프 (add_packet 기준, 초)
+    pub last_timestamp: f64,
+    /// TDS 메시지를 하나 이상 디코딩했는지
+    pub decoded: bool,
+    /// FIN / RST로 연결이 끝났는지
+    pub closed: bool,
+    /// 클라이언트 → 서버 스트림에서 빈틈(패킷 손실)이 시작된 위치 (빈틈이 없으면 None)
+    pub client_gap: Option<usize>,
+    /// 서버 → 클라이언트 스트림에서 빈틈이 시작된 위치 (빈틈이 없으면 None)
+    pub server_gap: Option<usize>,
+}
+
+/// 패킷 방향
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    ClientToServer,
+    ServerToClient,
 }
 
 /// TCP Reassembler
 pub struct TcpReassembler {
     flows: HashMap<FlowId, TcpStream>,
+    // 최근 패킷 키 (플로우, 방향, seq, 길이, 페이로드 해시)
+    recent_packets: VecDeque<u64>,
+    recent_set: HashSet<u64>,
+    duplicate_count: u64,
 }
 
 /// TCP Stream State
+#[derive(Default)]
 struct TcpStream {
-    client_segments: Vec<TcpSegment>,
-    server_segments: Vec<TcpSegment>,
+    client: StreamBuffer,
+    server: StreamBuffer,
+    // 마지막 패킷 타임스탬프
+    last_timestamp: f64,
+    // 같은 4-tuple에서 새 연결이 시작될 때마다 증가
+    generation: u32,
+    // FIN 또는 RST를 받아 연결이 끝남 (다음 SYN은 새 연결)
+    closed: bool,
+    // TDS 메시지를 하나 이상 디코딩함
+    decoded: bool,
+}
+
+impl TcpStream {
+    /// 같은 FlowId의 새 연결로 초기화 (세대 증가)
+    fn reset(&mut self) {
+        *self = Self {
+            generation: self.generation.wrapping_add(1),
+            ..Self::default()
+        };
+    }
+
+    /// 받은 데이터나 연결 상태가 있는지 (새로 만든 스트림이 아닌지)
+    fn is_started(&self) -> bool {
+        self.client.next_seq.is_some() || self.server.next_seq.is_some() || self.closed
+    }
+
+    fn buffer_mut(&mut self, direction: Direction) -> &mut StreamBuffer {
+        match direction {
+            Direction::ClientToServer => &mut self.client,
+            Direction::ServerToClient => &mut self.server,
+        }
+    }
+}
+
+/// 한 방향의 재조립 상태
+/// 처음 받은 세그먼트의 seq부터 빈틈없이 이어진 바이트를 계속 덧붙임
+/// 빈틈 뒤에 먼저 도착한 세그먼트는 빈틈이 채워질 때까지 보류
+#[derive(Default)]
+struct StreamBuffer {
+    data: Vec<u8>,
+    // data 다음에 올 seq (아직 받은 세그먼트가 없으면 None)
+    next_seq: Option<u32>,
+    pending: Vec<(u32, Vec<u8>)>,
+    // 받은 세그먼트 수 (빈 세그먼트 제외, 재전송 포함)
+    segments: u64,
+    // 보류할 수 있는 수를 넘어 버린 세그먼트가 있음 (빈틈이 채워져도 그 뒤 바이트를 잃음)
+    dropped: bool,
+    // 이미 처리해서 data 앞에서 버린 바이트 수 (data는 스트림의 이 위치부터)
+    discarded: usize,
 }
 
+impl StreamBuffer {
+    /// 세그먼트 추가, 새로 이어진 바이트 구간 반환
+    fn push(&mut self, seq: u32, data: &[u8]) -> Vec<Vec<u8>> {
+        let mut runs = Vec::new();
+        if data.is_empty() {
+            return runs;
+        }
+        self.segments += 1;
+
+        let next_seq = *self.next_seq.get_or_insert(seq);
+        if Self::is_ahead(next_seq, seq) {
+            // 빈틈 뒤의 세그먼트: 보류 (너무 많이 쌓이면 버림)
+            if self.pending.len() < MAX_PENDING_SEGMENTS {
+                self.pending.push((seq, data.to_vec()));
+            } else {
+                self.dropped = true;
+            }
+            return runs;
+        }
+        self.append(seq, data, &mut runs);
+
+        // 보류 중인 세그먼트 중 이제 이어지는 것 반영
+        while let Some(index) = self.next_seq.and_then(|next_seq| {
+            self.pending
+                .iter()
+                .position(|(seq, _)| !Self::is_ahead(next_seq, *seq))
+        }) {
+            let (seq, data) = self.pending.swap_remove(index);
+            self.append(seq, &data, &mut runs);
+        }
+
+        runs
+    }
+
+    /// next_seq 이전에 시작하는 세그먼트에서 아직 없는 뒷부분만 덧붙임 (재전송 겹침 제거)
+    fn append(&mut self, seq: u32, data: &[u8], runs: &mut Vec<Vec<u8>>) {
+        let Some(next_seq) = self.next_seq else {
+            return;
+        };
+        let overlap = next_seq.wrapping_sub(seq) as usize;
+        if overlap >= data.len() {
+            return;
+        }
+
+        let new_bytes = &data[overlap..];
+        self.data.extend_from_slice(new_bytes);
+        self.next_seq = Some(next_seq.wrapping_add(new_bytes.len() as u32));
+        runs.push(new_bytes.to_vec());
+    }
+
+    /// 빈틈이 시작된 위치 (data 길이, 빈틈 앞까지는 빈틈없이 이어진 바이트)
+    /// 빈틈 뒤의 세그먼트가 보류 중이거나 버려졌으면 Some, 빈틈이 채워지면 다시 None
+    fn gap_offset(&self) -> Option<usize> {
+        (!self.pending.is_empty() || self.dropped).then_some(self.discarded + self.data.len())
+    }
+
+    /// 기대한 seq에서 너무 멀리 떨어진 세그먼트인지 (앞뒤 모두)
+    fn is_discontinuous(&self, seq: u32) -> bool {
+        self.next_seq.is_some_and(|next_seq| {
+            (seq.wrapping_sub(next_seq) as i32).unsigned_abs() > MAX_SEQ_JUMP
+        })
+    }
+
+    /// seq가 next_seq보다 뒤인지 (seq 번호 순환 고려)
+    fn is_ahead(next_seq: u32, seq: u32) -> bool {
+        (seq.wrapping_sub(next_seq) as i32) > 0
+    }
+}
+
+/// 방향별로 보류할 수 있는 최대 세그먼트 수 (빈틈이 채워지지 않는 경우 대비)
+const MAX_PENDING_SEGMENTS: usize = 1024;
+
 impl TcpReassembler {
     pub fn new() -> Self {
         Self {
             flows: HashMap::new(),
+            recent_packets: VecDeque::new(),
+            recent_set: HashSet::new(),
+            duplicate_count: 0,
         }
     }
 
     /// Add TCP packet
+    /// 반환값: 이번 패킷으로 스트림에 새로 이어진 바이트 (push 참고, 빈틈 뒤의 세그먼트면 비어 있음)
+    /// 최근에 같은 패킷(플로우, 방향, seq, 길이, 페이로드)을 받았으면 버리고 None 반환
+    /// (SPAN 포트가 양방향을 한 인터페이스로 미러링하면 모든 패킷이 두 번 들어옴)
     pub fn add_packet(
         &mut self,
         flow_id: FlowId,
@@ -61,77 +260,218 @@ impl TcpReassembler {
         seq: u32,
         data: Vec<u8>,
         timestamp: f64,
-    ) {
+    ) -> Option<Vec<u8>> {
+        let _span = tracing::trace_span!("reassemble", flow = %flow_id).entered();
         let is_client = flow_id.is_client_to_server(src_ip, src_port);
-        let stream = self.flows.entry(flow_id).or_insert_with(|| TcpStream {
-            client_segments: Vec::new(),
-            server_segments: Vec::new(),
-        });
-
-        let segment = TcpSegment {
-            seq,
-            data,
-            timestamp,
-        };
 
-        if is_client {
-            stream.client_segments.push(segment);
+        let mut hasher = DefaultHasher::new();
+        (&flow_id, is_client, seq, data.len(), &data).hash(&mut hasher);
+        let key = hasher.finish();
+
+        if !self.recent_set.insert(key) {
+            self.duplicate_count += 1;
+            tracing::trace!(seq, len = data.len(), "duplicate segment dropped");
+            return None;
+        }
+        self.recent_packets.push_back(key);
+        if self.recent_packets.len() > DUPLICATE_WINDOW {
+            if let Some(oldest) = self.recent_packets.pop_front() {
+                self.recent_set.remove(&oldest);
+            }
+        }
+
+        let direction = if is_client {
+            Direction::ClientToServer
         } else {
-            stream.server_segments.push(segment);
+            Direction::ServerToClient
+        };
+        tracing::trace!(seq, len = data.len(), ?direction, "segment");
+        let runs = self.push(&flow_id, direction, seq, &data);
+        if let Some(stream) = self.flows.get_mut(&flow_id) {
+            stream.last_timestamp = timestamp;
         }
+
+        Some(runs.concat())
     }
 
-    /// Get reassembled client to server data
-    pub fn get_client_data(&self, flow_id: &FlowId) -> Option<Vec<u8>> {
-        self.flows
-            .get(flow_id)
-            .and_then(|stream| Self::reassemble_segments(&stream.client_segments))
+    /// ============================================
+    /// 증분 재조립
+    /// ============================================
+    /// 세그먼트를 추가하고 이번에 새로 이어진 바이트 구간들을 순서대로 반환
+    /// (이어 붙이면 지난 호출 이후 스트림에 추가된 바이트)
+    /// 스트림은 방향별로 처음 받은 세그먼트의 seq부터 시작하고, 빈틈 뒤의 세그먼트는 빈틈이 채워질 때 반환
+    /// 이미 받은 구간과 겹치는 부분(재전송)은 제외
+    /// seq가 기대한 범위에서 크게 벗어나면 4-tuple이 재사용된 새 연결로 보고 플로우를 초기화
+    pub fn push(
+        &mut self,
+        flow_id: &FlowId,
+        direction: Direction,
+        seq: u32,
+        data: &[u8],
+    ) -> Vec<Vec<u8>> {
+        let stream = self.flows.entry(flow_id.clone()).or_default();
+        if !data.is_empty() && stream.buffer_mut(direction).is_discontinuous(seq) {
+            stream.reset();
+        }
+        stream.buffer_mut(direction).push(seq, data)
     }
 
-    /// Get reassembled server to client data
-    pub fn get_server_data(&self, flow_id: &FlowId) -> Option<Vec<u8>> {
+    /// SYN (또는 SYN-ACK) 수신: 해당 방향 스트림이 ISN + 1부터 시작하도록 설정
+    /// 클라이언트 SYN은 새 연결의 시작이므로 이전 연결의 상태가 있으면 플로우를 초기화하고,
+    /// SYN-ACK은 같은 방향이 다른 ISN으로 진행 중이거나 연결이 끝난 경우에만 초기화
+    /// 같은 ISN의 SYN 재전송은 무시
+    pub fn syn(&mut self, flow_id: &FlowId, direction: Direction, isn: u32) {
+        let first_seq = isn.wrapping_add(1);
+        let stream = self.flows.entry(flow_id.clone()).or_default();
+
+        let next_seq = stream.buffer_mut(direction).next_seq;
+        if next_seq == Some(first_seq) && !stream.closed {
+            return;
+        }
+
+        let stale = match direction {
+            Direction::ClientToServer => stream.is_started(),
+            Direction::ServerToClient => next_seq.is_some() || stream.closed,
+        };
+        if stale {
+            stream.reset();
+        }
+        stream.buffer_mut(direction).next_seq = Some(first_seq);
+    }
+
+    /// FIN 또는 RST 수신: 연결 종료 표시
+    /// 이미 받은 데이터와 늦게 도착하는 세그먼트는 그대로 재조립하고, 다음 SYN에서 새 연결로 시작
+    pub fn close(&mut self, flow_id: &FlowId) {
+        if let Some(stream) = self.flows.get_mut(flow_id) {
+            stream.closed = true;
+        }
+    }
+
+    /// 플로우의 재조립 상태를 모두 버림 (끝났거나 오랫동안 패킷이 없는 연결)
+    /// 같은 4-tuple의 패킷이 다시 오면 새 플로우로 시작
+    pub fn remove(&mut self, flow_id: &FlowId) {
+        self.flows.remove(flow_id);
+    }
+
+    /// 연결이 FIN / RST로 끝났는지
+    pub fn is_closed(&self, flow_id: &FlowId) -> bool {
+        self.flows.get(flow_id).is_some_and(|stream| stream.closed)
+    }
+
+    /// 플로우의 연결 세대 (같은 4-tuple로 새 연결이 시작될 때마다 증가, 처음은 0)
+    pub fn generation(&self, flow_id: &FlowId) -> u32 {
         self.flows
             .get(flow_id)
-            .and_then(|stream| Self::reassemble_segments(&stream.server_segments))
+            .map_or(0, |stream| stream.generation)
     }
 
-    /// Reassemble segments
-    fn reassemble_segments(segments: &[TcpSegment]) -> Option<Vec<u8>> {
-        if segments.is_empty() {
-            return None;
+    /// 플로우에서 TDS 메시지를 디코딩했음을 표시 (flow_stats의 decoded)
+    pub fn mark_decoded(&mut self, flow_id: &FlowId) {
+        if let Some(stream) = self.flows.get_mut(flow_id) {
+            stream.decoded = true;
         }
+    }
 
-        let mut sorted: Vec<_> = segments.iter().collect();
-        sorted.sort_by_key(|s| s.seq);
-
-        let mut result = Vec::new();
-        let mut expected_seq = sorted[0].seq;
-
-        for segment in sorted {
-            // Check if the segment is already processed
-            if segment.seq < expected_seq {
-                let overlap = expected_seq - segment.seq;
-                if overlap < segment.data.len() as u32 {
-                    let start = overlap as usize;
-                    result.extend_from_slice(&segment.data[start..]);
-                    expected_seq = segment.seq + segment.data.len() as u32;
-                }
-            } else if segment.seq == expected_seq {
-                // Continuous data
-                result.extend_from_slice(&segment.data);
-                expected_seq += segment.data.len() as u32;
-            } else if segment.seq > expected_seq {
-                // 순서가 바뀐 경우: 빈 공간이 있으면 건너뛰기
-                // (패킷 손실 또는 순서 변경 - 일단 현재까지의 데이터 반환)
-                break;
-            }
+    /// 해당 방향 스트림에서 빈틈(패킷 손실 또는 아직 도착하지 않은 세그먼트)이 시작된 위치
+    /// client_data / server_data는 이 위치까지의 바이트이고, 빈틈 뒤의 바이트는 빈틈이 채워질 때까지 나오지 않음
+    /// 빈틈이 없으면 None
+    pub fn gap_offset(&self, flow_id: &FlowId, direction: Direction) -> Option<usize> {
+        let stream = self.flows.get(flow_id)?;
+        match direction {
+            Direction::ClientToServer => stream.client.gap_offset(),
+            Direction::ServerToClient => stream.server.gap_offset(),
         }
+    }
 
-        if result.is_empty() {
-            None
+    /// 해당 방향 스트림에서 지금까지 받은 바이트를 버림 (이미 처리한 서버 응답 등)
+    /// 이후 client_data / server_data는 버린 뒤에 이어진 바이트부터 반환
+    pub fn discard(&mut self, flow_id: &FlowId, direction: Direction) {
+        if let Some(stream) = self.flows.get_mut(flow_id) {
+            let buffer = stream.buffer_mut(direction);
+            buffer.discarded += buffer.data.len();
+            buffer.data = Vec::new();
+        }
+    }
+
+    /// 해당 방향 스트림에서 position(스트림 처음부터 센 위치) 앞의 바이트를 버림 (디코더가 처리를 마친 부분)
+    /// 이미 버린 위치나 아직 받지 않은 위치를 넘기면 받은 범위 안에서만 버림
+    pub fn consume(&mut self, flow_id: &FlowId, direction: Direction, position: usize) {
+        let Some(stream) = self.flows.get_mut(flow_id) else {
+            return;
+        };
+        let buffer = stream.buffer_mut(direction);
+        let count = position
+            .saturating_sub(buffer.discarded)
+            .min(buffer.data.len());
+        if count == buffer.data.len() {
+            buffer.data = Vec::new();
         } else {
-            Some(result)
+            buffer.data.drain(..count);
         }
+        buffer.discarded += count;
+    }
+
+    /// 해당 방향 스트림에서 discard / consume으로 버린 바이트 수 (client_data / server_data가 시작하는 스트림 위치)
+    pub fn discarded(&self, flow_id: &FlowId, direction: Direction) -> usize {
+        self.flows.get(flow_id).map_or(0, |stream| match direction {
+            Direction::ClientToServer => stream.client.discarded,
+            Direction::ServerToClient => stream.server.discarded,
+        })
+    }
+
+    /// 지금까지 버린 중복 패킷 수
+    pub fn duplicate_count(&self) -> u64 {
+        self.duplicate_count
+    }
+
+    /// Get reassembled client to server data (consume으로 버린 앞부분 제외)
+    pub fn get_client_data(&self, flow_id: &FlowId) -> Option<Vec<u8>> {
+        self.client_data(flow_id).map(<[u8]>::to_vec)
+    }
+
+    /// Get reassembled server to client data (discard로 버린 앞부분 제외)
+    pub fn get_server_data(&self, flow_id: &FlowId) -> Option<Vec<u8>> {
+        self.server_data(flow_id).map(<[u8]>::to_vec)
+    }
+
+    /// 재조립된 클라이언트 → 서버 스트림 (복사 없이 참조, consume으로 버린 앞부분 제외)
+    pub fn client_data(&self, flow_id: &FlowId) -> Option<&[u8]> {
+        self.flows
+            .get(flow_id)
+            .map(|stream| stream.client.data.as_slice())
+            .filter(|data| !data.is_empty())
+    }
+
+    /// 재조립된 서버 → 클라이언트 스트림 (복사 없이 참조, discard로 버린 앞부분 제외)
+    pub fn server_data(&self, flow_id: &FlowId) -> Option<&[u8]> {
+        self.flows
+            .get(flow_id)
+            .map(|stream| stream.server.data.as_slice())
+            .filter(|data| !data.is_empty())
+    }
+
+    /// 플로우의 마지막 패킷 타임스탬프 (add_packet 기준)
+    pub fn last_timestamp(&self, flow_id: &FlowId) -> Option<f64> {
+        self.flows.get(flow_id).map(|stream| stream.last_timestamp)
+    }
+
+    /// 모든 플로우의 재조립 상태
+    pub fn flow_stats(&self) -> Vec<FlowStat> {
+        self.flows
+            .iter()
+            .map(|(flow_id, stream)| FlowStat {
+                flow_id: flow_id.clone(),
+                client_bytes: stream.client.data.len(),
+                server_bytes: stream.server.data.len(),
+                client_segments: stream.client.segments,
+                server_segments: stream.server.segments,
+                last_timestamp: stream.last_timestamp,
+                decoded: stream.decoded,
+                closed: stream.closed,
+                client_gap: stream.client.gap_offset(),
+                server_gap: stream.server.gap_offset(),
+            })
+            .collect()
     }
 
     /// Get all flow IDs
@@ -145,3 +485,201 @@ impl Default for TcpReassembler {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow() -> FlowId {
+        "10.0.0.5:50000->10.0.0.1:1433".parse().unwrap()
+    }
+
+    #[test]
+    fn push_returns_only_newly_contiguous_bytes() {
+        let mut reassembler = TcpReassembler::new();
+        let flow = flow();
+        let c2s = Direction::ClientToServer;
+
+        assert_eq!(
+            reassembler.push(&flow, c2s, 100, b"abc"),
+            vec![b"abc".to_vec()]
+        );
+        assert_eq!(
+            reassembler.push(&flow, c2s, 103, b"de"),
+            vec![b"de".to_vec()]
+        );
+        // 빈틈 뒤의 세그먼트는 보류
+        assert!(reassembler.push(&flow, c2s, 108, b"ij").is_empty());
+        assert!(reassembler.push(&flow, c2s, 107, b"h").is_empty());
+        assert_eq!(reassembler.gap_offset(&flow, c2s), Some(5));
+        // 빈틈이 채워지면 이어진 구간을 순서대로 반환
+        assert_eq!(
+            reassembler.push(&flow, c2s, 105, b"fg"),
+            vec![b"fg".to_vec(), b"h".to_vec(), b"ij".to_vec()]
+        );
+        assert_eq!(reassembler.gap_offset(&flow, c2s), None);
+        assert_eq!(reassembler.client_data(&flow), Some(&b"abcdefghij"[..]));
+        assert_eq!(reassembler.server_data(&flow), None);
+    }
+
+    #[test]
+    fn dropped_segments_keep_the_gap_flagged() {
+        let mut reassembler = TcpReassembler::new();
+        let flow = flow();
+        let s2c = Direction::ServerToClient;
+
+        reassembler.push(&flow, s2c, 0, b"ab");
+        // 보류할 수 있는 수를 넘는 빈틈 뒤 세그먼트
+        for i in 0..=MAX_PENDING_SEGMENTS as u32 {
+            reassembler.push(&flow, s2c, 10 + i, b"x");
+        }
+        reassembler.push(&flow, s2c, 2, &[b'c'; 8]);
+
+        // 빈틈은 채워졌지만 버린 세그먼트가 있어 손실 표시 유지
+        assert_eq!(
+            reassembler.gap_offset(&flow, s2c),
+            Some(10 + MAX_PENDING_SEGMENTS)
+        );
+        let stats = reassembler.flow_stats();
+        assert_eq!(stats[0].server_gap, Some(10 + MAX_PENDING_SEGMENTS));
+        assert_eq!(stats[0].client_gap, None);
+    }
+
+    #[test]
+    fn add_packet_returns_new_bytes_and_skips_duplicates() {
+        let mut reassembler = TcpReassembler::new();
+        let flow = flow();
+        let add = |reassembler: &mut TcpReassembler, seq: u32, data: &[u8]| {
+            reassembler.add_packet(
+                flow.clone(),
+                flow.src_ip,
+                flow.src_port,
+                seq,
+                data.to_vec(),
+                1.0,
+            )
+        };
+
+        assert_eq!(add(&mut reassembler, 10, b"ab"), Some(b"ab".to_vec()));
+        assert_eq!(add(&mut reassembler, 10, b"ab"), None);
+        assert_eq!(add(&mut reassembler, 14, b"ef"), Some(Vec::new()));
+        assert_eq!(add(&mut reassembler, 12, b"cd"), Some(b"cdef".to_vec()));
+        assert_eq!(reassembler.duplicate_count(), 1);
+    }
+
+    #[test]
+    fn consume_drops_bytes_before_position() {
+        let mut reassembler = TcpReassembler::new();
+        let flow = flow();
+        let c2s = Direction::ClientToServer;
+
+        reassembler.push(&flow, c2s, 0, b"abcdef");
+        reassembler.consume(&flow, c2s, 4);
+        assert_eq!(reassembler.client_data(&flow), Some(&b"ef"[..]));
+        assert_eq!(reassembler.discarded(&flow, c2s), 4);
+
+        // 위치는 스트림 처음부터 셈 (이미 버린 위치는 무시, 받은 범위를 넘으면 받은 만큼만)
+        reassembler.consume(&flow, c2s, 2);
+        assert_eq!(reassembler.client_data(&flow), Some(&b"ef"[..]));
+        reassembler.push(&flow, c2s, 6, b"gh");
+        reassembler.consume(&flow, c2s, 7);
+        assert_eq!(reassembler.client_data(&flow), Some(&b"h"[..]));
+        reassembler.consume(&flow, c2s, 100);
+        assert_eq!(reassembler.client_data(&flow), None);
+        assert_eq!(reassembler.discarded(&flow, c2s), 8);
+
+        reassembler.remove(&flow);
+        assert!(reassembler.get_flows().is_empty());
+    }
+
+    #[test]
+    fn push_trims_retransmitted_overlap() {
+        let mut reassembler = TcpReassembler::new();
+        let flow = flow();
+        let s2c = Direction::ServerToClient;
+
+        reassembler.push(&flow, s2c, 1, b"abcd");
+        assert!(reassembler.push(&flow, s2c, 1, b"abcd").is_empty());
+        assert_eq!(
+            reassembler.push(&flow, s2c, 3, b"cdef"),
+            vec![b"ef".to_vec()]
+        );
+        assert_eq!(reassembler.server_data(&flow), Some(&b"abcdef"[..]));
+    }
+
+    #[test]
+    fn push_handles_sequence_wraparound() {
+        let mut reassembler = TcpReassembler::new();
+        let flow = flow();
+        let c2s = Direction::ClientToServer;
+
+        reassembler.push(&flow, c2s, u32::MAX - 1, b"ab");
+        assert_eq!(reassembler.push(&flow, c2s, 0, b"cd"), vec![b"cd".to_vec()]);
+        assert_eq!(reassembler.client_data(&flow), Some(&b"abcd"[..]));
+    }
+
+    #[test]
+    fn reused_four_tuple_with_new_isn_starts_clean_stream() {
+        let mut reassembler = TcpReassembler::new();
+        let flow = flow();
+        let c2s = Direction::ClientToServer;
+
+        reassembler.push(&flow, c2s, 1000, b"old connection");
+        reassembler.push(&flow, Direction::ServerToClient, 5000, b"old response");
+        assert_eq!(reassembler.generation(&flow), 0);
+
+        // 기대한 seq와 멀리 떨어진 세그먼트: 새 연결로 보고 양방향 모두 초기화
+        let new_isn = 1000u32.wrapping_add(MAX_SEQ_JUMP * 2);
+        assert_eq!(
+            reassembler.push(&flow, c2s, new_isn, b"new"),
+            vec![b"new".to_vec()]
+        );
+        assert_eq!(reassembler.generation(&flow), 1);
+        assert_eq!(reassembler.client_data(&flow), Some(&b"new"[..]));
+        assert_eq!(reassembler.server_data(&flow), None);
+    }
+
+    #[test]
+    fn syn_after_close_starts_new_generation() {
+        let mut reassembler = TcpReassembler::new();
+        let flow = flow();
+        let c2s = Direction::ClientToServer;
+
+        reassembler.syn(&flow, c2s, 99);
+        // 같은 ISN의 SYN 재전송은 무시
+        reassembler.syn(&flow, c2s, 99);
+        reassembler.push(&flow, c2s, 100, b"first");
+        assert_eq!(reassembler.generation(&flow), 0);
+
+        reassembler.close(&flow);
+        assert!(reassembler.is_closed(&flow));
+        // 늦게 도착한 세그먼트는 이전 연결에 이어짐
+        reassembler.push(&flow, c2s, 105, b"!");
+        assert_eq!(reassembler.client_data(&flow), Some(&b"first!"[..]));
+
+        // 가까운 seq라도 종료 후의 SYN은 새 연결
+        reassembler.syn(&flow, c2s, 199);
+        assert_eq!(reassembler.generation(&flow), 1);
+        assert!(!reassembler.is_closed(&flow));
+        assert_eq!(reassembler.client_data(&flow), None);
+        assert_eq!(
+            reassembler.push(&flow, c2s, 200, b"second"),
+            vec![b"second".to_vec()]
+        );
+    }
+
+    #[test]
+    fn flow_id_round_trips_through_string() {
+        let flow_id: FlowId = "10.0.0.5:50000->10.0.0.1:1433".parse().unwrap();
+        assert_eq!(flow_id.src_port, 50000);
+        assert_eq!(flow_id.dst_ip, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(flow_id.to_string(), "10.0.0.5:50000->10.0.0.1:1433");
+        assert_eq!(
+            flow_id.reversed().to_string(),
+            "10.0.0.1:1433->10.0.0.5:50000"
+        );
+
+        assert!("10.0.0.5:50000".parse::<FlowId>().is_err());
+        assert!("10.0.0.5->10.0.0.1:1433".parse::<FlowId>().is_err());
+    }
+}
@@ -1,6 +1,81 @@
-use encoding_rs::UTF_16LE;
-use log::debug;
+use crate::normalize::to_nfc;
+use crate::output::strip_leading_comments;
+use encoding_rs::WINDOWS_1252;
+use login::Login7;
+use reader::{decode_utf16le, ByteReader};
+use response::{ColumnInfo, ResponseToken};
+use serde::{Deserialize, Serialize};
+use stream::{DecodedMessage, ResponseStreamDecoder, TdsStreamDecoder};
 use tds_protocol::packet::{PacketHeader, PacketType};
+use tracing::debug;
+
+pub mod login;
+pub mod reader;
+pub mod response;
+pub mod smux;
+pub mod stream;
+/// 테스트용 TDS 패킷 빌더 (tds / extractor / output 테스트에서 같은 모양의 패킷을 쓰도록 공유)
+#[cfg(test)]
+pub(crate) mod test_packets;
+
+/// 패킷 상태 IGNORE 비트: 클라이언트가 이미 보낸 요청을 취소함 (서버는 무시)
+pub const STATUS_IGNORE: u8 = 0x02;
+
+/// 패킷 상태 RESETCONNECTION 비트: 연결 풀에서 재사용한 연결의 첫 요청 (서버가 세션 상태를 초기화)
+pub const STATUS_RESET_CONNECTION: u8 = 0x08;
+
+/// 패킷 상태 RESETCONNECTIONSKIPTRAN 비트: 진행 중인 트랜잭션은 유지하고 세션 상태만 초기화
+pub const STATUS_RESET_CONNECTION_SKIP_TRAN: u8 = 0x10;
+
+/// RPC 요청 사이의 배치 플래그 (TDS 7.2+: 0xFF, 실행하지 않는 요청: 0xFE, 이전 버전: 0x80)
+const RPC_BATCH_FLAG: u8 = 0xFF;
+const RPC_NO_EXEC_FLAG: u8 = 0xFE;
+const RPC_BATCH_FLAG_LEGACY: u8 = 0x80;
+
+/// RPC ProcID로 호출하는 시스템 프로시저 이름 (인덱스가 ProcID, 0은 없음)
+const RPC_PROC_NAMES: [&str; 16] = [
+    "",
+    "sp_cursor",
+    "sp_cursoropen",
+    "sp_cursorprepare",
+    "sp_cursorexecute",
+    "sp_cursorprepexec",
+    "sp_cursorunprepare",
+    "sp_cursorfetch",
+    "sp_cursoroption",
+    "sp_cursorclose",
+    "sp_executesql",
+    "sp_prepare",
+    "sp_execute",
+    "sp_prepexec",
+    "sp_prepexecrpc",
+    "sp_unprepare",
+];
+
+/// UTF-16LE 디코딩 결과에서 이 비율 이상이 자연스러운 문자면 다른 인코딩을 시도하지 않음
+const PLAUSIBLE_TEXT_RATIO: f64 = 0.9;
+
+/// 요청으로 볼 최소 SQL 길이 (공백을 뺀 유니코드 문자 수)
+const MIN_SQL_CHARS: usize = 3;
+
+/// SQL 텍스트 인코딩
+/// TDS 7 이상은 UTF-16LE이지만 일부 오래된 드라이버는 SQL Batch를 단일 바이트 텍스트로 보냄
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextEncoding {
+    Utf16Le,
+    Utf8,
+    Windows1252,
+}
+
+impl TextEncoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Windows1252 => "windows-1252",
+        }
+    }
+}
 
 /// TDS 패킷 타입 (하위 호환성을 위한 래퍼)
 #[repr(u8)]
@@ -47,6 +122,131 @@ impl From<PacketHeader> for TdsHeader {
     }
 }
 
+impl TdsHeader {
+    /// 메시지의 마지막 패킷인지 (EOM)
+    pub fn is_end_of_message(&self) -> bool {
+        self.status & 0x01 != 0
+    }
+
+    /// 연결 풀이 세션을 초기화하라고 요청했는지 (RESETCONNECTION)
+    pub fn is_reset_connection(&self) -> bool {
+        self.status & STATUS_RESET_CONNECTION != 0
+    }
+
+    /// 트랜잭션은 유지하고 세션을 초기화하라고 요청했는지 (RESETCONNECTIONSKIPTRAN)
+    pub fn is_reset_connection_skip_tran(&self) -> bool {
+        self.status & STATUS_RESET_CONNECTION_SKIP_TRAN != 0
+    }
+
+    /// 세션 경계인지 (두 초기화 비트 중 하나라도 있으면 이 요청부터 새 세션)
+    pub fn is_session_reset(&self) -> bool {
+        self.is_reset_connection() || self.is_reset_connection_skip_tran()
+    }
+
+    /// 헤더 필드를 (이름, 표시 문자열) 목록으로 변환 (상세 보기의 헤더 표용)
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let packet_type = match self.packet_type {
+            TdsPacketType::SqlBatch => "SQL Batch (0x01)".to_string(),
+            TdsPacketType::RpcRequest => "RPC (0x03)".to_string(),
+            TdsPacketType::Response => "응답 (0x04)".to_string(),
+            TdsPacketType::Unknown(value) => format!("알 수 없음 (0x{:02X})", value),
+        };
+
+        vec![
+            ("타입", packet_type),
+            (
+                "상태",
+                format!("0x{:02X} ({})", self.status, describe_status(self.status)),
+            ),
+            ("길이", self.length.to_string()),
+            ("SPID", self.spid.to_string()),
+            ("패킷 ID", self.packet_id.to_string()),
+            ("윈도우", self.window.to_string()),
+        ]
+    }
+}
+
+/// 헤더 상태 비트를 읽기 쉬운 문자열로 변환 (예: "EOM, RESETCONNECTION")
+fn describe_status(status: u8) -> String {
+    let names: Vec<&str> = [
+        (0x01, "EOM"),
+        (STATUS_IGNORE, "IGNORE"),
+        (STATUS_RESET_CONNECTION, "RESETCONNECTION"),
+        (STATUS_RESET_CONNECTION_SKIP_TRAN, "RESETCONNECTIONSKIPTRAN"),
+    ]
+    .iter()
+    .filter(|(bit, _)| status & bit != 0)
+    .map(|(_, name)| *name)
+    .collect();
+
+    if names.is_empty() {
+        "없음".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// DONE 계열 토큰 (DONE 0xFD / DONEPROC 0xFE / DONEINPROC 0xFF)
+/// 서버 응답에서 각 문장(결과 집합)의 종료를 알림
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DoneToken {
+    pub token: u8,
+    pub status: u16,
+    pub cur_cmd: u16,
+    pub row_count: u64,
+}
+
+impl DoneToken {
+    /// DONE_MORE (0x0001): 이 토큰 뒤에 결과가 더 있음
+    pub fn has_more(&self) -> bool {
+        self.status & 0x0001 != 0
+    }
+
+    /// DONE_COUNT (0x0010): row_count 값이 유효함
+    pub fn has_count(&self) -> bool {
+        self.status & 0x0010 != 0
+    }
+}
+
+/// RPC 요청 하나 (sp_executesql 등)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RpcCall {
+    /// 호출한 프로시저 이름 (ProcName, 또는 ProcID로 지정한 시스템 프로시저 이름)
+    pub procedure: Option<String>,
+    /// @stmt / @statement 파라미터의 SQL 본문 (저장 프로시저를 직접 호출하면 None)
+    pub statement: Option<String>,
+    /// 나머지 파라미터 (이름, 표시용 값), 보낸 순서 (NULL이나 표시하지 않는 타입은 없음)
+    pub params: Vec<(String, String)>,
+}
+
+impl RpcCall {
+    /// 한 줄로 표시 ("SQL -- @p1=값, @p2=값", SQL 본문이 SQL 문장이 아니면 "|"로 나열)
+    fn format(&self) -> Option<String> {
+        let parts: Vec<String> = self
+            .statement
+            .iter()
+            .cloned()
+            .chain(
+                self.params
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value)),
+            )
+            .collect();
+        if parts.is_empty() {
+            return None;
+        }
+
+        // @stmt가 있으면 그것을 메인으로, 나머지는 파라미터로
+        // 파라미터가 하나도 없으면 그대로 반환 (단일 문장)
+        let result = if parts.len() > 1 && TdsParser::starts_with_sql_keyword(&parts[0]) {
+            format!("{} -- {}", parts[0], parts[1..].join(", "))
+        } else {
+            parts.join(" | ")
+        };
+        Some(result)
+    }
+}
+
 /// TDS 파서
 pub struct TdsParser;
 
@@ -164,6 +364,13 @@ impl TdsParser {
     /// TDS 패킷 데이터를 UTF-16LE로 디코딩
     /// TDS 프로토콜은 문자열을 UTF-16LE 인코딩으로 전송
     pub fn decode_utf16le(bytes: &[u8]) -> Option<String> {
+        Self::decode_text(bytes, false).map(|(text, _)| text)
+    }
+
+    /// TDS 패킷 데이터를 텍스트로 디코딩하고 사용한 인코딩 반환
+    /// detect_encoding이면 UTF-16LE 결과가 깨졌을 때 같은 바이트를 UTF-8 (실패하면 windows-1252)로도
+    /// 디코딩해 자연스러운 문자 비율이 더 높은 쪽을 선택
+    pub fn decode_text(bytes: &[u8], detect_encoding: bool) -> Option<(String, TextEncoding)> {
         if bytes.is_empty() {
             return None;
         }
@@ -175,21 +382,8 @@ impl TdsParser {
             match PacketHeader::decode(&mut buf) {
                 Ok(header) => {
                     // SQLBatch 패킷의 경우 AllHeaders 섹션 건너뛰기
-                    if header.packet_type == PacketType::SqlBatch && bytes.len() >= 12 {
-                        // AllHeaders TotalLength를 동적으로 읽기
-                        let all_headers_total =
-                            u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize;
-
-                        // AllHeaders가 있는 경우: 헤더(8) + AllHeaders TotalLength
-                        if all_headers_total > 0
-                            && all_headers_total <= 65535
-                            && bytes.len() >= 8 + all_headers_total
-                        {
-                            &bytes[8 + all_headers_total..]
-                        } else {
-                            // AllHeaders가 없거나 잘못된 경우: 헤더 바로 다음
-                            &bytes[8..]
-                        }
+                    if header.packet_type == PacketType::SqlBatch {
+                        Self::skip_all_headers(&bytes[8..])
                     } else {
                         &bytes[8..]
                     }
@@ -200,28 +394,25 @@ impl TdsParser {
             bytes
         };
 
+        Self::decode_plain_text(data, detect_encoding)
+    }
+
+    /// TDS 헤더가 없는 텍스트 바이트 디코딩 (결과 검증 포함)
+    fn decode_plain_text(data: &[u8], detect_encoding: bool) -> Option<(String, TextEncoding)> {
         if data.is_empty() {
             return None;
         }
 
-        // UTF-16은 2바이트 단위이므로 홀수 길이 처리
-        let data = if data.len() % 2 != 0 {
-            &data[..data.len() - 1]
+        let (result, encoding) = if detect_encoding {
+            Self::decode_detected(data)
         } else {
-            data
+            (Self::decode_utf16le_bytes(data), TextEncoding::Utf16Le)
         };
-
-        if data.is_empty() {
-            return None;
-        }
-
-        // UTF-16LE 디코딩
-        let (decoded, _, _had_errors) = UTF_16LE.decode(data);
-        let result = decoded.into_owned();
+        let result = to_nfc(Self::strip_leading_noise(&result)).into_owned();
 
         // 결과 검증: 너무 짧거나 제어 문자가 너무 많으면 무시
         let trimmed = result.trim();
-        if trimmed.len() < 3 {
+        if Self::is_too_short(trimmed) {
             return None;
         }
 
@@ -235,7 +426,92 @@ impl TdsParser {
             return None; // 제어 문자가 50% 이상이면 무시
         }
 
-        Some(result)
+        Some((result, encoding))
+    }
+
+    /// 요청으로 보기에 너무 짧은 텍스트인지 (공백을 뺀 텍스트의 문자 수 기준, 바이트 수가 아님)
+    /// 한글(UTF-8 3바이트)이나 이모지(4바이트)도 한 글자로 셈
+    pub fn is_too_short(trimmed: &str) -> bool {
+        trimmed.chars().count() < MIN_SQL_CHARS
+    }
+
+    /// SQL Batch / RPC 메시지 본문 앞의 ALL_HEADERS (TDS 7.2+) 건너뛰기
+    /// 첫 4바이트가 ALL_HEADERS 전체 길이이며, 값이 0이거나 본문보다 길면 ALL_HEADERS가 없는 것으로 봄
+    fn skip_all_headers(body: &[u8]) -> &[u8] {
+        let Some(total) = ByteReader::new(body).read_u32_le() else {
+            return body;
+        };
+        let total = total as usize;
+        if total > 0 && total <= 65535 && total <= body.len() {
+            &body[total..]
+        } else {
+            body
+        }
+    }
+
+    /// 텍스트 앞의 BOM(U+FEFF)과 NUL 등 제어 문자 제거 (공백과 줄바꿈은 유지)
+    /// BOM을 붙여 보내는 드라이버나 AllHeaders 뒤에 남은 NUL 때문에 SQL 키워드로 시작하지 않는 것을 방지
+    fn strip_leading_noise(text: &str) -> &str {
+        text.trim_start_matches(|c: char| c == '\u{FEFF}' || (c.is_control() && !c.is_whitespace()))
+    }
+
+    /// UTF-16LE 디코딩 (홀수 길이면 마지막 바이트 무시, 앞의 BOM 제거)
+    fn decode_utf16le_bytes(data: &[u8]) -> String {
+        let even_len = data.len() - data.len() % 2;
+        decode_utf16le(&data[..even_len]).0.into_owned()
+    }
+
+    /// UTF-16LE를 먼저 시도하고, 오류가 있거나 자연스럽지 않으면 단일 바이트 인코딩과 비교
+    fn decode_detected(data: &[u8]) -> (String, TextEncoding) {
+        let even_len = data.len() - data.len() % 2;
+        let (decoded, had_errors) = decode_utf16le(&data[..even_len]);
+        let utf16_ratio = Self::plausible_ratio(&decoded);
+        if !had_errors && utf16_ratio >= PLAUSIBLE_TEXT_RATIO {
+            return (decoded.into_owned(), TextEncoding::Utf16Le);
+        }
+
+        let (single_byte, encoding) = match std::str::from_utf8(data) {
+            Ok(text) => (text.to_string(), TextEncoding::Utf8),
+            Err(_) => (
+                WINDOWS_1252
+                    .decode_without_bom_handling(data)
+                    .0
+                    .into_owned(),
+                TextEncoding::Windows1252,
+            ),
+        };
+
+        if Self::plausible_ratio(&single_byte) > utf16_ratio {
+            (single_byte, encoding)
+        } else {
+            (decoded.into_owned(), TextEncoding::Utf16Le)
+        }
+    }
+
+    /// SQL 텍스트로 자연스러운 문자 비율 (ASCII, 공백, 라틴 문자, 한글, 이모지)
+    /// 단일 바이트 텍스트를 UTF-16LE로 읽으면 ASCII 두 글자가 한자 한 글자처럼 되므로 한자는 제외
+    /// 이모지(U+1F000 이상)는 UTF-16LE의 서로게이트 쌍으로만 나오므로 단일 바이트 텍스트를 잘못 읽어 생기지 않음
+    /// 문자 수는 유니코드 스칼라 값 기준 (서로게이트 쌍은 한 문자)
+    fn plausible_ratio(text: &str) -> f64 {
+        let total = text.chars().count();
+        if total == 0 {
+            return 0.0;
+        }
+
+        let plausible = text
+            .chars()
+            .filter(|&c| {
+                c.is_ascii_graphic()
+                    || c.is_whitespace()
+                    || ('\u{00A0}'..='\u{024F}').contains(&c)
+                    || ('\u{1100}'..='\u{11FF}').contains(&c)
+                    || ('\u{3130}'..='\u{318F}').contains(&c)
+                    || ('\u{AC00}'..='\u{D7A3}').contains(&c)
+                    || ('\u{1F000}'..='\u{1FAFF}').contains(&c)
+                    || c == '\u{FE0F}'
+            })
+            .count();
+        plausible as f64 / total as f64
     }
 
     /// ============================================
@@ -245,6 +521,15 @@ impl TdsParser {
     /// 단일 패킷 처리 (하위 호환성)
     /// 첫 번째 바이트가 0x01 (SQL Batch) 또는 0x03 (RPC)인 패킷만 처리
     pub fn decode_tds_packet(data: &[u8]) -> Option<String> {
+        Self::decode_tds_packet_with_encoding(data, true).map(|(text, _)| text)
+    }
+
+    /// decode_tds_packet과 같지만 텍스트 인코딩도 반환 (RPC는 항상 UTF-16LE)
+    /// detect_encoding이 false면 SQL Batch를 항상 UTF-16LE로 디코딩
+    pub fn decode_tds_packet_with_encoding(
+        data: &[u8],
+        detect_encoding: bool,
+    ) -> Option<(String, TextEncoding)> {
         // 1단계: TDS 패킷인지 확인
         // 첫 번째 바이트가 0x01 (SQL Batch) 또는 0x03 (RPC)인 패킷만 처리
         if !Self::looks_like_tds(data) {
@@ -257,381 +542,838 @@ impl TdsParser {
         // 3단계: 패킷 타입에 따라 다른 파싱 로직 적용
         match header.packet_type {
             TdsPacketType::RpcRequest => {
-                // RPC 타입은 바이너리 프로토콜로 파싱
-                Self::parse_rpc_packet(data)
+                // RPC 타입은 바이너리 프로토콜로 파싱 (패킷이 완전해야 함)
+                let body = data.get(8..header.length as usize)?;
+                Self::decode_message(header.packet_type, body, detect_encoding)
             }
             _ => {
                 // SQLBatch 등은 기존 로직 사용
                 let payload = Self::extract_payload(data)?;
-                Self::decode_utf16le(payload)
+                Self::decode_text(payload, detect_encoding)
             }
         }
     }
 
+    /// looks_like_tds 확인 없이 호출한 쪽이 알려준 패킷 타입으로 디코딩 (TDS 헤더 포함 패킷)
+    /// Wireshark 등에서 이미 타입을 확인한 패킷을 테스트하거나 hex 디코딩할 때 사용
+    /// 헤더의 타입 바이트는 무시하며, 헤더의 길이가 8 ~ 실제 바이트 수 범위를 벗어나면 끝까지 본문으로 봄
+    pub fn decode_tds_packet_unchecked(data: &[u8], packet_type: TdsPacketType) -> Option<String> {
+        let body = data.get(8..)?;
+        let length = ByteReader::new(data.get(2..4)?).read_u16_be()? as usize;
+        let body = if (8..=data.len()).contains(&length) {
+            &body[..length - 8]
+        } else {
+            body
+        };
+        Self::decode_message(packet_type, body, true).map(|(text, _)| text)
+    }
+
     /// ============================================
-    /// RPC 패킷 파싱 (0x03)
+    /// 메시지 본문 디코딩
     /// ============================================
-    /// RPCRequest 패킷을 바이너리 구조로 파싱하여 SQL 쿼리 추출
-    /// TDS 7.2+ 기준, sp_executesql 패턴 지원
-    fn parse_rpc_packet(data: &[u8]) -> Option<String> {
-        if data.len() < 8 {
-            return None;
-        }
-
-        let packet_length = u16::from_be_bytes([data[2], data[3]]) as usize;
-        if data.len() < packet_length {
-            return None;
+    /// 패킷 타입과 TDS 헤더를 뗀 메시지 본문으로 디코딩
+    /// 여러 패킷으로 나뉜 메시지는 각 패킷의 본문을 이어 붙여서 전달 (TdsStreamDecoder 참고)
+    /// SQL Batch는 AllHeaders 다음의 텍스트, RPC는 파라미터를 파싱한 결과 (항상 UTF-16LE)
+    pub fn decode_message(
+        packet_type: TdsPacketType,
+        body: &[u8],
+        detect_encoding: bool,
+    ) -> Option<(String, TextEncoding)> {
+        match packet_type {
+            TdsPacketType::RpcRequest => {
+                Self::parse_rpc_body(body).map(|text| (text, TextEncoding::Utf16Le))
+            }
+            TdsPacketType::SqlBatch => {
+                Self::decode_plain_text(Self::skip_all_headers(body), detect_encoding)
+            }
+            _ => None,
         }
+    }
 
-        let mut pos = 8; // TDS 헤더 건너뛰기
+    /// RPC 메시지 본문에서 OptionFlags 위치로 옮긴 커서 (첫 번째 요청)
+    /// ALL_HEADERS → ProcID / ProcName 다음
+    fn rpc_option_flags_reader(body: &[u8]) -> Option<ByteReader<'_>> {
+        let mut reader = ByteReader::new(Self::skip_all_headers(body));
+        Self::read_rpc_proc(&mut reader)?;
+        Some(reader)
+    }
 
-        // ALL_HEADERS 건너뛰기 (TDS 7.2+)
-        if pos + 4 <= data.len() {
-            let total_header_len =
-                u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
-                    as usize;
+    /// RPC 요청의 ProcID / ProcName 읽기
+    /// 반환값: 프로시저 이름 (ProcID는 알려진 시스템 프로시저 이름, 모르는 ID면 "ProcID n")
+    fn read_rpc_proc(reader: &mut ByteReader) -> Option<String> {
+        // ProcID vs ProcName 파싱
+        let mut peek = *reader;
+        let proc_id_marker = peek.read_u16_le()?;
 
-            if total_header_len > 0
-                && total_header_len <= 65535
-                && pos + total_header_len <= data.len()
-            {
-                pos += total_header_len;
-            }
+        if proc_id_marker == 0xFFFF {
+            // ProcID 사용 (0x000A는 sp_executesql)
+            *reader = peek;
+            let proc_id = reader.read_u16_le()?;
+            Some(match RPC_PROC_NAMES.get(usize::from(proc_id)) {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => format!("ProcID {}", proc_id),
+            })
+        } else {
+            // ProcName 사용 (1바이트 문자 수 + UTF-16LE 문자열)
+            let name = reader.read_b_varchar()?;
+            debug!("RPC ProcName: {}", name);
+            Some(name)
         }
+    }
 
-        // ProcID vs ProcName 파싱
-        if pos + 2 > data.len() {
+    /// ============================================
+    /// RPC OptionFlags
+    /// ============================================
+    /// RPC 패킷(0x03)의 OptionFlags 값 (RPC가 아니면 None)
+    /// 0x01: WITH RECOMPILE, 0x02: NO METADATA, 0x04: REUSE METADATA
+    pub fn rpc_option_flags(data: &[u8]) -> Option<u16> {
+        if data.first() != Some(&0x03) {
             return None;
         }
 
-        let proc_id_marker = u16::from_le_bytes([data[pos], data[pos + 1]]);
-        pos += 2;
+        Self::rpc_option_flags_reader(data.get(8..)?)?.read_u16_le()
+    }
 
-        if proc_id_marker == 0xFFFF {
-            // ProcID 사용
-            if pos + 2 > data.len() {
-                return None;
-            }
-            let _proc_id = u16::from_le_bytes([data[pos], data[pos + 1]]);
-            pos += 2;
-            // proc_id == 0x000A는 sp_executesql
+    /// RPC OptionFlags를 읽기 쉬운 문자열로 변환 (예: "WITH RECOMPILE")
+    pub fn describe_rpc_flags(flags: u16) -> String {
+        let names: Vec<&str> = [
+            (0x01, "WITH RECOMPILE"),
+            (0x02, "NO METADATA"),
+            (0x04, "REUSE METADATA"),
+        ]
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+
+        if names.is_empty() {
+            "없음".to_string()
         } else {
-            // ProcName 사용 (UTF-16LE 문자열)
-            pos -= 2; // marker를 다시 읽어야 함
-            if pos >= data.len() {
-                return None;
-            }
-            let name_len = data[pos] as usize;
-            pos += 1;
-
-            if pos + name_len * 2 > data.len() {
-                return None;
-            }
-
-            let name_bytes = &data[pos..pos + name_len * 2];
-            let (name, _, _) = UTF_16LE.decode(name_bytes);
-            debug!("RPC ProcName: {}", name);
-            pos += name_len * 2;
+            names.join(", ")
         }
+    }
 
-        // OptionFlags 건너뛰기 (2 bytes)
-        if pos + 2 > data.len() {
+    /// ============================================
+    /// RPC 패킷 파싱 (0x03)
+    /// ============================================
+    /// RPCRequest 메시지 본문(TDS 헤더 제외)을 바이너리 구조로 파싱하여 SQL 쿼리 추출
+    /// TDS 7.2+ 기준, sp_executesql 패턴 지원
+    /// 한 메시지에 배치 플래그로 구분된 여러 RPC 요청이 있으면 요청마다 한 줄로 모두 반환
+    fn parse_rpc_body(body: &[u8]) -> Option<String> {
+        let requests: Vec<String> = Self::parse_rpc_calls(body)
+            .iter()
+            .filter_map(RpcCall::format)
+            .collect();
+
+        if requests.is_empty() {
             return None;
         }
-        let _option_flags = u16::from_le_bytes([data[pos], data[pos + 1]]);
-        pos += 2;
+        Some(to_nfc(&requests.join("\n")).into_owned())
+    }
 
-        // 파라미터 반복 파싱
-        let mut sql_parts = Vec::new();
+    /// 여러 패킷으로 이루어진 메시지(헤더를 포함한 패킷들을 이어 붙인 바이트)를 패킷별로 나눔
+    /// 헤더 길이가 잘못되었거나 끝의 패킷이 잘렸으면 남은 바이트 전체를 마지막 패킷으로 봄
+    /// (이벤트의 raw_data는 EOM 패킷까지 모은 메시지 전체)
+    pub fn message_packets(message: &[u8]) -> Vec<&[u8]> {
+        let mut packets = Vec::new();
+        let mut rest = message;
+        while let Some(header) = Self::parse_header(rest) {
+            let length = (header.length as usize).clamp(8, rest.len());
+            packets.push(&rest[..length]);
+            rest = &rest[length..];
+        }
+        packets
+    }
 
-        while pos < packet_length && pos < data.len() {
-            // ParamName 파싱
-            if pos >= data.len() {
-                break;
-            }
-            let param_name_len = data[pos] as usize;
-            pos += 1;
+    /// RPC 메시지(헤더를 포함한 패킷들을 이어 붙인 바이트)의 RPC 요청들
+    /// SQL 본문과 파라미터를 따로 다룰 때 사용 (문자열은 NFC로 정규화)
+    pub fn rpc_calls(message: &[u8]) -> Vec<RpcCall> {
+        // 각 패킷의 헤더를 떼고 본문만 이어 붙임
+        let body: Vec<u8> = Self::message_packets(message)
+            .into_iter()
+            .take_while(|packet| packet[0] == 0x03)
+            .flat_map(|packet| &packet[8..])
+            .copied()
+            .collect();
+
+        let normalize = |text: &str| to_nfc(text).into_owned();
+        Self::parse_rpc_calls(&body)
+            .into_iter()
+            .map(|call| RpcCall {
+                procedure: call.procedure.as_deref().map(normalize),
+                statement: call.statement.as_deref().map(normalize),
+                params: call
+                    .params
+                    .iter()
+                    .map(|(name, value)| (normalize(name), normalize(value)))
+                    .collect(),
+            })
+            .collect()
+    }
 
-            if pos + param_name_len * 2 > data.len() {
+    /// RPCRequest 메시지 본문(TDS 헤더 제외)의 RPC 요청들 (배치 플래그로 이어진 요청 포함)
+    fn parse_rpc_calls(body: &[u8]) -> Vec<RpcCall> {
+        let mut calls = Vec::new();
+        let mut reader = ByteReader::new(Self::skip_all_headers(body));
+
+        // 요청마다 ProcID / ProcName (배치 플래그 다음 요청도 같음)
+        while let Some(procedure) = Self::read_rpc_proc(&mut reader) {
+            // OptionFlags 건너뛰기 (2 bytes)
+            if reader.skip(2).is_none() {
                 break;
             }
 
-            let param_name_bytes = &data[pos..pos + param_name_len * 2];
-            let (param_name, _, _) = UTF_16LE.decode(param_name_bytes);
-            pos += param_name_len * 2;
+            // 파라미터 반복 파싱 (데이터가 모자라면 그때까지 읽은 파라미터만 사용)
+            let (mut call, more) = Self::parse_rpc_params(&mut reader);
+            call.procedure = Some(procedure);
+            calls.push(call);
 
-            // StatusFlags 건너뛰기 (1 byte)
-            if pos >= data.len() {
+            if !more {
                 break;
             }
-            let _status_flags = data[pos];
-            pos += 1;
+        }
 
-            // TYPE_INFO 파싱
-            if pos >= data.len() {
-                break;
-            }
-            let type_id = data[pos];
-            pos += 1;
-
-            // 타입별 추가 바이트 처리 및 데이터 파싱
-            match type_id {
-                0xE7 => {
-                    // NVARCHAR: maxLen(2) + collation(5)
-                    if pos + 7 > data.len() {
-                        break;
-                    }
-                    let _max_len = u16::from_le_bytes([data[pos], data[pos + 1]]);
-                    pos += 7; // 2 + 5
+        calls
+    }
 
-                    // DataLength + Data 파싱
-                    if pos + 2 > data.len() {
-                        break;
-                    }
-                    let data_len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
-                    pos += 2;
+    /// RPC 요청 하나의 파라미터 목록 파싱 (@stmt는 SQL 본문, 나머지는 (이름, 값))
+    /// 반환값: (요청, 배치 플래그를 만나 다음 요청이 이어지는지)
+    fn parse_rpc_params(reader: &mut ByteReader) -> (RpcCall, bool) {
+        let mut call = RpcCall::default();
+
+        while !reader.is_empty() {
+            // 배치 플래그: 다음 RPC 요청 시작
+            let mut peek = *reader;
+            if matches!(
+                peek.read_u8(),
+                Some(RPC_BATCH_FLAG | RPC_NO_EXEC_FLAG | RPC_BATCH_FLAG_LEGACY)
+            ) {
+                *reader = peek;
+                return (call, true);
+            }
 
-                    if data_len == 0xFFFF {
-                        // NULL
-                        continue;
-                    }
+            // ParamName 파싱
+            let Some(param_name) = reader.read_b_varchar() else {
+                break;
+            };
 
-                    if pos + data_len > data.len() {
-                        break;
-                    }
+            // StatusFlags 건너뛰기 (1 byte)
+            let Some(_status_flags) = reader.read_u8() else {
+                break;
+            };
 
-                    let data_bytes = &data[pos..pos + data_len];
-                    pos += data_len;
-
-                    // NVARCHAR는 UTF-16LE로 디코딩
-                    if data_bytes.len().is_multiple_of(2) {
-                        let (decoded, _, _) = UTF_16LE.decode(data_bytes);
-                        let trimmed = decoded.trim();
-                        if !trimmed.is_empty() {
-                            // @stmt 파라미터는 SQL 쿼리 본문
-                            if param_name == "@stmt" || param_name == "@statement" {
-                                sql_parts.insert(0, trimmed.to_string());
-                            } else {
-                                sql_parts.push(format!("{}={}", param_name, trimmed));
-                            }
+            // TYPE_INFO + 값 (응답의 COLMETADATA / ROW와 같은 형식)
+            let Some(type_info) = response::parse_type_info(reader) else {
+                break;
+            };
+            let Some(value) = response::read_value(reader, &type_info) else {
+                break;
+            };
+            let Some(data_bytes) = value else {
+                // NULL
+                continue;
+            };
+            let data_bytes = data_bytes.as_slice();
+
+            match type_info.type_id {
+                0xE7 | 0xEF if data_bytes.len().is_multiple_of(2) => {
+                    // NVARCHAR / NCHAR는 UTF-16LE로 디코딩
+                    let (decoded, _) = decode_utf16le(data_bytes);
+                    let trimmed = decoded.trim();
+                    if !trimmed.is_empty() {
+                        // @stmt 파라미터는 SQL 쿼리 본문
+                        let is_statement = param_name.eq_ignore_ascii_case("@stmt")
+                            || param_name.eq_ignore_ascii_case("@statement");
+                        if is_statement && call.statement.is_none() {
+                            call.statement = Some(trimmed.to_string());
+                        } else {
+                            call.params.push((param_name, trimmed.to_string()));
                         }
                     }
                 }
-                0xA7 => {
-                    // VARCHAR: maxLen(2) + collation(5)
-                    if pos + 7 > data.len() {
-                        break;
-                    }
-                    let _max_len = u16::from_le_bytes([data[pos], data[pos + 1]]);
-                    let _collation = &data[pos + 2..pos + 7];
-                    pos += 7;
-
-                    // DataLength + Data 파싱
-                    if pos + 2 > data.len() {
-                        break;
-                    }
-                    let data_len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
-                    pos += 2;
-
-                    if data_len == 0xFFFF {
-                        // NULL
-                        continue;
-                    }
-
-                    if pos + data_len > data.len() {
-                        break;
-                    }
-
-                    let data_bytes = &data[pos..pos + data_len];
-                    pos += data_len;
-
+                0xA7 | 0xAF => {
                     // VARCHAR는 코드페이지로 디코딩 (일반적으로 CP949)
                     // 간단하게 Latin1 또는 UTF-8로 시도
                     if let Ok(decoded) = String::from_utf8(data_bytes.to_vec()) {
                         if !decoded.trim().is_empty() {
-                            sql_parts.push(format!("{}={}", param_name, decoded));
+                            call.params.push((param_name, decoded));
                         }
                     }
                 }
-                0x26 => {
-                    // INT: 추가 바이트 없음
-                    // DataLength + Data 파싱
-                    if pos + 2 > data.len() {
-                        break;
-                    }
-                    let data_len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
-                    pos += 2;
-
-                    if data_len == 0xFFFF {
-                        // NULL
-                        continue;
-                    }
-
-                    if pos + data_len > data.len() {
-                        break;
+                0x26 | 0x30 | 0x34 | 0x38 | 0x7F => {
+                    // INTN / TINYINT / SMALLINT / INT / BIGINT: 길이로 크기 구분
+                    let int_val = match *data_bytes {
+                        [b] => Some(b as i64),
+                        [b0, b1] => Some(i16::from_le_bytes([b0, b1]) as i64),
+                        [b0, b1, b2, b3] => Some(i32::from_le_bytes([b0, b1, b2, b3]) as i64),
+                        _ => data_bytes.try_into().ok().map(i64::from_le_bytes),
+                    };
+                    if let Some(int_val) = int_val {
+                        call.params.push((param_name, int_val.to_string()));
                     }
-
-                    if data_len == 4 {
-                        let int_val = i32::from_le_bytes([
-                            data[pos],
-                            data[pos + 1],
-                            data[pos + 2],
-                            data[pos + 3],
-                        ]);
-                        sql_parts.push(format!("{}={}", param_name, int_val));
-                    }
-                    pos += data_len;
                 }
-                0x6A => {
-                    // FLOAT: 추가 바이트 없음
-                    // DataLength + Data 파싱
-                    if pos + 2 > data.len() {
-                        break;
-                    }
-                    let data_len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
-                    pos += 2;
-
-                    if data_len == 0xFFFF {
-                        // NULL
-                        continue;
+                0x6D | 0x3B | 0x3E => {
+                    // FLTN / REAL / FLOAT: 4바이트는 real, 8바이트는 float
+                    let float_val = match *data_bytes {
+                        [b0, b1, b2, b3] => Some(f32::from_le_bytes([b0, b1, b2, b3]) as f64),
+                        _ => ByteReader::new(data_bytes)
+                            .read_f64_le()
+                            .filter(|_| data_bytes.len() == 8),
+                    };
+                    if let Some(float_val) = float_val {
+                        call.params.push((param_name, float_val.to_string()));
                     }
-
-                    if pos + data_len > data.len() {
-                        break;
-                    }
-
-                    if data_len == 8 {
-                        let float_val = f64::from_le_bytes([
-                            data[pos],
-                            data[pos + 1],
-                            data[pos + 2],
-                            data[pos + 3],
-                            data[pos + 4],
-                            data[pos + 5],
-                            data[pos + 6],
-                            data[pos + 7],
-                        ]);
-                        sql_parts.push(format!("{}={}", param_name, float_val));
+                }
+                0x6F | 0x3A | 0x3D => {
+                    // DATETIMN / SMALLDATETIME / DATETIME: ISO 형식으로 표시
+                    if let Some(datetime) = Self::decode_legacy_datetime(data_bytes) {
+                        call.params.push((param_name, datetime));
                     }
-                    pos += data_len;
                 }
                 _ => {
-                    // 알 수 없는 타입: DataLength만 읽고 건너뛰기
-                    if pos + 2 > data.len() {
-                        break;
-                    }
-                    let data_len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
-                    pos += 2;
-
-                    if data_len == 0xFFFF {
-                        // NULL
-                        continue;
-                    }
-
-                    if pos + data_len > data.len() {
-                        break;
-                    }
-                    pos += data_len;
+                    // 표시하지 않는 타입 (또는 홀수 길이 NVARCHAR): 데이터는 이미 건너뜀
                 }
             }
         }
 
-        // SQL 쿼리 조합
-        if sql_parts.is_empty() {
-            return None;
-        }
+        (call, false)
+    }
 
-        // @stmt가 있으면 그것을 메인으로, 나머지는 파라미터로
-        let result = if sql_parts.len() > 1 && sql_parts[0].starts_with("SELECT")
-            || sql_parts[0].starts_with("INSERT")
-            || sql_parts[0].starts_with("UPDATE")
-            || sql_parts[0].starts_with("DELETE")
-            || sql_parts[0].starts_with("EXEC")
-        {
-            format!("{} -- {}", sql_parts[0], sql_parts[1..].join(", "))
-        } else {
-            sql_parts.join(" | ")
-        };
+    /// datetime (8바이트) / smalldatetime (4바이트) 값을 ISO 형식 문자열로 변환
+    /// - datetime: 1900-01-01부터의 일 수(i32) + 자정부터의 1/300초 단위 틱(u32), 밀리초로 반올림
+    /// - smalldatetime: 1900-01-01부터의 일 수(u16) + 자정부터의 분(u16)
+    fn decode_legacy_datetime(bytes: &[u8]) -> Option<String> {
+        let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1)?.and_hms_opt(0, 0, 0)?;
+        let mut reader = ByteReader::new(bytes);
+
+        match bytes.len() {
+            8 => {
+                let days = reader.read_i32_le()?;
+                let ticks = reader.read_u32_le()?;
+                // 1틱 = 10/3 ms (.000, .003, .007로 표시되는 정밀도)
+                let millis = (ticks as i64 * 10 + 1) / 3;
+                let datetime = base
+                    .checked_add_signed(chrono::Duration::days(days as i64))?
+                    .checked_add_signed(chrono::Duration::milliseconds(millis))?;
+                Some(datetime.format("%Y-%m-%dT%H:%M:%S%.3f").to_string())
+            }
+            4 => {
+                let days = reader.read_u16_le()?;
+                let minutes = reader.read_u16_le()?;
+                let datetime = base
+                    .checked_add_signed(chrono::Duration::days(days as i64))?
+                    .checked_add_signed(chrono::Duration::minutes(minutes as i64))?;
+                Some(datetime.format("%Y-%m-%dT%H:%M:%S").to_string())
+            }
+            _ => None,
+        }
+    }
 
-        Some(result)
+    /// SQL 문장이 SELECT / INSERT / UPDATE / DELETE / EXEC 키워드로 시작하는지 확인
+    /// 대소문자 구분 없음 (ORM이 소문자로 보내는 경우)
+    /// 앞쪽 주석은 무시 (예: "/* hint */ SELECT ...")
+    fn starts_with_sql_keyword(statement: &str) -> bool {
+        let upper = strip_leading_comments(statement).to_uppercase();
+        ["SELECT", "INSERT", "UPDATE", "DELETE", "EXEC"]
+            .iter()
+            .any(|keyword| upper.starts_with(keyword))
     }
 
     /// ============================================
     /// 6단계: 여러 TDS 패킷 프레이밍 및 디코딩
     /// ============================================
     /// 재조립된 TCP 스트림에서 여러 TDS 패킷이 연속으로 붙어있을 수 있음
-    /// 각 패킷을 프레이밍하여 개별적으로 처리
-    pub fn decode_tds_packets(data: &[u8]) -> Vec<String> {
-        let (decoded, _) = Self::decode_tds_packets_with_raw(data);
-        decoded
+    /// 각 패킷을 프레이밍하여 개별적으로 처리하고 원본 패킷 데이터도 반환
+    /// 첫 번째 바이트가 0x01 (SQL Batch) 또는 0x03 (RPC)인 패킷만 처리 (IGNORE 비트가 있는 패킷 제외)
+    /// 디코딩에 실패한 SQL Batch / RPC 메시지는 텍스트 없이 원본만 포함 (진단 모드에서 확인)
+    /// 여러 패킷으로 나뉜 메시지는 하나로 모아 디코딩하고 원본도 이어 붙인 패킷들 (TdsStreamDecoder 참고)
+    pub fn decode_tds_packets_with_encoding(
+        data: &[u8],
+        detect_encoding: bool,
+    ) -> Vec<DecodedMessage> {
+        TdsStreamDecoder::new(detect_encoding).push(data)
+    }
+
+    /// decode_tds_packets_with_encoding에서 디코딩한 메시지의 텍스트와 원본만 반환
+    pub fn decode_tds_packets_with_raw(data: &[u8]) -> (Vec<String>, Vec<Vec<u8>>) {
+        Self::decode_tds_packets_with_encoding(data, true)
+            .into_iter()
+            .filter_map(|(decoded, raw)| decoded.map(|(text, _)| (text, raw)))
+            .unzip()
+    }
+
+    /// 스트림 끝의 완전하지 않은 SQL Batch / RPC 메시지를 있는 바이트만으로 디코딩
+    /// 연결이 끝나거나 캡처를 중지해 나머지 바이트가 더 오지 않을 때 사용
+    /// 헤더의 길이를 실제 바이트 수로 고친 패킷을 디코딩하며, 반환하는 원본 패킷도 고친 패킷
+    /// 반환값: 끝에 잘린 메시지가 없으면 None
+    pub fn decode_truncated_packet(data: &[u8], detect_encoding: bool) -> Option<DecodedMessage> {
+        let mut decoder = TdsStreamDecoder::new(detect_encoding);
+        decoder.push(data);
+        decoder.finish()
     }
 
     /// ============================================
-    /// 6-2단계: 여러 TDS 패킷 프레이밍 및 디코딩 (원본 데이터 포함)
+    /// TDS 패킷 프레이밍 (디코딩 없음)
     /// ============================================
-    /// 재조립된 TCP 스트림에서 여러 TDS 패킷이 연속으로 붙어있을 수 있음
-    /// 각 패킷을 프레이밍하여 개별적으로 처리하고 원본 패킷 데이터도 반환
-    /// 첫 번째 바이트가 0x01 (SQL Batch) 또는 0x03 (RPC)인 패킷만 처리
-    pub fn decode_tds_packets_with_raw(data: &[u8]) -> (Vec<String>, Vec<Vec<u8>>) {
-        let mut decoded_results = Vec::new();
-        let mut raw_results = Vec::new();
+    /// 패킷 타입과 관계없이 완전한 TDS 패킷의 원본 바이트만 잘라서 반환
+    /// raw 모드(프로토콜 디버깅용)에서 사용
+    pub fn frame_tds_packets(data: &[u8]) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
         let mut buf = data;
 
-        // 프레이밍 루프: 버퍼에 패킷이 있는 동안 반복
         while buf.len() >= 8 {
-            // 0단계: 첫 번째 바이트가 0x01 (SQL Batch) 또는 0x03 (RPC)인지 확인
-            let packet_type_byte = buf[0];
-            if packet_type_byte != 0x01 && packet_type_byte != 0x03 {
-                // SQL 추출에 필요한 패킷 타입이 아니면 건너뛰기
-                // 다음 패킷을 찾기 위해 1바이트씩 이동
-                buf = &buf[1..];
-                continue;
-            }
-
-            // 1단계: 헤더 파싱
-            let mut header_buf = &buf[..8];
-            let header = match PacketHeader::decode(&mut header_buf) {
-                Ok(h) => h,
-                Err(_) => {
+            let header = match Self::parse_header(buf) {
+                Some(h) => h,
+                None => {
                     // 유효한 헤더가 아니면 1바이트씩 이동하여 다음 패킷 찾기
                     buf = &buf[1..];
                     continue;
                 }
             };
 
-            // 1-2단계: 패킷 타입 확인 (SQL Batch 또는 RPC만 처리)
-            if !matches!(header.packet_type, PacketType::SqlBatch | PacketType::Rpc) {
-                // SQL 추출에 필요한 패킷 타입이 아니면 건너뛰기
-                let packet_length = header.length as usize;
-                if buf.len() < packet_length {
-                    break;
-                }
-                buf = &buf[packet_length..];
+            let packet_length = header.length as usize;
+            if packet_length < 8 {
+                buf = &buf[1..];
                 continue;
             }
 
-            let packet_length = header.length as usize;
-
-            // 2단계: 패킷이 완전한지 확인
             if buf.len() < packet_length {
                 // 패킷이 완전하지 않음 (더 기다려야 함)
                 break;
             }
 
-            // 3단계: 단일 패킷 추출
-            let packet = &buf[..packet_length];
-            let packet_bytes = packet.to_vec(); // 원본 패킷 복사
+            packets.push(buf[..packet_length].to_vec());
+            buf = &buf[packet_length..];
+        }
 
-            // 4단계: 패킷 디코딩
-            if let Some(decoded) = Self::decode_tds_packet(packet) {
-                decoded_results.push(decoded);
-                raw_results.push(packet_bytes);
-            }
+        packets
+    }
 
-            // 5단계: 다음 패킷으로 이동
+    /// ============================================
+    /// 로그인 단계 패킷 건너뛰기
+    /// ============================================
+    /// 스트림 앞쪽의 완전한 PRELOGIN(0x12) / LOGIN7(0x10) / SSPI(0x11) 패킷을 건너뛴 나머지
+    /// 연결 시작부터 캡처한 스트림도 SQL Batch / RPC부터 처리할 수 있게 함
+    pub fn skip_login_packets(data: &[u8]) -> &[u8] {
+        let mut buf = data;
+        while let Some(&packet_type) = buf.first() {
+            if !matches!(packet_type, 0x10..=0x12) {
+                break;
+            }
+            let Some(header) = Self::parse_header(buf) else {
+                break;
+            };
+            let packet_length = header.length as usize;
+            if packet_length < 8 || buf.len() < packet_length {
+                break;
+            }
             buf = &buf[packet_length..];
         }
+        buf
+    }
+
+    /// ============================================
+    /// LOGIN7 찾기
+    /// ============================================
+    /// 클라이언트 스트림에서 첫 LOGIN7 패킷을 찾아 파싱
+    /// 연결 시작부터 캡처했고 로그인이 암호화되지 않은 경우에만 찾을 수 있음
+    pub fn find_login7(data: &[u8]) -> Option<Login7> {
+        Self::frame_tds_packets(data)
+            .iter()
+            .find(|packet| packet.first() == Some(&0x10))
+            .and_then(|packet| login::parse_login7(packet))
+    }
+
+    /// ============================================
+    /// 서버 응답: 토큰 스트림 파싱
+    /// ============================================
+    /// 재조립된 서버 → 클라이언트 스트림에서 TabularResult (0x04) 패킷 본문을
+    /// 응답 메시지(EOM 상태 비트) 단위로 모아 토큰을 순서대로 추출
+    /// 메시지 하나를 해석하지 못해도 다음 메시지부터 다시 파싱
+    /// 아직 끝나지 않은 메시지도 완전한 토큰까지는 반영 (ResponseStreamDecoder에 스트림 전체를 한 번에 넘긴 결과)
+    pub fn parse_server_tokens(data: &[u8]) -> Vec<ResponseToken> {
+        ResponseStreamDecoder::new().push(data)
+    }
+
+    /// ============================================
+    /// 서버 응답: 결과 집합 컬럼
+    /// ============================================
+    /// 서버 토큰 스트림의 첫 COLMETADATA 토큰에서 (컬럼 이름, 타입명) 목록 추출
+    /// 타입명은 SQL Server 표기 (예: "int", "nvarchar(50)"), 결과 집합이 없으면 빈 목록
+    pub fn parse_colmetadata(data: &[u8]) -> Vec<(String, String)> {
+        Self::parse_server_tokens(data)
+            .into_iter()
+            .find_map(|token| match token {
+                ResponseToken::ColMetadata(columns) => Some(Self::column_names(&columns)),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// COLMETADATA 컬럼들의 (이름, 타입명) 목록
+    pub fn column_names(columns: &[ColumnInfo]) -> Vec<(String, String)> {
+        columns
+            .iter()
+            .map(|column| (column.name.clone(), column.type_name()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_packets::{batch_body, utf16};
+    use super::*;
+
+    /// B_VARCHAR 형식의 파라미터 이름 (1바이트 문자 수 + UTF-16LE)
+    fn b_varchar(text: &str) -> Vec<u8> {
+        let mut bytes = vec![text.encode_utf16().count() as u8];
+        bytes.extend(utf16(text));
+        bytes
+    }
+
+    /// NVARCHAR(4000) 파라미터
+    fn nvarchar_param(name: &str, value: &str) -> Vec<u8> {
+        let data = utf16(value);
+        let mut param = b_varchar(name);
+        param.extend([0, 0xE7]);
+        param.extend(8000u16.to_le_bytes());
+        param.extend([0x09, 0x04, 0xD0, 0x00, 0x34]);
+        param.extend((data.len() as u16).to_le_bytes());
+        param.extend(data);
+        param
+    }
+
+    /// INTN(4) 파라미터
+    fn int_param(name: &str, value: i32) -> Vec<u8> {
+        let mut param = b_varchar(name);
+        param.extend([0, 0x26, 4, 4]);
+        param.extend(value.to_le_bytes());
+        param
+    }
+
+    /// sp_executesql (ProcID 10) RPC 메시지 본문, ALL_HEADERS 포함
+    fn rpc_body(params: &[Vec<u8>]) -> Vec<u8> {
+        let mut body = vec![22, 0, 0, 0, 18, 0, 0, 0, 2, 0];
+        body.extend([0; 8]);
+        body.extend(1u32.to_le_bytes());
+        body.extend([0xFF, 0xFF, 0x0A, 0x00, 0x00, 0x00]);
+        body.extend(params.concat());
+        body
+    }
+
+    fn decode_rpc(params: &[Vec<u8>]) -> Option<String> {
+        TdsParser::decode_message(TdsPacketType::RpcRequest, &rpc_body(params), false)
+            .map(|(text, _)| text)
+    }
+
+    fn rpc_call(statement: Option<&str>, params: &[(&str, &str)]) -> RpcCall {
+        RpcCall {
+            procedure: None,
+            statement: statement.map(str::to_string),
+            params: params
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn batch_flag_ends_rpc_parameters() {
+        let mut body = rpc_body(&[
+            nvarchar_param("@stmt", "UPDATE TB_A SET x = @x"),
+            int_param("@x", 5),
+        ]);
+        // 이전 버전 배치 플래그 다음 sp_executesql 요청, 끝에 남은 배치 플래그
+        body.push(RPC_BATCH_FLAG_LEGACY);
+        body.extend([0xFF, 0xFF, 0x0A, 0x00, 0x00, 0x00]);
+        body.extend(nvarchar_param("@stmt", "SELECT 1"));
+        body.push(RPC_BATCH_FLAG);
+
+        assert_eq!(
+            TdsParser::decode_message(TdsPacketType::RpcRequest, &body, false),
+            Some((
+                "UPDATE TB_A SET x = @x -- @x=5\nSELECT 1".to_string(),
+                TextEncoding::Utf16Le
+            ))
+        );
+    }
+
+    #[test]
+    fn single_statement_rpc_is_returned_as_is() {
+        assert_eq!(
+            decode_rpc(&[nvarchar_param("@stmt", "SELECT 1")]).as_deref(),
+            Some("SELECT 1")
+        );
+        // INSERT / UPDATE / DELETE / EXEC도 파라미터가 없으면 " -- " 없이 그대로
+        for statement in [
+            "INSERT INTO TB_A VALUES (1)",
+            "UPDATE TB_A SET X = 1",
+            "DELETE FROM TB_A",
+            "EXEC sp_who",
+        ] {
+            assert_eq!(
+                rpc_call(Some(statement), &[]).format().as_deref(),
+                Some(statement)
+            );
+        }
+    }
+
+    #[test]
+    fn statement_with_params_uses_comment_format() {
+        assert_eq!(
+            decode_rpc(&[
+                nvarchar_param("@stmt", "SELECT * FROM TB_A WHERE ID = @p1"),
+                nvarchar_param("@params", "@p1 int"),
+                int_param("@p1", 42),
+            ])
+            .as_deref(),
+            Some("SELECT * FROM TB_A WHERE ID = @p1 -- @params=@p1 int, @p1=42")
+        );
+        assert_eq!(
+            rpc_call(
+                Some("INSERT INTO TB_A VALUES (@p1)"),
+                &[("@p1", "7"), ("@p2", "x")]
+            )
+            .format()
+            .as_deref(),
+            Some("INSERT INTO TB_A VALUES (@p1) -- @p1=7, @p2=x")
+        );
+    }
+
+    #[test]
+    fn non_sql_parts_are_joined_with_pipes() {
+        assert_eq!(
+            rpc_call(None, &[("@id", "1"), ("@name", "a")])
+                .format()
+                .as_deref(),
+            Some("@id=1 | @name=a")
+        );
+        assert_eq!(
+            rpc_call(Some("sp_helptext"), &[("@objname", "TB_A")])
+                .format()
+                .as_deref(),
+            Some("sp_helptext | @objname=TB_A")
+        );
+        assert_eq!(rpc_call(None, &[]).format(), None);
+    }
+
+    #[test]
+    fn truncated_rpc_bodies_keep_complete_params() {
+        let body = rpc_body(&[
+            nvarchar_param("@stmt", "SELECT * FROM TB_A WHERE ID = @p1"),
+            int_param("@p1", 42),
+        ]);
+        let full = TdsParser::parse_rpc_calls(&body);
+        assert_eq!(full.len(), 1);
+        assert_eq!(full[0].procedure.as_deref(), Some("sp_executesql"));
+        assert_eq!(full[0].params, vec![("@p1".to_string(), "42".to_string())]);
+
+        // 어디서 잘려도 panic 없이 그때까지 읽은 파라미터만 반환
+        for len in 0..body.len() {
+            let calls = TdsParser::parse_rpc_calls(&body[..len]);
+            for call in &calls {
+                assert!(call.params.is_empty() || call.params == full[0].params);
+                assert!(call.statement.is_none() || call.statement == full[0].statement);
+            }
+        }
+        // 마지막 INT 값이 잘리면 @p1 없이 문장만
+        let calls = TdsParser::parse_rpc_calls(&body[..body.len() - 1]);
+        assert_eq!(calls[0].statement, full[0].statement);
+        assert!(calls[0].params.is_empty());
+    }
+
+    fn decode_batch(text: &[u8], detect_encoding: bool) -> Option<(String, TextEncoding)> {
+        TdsParser::decode_message(TdsPacketType::SqlBatch, &batch_body(text), detect_encoding)
+    }
+
+    #[test]
+    fn message_is_split_into_packets() {
+        let first = [0x01, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x01, 0x00, 0xAA, 0xBB];
+        let second = [0x01, 0x01, 0x00, 0x09, 0x00, 0x00, 0x02, 0x00, 0xCC];
+        let message = [&first[..], &second[..]].concat();
+        assert_eq!(
+            TdsParser::message_packets(&message),
+            vec![&first[..], &second[..]]
+        );
+
+        // 끝의 패킷이 잘렸으면 남은 바이트 전체가 마지막 패킷
+        assert_eq!(
+            TdsParser::message_packets(&message[..18]),
+            vec![&first[..], &second[..8]]
+        );
+    }
+
+    #[test]
+    fn detects_utf16le_batch() {
+        assert_eq!(
+            decode_batch(&utf16("SELECT * FROM TB_환자"), true),
+            Some(("SELECT * FROM TB_환자".to_string(), TextEncoding::Utf16Le))
+        );
+    }
+
+    #[test]
+    fn leading_bom_and_nul_are_stripped_from_batch_text() {
+        for prefix in ["\u{FEFF}", "\0", "\u{FEFF}\0\u{1}"] {
+            let (text, encoding) =
+                decode_batch(&utf16(&format!("{}SELECT * FROM TB_환자", prefix)), true).unwrap();
+            assert_eq!(text, "SELECT * FROM TB_환자");
+            assert_eq!(encoding, TextEncoding::Utf16Le);
+            assert_eq!(crate::extract_operations(&text), vec!["SELECT"]);
+        }
+        // 앞의 줄바꿈은 제어 문자이지만 SQL 텍스트의 일부로 유지
+        assert_eq!(
+            decode_batch(&utf16("\u{FEFF}\r\nSELECT 1"), true)
+                .unwrap()
+                .0,
+            "\r\nSELECT 1"
+        );
+    }
+
+    #[test]
+    fn short_text_is_measured_in_characters() {
+        assert!(TdsParser::is_too_short("가나"));
+        assert!(TdsParser::is_too_short("🚀1"));
+        assert!(!TdsParser::is_too_short("가나다"));
+        assert_eq!(decode_batch(&utf16("\u{FEFF}가나"), true), None);
+
+        // 서로게이트 쌍 이모지가 든 주석이 있어도 UTF-16LE로 판단
+        let sql = "/* 🚀✅ 배포 확인 🎉 */ SELECT * FROM TB_환자";
+        assert_eq!(
+            decode_batch(&utf16(&format!("\u{FEFF}{}", sql)), true),
+            Some((sql.to_string(), TextEncoding::Utf16Le))
+        );
+    }
+
+    #[test]
+    fn detects_single_byte_batch_encodings() {
+        assert_eq!(
+            decode_batch("SELECT * FROM TB_환자".as_bytes(), true),
+            Some(("SELECT * FROM TB_환자".to_string(), TextEncoding::Utf8))
+        );
+        // 0xE9는 UTF-8로 잘못된 바이트이므로 windows-1252 (é)
+        assert_eq!(
+            decode_batch(b"SELECT 'caf\xE9' AS name", true),
+            Some((
+                "SELECT 'café' AS name".to_string(),
+                TextEncoding::Windows1252
+            ))
+        );
+    }
+
+    #[test]
+    fn detection_off_always_uses_utf16le() {
+        let (text, encoding) = decode_batch(b"SELECT * FROM TB_A WHERE X = 1", false).unwrap();
+        assert_eq!(encoding, TextEncoding::Utf16Le);
+        assert_ne!(text, "SELECT * FROM TB_A WHERE X = 1");
+    }
+
+    #[test]
+    fn header_fields_are_formatted_for_display() {
+        let header =
+            TdsParser::parse_header(&[0x01, 0x09, 0x00, 0x2A, 0x00, 0x35, 0x02, 0x00]).unwrap();
+        assert!(header.is_end_of_message());
+        assert!(header.is_session_reset());
+
+        assert_eq!(
+            header.fields(),
+            vec![
+                ("타입", "SQL Batch (0x01)".to_string()),
+                ("상태", "0x09 (EOM, RESETCONNECTION)".to_string()),
+                ("길이", "42".to_string()),
+                ("SPID", "53".to_string()),
+                ("패킷 ID", "2".to_string()),
+                ("윈도우", "0".to_string()),
+            ]
+        );
+        assert_eq!(describe_status(0x00), "없음");
+    }
+
+    #[test]
+    fn reset_connection_bits_are_read_from_status() {
+        let header = |status: u8| {
+            TdsParser::parse_header(&[0x01, status, 0x00, 0x08, 0x00, 0x00, 0x01, 0x00]).unwrap()
+        };
+
+        let reset = header(0x09);
+        assert!(reset.is_reset_connection());
+        assert!(!reset.is_reset_connection_skip_tran());
+        assert!(reset.is_session_reset());
+
+        let skip_tran = header(0x11);
+        assert!(!skip_tran.is_reset_connection());
+        assert!(skip_tran.is_reset_connection_skip_tran());
+        assert!(skip_tran.is_session_reset());
+
+        assert!(!header(0x01).is_session_reset());
+    }
+
+    /// int 컬럼 id와 nvarchar(50) 컬럼 이름이 있는 COLMETADATA 토큰
+    fn colmetadata_token() -> Vec<u8> {
+        let mut token = vec![0x81];
+        token.extend(2u16.to_le_bytes());
+        token.extend([0, 0, 0, 0, 0x09, 0x00, 0x38]);
+        token.extend(b_varchar("id"));
+        token.extend([0, 0, 0, 0, 0x09, 0x00, 0xE7]);
+        token.extend(100u16.to_le_bytes());
+        token.extend([0x09, 0x04, 0xD0, 0x00, 0x34]);
+        token.extend(b_varchar("이름"));
+        token
+    }
+
+    /// 마지막 패킷(EOM)인 TabularResult 패킷
+    fn tabular_result(body: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x04, 0x01];
+        packet.extend(((body.len() + 8) as u16).to_be_bytes());
+        packet.extend([0, 0, 1, 0]);
+        packet.extend(body);
+        packet
+    }
+
+    #[test]
+    fn colmetadata_columns_are_decoded() {
+        let mut body = colmetadata_token();
+        // DONE (DONE_COUNT, 0행)
+        body.extend([0xFD, 0x10, 0x00, 0xC1, 0x00]);
+        body.extend(0u64.to_le_bytes());
+
+        assert_eq!(
+            TdsParser::parse_colmetadata(&tabular_result(&body)),
+            vec![
+                ("id".to_string(), "int".to_string()),
+                ("이름".to_string(), "nvarchar(50)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn response_without_result_set_has_no_columns() {
+        let mut no_metadata = vec![0x81, 0xFF, 0xFF, 0xFD, 0x00, 0x00, 0xC1, 0x00];
+        no_metadata.extend(0u64.to_le_bytes());
+        assert!(TdsParser::parse_colmetadata(&tabular_result(&no_metadata)).is_empty());
+        assert!(TdsParser::parse_colmetadata(&[]).is_empty());
+    }
+
+    #[test]
+    fn sql_keyword_match_ignores_case_and_leading_comments() {
+        assert!(TdsParser::starts_with_sql_keyword("select 1"));
+        assert!(TdsParser::starts_with_sql_keyword("  Exec sp_who"));
+        assert!(TdsParser::starts_with_sql_keyword("/* hint */ SELECT 1"));
+        assert!(TdsParser::starts_with_sql_keyword(
+            "-- note\ninsert into TB_A values (1)"
+        ));
+        assert!(!TdsParser::starts_with_sql_keyword("-- SELECT 1"));
+        assert!(!TdsParser::starts_with_sql_keyword("sp_helptext"));
+    }
 
-        (decoded_results, raw_results)
+    #[test]
+    fn lowercase_statement_param_and_sql_use_comment_format() {
+        assert_eq!(
+            decode_rpc(&[
+                nvarchar_param("@STMT", "/* orm */ select * from tb_a where id = @p1"),
+                int_param("@p1", 1),
+            ])
+            .as_deref(),
+            Some("/* orm */ select * from tb_a where id = @p1 -- @p1=1")
+        );
     }
 }
@@ -0,0 +1,140 @@
+use encoding_rs::UTF_16LE;
+
+/// LOGIN7 고정 길이 부분 (Length ~ ClientLCID) 크기
+const FIXED_LEN: usize = 36;
+
+/// LOGIN7 메시지 (0x10)
+/// 연결 시 클라이언트가 보내는 로그인 정보 중 문자열 필드 (비밀번호 제외)
+/// 문자열이 없으면 빈 문자열
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Login7 {
+    pub host_name: String,
+    pub user_name: String,
+    /// 연결 문자열의 Application Name
+    pub app_name: String,
+    pub server_name: String,
+    /// 클라이언트 라이브러리 이름 (예: ".Net SqlClient Data Provider")
+    pub library_name: String,
+    pub language: String,
+    pub database: String,
+}
+
+/// ============================================
+/// LOGIN7 파싱
+/// ============================================
+/// TDS 헤더를 포함한 LOGIN7 패킷에서 문자열 필드 추출
+/// 각 필드는 본문 시작 기준 (offset, 문자 수) u16 쌍으로 위치가 지정되고 UTF-16LE로 저장됨
+/// TLS로 암호화된 LOGIN7(기본 설정의 로그인 암호화)은 해석할 수 없어 None
+pub fn parse_login7(packet: &[u8]) -> Option<Login7> {
+    if packet.first() != Some(&0x10) {
+        return None;
+    }
+
+    let body = packet.get(8..)?;
+    let length_bytes = body.get(..4)?;
+
+    // 본문 앞 4바이트는 LOGIN7 전체 길이 (OffsetLength 9개 항목까지는 있어야 함)
+    let length = u32::from_le_bytes([
+        length_bytes[0],
+        length_bytes[1],
+        length_bytes[2],
+        length_bytes[3],
+    ]) as usize;
+    if length < FIXED_LEN + 4 * 9 || length > body.len() {
+        return None;
+    }
+    let body = &body[..length];
+
+    // OffsetLength 항목 순서: HostName, UserName, Password, AppName, ServerName,
+    // Extension, CltIntName, Language, Database
+    let field = |index: usize| -> Option<String> {
+        let pos = FIXED_LEN + index * 4;
+        let offset = u16::from_le_bytes([body[pos], body[pos + 1]]) as usize;
+        let chars = u16::from_le_bytes([body[pos + 2], body[pos + 3]]) as usize;
+        let bytes = body.get(offset..offset + chars * 2)?;
+        Some(UTF_16LE.decode(bytes).0.into_owned())
+    };
+
+    Some(Login7 {
+        host_name: field(0)?,
+        user_name: field(1)?,
+        app_name: field(3)?,
+        server_name: field(4)?,
+        library_name: field(6)?,
+        language: field(7)?,
+        database: field(8)?,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// 문자열 필드로 만든 LOGIN7 패킷 (TDS 헤더 포함, 비밀번호는 빈 문자열)
+    /// fields 순서: HostName, UserName, AppName, ServerName, CltIntName, Language, Database
+    pub(crate) fn login7_packet(fields: [&str; 7]) -> Vec<u8> {
+        let [host, user, app, server, library, language, database] = fields;
+        let values = [host, user, "", app, server, "", library, language, database];
+
+        // 고정 길이 부분 + OffsetLength 9개 + ClientID 등 나머지 22바이트 뒤에 문자열 데이터
+        let data_start = FIXED_LEN + 4 * 9 + 22;
+        let mut offsets = Vec::new();
+        let mut data = Vec::new();
+        for value in values {
+            let offset = (data_start + data.len()) as u16;
+            offsets.extend(offset.to_le_bytes());
+            offsets.extend((value.encode_utf16().count() as u16).to_le_bytes());
+            data.extend(value.encode_utf16().flat_map(u16::to_le_bytes));
+        }
+
+        let length = data_start + data.len();
+        let mut body = (length as u32).to_le_bytes().to_vec();
+        body.extend([0x04, 0x00, 0x00, 0x74]);
+        body.resize(FIXED_LEN, 0);
+        body.extend(offsets);
+        body.resize(data_start, 0);
+        body.extend(data);
+
+        let mut packet = vec![0x10, 0x01];
+        packet.extend(((body.len() + 8) as u16).to_be_bytes());
+        packet.extend([0, 0, 1, 0]);
+        packet.extend(body);
+        packet
+    }
+
+    #[test]
+    fn parses_login7_string_fields() {
+        let packet = login7_packet([
+            "WS-01",
+            "sa",
+            "진료 서비스",
+            "DBSRV",
+            ".Net SqlClient Data Provider",
+            "us_english",
+            "DentWeb",
+        ]);
+
+        assert_eq!(
+            parse_login7(&packet),
+            Some(Login7 {
+                host_name: "WS-01".to_string(),
+                user_name: "sa".to_string(),
+                app_name: "진료 서비스".to_string(),
+                server_name: "DBSRV".to_string(),
+                library_name: ".Net SqlClient Data Provider".to_string(),
+                language: "us_english".to_string(),
+                database: "DentWeb".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_other_packets_and_truncated_login() {
+        let packet = login7_packet(["h", "u", "a", "s", "l", "", "d"]);
+        assert_eq!(parse_login7(&packet[..packet.len() - 4]), None);
+
+        let mut prelogin = packet.clone();
+        prelogin[0] = 0x12;
+        assert_eq!(parse_login7(&prelogin), None);
+    }
+}
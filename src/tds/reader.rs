@@ -0,0 +1,158 @@
+use encoding_rs::UTF_16LE;
+use std::borrow::Cow;
+
+/// ============================================
+/// 바이트 읽기 커서
+/// ============================================
+/// 범위를 검사하는 바이트 읽기 도우미
+/// 모든 읽기는 Option을 반환하고, 데이터가 모자라면 위치를 옮기지 않고 None
+/// Copy이므로 복사본으로 미리 읽어보고(peek) 필요할 때만 원래 커서에 반영
+#[derive(Debug, Clone, Copy)]
+pub struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// 현재 위치 (처음부터 읽은 바이트 수)
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// 남은 바이트 수
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let bytes = self.data.get(self.pos..end)?;
+        self.pos = end;
+        Some(bytes)
+    }
+
+    pub fn skip(&mut self, len: usize) -> Option<()> {
+        self.read_bytes(len).map(|_| ())
+    }
+
+    pub fn read_u8(&mut self) -> Option<u8> {
+        self.read_bytes(1).map(|b| b[0])
+    }
+
+    pub fn read_u16_le(&mut self) -> Option<u16> {
+        self.read_bytes(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    pub fn read_u16_be(&mut self) -> Option<u16> {
+        self.read_bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub fn read_u32_le(&mut self) -> Option<u32> {
+        self.read_bytes(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub fn read_i32_le(&mut self) -> Option<i32> {
+        self.read_u32_le().map(|v| v as i32)
+    }
+
+    pub fn read_u64_le(&mut self) -> Option<u64> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.read_bytes(8)?);
+        Some(u64::from_le_bytes(bytes))
+    }
+
+    pub fn read_f64_le(&mut self) -> Option<f64> {
+        self.read_u64_le().map(f64::from_bits)
+    }
+
+    /// 1바이트 문자 수 + UTF-16LE 문자열
+    pub fn read_b_varchar(&mut self) -> Option<String> {
+        let mut peek = *self;
+        let chars = peek.read_u8()? as usize;
+        let bytes = peek.read_bytes(chars * 2)?;
+        *self = peek;
+        Some(decode_utf16le(bytes).0.into_owned())
+    }
+
+    /// 2바이트 문자 수 + UTF-16LE 문자열
+    pub fn read_us_varchar(&mut self) -> Option<String> {
+        let mut peek = *self;
+        let chars = peek.read_u16_le()? as usize;
+        let bytes = peek.read_bytes(chars * 2)?;
+        *self = peek;
+        Some(decode_utf16le(bytes).0.into_owned())
+    }
+}
+
+/// UTF-16LE 디코딩 (앞의 BOM FF FE는 제거)
+/// 다른 BOM(UTF-8의 EF BB BF 등)으로 시작해도 인코딩을 바꾸지 않고 UTF-16LE로 읽음
+/// 서로게이트 쌍은 한 문자로 합쳐지고, 짝이 맞지 않는 서로게이트는 U+FFFD로 바뀜
+/// 반환값: (텍스트, 잘못된 서로게이트 등 디코딩 오류가 있었는지)
+pub fn decode_utf16le(bytes: &[u8]) -> (Cow<'_, str>, bool) {
+    let bytes = bytes.strip_prefix(&[0xFF, 0xFE]).unwrap_or(bytes);
+    UTF_16LE.decode_without_bom_handling(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_little_and_big_endian_values() {
+        let mut reader = ByteReader::new(&[0x01, 0x34, 0x12, 0x12, 0x34, 0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(reader.read_u8(), Some(0x01));
+        assert_eq!(reader.read_u16_le(), Some(0x1234));
+        assert_eq!(reader.read_u16_be(), Some(0x1234));
+        assert_eq!(reader.read_u32_le(), Some(0x1234_5678));
+        assert_eq!(reader.position(), 9);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn short_read_returns_none_without_moving() {
+        let mut reader = ByteReader::new(&[0x01, 0x02, 0x03]);
+        assert_eq!(reader.read_u32_le(), None);
+        assert_eq!(reader.read_bytes(usize::MAX), None);
+        assert_eq!(reader.position(), 0);
+        assert_eq!(reader.read_bytes(3), Some(&[0x01, 0x02, 0x03][..]));
+        assert_eq!(reader.read_u8(), None);
+    }
+
+    #[test]
+    fn utf16le_drops_bom_and_joins_surrogate_pairs() {
+        let bytes: Vec<u8> = "\u{FEFF}-- 🚀 배포\nSELECT 1"
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        assert_eq!(&bytes[..2], &[0xFF, 0xFE]);
+        let (text, had_errors) = decode_utf16le(&bytes);
+        assert_eq!(text, "-- 🚀 배포\nSELECT 1");
+        assert!(!had_errors);
+
+        // 짝이 없는 상위 서로게이트는 U+FFFD
+        let (text, had_errors) = decode_utf16le(&[0x3D, 0xD8, b'A', 0]);
+        assert_eq!(text, "\u{FFFD}A");
+        assert!(had_errors);
+    }
+
+    #[test]
+    fn truncated_varchar_does_not_consume_length() {
+        // 문자 수는 3인데 두 글자만 있음
+        let mut reader = ByteReader::new(&[3, b'a', 0, b'b', 0]);
+        assert_eq!(reader.read_b_varchar(), None);
+        assert_eq!(reader.remaining(), 5);
+
+        let mut reader = ByteReader::new(&[2, 0, b'h', 0, 0x5C, 0xD5]);
+        assert_eq!(reader.read_us_varchar().as_deref(), Some("h한"));
+        assert!(reader.is_empty());
+    }
+}
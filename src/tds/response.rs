@@ -0,0 +1,536 @@
+use super::reader::ByteReader;
+use super::DoneToken;
+
+/// 서버 응답 토큰 (TabularResult 0x04 패킷 본문)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseToken {
+    /// COLMETADATA (0x81): 결과 집합 컬럼 정보
+    ColMetadata(Vec<ColumnInfo>),
+    /// ROW (0xD1) / NBCROW (0xD2): 값은 건너뜀
+    Row,
+    /// DONE (0xFD) / DONEPROC (0xFE) / DONEINPROC (0xFF)
+    Done(DoneToken),
+    /// ERROR (0xAA)
+    Error(ServerMessage),
+    /// INFO (0xAB)
+    Info(ServerMessage),
+    /// ENVCHANGE (0xE3): 데이터베이스/언어 등 세션 환경 변경
+    EnvChange(EnvChange),
+    /// RETURNSTATUS (0x79): 프로시저 반환 값
+    ReturnStatus(i32),
+    /// 내용은 해석하지 않고 건너뛴 토큰
+    Other(u8),
+}
+
+/// ERROR / INFO 토큰 내용
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerMessage {
+    pub number: i32,
+    pub state: u8,
+    pub class: u8,
+    pub message: String,
+    pub server: String,
+    pub procedure: String,
+    pub line: i32,
+}
+
+/// ENVCHANGE 토큰 내용
+/// 문자열 타입(1~6, 13)이 아니면 new_value / old_value는 빈 문자열
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvChange {
+    pub env_type: u8,
+    pub new_value: String,
+    pub old_value: String,
+}
+
+impl EnvChange {
+    /// ENVCHANGE 타입 0x01: 데이터베이스 변경 (USE database)
+    pub const DATABASE: u8 = 0x01;
+    /// ENVCHANGE 타입 0x02: 언어 변경
+    pub const LANGUAGE: u8 = 0x02;
+}
+
+/// 값 길이 형식 (ROW 파싱에 사용)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueFormat {
+    /// 고정 길이
+    Fixed(usize),
+    /// 1바이트 길이 + 데이터
+    ByteLen,
+    /// 2바이트 길이 + 데이터 (0xFFFF = NULL)
+    UShortLen,
+    /// PLP (varchar(max) 등): 8바이트 전체 길이 + 청크들
+    Plp,
+    /// text / ntext / image: TextPointer + Timestamp + 4바이트 길이 + 데이터
+    LongLen,
+    /// sql_variant: 4바이트 길이 + 데이터
+    Variant,
+}
+
+/// 결과 집합 컬럼 정보 (COLMETADATA)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub type_id: u8,
+    /// 최대 길이 (바이트), 0xFFFF면 MAX
+    pub max_len: u32,
+    pub precision: u8,
+    pub scale: u8,
+    format: ValueFormat,
+}
+
+impl ColumnInfo {
+    /// SQL Server 타입명 (예: "int", "nvarchar(50)", "decimal(18,2)")
+    pub fn type_name(&self) -> String {
+        let sized = |name: &str, len: u32| {
+            if len == 0xFFFF {
+                format!("{}(max)", name)
+            } else {
+                format!("{}({})", name, len)
+            }
+        };
+
+        match self.type_id {
+            0x1F => "null".to_string(),
+            0x30 => "tinyint".to_string(),
+            0x32 | 0x68 => "bit".to_string(),
+            0x34 => "smallint".to_string(),
+            0x38 => "int".to_string(),
+            0x7F => "bigint".to_string(),
+            0x26 => match self.max_len {
+                1 => "tinyint".to_string(),
+                2 => "smallint".to_string(),
+                8 => "bigint".to_string(),
+                _ => "int".to_string(),
+            },
+            0x3A => "smalldatetime".to_string(),
+            0x3D => "datetime".to_string(),
+            0x6F => {
+                if self.max_len == 4 {
+                    "smalldatetime".to_string()
+                } else {
+                    "datetime".to_string()
+                }
+            }
+            0x3B => "real".to_string(),
+            0x3E => "float".to_string(),
+            0x6D => {
+                if self.max_len == 4 {
+                    "real".to_string()
+                } else {
+                    "float".to_string()
+                }
+            }
+            0x3C => "money".to_string(),
+            0x7A => "smallmoney".to_string(),
+            0x6E => {
+                if self.max_len == 4 {
+                    "smallmoney".to_string()
+                } else {
+                    "money".to_string()
+                }
+            }
+            0x6A => format!("decimal({},{})", self.precision, self.scale),
+            0x6C => format!("numeric({},{})", self.precision, self.scale),
+            0x24 => "uniqueidentifier".to_string(),
+            0x28 => "date".to_string(),
+            0x29 => format!("time({})", self.scale),
+            0x2A => format!("datetime2({})", self.scale),
+            0x2B => format!("datetimeoffset({})", self.scale),
+            0xA7 | 0x27 => sized("varchar", self.max_len),
+            0xAF | 0x2F => sized("char", self.max_len),
+            0xA5 | 0x25 => sized("varbinary", self.max_len),
+            0xAD | 0x2D => sized("binary", self.max_len),
+            // NVARCHAR / NCHAR 길이는 바이트 단위 → 문자 수로 변환
+            0xE7 => sized(
+                "nvarchar",
+                if self.max_len == 0xFFFF {
+                    0xFFFF
+                } else {
+                    self.max_len / 2
+                },
+            ),
+            0xEF => sized("nchar", self.max_len / 2),
+            0x23 => "text".to_string(),
+            0x63 => "ntext".to_string(),
+            0x22 => "image".to_string(),
+            0xF1 => "xml".to_string(),
+            0x62 => "sql_variant".to_string(),
+            0xF0 => "udt".to_string(),
+            other => format!("0x{:02X}", other),
+        }
+    }
+}
+
+/// ============================================
+/// 응답 토큰 스트림 파싱
+/// ============================================
+/// TDS 헤더를 제거한 응답 메시지 본문을 토큰 단위로 순회
+/// ROW 길이는 직전 COLMETADATA의 컬럼 타입으로 계산
+/// 알 수 없는 토큰이나 잘린 데이터를 만나면 그때까지 파싱한 토큰만 반환
+pub fn parse_response_tokens(bytes: &[u8]) -> Vec<ResponseToken> {
+    parse_tokens(bytes, &mut Vec::new()).0
+}
+
+/// 응답 메시지 본문의 앞부분부터 완전한 토큰들을 파싱 (parse_response_tokens 참고)
+/// columns는 직전 COLMETADATA의 컬럼 (본문을 나눠서 파싱할 때 이어서 사용)
+/// 반환값: (토큰들, 토큰으로 읽은 바이트 수)
+pub(crate) fn parse_tokens(
+    bytes: &[u8],
+    columns: &mut Vec<ColumnInfo>,
+) -> (Vec<ResponseToken>, usize) {
+    let mut reader = ByteReader::new(bytes);
+    let mut tokens = Vec::new();
+    let mut consumed = 0;
+
+    while let Some(token) = reader.read_u8() {
+        let parsed = match token {
+            0x81 => parse_colmetadata(&mut reader).map(|cols| {
+                *columns = cols.clone();
+                ResponseToken::ColMetadata(cols)
+            }),
+            0xD1 => skip_row(&mut reader, columns, false).map(|_| ResponseToken::Row),
+            0xD2 => skip_row(&mut reader, columns, true).map(|_| ResponseToken::Row),
+            0xFD..=0xFF => parse_done(&mut reader, token).map(ResponseToken::Done),
+            0xAA => parse_message(&mut reader).map(ResponseToken::Error),
+            0xAB => parse_message(&mut reader).map(ResponseToken::Info),
+            0xE3 => parse_envchange(&mut reader).map(ResponseToken::EnvChange),
+            0x79 => reader.read_i32_le().map(ResponseToken::ReturnStatus),
+            0xAC => skip_return_value(&mut reader).map(|_| ResponseToken::Other(token)),
+            // ORDER, LOGINACK, COLINFO, TABNAME, SSPI: 2바이트 길이로 건너뛰기
+            0xA9 | 0xAD | 0xA5 | 0xA4 | 0xED => reader
+                .read_u16_le()
+                .and_then(|len| reader.skip(len as usize))
+                .map(|_| ResponseToken::Other(token)),
+            // FEDAUTHINFO: 4바이트 길이
+            0xEE => reader
+                .read_u32_le()
+                .and_then(|len| reader.skip(len as usize))
+                .map(|_| ResponseToken::Other(token)),
+            // FEATUREEXTACK: (FeatureId, 4바이트 길이, 데이터) 반복, 0xFF로 종료
+            0xAE => skip_feature_ext_ack(&mut reader).map(|_| ResponseToken::Other(token)),
+            _ => None,
+        };
+
+        match parsed {
+            Some(parsed) => tokens.push(parsed),
+            None => break,
+        }
+        consumed = reader.position();
+    }
+
+    (tokens, consumed)
+}
+
+/// DONE 계열 토큰 본문: Status(2) + CurCmd(2) + DoneRowCount(8) (TDS 7.2+)
+fn parse_done(reader: &mut ByteReader, token: u8) -> Option<DoneToken> {
+    Some(DoneToken {
+        token,
+        status: reader.read_u16_le()?,
+        cur_cmd: reader.read_u16_le()?,
+        row_count: reader.read_u64_le()?,
+    })
+}
+
+fn parse_message(reader: &mut ByteReader) -> Option<ServerMessage> {
+    let len = reader.read_u16_le()? as usize;
+    let mut body = ByteReader::new(reader.read_bytes(len)?);
+
+    Some(ServerMessage {
+        number: body.read_i32_le()?,
+        state: body.read_u8()?,
+        class: body.read_u8()?,
+        message: body.read_us_varchar()?,
+        server: body.read_b_varchar()?,
+        procedure: body.read_b_varchar()?,
+        line: body.read_i32_le().unwrap_or(0),
+    })
+}
+
+fn parse_envchange(reader: &mut ByteReader) -> Option<EnvChange> {
+    let len = reader.read_u16_le()? as usize;
+    let mut body = ByteReader::new(reader.read_bytes(len)?);
+    let env_type = body.read_u8()?;
+
+    // 1~6, 13: B_VARCHAR 새 값 + B_VARCHAR 이전 값
+    let (new_value, old_value) = if matches!(env_type, 1..=6 | 13) {
+        (body.read_b_varchar()?, body.read_b_varchar()?)
+    } else {
+        (String::new(), String::new())
+    };
+
+    Some(EnvChange {
+        env_type,
+        new_value,
+        old_value,
+    })
+}
+
+fn parse_colmetadata(reader: &mut ByteReader) -> Option<Vec<ColumnInfo>> {
+    let count = reader.read_u16_le()?;
+    if count == 0xFFFF {
+        // 메타데이터 없음
+        return Some(Vec::new());
+    }
+
+    let mut columns = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let _user_type = reader.read_u32_le()?;
+        let _flags = reader.read_u16_le()?;
+        let mut column = parse_type_info(reader)?;
+
+        // text / ntext / image는 TableName이 붙음
+        if column.format == ValueFormat::LongLen {
+            let parts = reader.read_u8()?;
+            for _ in 0..parts {
+                reader.read_us_varchar()?;
+            }
+        }
+
+        column.name = reader.read_b_varchar()?;
+        columns.push(column);
+    }
+
+    Some(columns)
+}
+
+/// TYPE_INFO 파싱 (이름은 비어있음)
+/// RPC 파라미터의 TYPE_INFO도 같은 형식
+pub(crate) fn parse_type_info(reader: &mut ByteReader) -> Option<ColumnInfo> {
+    let type_id = reader.read_u8()?;
+    let mut max_len = 0u32;
+    let mut precision = 0u8;
+    let mut scale = 0u8;
+
+    let format = match type_id {
+        // 고정 길이 타입
+        0x1F => ValueFormat::Fixed(0),
+        0x30 | 0x32 => ValueFormat::Fixed(1),
+        0x34 => ValueFormat::Fixed(2),
+        0x38 | 0x3A | 0x3B | 0x7A => ValueFormat::Fixed(4),
+        0x3C | 0x3D | 0x3E | 0x7F => ValueFormat::Fixed(8),
+        // 1바이트 최대 길이
+        0x24 | 0x26 | 0x68 | 0x6D | 0x6E | 0x6F | 0x2F | 0x27 | 0x2D | 0x25 => {
+            max_len = reader.read_u8()? as u32;
+            ValueFormat::ByteLen
+        }
+        // DECIMALN / NUMERICN: 최대 길이 + precision + scale
+        0x6A | 0x6C => {
+            max_len = reader.read_u8()? as u32;
+            precision = reader.read_u8()?;
+            scale = reader.read_u8()?;
+            ValueFormat::ByteLen
+        }
+        // DATE: 추가 정보 없음
+        0x28 => ValueFormat::ByteLen,
+        // TIME / DATETIME2 / DATETIMEOFFSET: scale
+        0x29..=0x2B => {
+            scale = reader.read_u8()?;
+            ValueFormat::ByteLen
+        }
+        // BIGVARBINARY / BIGBINARY: 2바이트 최대 길이
+        0xA5 | 0xAD => {
+            max_len = reader.read_u16_le()? as u32;
+            if max_len == 0xFFFF {
+                ValueFormat::Plp
+            } else {
+                ValueFormat::UShortLen
+            }
+        }
+        // BIGVARCHAR / BIGCHAR / NVARCHAR / NCHAR: 2바이트 최대 길이 + collation(5)
+        0xA7 | 0xAF | 0xE7 | 0xEF => {
+            max_len = reader.read_u16_le()? as u32;
+            reader.skip(5)?;
+            if max_len == 0xFFFF {
+                ValueFormat::Plp
+            } else {
+                ValueFormat::UShortLen
+            }
+        }
+        // TEXT / NTEXT: 4바이트 최대 길이 + collation(5)
+        0x23 | 0x63 => {
+            max_len = reader.read_u32_le()?;
+            reader.skip(5)?;
+            ValueFormat::LongLen
+        }
+        // IMAGE: 4바이트 최대 길이
+        0x22 => {
+            max_len = reader.read_u32_le()?;
+            ValueFormat::LongLen
+        }
+        // XML: 스키마 정보 (있으면 DB명, 스키마명, 컬렉션명)
+        0xF1 => {
+            if reader.read_u8()? == 1 {
+                reader.read_b_varchar()?;
+                reader.read_b_varchar()?;
+                reader.read_us_varchar()?;
+            }
+            ValueFormat::Plp
+        }
+        // UDT: 최대 길이 + DB명, 스키마명, 타입명, 어셈블리명
+        0xF0 => {
+            max_len = reader.read_u16_le()? as u32;
+            reader.read_b_varchar()?;
+            reader.read_b_varchar()?;
+            reader.read_b_varchar()?;
+            reader.read_us_varchar()?;
+            ValueFormat::Plp
+        }
+        // SQL_VARIANT: 4바이트 최대 길이
+        0x62 => {
+            max_len = reader.read_u32_le()?;
+            ValueFormat::Variant
+        }
+        _ => return None,
+    };
+
+    Some(ColumnInfo {
+        name: String::new(),
+        type_id,
+        max_len,
+        precision,
+        scale,
+        format,
+    })
+}
+
+/// 값 하나 건너뛰기
+fn skip_value(reader: &mut ByteReader, format: ValueFormat) -> Option<()> {
+    match format {
+        ValueFormat::Fixed(len) => reader.skip(len),
+        ValueFormat::ByteLen => {
+            let len = reader.read_u8()? as usize;
+            reader.skip(len)
+        }
+        ValueFormat::UShortLen => {
+            let len = reader.read_u16_le()?;
+            if len == 0xFFFF {
+                Some(()) // NULL
+            } else {
+                reader.skip(len as usize)
+            }
+        }
+        ValueFormat::Plp => {
+            let total = reader.read_u64_le()?;
+            if total == u64::MAX {
+                return Some(()); // NULL
+            }
+            loop {
+                let chunk = reader.read_u32_le()? as usize;
+                if chunk == 0 {
+                    return Some(());
+                }
+                reader.skip(chunk)?;
+            }
+        }
+        ValueFormat::LongLen => {
+            let text_ptr_len = reader.read_u8()? as usize;
+            if text_ptr_len == 0 {
+                return Some(()); // NULL
+            }
+            // TextPointer + Timestamp(8)
+            reader.skip(text_ptr_len + 8)?;
+            let len = reader.read_u32_le()? as usize;
+            reader.skip(len)
+        }
+        ValueFormat::Variant => {
+            let len = reader.read_u32_le()? as usize;
+            reader.skip(len)
+        }
+    }
+}
+
+/// 값 하나 읽기 (NULL이면 Some(None), 데이터가 모자라면 None)
+/// PLP 값은 청크를 이어 붙여 반환
+pub(crate) fn read_value(reader: &mut ByteReader, column: &ColumnInfo) -> Option<Option<Vec<u8>>> {
+    let bytes = match column.format {
+        ValueFormat::Fixed(len) => reader.read_bytes(len)?.to_vec(),
+        ValueFormat::ByteLen => {
+            let len = reader.read_u8()? as usize;
+            if len == 0 {
+                return Some(None); // NULL
+            }
+            reader.read_bytes(len)?.to_vec()
+        }
+        ValueFormat::UShortLen => {
+            let len = reader.read_u16_le()?;
+            if len == 0xFFFF {
+                return Some(None);
+            }
+            reader.read_bytes(len as usize)?.to_vec()
+        }
+        ValueFormat::Plp => {
+            let total = reader.read_u64_le()?;
+            if total == u64::MAX {
+                return Some(None);
+            }
+            let mut bytes = Vec::new();
+            loop {
+                let chunk = reader.read_u32_le()? as usize;
+                if chunk == 0 {
+                    break bytes;
+                }
+                bytes.extend_from_slice(reader.read_bytes(chunk)?);
+            }
+        }
+        ValueFormat::LongLen => {
+            let text_ptr_len = reader.read_u8()? as usize;
+            if text_ptr_len == 0 {
+                return Some(None);
+            }
+            reader.skip(text_ptr_len + 8)?;
+            let len = reader.read_u32_le()? as usize;
+            reader.read_bytes(len)?.to_vec()
+        }
+        ValueFormat::Variant => {
+            let len = reader.read_u32_le()? as usize;
+            reader.read_bytes(len)?.to_vec()
+        }
+    };
+
+    Some(Some(bytes))
+}
+
+/// ROW / NBCROW 건너뛰기
+fn skip_row(reader: &mut ByteReader, columns: &[ColumnInfo], null_bitmap: bool) -> Option<()> {
+    if columns.is_empty() {
+        // 컬럼 정보 없이 행 길이를 알 수 없음
+        return None;
+    }
+
+    let bitmap = if null_bitmap {
+        reader.read_bytes(columns.len().div_ceil(8))?
+    } else {
+        &[]
+    };
+
+    for (i, column) in columns.iter().enumerate() {
+        let is_null = null_bitmap && bitmap[i / 8] & (1 << (i % 8)) != 0;
+        if !is_null {
+            skip_value(reader, column.format)?;
+        }
+    }
+
+    Some(())
+}
+
+/// RETURNVALUE: ParamOrdinal(2) + ParamName + Status(1) + UserType(4) + Flags(2) + TYPE_INFO + 값
+fn skip_return_value(reader: &mut ByteReader) -> Option<()> {
+    reader.read_u16_le()?;
+    reader.read_b_varchar()?;
+    reader.read_u8()?;
+    reader.read_u32_le()?;
+    reader.read_u16_le()?;
+    let column = parse_type_info(reader)?;
+    skip_value(reader, column.format)
+}
+
+fn skip_feature_ext_ack(reader: &mut ByteReader) -> Option<()> {
+    loop {
+        if reader.read_u8()? == 0xFF {
+            return Some(());
+        }
+        let len = reader.read_u32_le()? as usize;
+        reader.skip(len)?;
+    }
+}
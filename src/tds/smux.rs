@@ -0,0 +1,399 @@
+use super::reader::ByteReader;
+use super::stream::{DecodedMessage, TdsStreamDecoder};
+use super::TdsParser;
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// SMUX 헤더 첫 바이트 (SMID)
+pub const SMUX_ID: u8 = 0x53;
+
+/// SMUX 헤더 크기: SMID(1) + FLAGS(1) + SID(2) + LENGTH(4) + SEQNUM(4) + WNDW(4)
+pub const SMUX_HEADER_LEN: usize = 16;
+
+/// SMUX FLAGS: 세션 시작 / 수신 확인 / 세션 종료 / 데이터
+pub const SMUX_SYN: u8 = 0x01;
+pub const SMUX_ACK: u8 = 0x02;
+pub const SMUX_FIN: u8 = 0x04;
+pub const SMUX_DATA: u8 = 0x08;
+
+/// SMUX 패킷 헤더 (MARS 연결에서 TDS 패킷 앞에 붙음, 정수는 little-endian)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmuxHeader {
+    pub flags: u8,
+    /// 세션 ID (MARS 세션마다 독립된 TDS 스트림)
+    pub sid: u16,
+    /// 헤더를 포함한 SMUX 패킷 길이
+    pub length: u32,
+    pub seqnum: u32,
+    pub window: u32,
+}
+
+impl SmuxHeader {
+    /// SMUX 헤더 파싱 (SMID가 0x53이 아니거나 FLAGS / LENGTH가 맞지 않으면 None)
+    /// DATA 외의 패킷(SYN / ACK / FIN)은 헤더만으로 이루어짐
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut reader = ByteReader::new(data.get(..SMUX_HEADER_LEN)?);
+        if reader.read_u8()? != SMUX_ID {
+            return None;
+        }
+        let flags = reader.read_u8()?;
+        let header = Self {
+            flags,
+            sid: reader.read_u16_le()?,
+            length: reader.read_u32_le()?,
+            seqnum: reader.read_u32_le()?,
+            window: reader.read_u32_le()?,
+        };
+
+        let length = header.length as usize;
+        let valid = match flags {
+            SMUX_DATA => length > SMUX_HEADER_LEN,
+            SMUX_SYN | SMUX_ACK | SMUX_FIN => length == SMUX_HEADER_LEN,
+            _ => false,
+        };
+        valid.then_some(header)
+    }
+
+    pub fn is_data(&self) -> bool {
+        self.flags == SMUX_DATA
+    }
+}
+
+/// 스트림이 SMUX 패킷으로 시작하는지 (MARS 연결, 로그인 단계 이후)
+/// MARS는 PRELOGIN에서 협상되지만 연결 도중부터 캡처해도 알 수 있도록 헤더 모양으로 판단
+pub fn looks_like_smux(data: &[u8]) -> bool {
+    SmuxHeader::parse(data).is_some()
+}
+
+/// ============================================
+/// SMUX 헤더 제거
+/// ============================================
+/// MARS 연결의 서버 응답 스트림을 받은 순서대로 넘기면 SMUX 헤더를 떼고 DATA 본문만 이어 붙여 반환
+/// - SMUX가 시작되기 전의 TDS 패킷(로그인 응답 등)과 MARS가 아닌 연결의 패킷은 그대로 반환
+/// - 완전하지 않은 패킷은 보관했다가 다음 push에서 이어서 처리
+/// - SMUX / TDS 헤더로 해석할 수 없는 바이트를 만나면 그 뒤로는 나누지 않고 그대로 반환 (암호화된 연결 등)
+///
+/// 세션 구분 없이 받은 순서대로 이어 붙임 (ENVCHANGE처럼 세션과 관계없는 토큰을 읽는 용도)
+#[derive(Default)]
+pub struct SmuxDemuxer {
+    // 아직 처리하지 않은 바이트 (완전하지 않은 SMUX / TDS 패킷)
+    buffer: Vec<u8>,
+    // 헤더가 아닌 바이트를 만나 이후 바이트를 그대로 넘김
+    passthrough: bool,
+}
+
+impl SmuxDemuxer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 스트림 바이트를 추가하고 이번에 완성된 패킷들의 바이트 반환
+    pub fn push(&mut self, data: &[u8]) -> Vec<u8> {
+        if self.passthrough {
+            return data.to_vec();
+        }
+        self.buffer.extend_from_slice(data);
+
+        let mut output = Vec::with_capacity(self.buffer.len());
+        let mut pos = 0;
+        while pos < self.buffer.len() {
+            let rest = &self.buffer[pos..];
+            if let Some(header) = SmuxHeader::parse(rest) {
+                let length = header.length as usize;
+                if rest.len() < length {
+                    break;
+                }
+                if header.is_data() {
+                    output.extend_from_slice(&rest[SMUX_HEADER_LEN..length]);
+                }
+                pos += length;
+                continue;
+            }
+            // SMUX 헤더가 아직 다 오지 않음 (TDS 패킷 타입에는 0x53이 없음)
+            if rest[0] == SMUX_ID && rest.len() < SMUX_HEADER_LEN {
+                break;
+            }
+            if rest.len() < 8 {
+                break;
+            }
+
+            // SMUX 이전의 TDS 패킷은 통째로 복사 (본문의 0x53을 SMUX 헤더로 보지 않도록)
+            let Some(packet_length) = TdsParser::parse_header(rest)
+                .map(|header| header.length as usize)
+                .filter(|&length| length >= 8)
+            else {
+                output.extend_from_slice(rest);
+                pos = self.buffer.len();
+                self.passthrough = true;
+                break;
+            };
+            if rest.len() < packet_length {
+                break;
+            }
+            output.extend_from_slice(&rest[..packet_length]);
+            pos += packet_length;
+        }
+
+        // 처리한 바이트는 버리고 완전하지 않은 패킷만 남김
+        self.buffer.drain(..pos);
+        output
+    }
+
+    /// 완전하지 않은 패킷 없이 받은 바이트를 모두 넘겼는지 (패킷 경계)
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+/// ============================================
+/// MARS 요청 스트림 디코더
+/// ============================================
+/// 클라이언트 스트림의 SMUX 패킷을 세션(SID)별로 나눠 세션마다 TdsStreamDecoder로 디코딩
+/// - SMUX 헤더는 떼고 DATA 본문만 세션의 디코더에 넘김 (SYN / ACK는 무시)
+/// - FIN을 받으면 그 세션의 디코더를 버림
+/// - SMUX 헤더가 아닌 바이트는 1바이트씩 건너뛰어 다음 SMUX 패킷을 찾음
+pub struct SmuxStreamDecoder {
+    detect_encoding: bool,
+    // 아직 처리하지 않은 바이트 (완전하지 않은 SMUX 패킷)
+    buffer: Vec<u8>,
+    sessions: BTreeMap<u16, TdsStreamDecoder>,
+    // buffer 첫 바이트의 스트림 위치
+    offset: usize,
+    // 모든 세션이 디코딩을 마친 스트림 앞부분의 길이 (TdsStreamDecoder::consumed 참고)
+    consumed: usize,
+}
+
+impl SmuxStreamDecoder {
+    /// detect_encoding은 TdsParser::decode_text 참고
+    pub fn new(detect_encoding: bool) -> Self {
+        Self {
+            detect_encoding,
+            buffer: Vec::new(),
+            sessions: BTreeMap::new(),
+            offset: 0,
+            consumed: 0,
+        }
+    }
+
+    /// 스트림 바이트를 추가하고 이번에 완성된 메시지들을 받은 순서대로 반환 (세션 구분 없음)
+    pub fn push(&mut self, data: &[u8]) -> Vec<DecodedMessage> {
+        self.buffer.extend_from_slice(data);
+
+        let mut messages = Vec::new();
+        let mut pos = 0;
+        while self.buffer.len() - pos >= SMUX_HEADER_LEN {
+            self.advance_consumed(pos);
+            let buf = &self.buffer[pos..];
+            let Some(header) = SmuxHeader::parse(buf) else {
+                pos += 1;
+                continue;
+            };
+            let length = header.length as usize;
+
+            // 패킷이 완전하지 않음 (더 기다려야 함)
+            if buf.len() < length {
+                break;
+            }
+
+            match header.flags {
+                SMUX_DATA => {
+                    let detect_encoding = self.detect_encoding;
+                    let decoder = self
+                        .sessions
+                        .entry(header.sid)
+                        .or_insert_with(|| TdsStreamDecoder::new(detect_encoding));
+                    messages.extend(decoder.push(&buf[SMUX_HEADER_LEN..length]));
+                }
+                SMUX_FIN => {
+                    debug!("MARS 세션 종료 (SID {})", header.sid);
+                    self.sessions.remove(&header.sid);
+                }
+                _ => {}
+            }
+            pos += length;
+        }
+
+        // 처리한 바이트는 버리고 완전하지 않은 패킷만 남김
+        self.advance_consumed(pos);
+        self.buffer.drain(..pos);
+        self.offset += pos;
+        messages
+    }
+
+    /// 메시지를 모으는 세션이 없으면 buffer의 pos까지 처리를 마친 것으로 기록
+    fn advance_consumed(&mut self, pos: usize) {
+        if self.sessions.values().all(TdsStreamDecoder::is_idle) {
+            self.consumed = self.offset + pos;
+        }
+    }
+
+    /// 지금까지 넘겨받은 바이트 중 모든 세션이 디코딩을 마쳤거나 건너뛴 앞부분의 길이
+    /// 한 세션이라도 완전하지 않은 패킷이나 메시지를 모으는 중이면 더 나아가지 않음
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// 나머지 바이트를 기다리던 메시지를 있는 바이트만으로 디코딩 (TdsStreamDecoder::finish 참고)
+    /// 끝에 잘린 SMUX DATA 패킷의 본문도 그 세션에 넘긴 뒤 디코딩
+    /// 반환값: 세션 ID 순서로 각 세션에서 나온 메시지 (세션마다 최대 하나, 잘린 패킷으로 완성된 메시지 포함)
+    pub fn finish(&mut self) -> Vec<DecodedMessage> {
+        let mut messages = Vec::new();
+        let tail = std::mem::take(&mut self.buffer);
+        self.offset += tail.len();
+        self.consumed = self.offset;
+        if let Some(header) = SmuxHeader::parse(&tail).filter(SmuxHeader::is_data) {
+            let detect_encoding = self.detect_encoding;
+            let decoder = self
+                .sessions
+                .entry(header.sid)
+                .or_insert_with(|| TdsStreamDecoder::new(detect_encoding));
+            messages.extend(decoder.push(&tail[SMUX_HEADER_LEN..]));
+        }
+
+        messages.extend(
+            self.sessions
+                .values_mut()
+                .filter_map(TdsStreamDecoder::finish),
+        );
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_packets::sql_batch_packet;
+    use super::*;
+
+    /// SMUX 패킷 (DATA가 아니면 payload는 비어 있어야 함)
+    fn smux(flags: u8, sid: u16, seqnum: u32, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![SMUX_ID, flags];
+        packet.extend(sid.to_le_bytes());
+        packet.extend(((SMUX_HEADER_LEN + payload.len()) as u32).to_le_bytes());
+        packet.extend(seqnum.to_le_bytes());
+        packet.extend(4u32.to_le_bytes());
+        packet.extend(payload);
+        packet
+    }
+
+    fn texts(messages: Vec<DecodedMessage>) -> Vec<String> {
+        messages
+            .into_iter()
+            .map(|(decoded, _)| decoded.expect("decoded text").0)
+            .collect()
+    }
+
+    #[test]
+    fn header_is_sixteen_bytes() {
+        let packet = smux(SMUX_DATA, 3, 7, &[0xAA; 4]);
+        assert_eq!(
+            SmuxHeader::parse(&packet),
+            Some(SmuxHeader {
+                flags: SMUX_DATA,
+                sid: 3,
+                length: 20,
+                seqnum: 7,
+                window: 4,
+            })
+        );
+        assert!(looks_like_smux(&packet));
+
+        // 헤더가 다 오지 않았거나 SMID / FLAGS / LENGTH가 맞지 않음
+        assert_eq!(SmuxHeader::parse(&packet[..SMUX_HEADER_LEN - 1]), None);
+        assert_eq!(SmuxHeader::parse(&sql_batch_packet("SELECT 1", 0x01)), None);
+        assert_eq!(SmuxHeader::parse(&smux(SMUX_DATA, 3, 7, &[])), None);
+        assert_eq!(SmuxHeader::parse(&smux(SMUX_SYN, 3, 0, &[0])), None);
+        assert_eq!(SmuxHeader::parse(&smux(0x10, 3, 0, &[])), None);
+        assert!(SmuxHeader::parse(&smux(SMUX_SYN, 3, 0, &[])).is_some_and(|h| !h.is_data()));
+    }
+
+    #[test]
+    fn frame_split_across_pushes_is_decoded_once() {
+        let mut stream = smux(SMUX_SYN, 0, 0, &[]);
+        stream.extend(smux(
+            SMUX_DATA,
+            0,
+            1,
+            &sql_batch_packet("SELECT 1 FROM TB_A", 0x01),
+        ));
+
+        let mut decoder = SmuxStreamDecoder::new(false);
+        // SMUX 헤더 중간, TDS 헤더 중간, 본문 중간에서 나눔
+        assert!(decoder.push(&stream[..SMUX_HEADER_LEN + 10]).is_empty());
+        assert!(decoder.push(&stream[SMUX_HEADER_LEN + 10..40]).is_empty());
+        assert!(decoder.push(&stream[40..stream.len() - 3]).is_empty());
+        // SYN까지만 처리를 마침
+        assert_eq!(decoder.consumed(), SMUX_HEADER_LEN);
+        assert_eq!(
+            texts(decoder.push(&stream[stream.len() - 3..])),
+            vec!["SELECT 1 FROM TB_A"]
+        );
+        assert_eq!(decoder.consumed(), stream.len());
+        assert!(decoder.finish().is_empty());
+    }
+
+    #[test]
+    fn sessions_are_decoded_independently() {
+        // 세션 1의 메시지는 두 TDS 패킷으로 나뉘고 그 사이에 세션 2의 메시지가 끼어 있음
+        let first = sql_batch_packet("SELECT 1 FROM TB_A", 0x00);
+        let second = vec![0x01, 0x01, 0x00, 0x0C, 0, 0, 2, 0, b' ', 0, b'X', 0];
+        let mut stream = smux(SMUX_SYN, 1, 0, &[]);
+        stream.extend(smux(SMUX_SYN, 2, 0, &[]));
+        stream.extend(smux(SMUX_DATA, 1, 1, &first));
+        stream.extend(smux(
+            SMUX_DATA,
+            2,
+            1,
+            &sql_batch_packet("SELECT 2 FROM TB_B", 0x01),
+        ));
+        stream.extend(smux(SMUX_ACK, 1, 1, &[]));
+        stream.extend(smux(SMUX_DATA, 1, 2, &second));
+
+        let mut decoder = SmuxStreamDecoder::new(false);
+        assert_eq!(
+            texts(decoder.push(&stream)),
+            vec!["SELECT 2 FROM TB_B", "SELECT 1 FROM TB_A X"]
+        );
+
+        // FIN을 받은 세션의 EOM을 기다리던 메시지는 버림
+        let mut stream = smux(
+            SMUX_DATA,
+            1,
+            3,
+            &sql_batch_packet("SELECT 3 FROM TB_C", 0x00),
+        );
+        stream.extend(smux(
+            SMUX_DATA,
+            2,
+            2,
+            &sql_batch_packet("SELECT 4 FROM TB_D", 0x00),
+        ));
+        stream.extend(smux(SMUX_FIN, 1, 4, &[]));
+        assert!(decoder.push(&stream).is_empty());
+        assert_eq!(texts(decoder.finish()), vec!["SELECT 4 FROM TB_D"]);
+    }
+
+    #[test]
+    fn finish_returns_pending_message_of_every_session() {
+        let mut stream = smux(
+            SMUX_DATA,
+            2,
+            1,
+            &sql_batch_packet("SELECT 2 FROM TB_B", 0x00),
+        );
+        let truncated = smux(
+            SMUX_DATA,
+            1,
+            1,
+            &sql_batch_packet("SELECT 1 FROM TB_A", 0x01),
+        );
+        stream.extend(&truncated[..truncated.len() - 4]);
+
+        let mut decoder = SmuxStreamDecoder::new(false);
+        assert!(decoder.push(&stream).is_empty());
+        assert_eq!(
+            texts(decoder.finish()),
+            vec!["SELECT 1 FROM TB", "SELECT 2 FROM TB_B"]
+        );
+        assert!(decoder.finish().is_empty());
+    }
+}
@@ -0,0 +1,451 @@
+use super::response::{self, ColumnInfo, ResponseToken};
+use super::smux::SmuxDemuxer;
+use super::{TdsPacketType, TdsParser, TextEncoding, STATUS_IGNORE};
+use tracing::debug;
+
+/// 하나의 메시지로 모을 수 있는 최대 바이트 수
+/// EOM이 오지 않는 스트림(잘못 찾은 헤더 등)에서 메모리가 계속 늘어나지 않도록 넘으면 있는 만큼 디코딩
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// 디코딩한 요청 메시지: ((텍스트, 인코딩), 메시지를 이루는 원본 패킷들을 이어 붙인 바이트)
+/// 디코딩하지 못한 SQL Batch / RPC 메시지와 raw 모드의 패킷은 텍스트가 None
+pub type DecodedMessage = (Option<(String, TextEncoding)>, Vec<u8>);
+
+/// EOM 패킷을 기다리는 메시지
+struct PendingMessage {
+    packet_type: TdsPacketType,
+    // 각 패킷의 TDS 헤더를 뗀 본문을 이어 붙인 바이트
+    body: Vec<u8>,
+    // 원본 패킷들을 이어 붙인 바이트
+    raw: Vec<u8>,
+}
+
+/// ============================================
+/// 증분 TDS 스트림 디코더
+/// ============================================
+/// 플로우 한 방향의 스트림 바이트를 받은 순서대로 넘기면 완성된 메시지를 디코딩해서 반환
+/// - 아직 나머지 바이트가 오지 않은 패킷은 내부에 보관했다가 다음 push에서 이어서 처리
+/// - 여러 패킷으로 나뉜 메시지(상태 바이트의 EOM 비트가 마지막 패킷에만 있음)는 본문을 모아 한 번에 디코딩
+/// - 이미 처리한 바이트는 버리므로 긴 연결에서도 스트림 전체를 다시 훑지 않음
+///
+/// SQL Batch(0x01) / RPC(0x03) 패킷만 처리하고 다른 바이트는 1바이트씩 건너뛰어 다음 패킷을 찾음
+/// IGNORE 비트가 있는 패킷으로 끝나는 메시지는 클라이언트가 취소한 요청이므로 버림
+pub struct TdsStreamDecoder {
+    detect_encoding: bool,
+    // true면 패킷 타입과 관계없이 완전한 패킷을 디코딩하지 않고 그대로 반환 (raw 모드)
+    raw_mode: bool,
+    // 아직 처리하지 않은 바이트 (완전하지 않은 패킷)
+    buffer: Vec<u8>,
+    message: Option<PendingMessage>,
+    // buffer 첫 바이트의 스트림 위치 (지금까지 넘겨받은 바이트 중 buffer 앞에서 버린 수)
+    offset: usize,
+    // 디코딩을 마쳤거나 건너뛴 스트림 앞부분의 길이 (consumed 참고)
+    consumed: usize,
+}
+
+impl TdsStreamDecoder {
+    /// SQL Batch / RPC 메시지를 디코딩하는 디코더
+    /// detect_encoding은 TdsParser::decode_text 참고
+    pub fn new(detect_encoding: bool) -> Self {
+        Self {
+            detect_encoding,
+            raw_mode: false,
+            buffer: Vec::new(),
+            message: None,
+            offset: 0,
+            consumed: 0,
+        }
+    }
+
+    /// 모든 타입의 완전한 패킷을 하나씩 그대로 반환하는 디코더 (프로토콜 디버깅용 raw 모드)
+    pub fn raw() -> Self {
+        Self {
+            raw_mode: true,
+            ..Self::new(false)
+        }
+    }
+
+    /// 스트림 바이트를 추가하고 이번에 완성된 메시지들을 순서대로 반환
+    pub fn push(&mut self, data: &[u8]) -> Vec<DecodedMessage> {
+        self.buffer.extend_from_slice(data);
+
+        let mut messages = Vec::new();
+        let mut pos = 0;
+        while self.buffer.len() - pos >= 8 {
+            self.advance_consumed(pos);
+            let buf = &self.buffer[pos..];
+
+            // SQL 추출에 필요한 패킷 타입이 아니면 다음 패킷을 찾기 위해 1바이트씩 이동
+            if !self.raw_mode && buf[0] != 0x01 && buf[0] != 0x03 {
+                pos += 1;
+                continue;
+            }
+
+            // 유효한 헤더가 아니거나 길이가 헤더보다 짧으면 1바이트씩 이동
+            let Some(header) = TdsParser::parse_header(buf) else {
+                pos += 1;
+                continue;
+            };
+            let packet_length = header.length as usize;
+            if packet_length < 8 {
+                pos += 1;
+                continue;
+            }
+
+            // 패킷이 완전하지 않음 (더 기다려야 함)
+            if buf.len() < packet_length {
+                break;
+            }
+
+            let packet = &buf[..packet_length];
+            pos += packet_length;
+
+            if self.raw_mode {
+                messages.push((None, packet.to_vec()));
+                continue;
+            }
+
+            // IGNORE 비트가 있으면 클라이언트가 취소한 요청이므로 모으던 메시지까지 버림
+            if header.status & STATUS_IGNORE != 0 {
+                debug!("IGNORE 비트가 설정된 패킷 건너뜀 ({}바이트)", packet_length);
+                self.message = None;
+                continue;
+            }
+
+            // 다른 타입의 패킷이 오면 모으던 메시지는 끝난 것으로 보고 있는 만큼 디코딩
+            if self
+                .message
+                .as_ref()
+                .is_some_and(|message| message.packet_type != header.packet_type)
+            {
+                messages.extend(Self::take_message(&mut self.message, self.detect_encoding));
+            }
+
+            let message = self.message.get_or_insert_with(|| PendingMessage {
+                packet_type: header.packet_type,
+                body: Vec::new(),
+                raw: Vec::new(),
+            });
+            message.body.extend_from_slice(&packet[8..]);
+            message.raw.extend_from_slice(packet);
+
+            if header.is_end_of_message() || message.raw.len() >= MAX_MESSAGE_LEN {
+                messages.extend(Self::take_message(&mut self.message, self.detect_encoding));
+            }
+        }
+
+        // 처리한 바이트는 버리고 완전하지 않은 패킷만 남김
+        self.advance_consumed(pos);
+        self.buffer.drain(..pos);
+        self.offset += pos;
+        messages
+    }
+
+    /// 모으던 메시지가 없으면 buffer의 pos까지 처리를 마친 것으로 기록
+    fn advance_consumed(&mut self, pos: usize) {
+        if self.message.is_none() {
+            self.consumed = self.offset + pos;
+        }
+    }
+
+    /// 지금까지 넘겨받은 바이트 중 디코딩을 마쳤거나 건너뛴 앞부분의 길이
+    /// 이 위치 뒤의 바이트는 완전하지 않은 패킷이나 EOM을 기다리는 메시지 (재조립 버퍼에서 앞부분을 버릴 때 사용)
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// 완전하지 않은 패킷이나 EOM을 기다리는 메시지 없이 받은 바이트를 모두 처리했는지
+    pub fn is_idle(&self) -> bool {
+        self.buffer.is_empty() && self.message.is_none()
+    }
+
+    /// 나머지 바이트를 기다리던 메시지를 있는 바이트만으로 디코딩
+    /// 연결이 끝나거나 캡처를 중지해 나머지 바이트가 더 오지 않을 때 사용
+    /// 반환하는 원본 바이트의 마지막 패킷은 헤더의 길이를 실제 바이트 수로 고친 패킷
+    /// 반환값: EOM을 기다리던 메시지나 끝에 잘린 SQL Batch / RPC 패킷이 없으면 None
+    pub fn finish(&mut self) -> Option<DecodedMessage> {
+        if self.raw_mode {
+            return None;
+        }
+
+        // push 후 버퍼에 8바이트 이상 남아 있으면 완전하지 않은 SQL Batch / RPC 패킷으로 시작
+        let tail = std::mem::take(&mut self.buffer);
+        self.offset += tail.len();
+        self.consumed = self.offset;
+        if let Some(header) = TdsParser::parse_header(&tail) {
+            if header.status & STATUS_IGNORE == 0 {
+                if self
+                    .message
+                    .as_ref()
+                    .is_some_and(|message| message.packet_type != header.packet_type)
+                {
+                    self.message = None;
+                }
+                let mut packet = tail;
+                let length = packet.len() as u16;
+                packet[2..4].copy_from_slice(&length.to_be_bytes());
+
+                let message = self.message.get_or_insert_with(|| PendingMessage {
+                    packet_type: header.packet_type,
+                    body: Vec::new(),
+                    raw: Vec::new(),
+                });
+                message.body.extend_from_slice(&packet[8..]);
+                message.raw.extend_from_slice(&packet);
+            }
+        }
+
+        Self::take_message(&mut self.message, self.detect_encoding)
+    }
+
+    /// 모으던 메시지를 꺼내서 디코딩
+    fn take_message(
+        message: &mut Option<PendingMessage>,
+        detect_encoding: bool,
+    ) -> Option<DecodedMessage> {
+        let message = message.take()?;
+        let decoded =
+            TdsParser::decode_message(message.packet_type, &message.body, detect_encoding);
+        Some((decoded, message.raw))
+    }
+}
+
+/// ============================================
+/// 증분 서버 응답 디코더
+/// ============================================
+/// 플로우의 서버 → 클라이언트 스트림 바이트를 받은 순서대로 넘기면 새로 완성된 응답 토큰을 반환
+/// - TabularResult(0x04) 패킷 본문을 응답 메시지(EOM 상태 비트) 단위로 이어서 토큰을 읽음
+/// - 메시지가 끝나기 전이라도 완전한 토큰은 바로 반환하고, 토큰으로 읽은 바이트는 버림
+/// - MARS 연결의 SMUX 헤더는 떼고 세션 구분 없이 받은 순서대로 처리 (SmuxDemuxer)
+///
+/// 이미 처리한 바이트를 버리므로 큰 결과 집합이나 오래 유지되는 연결에서도 스트림 전체를 다시 훑지 않음
+/// TDS 헤더로 해석할 수 없는 바이트(암호화된 연결 등)를 만나면 그 뒤로는 디코딩하지 않음
+#[derive(Default)]
+pub struct ResponseStreamDecoder {
+    smux: SmuxDemuxer,
+    // 아직 처리하지 않은 바이트 (완전하지 않은 패킷)
+    buffer: Vec<u8>,
+    // 응답 메시지 본문 중 아직 토큰으로 읽지 않은 바이트 (완전하지 않은 토큰)
+    message: Vec<u8>,
+    // 메시지의 마지막 COLMETADATA 컬럼 (ROW 길이 계산용)
+    columns: Vec<ColumnInfo>,
+    stopped: bool,
+}
+
+impl ResponseStreamDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 스트림 바이트를 추가하고 이번에 새로 읽은 토큰들을 순서대로 반환
+    pub fn push(&mut self, data: &[u8]) -> Vec<ResponseToken> {
+        if self.stopped {
+            return Vec::new();
+        }
+        let data = self.smux.push(data);
+        self.buffer.extend_from_slice(&data);
+
+        let mut tokens = Vec::new();
+        let mut pos = 0;
+        while self.buffer.len() - pos >= 8 {
+            let buf = &self.buffer[pos..];
+            let Some(header) = TdsParser::parse_header(buf).filter(|header| header.length >= 8)
+            else {
+                debug!("서버 응답 스트림에서 TDS 헤더를 찾지 못해 응답 디코딩 중지");
+                self.stop();
+                return tokens;
+            };
+            let packet_length = header.length as usize;
+
+            // 패킷이 완전하지 않음 (더 기다려야 함)
+            if buf.len() < packet_length {
+                break;
+            }
+
+            if header.packet_type == TdsPacketType::Response {
+                self.message.extend_from_slice(&buf[8..packet_length]);
+                let (parsed, consumed) = response::parse_tokens(&self.message, &mut self.columns);
+                tokens.extend(parsed);
+
+                // EOM (0x01): 응답 메시지의 마지막 패킷 (읽지 못한 나머지 토큰은 버림)
+                if header.is_end_of_message() {
+                    self.message.clear();
+                    self.columns.clear();
+                } else {
+                    self.message.drain(..consumed);
+                }
+            }
+            pos += packet_length;
+        }
+
+        // 처리한 바이트는 버리고 완전하지 않은 패킷만 남김
+        self.buffer.drain(..pos);
+        tokens
+    }
+
+    /// 받은 바이트를 모두 처리했고 응답 메시지 중간이 아닌지
+    /// (마지막 DONE까지 읽었으면 true, 재조립한 응답 스트림을 비워도 됨)
+    pub fn is_idle(&self) -> bool {
+        self.stopped || (self.buffer.is_empty() && self.message.is_empty() && self.smux.is_empty())
+    }
+
+    fn stop(&mut self) {
+        self.stopped = true;
+        self.smux = SmuxDemuxer::new();
+        self.buffer = Vec::new();
+        self.message = Vec::new();
+        self.columns = Vec::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::smux::{SMUX_DATA, SMUX_HEADER_LEN, SMUX_ID};
+    use super::super::test_packets::{sql_batch_packet, tds_packet, utf16};
+    use super::super::DoneToken;
+    use super::*;
+
+    fn done(status: u16, row_count: u64) -> Vec<u8> {
+        let mut token = vec![0xFD];
+        token.extend(status.to_le_bytes());
+        token.extend(0xC1u16.to_le_bytes());
+        token.extend(row_count.to_le_bytes());
+        token
+    }
+
+    fn done_token(status: u16, row_count: u64) -> ResponseToken {
+        ResponseToken::Done(DoneToken {
+            token: 0xFD,
+            status,
+            cur_cmd: 0xC1,
+            row_count,
+        })
+    }
+
+    /// DONE_MORE + DONE_COUNT 토큰 두 개와 마지막 DONE이 세 패킷에 나뉜 응답 메시지
+    fn multi_packet_response() -> Vec<u8> {
+        let mut body = done(0x11, 3);
+        body.extend(done(0x11, 4));
+        body.extend(done(0x10, 5));
+        let (first, rest) = body.split_at(7);
+        let (second, third) = rest.split_at(13);
+        [
+            tds_packet(0x04, 0x00, first),
+            tds_packet(0x04, 0x00, second),
+            tds_packet(0x04, 0x01, third),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn request_decoder_consumes_only_finished_messages() {
+        let first = sql_batch_packet("SELECT 1 FROM TB_A", 0x00);
+        let second = tds_packet(0x01, 0x01, &utf16(" X"));
+        let mut decoder = TdsStreamDecoder::new(false);
+
+        // 패킷 앞의 다른 바이트는 건너뛴 것으로 셈
+        assert!(decoder.push(&[0xAA; 3]).is_empty());
+        assert!(decoder.push(&first).is_empty());
+        assert_eq!(decoder.consumed(), 3);
+
+        // EOM을 기다리는 동안은 메시지가 시작된 위치에 머무름
+        assert!(decoder.push(&second[..5]).is_empty());
+        assert_eq!(decoder.consumed(), 3);
+        assert!(!decoder.is_idle());
+
+        let messages = decoder.push(&second[5..]);
+        assert_eq!(messages[0].0.as_ref().unwrap().0, "SELECT 1 FROM TB_A X");
+        assert_eq!(decoder.consumed(), 3 + first.len() + second.len());
+        assert!(decoder.is_idle());
+    }
+
+    #[test]
+    fn response_tokens_are_returned_once_across_pushes() {
+        let stream = multi_packet_response();
+        let expected = vec![
+            done_token(0x11, 3),
+            done_token(0x11, 4),
+            done_token(0x10, 5),
+        ];
+        assert_eq!(TdsParser::parse_server_tokens(&stream), expected);
+
+        // 어느 위치에서 나눠 받아도 각 토큰은 한 번씩만 나옴
+        for chunk_size in 1..stream.len() {
+            let mut decoder = ResponseStreamDecoder::new();
+            let tokens: Vec<ResponseToken> = stream
+                .chunks(chunk_size)
+                .flat_map(|chunk| decoder.push(chunk))
+                .collect();
+            assert_eq!(tokens, expected, "chunk size {}", chunk_size);
+            assert!(decoder.is_idle());
+        }
+    }
+
+    #[test]
+    fn response_decoder_is_idle_only_after_end_of_message() {
+        let stream = multi_packet_response();
+        let mut decoder = ResponseStreamDecoder::new();
+        assert!(decoder.is_idle());
+
+        // 첫 패킷의 본문은 토큰 중간에서 끝남
+        let first_len = 8 + 7;
+        assert!(decoder.push(&stream[..first_len]).is_empty());
+        assert!(!decoder.is_idle());
+
+        // 패킷 헤더 일부만 받은 상태
+        assert_eq!(decoder.push(&stream[first_len..first_len + 4]), vec![]);
+        assert!(!decoder.is_idle());
+
+        let tokens = decoder.push(&stream[first_len + 4..]);
+        assert_eq!(tokens.len(), 3);
+        assert!(decoder.is_idle());
+    }
+
+    #[test]
+    fn response_decoder_strips_smux_headers() {
+        // 로그인 응답(SMUX 이전)은 그대로, 이후 응답은 SMUX DATA 본문으로
+        let login_response = tds_packet(0x04, 0x01, &done(0x00, 0));
+        let response = tds_packet(0x04, 0x01, &done(0x10, 7));
+        let mut smux = vec![SMUX_ID, SMUX_DATA, 1, 0];
+        smux.extend(((SMUX_HEADER_LEN + response.len()) as u32).to_le_bytes());
+        smux.extend([0u8; 8]);
+        smux.extend(&response);
+
+        let mut decoder = ResponseStreamDecoder::new();
+        assert_eq!(decoder.push(&login_response), vec![done_token(0x00, 0)]);
+        assert_eq!(decoder.push(&smux[..10]), vec![]);
+        assert_eq!(decoder.push(&smux[10..]), vec![done_token(0x10, 7)]);
+        assert!(decoder.is_idle());
+    }
+
+    #[test]
+    fn response_decoder_stops_at_invalid_header() {
+        let mut decoder = ResponseStreamDecoder::new();
+        // TLS 레코드처럼 TDS 헤더로 해석할 수 없는 바이트
+        assert_eq!(
+            decoder.push(&[0x17, 0x03, 0x03, 0x00, 0x20, 0, 0, 0, 0]),
+            vec![]
+        );
+        assert!(decoder.is_idle());
+        // 이후 바이트는 디코딩하지 않고 보관하지도 않음
+        assert_eq!(
+            decoder.push(&tds_packet(0x04, 0x01, &done(0x10, 1))),
+            vec![]
+        );
+        assert!(decoder.buffer.is_empty());
+    }
+
+    #[test]
+    fn unknown_token_skips_rest_of_message_only() {
+        let mut body = done(0x11, 1);
+        body.push(0x01); // 알 수 없는 토큰
+        body.extend(done(0x10, 2));
+        let mut stream = tds_packet(0x04, 0x01, &body);
+        stream.extend(tds_packet(0x04, 0x01, &done(0x10, 3)));
+
+        assert_eq!(
+            TdsParser::parse_server_tokens(&stream),
+            vec![done_token(0x11, 1), done_token(0x10, 3)]
+        );
+    }
+}
@@ -0,0 +1,32 @@
+/// UTF-16LE 바이트
+pub(crate) fn utf16(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+/// TDS 패킷 (status의 0x01이 EOM)
+pub(crate) fn tds_packet(packet_type: u8, status: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![packet_type, status];
+    packet.extend(((body.len() + 8) as u16).to_be_bytes());
+    packet.extend([0, 0, 1, 0]);
+    packet.extend(body);
+    packet
+}
+
+/// ALL_HEADERS(트랜잭션 디스크립터)가 붙은 SQL Batch 메시지 본문
+pub(crate) fn batch_body(text: &[u8]) -> Vec<u8> {
+    let mut body = vec![22, 0, 0, 0, 18, 0, 0, 0, 2, 0];
+    body.extend([0; 8]);
+    body.extend(1u32.to_le_bytes());
+    body.extend(text);
+    body
+}
+
+/// SQL Batch 패킷 하나 (헤더 + ALL_HEADERS + UTF-16LE 본문)
+pub(crate) fn sql_batch_packet(sql: &str, status: u8) -> Vec<u8> {
+    tds_packet(0x01, status, &batch_body(&utf16(sql)))
+}
+
+/// EOM이 붙은 SQL Batch 패킷 하나 (메시지 전체)
+pub(crate) fn sql_batch(sql: &str) -> Vec<u8> {
+    sql_batch_packet(sql, 0x01)
+}
@@ -0,0 +1,168 @@
+/// 시스템 트레이 명령
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayCommand {
+    ShowWindow,
+    ToggleCapture,
+    Quit,
+}
+
+/// 캡처 상태에 따른 캡처 토글 메뉴 문구
+pub fn capture_menu_label(is_capturing: bool) -> &'static str {
+    if is_capturing {
+        "캡처 중지"
+    } else {
+        "캡처 시작"
+    }
+}
+
+/// 캡처 상태와 이벤트 수에 따른 트레이 툴팁 문구
+pub fn tooltip_text(is_capturing: bool, event_count: usize) -> String {
+    if is_capturing {
+        format!("MSSQL TDS SQL 추출기 - 캡처 중 ({}개 이벤트)", event_count)
+    } else {
+        format!("MSSQL TDS SQL 추출기 - 대기 중 ({}개 이벤트)", event_count)
+    }
+}
+
+#[cfg(windows)]
+pub use windows_tray::SystemTray;
+
+#[cfg(windows)]
+mod windows_tray {
+    use super::{capture_menu_label, tooltip_text, TrayCommand};
+    use std::sync::mpsc;
+    use tray_icon::menu::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem};
+    use tray_icon::{Icon, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+    /// 시스템 트레이 아이콘 (Windows 전용)
+    /// 창이 숨겨져 있어도 메뉴 이벤트는 핸들러에서 바로 처리됨
+    pub struct SystemTray {
+        tray: TrayIcon,
+        toggle_item: MenuItem,
+        receiver: mpsc::Receiver<TrayCommand>,
+        last_tooltip: String,
+        last_capturing: bool,
+    }
+
+    impl SystemTray {
+        pub fn new(ctx: &egui::Context) -> Result<Self, String> {
+            let show_item = MenuItem::new("창 보기", true, None);
+            let toggle_item = MenuItem::new(capture_menu_label(false), true, None);
+            let quit_item = MenuItem::new("종료", true, None);
+
+            let menu = Menu::new();
+            menu.append(&show_item).map_err(|e| e.to_string())?;
+            menu.append(&toggle_item).map_err(|e| e.to_string())?;
+            menu.append(&PredefinedMenuItem::separator())
+                .map_err(|e| e.to_string())?;
+            menu.append(&quit_item).map_err(|e| e.to_string())?;
+
+            let last_tooltip = tooltip_text(false, 0);
+            let tray = TrayIconBuilder::new()
+                .with_menu(Box::new(menu))
+                .with_tooltip(&last_tooltip)
+                .with_icon(Self::default_icon()?)
+                .build()
+                .map_err(|e| e.to_string())?;
+
+            let (sender, receiver) = mpsc::channel();
+            let menu_ids: [(MenuId, TrayCommand); 3] = [
+                (show_item.id().clone(), TrayCommand::ShowWindow),
+                (toggle_item.id().clone(), TrayCommand::ToggleCapture),
+                (quit_item.id().clone(), TrayCommand::Quit),
+            ];
+
+            // 메뉴 클릭 처리
+            let menu_ctx = ctx.clone();
+            let menu_sender = sender.clone();
+            MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+                let Some(command) = menu_ids
+                    .iter()
+                    .find(|(id, _)| id == event.id())
+                    .map(|(_, command)| *command)
+                else {
+                    return;
+                };
+
+                if command == TrayCommand::ShowWindow {
+                    Self::show_window(&menu_ctx);
+                }
+                let _ = menu_sender.send(command);
+                menu_ctx.request_repaint();
+            }));
+
+            // 트레이 아이콘 더블클릭 시 창 보기
+            let icon_ctx = ctx.clone();
+            TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| {
+                if let TrayIconEvent::DoubleClick { .. } = event {
+                    Self::show_window(&icon_ctx);
+                    let _ = sender.send(TrayCommand::ShowWindow);
+                    icon_ctx.request_repaint();
+                }
+            }));
+
+            Ok(Self {
+                tray,
+                toggle_item,
+                receiver,
+                last_tooltip,
+                last_capturing: false,
+            })
+        }
+
+        /// 대기 중인 트레이 명령 가져오기
+        pub fn try_recv(&self) -> Option<TrayCommand> {
+            self.receiver.try_recv().ok()
+        }
+
+        /// 캡처 상태와 이벤트 수를 메뉴/툴팁에 반영 (변경된 경우만)
+        pub fn update(&mut self, is_capturing: bool, event_count: usize) {
+            if self.last_capturing != is_capturing {
+                self.toggle_item.set_text(capture_menu_label(is_capturing));
+                self.last_capturing = is_capturing;
+            }
+
+            let tooltip = tooltip_text(is_capturing, event_count);
+            if tooltip != self.last_tooltip {
+                let _ = self.tray.set_tooltip(Some(&tooltip));
+                self.last_tooltip = tooltip;
+            }
+        }
+
+        fn show_window(ctx: &egui::Context) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+
+        /// 단색 16x16 아이콘
+        fn default_icon() -> Result<Icon, String> {
+            let size = 16;
+            let rgba = [40u8, 120, 200, 255].repeat(size * size);
+            Icon::from_rgba(rgba, size as u32, size as u32).map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_menu_label_follows_capture_state() {
+        assert_eq!(capture_menu_label(false), "캡처 시작");
+        assert_eq!(capture_menu_label(true), "캡처 중지");
+    }
+
+    #[test]
+    fn tooltip_shows_state_and_event_count() {
+        assert_eq!(
+            tooltip_text(true, 42),
+            "MSSQL TDS SQL 추출기 - 캡처 중 (42개 이벤트)"
+        );
+        assert_eq!(
+            tooltip_text(false, 0),
+            "MSSQL TDS SQL 추출기 - 대기 중 (0개 이벤트)"
+        );
+    }
+}
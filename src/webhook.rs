@@ -0,0 +1,194 @@
+use crate::SqlEvent;
+use serde::Serialize;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 전송 대기열 크기 (가득 차면 새 경고는 버림)
+const QUEUE_SIZE: usize = 100;
+/// 전송 실패 시 최대 시도 횟수
+const MAX_ATTEMPTS: u64 = 3;
+/// 전송 간 최소 간격 (rate limit)
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 웹훅으로 보내는 경고 내용
+/// text는 Slack / Teams 수신 웹훅에서 메시지로 표시됨
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlertPayload {
+    pub text: String,
+    pub timestamp: String,
+    pub operation: String,
+    pub sql: String,
+    pub flow: String,
+    pub rules: Vec<String>,
+}
+
+impl AlertPayload {
+    pub fn new(event: &SqlEvent, rules: &[&str]) -> Self {
+        let timestamp = event.timestamp.format("%Y-%m-%d %H:%M:%S%.3f").to_string();
+        Self {
+            text: format!(
+                "[경고] {} ({}) {}\n{}",
+                rules.join(", "),
+                timestamp,
+                event.flow_id,
+                event.full_text()
+            ),
+            timestamp,
+            operation: event.operation.clone(),
+            sql: event.full_text().into_owned(),
+            flow: event.flow_id.clone(),
+            rules: rules.iter().map(|rule| rule.to_string()).collect(),
+        }
+    }
+}
+
+/// 경고 웹훅 전송기
+/// 전송은 별도 스레드에서 처리하므로 notify는 막히지 않음
+pub struct WebhookNotifier {
+    sender: mpsc::SyncSender<AlertPayload>,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_SIZE);
+        let url = url.to_string();
+        thread::spawn(move || run_worker(&url, receiver));
+        Self { sender }
+    }
+
+    /// 경고 전송 요청 (대기열이 가득 차면 버림)
+    pub fn notify(&self, event: &SqlEvent, rules: &[&str]) {
+        if let Err(mpsc::TrySendError::Full(_)) =
+            self.sender.try_send(AlertPayload::new(event, rules))
+        {
+            tracing::warn!("웹훅 대기열이 가득 차서 경고를 버림");
+        }
+    }
+}
+
+/// 대기열의 경고를 순서대로 POST (실패 시 재시도, 전송 간격 제한)
+fn run_worker(url: &str, receiver: mpsc::Receiver<AlertPayload>) {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(5))
+        .build();
+    let mut last_sent: Option<Instant> = None;
+
+    for payload in receiver {
+        if let Some(last) = last_sent {
+            let elapsed = last.elapsed();
+            if elapsed < MIN_INTERVAL {
+                thread::sleep(MIN_INTERVAL - elapsed);
+            }
+        }
+
+        let body = match serde_json::to_string(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::warn!("웹훅 내용 생성 실패: {}", e);
+                continue;
+            }
+        };
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            match agent
+                .post(url)
+                .set("Content-Type", "application/json")
+                .send_string(&body)
+            {
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::warn!("웹훅 전송 실패 ({}/{}): {}", attempt, MAX_ATTEMPTS, e);
+                    if attempt < MAX_ATTEMPTS {
+                        thread::sleep(Duration::from_secs(attempt));
+                    }
+                }
+            }
+        }
+
+        last_sent = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// 요청 하나를 받아 200으로 응답하고 (요청 줄, 본문)을 넘겨주는 모의 HTTP 서버
+    fn mock_server() -> (String, mpsc::Receiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+
+            let _ = sender.send((
+                request_line.trim().to_string(),
+                String::from_utf8(body).unwrap(),
+            ));
+        });
+
+        (url, receiver)
+    }
+
+    #[test]
+    fn payload_contains_event_fields() {
+        let payload = AlertPayload::new(&SqlEvent::for_test("DROP TABLE TB_x", 0), &["DROP"]);
+
+        assert_eq!(payload.timestamp, "1970-01-01 00:00:00.000");
+        assert_eq!(payload.operation, "SQL_BATCH");
+        assert_eq!(payload.sql, "DROP TABLE TB_x");
+        assert_eq!(payload.flow, "10.0.0.5:50000->10.0.0.1:1433");
+        assert_eq!(payload.rules, vec!["DROP"]);
+        assert!(payload
+            .text
+            .starts_with("[경고] DROP (1970-01-01 00:00:00.000)"));
+    }
+
+    #[test]
+    fn notifier_posts_json_payload() {
+        let (url, receiver) = mock_server();
+        let notifier = WebhookNotifier::new(&url);
+        notifier.notify(&SqlEvent::for_test("TRUNCATE TABLE TB_x", 0), &["TRUNCATE"]);
+
+        let (request_line, body) = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(request_line, "POST /hook HTTP/1.1");
+
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["sql"], "TRUNCATE TABLE TB_x");
+        assert_eq!(json["operation"], "SQL_BATCH");
+        assert_eq!(json["flow"], "10.0.0.5:50000->10.0.0.1:1433");
+        assert_eq!(json["timestamp"], "1970-01-01 00:00:00.000");
+        assert_eq!(json["rules"], serde_json::json!(["TRUNCATE"]));
+        assert!(json["text"]
+            .as_str()
+            .unwrap()
+            .contains("TRUNCATE TABLE TB_x"));
+    }
+}